@@ -0,0 +1,80 @@
+//! Benchmarks of the core label operations across label sizes.
+//!
+//! Run with the modules to compare enabled, e.g.
+//! `cargo bench --features dclabel,buckle`. Each module is measured on the
+//! same fixtures, labels whose secrecy has 1, 10 or 100 clauses, so results
+//! are comparable across modules and across changes. Use criterion's
+//! `--save-baseline` and `--baseline` flags to catch regressions.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+#[allow(unused_imports)]
+use labeled::{HasPrivilege, Label};
+
+#[allow(dead_code)]
+const SIZES: [usize; 3] = [1, 10, 100];
+
+/// A label whose secrecy has `clauses` overlapping clauses of three
+/// principals, starting at principal `offset`, and whose integrity has one.
+#[allow(dead_code)]
+fn fixture(clauses: usize, offset: usize) -> String {
+    let secrecy: Vec<String> = (offset..offset + clauses)
+        .map(|p| format!("p{}|p{}|p{}", p, p + 1, p + 2))
+        .collect();
+    format!("{},p{}", secrecy.join("&"), offset)
+}
+
+macro_rules! bench_labels {
+    ($name:ident, $group:expr, $label:ty, $parse:expr) => {
+        fn $name(c: &mut Criterion) {
+            let parse: fn(&str) -> $label = $parse;
+            let mut group = c.benchmark_group($group);
+            for size in SIZES.iter().copied() {
+                let text = fixture(size, 0);
+                // Overlapping labels, so lub and glb have clauses to reduce.
+                let (a, b) = (parse(&text), parse(&fixture(size, size / 2)));
+                let privilege = parse(&fixture(size / 2 + 1, 0)).secrecy().clone();
+
+                group.bench_with_input(BenchmarkId::new("parse", size), &text, |bench, text| {
+                    bench.iter(|| parse(black_box(text)))
+                });
+                group.bench_function(BenchmarkId::new("lub", size), |bench| {
+                    bench.iter_batched(|| (a.clone(), b.clone()), |(a, b)| a.lub(b), BatchSize::SmallInput)
+                });
+                group.bench_function(BenchmarkId::new("glb", size), |bench| {
+                    bench.iter_batched(|| (a.clone(), b.clone()), |(a, b)| a.glb(b), BatchSize::SmallInput)
+                });
+                // Reducing a reduced label, as after every lub and glb.
+                group.bench_function(BenchmarkId::new("reduce", size), |bench| {
+                    bench.iter_batched(|| a.clone(), |mut a| a.reduce(), BatchSize::SmallInput)
+                });
+                group.bench_function(BenchmarkId::new("can_flow_to", size), |bench| {
+                    bench.iter(|| black_box(&a).can_flow_to(black_box(&b)))
+                });
+                group.bench_function(BenchmarkId::new("downgrade", size), |bench| {
+                    bench.iter_batched(|| a.clone(), |a| a.downgrade(&privilege), BatchSize::SmallInput)
+                });
+            }
+            group.finish();
+        }
+    };
+}
+
+#[cfg(feature = "dclabel")]
+bench_labels!(dclabel, "dclabel", labeled::dclabel::DCLabel, |s| labeled::dclabel::DCLabel::parse(s).unwrap().1);
+#[cfg(feature = "buckle")]
+bench_labels!(buckle, "buckle", labeled::buckle::Buckle, |s| labeled::buckle::Buckle::parse(s).unwrap());
+#[cfg(feature = "buckle2")]
+bench_labels!(buckle2, "buckle2", labeled::buckle2::Buckle2, |s| labeled::buckle2::Buckle2::parse(s).unwrap());
+
+#[allow(unused_variables)]
+fn labels(c: &mut Criterion) {
+    #[cfg(feature = "dclabel")]
+    dclabel(c);
+    #[cfg(feature = "buckle")]
+    buckle(c);
+    #[cfg(feature = "buckle2")]
+    buckle2(c);
+}
+
+criterion_group!(benches, labels);
+criterion_main!(benches);
@@ -0,0 +1,14 @@
+fn main() {
+    println!("cargo:rerun-if-changed=schema/label.capnp");
+    // The `capnp` feature's accessors are generated from the checked-in
+    // schema, which needs the `capnp` schema compiler on the `PATH`.
+    #[cfg(feature = "capnp")]
+    {
+        capnpc::CompilerCommand::new()
+            .src_prefix("schema")
+            .file("schema/label.capnp")
+            .default_parent_module(vec!["capnproto".into()])
+            .run()
+            .expect("compiling schema/label.capnp");
+    }
+}
@@ -0,0 +1,53 @@
+//! Generates a test-vector file from this crate's results, e.g. to check
+//! another implementation of DC labels against this one.
+//!
+//! Reads one case per line of standard input, as `lhs;rhs` or
+//! `lhs;rhs;privilege` in the syntax of the label type given as the only
+//! argument, and writes the vector file to standard output:
+//!
+//! ```text
+//! echo 'amit,T;yue,T;amit' | cargo run --example vectors --features vectors,dclabel,buckle -- dclabel
+//! ```
+
+use labeled::vectors::{generate, Case, LabelType, VectorFile, VectorLabel, VERSION};
+use std::io::BufRead;
+
+fn cases<L: VectorLabel>(lines: &[String]) -> Result<Vec<Case>, String> {
+    let mut cases = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let fields: Vec<&str> = line.split(';').collect();
+        let (lhs, rhs, privilege) = match fields[..] {
+            [lhs, rhs] => (lhs, rhs, None),
+            [lhs, rhs, privilege] => (lhs, rhs, Some(privilege)),
+            _ => return Err(format!("line {}: expected lhs;rhs[;privilege]", i + 1)),
+        };
+        let label = |input| L::parse_label(input).ok_or_else(|| format!("line {}: invalid label {:?}", i + 1, input));
+        let privilege = match privilege {
+            None => None,
+            Some(p) => Some(L::parse_privilege(p).ok_or_else(|| format!("line {}: invalid privilege {:?}", i + 1, p))?),
+        };
+        cases.push(generate(line.as_str(), &label(lhs)?, &label(rhs)?, privilege.as_ref()));
+    }
+    Ok(cases)
+}
+
+fn main() -> Result<(), String> {
+    let label_type = match std::env::args().nth(1).as_deref() {
+        Some("dclabel") => LabelType::DCLabel,
+        Some("buckle") => LabelType::Buckle,
+        _ => return Err("usage: vectors dclabel|buckle".into()),
+    };
+    let lines: Vec<String> = std::io::stdin()
+        .lock()
+        .lines()
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let lines: Vec<String> = lines.into_iter().filter(|line| !line.is_empty()).collect();
+    let cases = match label_type {
+        LabelType::DCLabel => cases::<labeled::dclabel::DCLabel>(&lines)?,
+        LabelType::Buckle => cases::<labeled::buckle::Buckle>(&lines)?,
+    };
+    let file = VectorFile { version: VERSION, label_type, cases };
+    println!("{}", serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?);
+    Ok(())
+}
@@ -0,0 +1,36 @@
+//! Fuzzes `Buckle2::parse_in`, into the global heap and into an arena. It
+//! must not panic, must use memory linear in the input, and the label it
+//! returns must print to text that parses back to it, and round trip through
+//! the canonical text syntax whenever it is representable there.
+
+#![no_main]
+
+use labeled::arena::LabelArena;
+use labeled::buckle2::Buckle2;
+use labeled::text::{self, Mode, TextLabel};
+use libfuzzer_sys::fuzz_target;
+
+/// Heap bytes parsing may take per byte of input, with room for parsers
+/// allocating temporaries.
+const BYTES_PER_INPUT_BYTE: usize = 256;
+
+fuzz_target!(|input: &str| {
+    let label = match Buckle2::parse(input) {
+        Ok(label) => label,
+        Err(_) => return,
+    };
+    let bound = BYTES_PER_INPUT_BYTE * (input.len() + 1);
+    assert!(label.approx_heap_size() <= bound);
+
+    let arena = LabelArena::new();
+    arena.parse(input).expect("parses into the arena");
+    assert!(arena.allocated() <= bound);
+
+    let printed = label.to_string();
+    assert_eq!(Ok(&label), Buckle2::parse(&printed).as_ref(), "{:?} printed as {:?}", input, printed);
+
+    if let Ok(canonical) = label.to_text() {
+        let reparsed: Buckle2 = text::parse(&canonical, Mode::Strict).expect("canonical text parses");
+        assert_eq!(Ok(canonical), reparsed.to_text());
+    }
+});
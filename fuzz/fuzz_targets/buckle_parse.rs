@@ -0,0 +1,27 @@
+//! Fuzzes `Buckle::parse`, checking the same properties as `dclabel_parse`.
+
+#![no_main]
+
+use labeled::buckle::Buckle;
+use labeled::text::{self, Mode, TextLabel};
+use libfuzzer_sys::fuzz_target;
+
+/// Heap bytes parsing may take per byte of input, with room for parsers
+/// allocating temporaries.
+const BYTES_PER_INPUT_BYTE: usize = 256;
+
+fuzz_target!(|input: &str| {
+    let label = match Buckle::parser(input) {
+        Ok(("", label)) => label,
+        _ => return,
+    };
+    assert!(label.approx_heap_size() <= BYTES_PER_INPUT_BYTE * (input.len() + 1));
+
+    let printed = label.to_string();
+    assert_eq!(Ok(("", label.clone())), Buckle::parser(&printed), "{:?} printed as {:?}", input, printed);
+
+    if let Ok(canonical) = label.to_text() {
+        let reparsed: Buckle = text::parse(&canonical, Mode::Strict).expect("canonical text parses");
+        assert_eq!(Ok(canonical), reparsed.to_text());
+    }
+});
@@ -0,0 +1,32 @@
+//! Fuzzes `DCLabel::parse`: it must not panic, must use memory linear in the
+//! input, and the label it returns must print back to text naming the same
+//! label.
+//!
+//! The canonical text syntax must also round trip every label it can
+//! represent.
+
+#![no_main]
+
+use labeled::dclabel::DCLabel;
+use labeled::text::{self, Mode, TextLabel};
+use libfuzzer_sys::fuzz_target;
+
+/// Heap bytes parsing may take per byte of input, with room for parsers
+/// allocating temporaries.
+const BYTES_PER_INPUT_BYTE: usize = 256;
+
+fuzz_target!(|input: &str| {
+    let label = match DCLabel::parse(input) {
+        Ok(("", label)) => label,
+        _ => return,
+    };
+    assert!(label.approx_heap_size() <= BYTES_PER_INPUT_BYTE * (input.len() + 1));
+
+    let printed = label.to_string();
+    assert_eq!(Ok(("", label.clone())), DCLabel::parse(&printed), "{:?} printed as {:?}", input, printed);
+
+    if let Ok(canonical) = label.to_text() {
+        let reparsed: DCLabel = text::parse(&canonical, Mode::Strict).expect("canonical text parses");
+        assert_eq!(Ok(canonical), reparsed.to_text());
+    }
+});
@@ -0,0 +1,248 @@
+//! Label literals for the `labeled` crate, checked at compile time.
+//!
+//! `buckle!("amit/grades,amit")` and `dclabel!("amit|yue,T")` parse their
+//! string literal as `Buckle::parse` and `DCLabel::parse` do, in the crate's
+//! canonical text syntax read leniently, failing the build on invalid syntax,
+//! and expand to constructor calls building the same label, so no text is
+//! parsed at run time. Unlike the parsers, which stop at the end of the
+//! label, the macros also reject trailing input.
+//!
+//! The expansions name the crate as `::labeled`, so use the macros through
+//! its `macros` feature rather than depending on this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, LitStr};
+
+/// The clauses of a component, each a disjunction of paths of principals,
+/// or `None` for `F`. `T` has no clauses.
+type Component = Option<Vec<Vec<Vec<String>>>>;
+
+/// Parses a literal `Buckle` label into a `labeled::buckle::Buckle`.
+#[proc_macro]
+pub fn buckle(input: TokenStream) -> TokenStream {
+    let literal = parse_macro_input!(input as LitStr);
+    match label(&literal.value(), true) {
+        Ok((secrecy, integrity)) => {
+            let (secrecy, integrity) = (buckle_component(secrecy), buckle_component(integrity));
+            quote!(::labeled::buckle::Buckle::new(#secrecy, #integrity)).into()
+        }
+        Err(offset) => error(&literal, offset),
+    }
+}
+
+/// Parses a literal `DCLabel` into a `labeled::dclabel::DCLabel`.
+#[proc_macro]
+pub fn dclabel(input: TokenStream) -> TokenStream {
+    let literal = parse_macro_input!(input as LitStr);
+    match label(&literal.value(), false) {
+        Ok((secrecy, integrity)) => {
+            let (secrecy, integrity) = (dclabel_component(secrecy), dclabel_component(integrity));
+            quote!(::labeled::dclabel::DCLabel::new(#secrecy, #integrity)).into()
+        }
+        Err(offset) => error(&literal, offset),
+    }
+}
+
+fn error(literal: &LitStr, offset: usize) -> TokenStream {
+    let message = format!("invalid label syntax at byte {}", offset);
+    syn::Error::new(literal.span(), message).to_compile_error().into()
+}
+
+fn buckle_component(component: Component) -> TokenStream2 {
+    match component {
+        None => quote!(::labeled::buckle::Component::dc_false()),
+        Some(clauses) if clauses.is_empty() => quote!(::labeled::buckle::Component::dc_true()),
+        Some(clauses) => {
+            let clauses = clauses.iter().map(|paths| {
+                let paths = paths.iter().map(|path| quote!(&[#(#path),*][..]));
+                quote!(::labeled::buckle::Clause::from_paths(&[#(#paths),*]))
+            });
+            quote!(::labeled::buckle::Component::formula([#(#clauses),*]))
+        }
+    }
+}
+
+fn dclabel_component(component: Component) -> TokenStream2 {
+    match component {
+        None => quote!(::labeled::dclabel::Component::dc_false()),
+        Some(clauses) if clauses.is_empty() => quote!(::labeled::dclabel::Component::dc_true()),
+        Some(clauses) => {
+            let clauses = clauses.iter().map(|principals| {
+                let principals = principals.iter().flatten();
+                quote!(::labeled::dclabel::Clause::new([#(#principals),*]))
+            });
+            quote!(::labeled::dclabel::Component::formula([#(#clauses),*]))
+        }
+    }
+}
+
+/// Parses a whole label, with paths if `paths`, or returns the byte offset
+/// of the first error.
+fn label(input: &str, paths: bool) -> Result<(Component, Component), usize> {
+    let mut parser = Parser { input: input.as_bytes(), pos: 0, paths };
+    parser.skip_whitespace();
+    let secrecy = parser.component()?;
+    if !parser.separator(b',') {
+        return Err(parser.pos);
+    }
+    let integrity = parser.component()?;
+    parser.skip_whitespace();
+    if parser.pos != input.len() {
+        return Err(parser.pos);
+    }
+    Ok((secrecy, integrity))
+}
+
+const SPECIAL: &[u8] = b",|&/\\";
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+    paths: bool,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.input.get(self.pos).is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    /// Consumes `separator` and the whitespace around it.
+    fn separator(&mut self, separator: u8) -> bool {
+        let start = self.pos;
+        self.skip_whitespace();
+        if self.input.get(self.pos) == Some(&separator) {
+            self.pos += 1;
+            self.skip_whitespace();
+            true
+        } else {
+            self.pos = start;
+            false
+        }
+    }
+
+    /// Returns the byte the escape at `at` stands for and the offset after
+    /// it: `\x` and two hex digits is a byte, and `\` before any other
+    /// character is that character.
+    fn escape(&self, at: usize) -> Result<(u8, usize), usize> {
+        let digit = |i: usize| self.input.get(i).and_then(|&b| (b as char).to_digit(16));
+        match self.input.get(at + 1) {
+            Some(b'x') => match (digit(at + 2), digit(at + 3)) {
+                (Some(hi), Some(lo)) => Ok(((hi << 4 | lo) as u8, at + 4)),
+                _ => Ok((b'x', at + 2)),
+            },
+            Some(&b) => Ok((b, at + 2)),
+            None => Err(at + 1),
+        }
+    }
+
+    /// Parses a non-empty principal, which ends before a separator or an
+    /// ASCII control character and doesn't keep trailing unescaped
+    /// whitespace. Returns it and whether it was a single unescaped
+    /// character.
+    fn principal(&mut self) -> Result<(String, bool), usize> {
+        let start = self.pos;
+        let (mut bytes, mut escaped, mut significant) = (Vec::new(), false, 0);
+        while let Some(&b) = self.input.get(self.pos) {
+            if b == b'\\' {
+                let (b, next) = self.escape(self.pos)?;
+                bytes.push(b);
+                self.pos = next;
+                escaped = true;
+                significant = bytes.len();
+            } else if SPECIAL.contains(&b) || (b.is_ascii_control() && !b.is_ascii_whitespace()) {
+                break;
+            } else {
+                bytes.push(b);
+                self.pos += 1;
+                if !b.is_ascii_whitespace() {
+                    significant = bytes.len();
+                }
+            }
+        }
+        self.pos -= bytes.len() - significant;
+        bytes.truncate(significant);
+        if bytes.is_empty() {
+            return Err(start);
+        }
+        let single = !escaped && bytes.len() == 1;
+        String::from_utf8(bytes).map(|p| (p, single)).map_err(|_| start)
+    }
+
+    fn component(&mut self) -> Result<Component, usize> {
+        let start = self.pos;
+        let (first, single) = self.principal()?;
+        if single && (first == "T" || first == "F") {
+            let end = self.pos;
+            if !(self.separator(b'/') || self.separator(b'|') || self.separator(b'&')) {
+                return Ok((first == "T").then(Vec::new));
+            }
+            self.pos = end;
+        }
+        let mut first = Some((first, start));
+        let mut clauses = Vec::new();
+        loop {
+            let mut clause = Vec::new();
+            loop {
+                let mut path = Vec::new();
+                loop {
+                    let (principal, start) = match first.take() {
+                        Some(first) => first,
+                        None => {
+                            let start = self.pos;
+                            (self.principal()?.0, start)
+                        }
+                    };
+                    if !self.paths && !path.is_empty() {
+                        return Err(start);
+                    }
+                    path.push(principal);
+                    if !self.separator(b'/') {
+                        break;
+                    }
+                }
+                clause.push(path);
+                if !self.separator(b'|') {
+                    break;
+                }
+            }
+            clauses.push(clause);
+            if !self.separator(b'&') {
+                break;
+            }
+        }
+        Ok(Some(clauses))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(clauses: &[&[&[&str]]]) -> Component {
+        Some(clauses.iter().map(|c| c.iter().map(|p| p.iter().map(|s| s.to_string()).collect()).collect()).collect())
+    }
+
+    #[test]
+    fn test_label() {
+        assert_eq!(Ok((paths(&[&[&["amit", "grades"]]]), paths(&[&[&["amit"]]]))), label("amit/grades,amit", true));
+        assert_eq!(Ok((paths(&[&[&["a"], &["b"]], &[&["c"]]]), None)), label("a|b&c,F", true));
+        assert_eq!(Ok((paths(&[]), paths(&[&[&["T", ",/"]]]))), label(r"T,\T/\,\/", true));
+        assert_eq!(Ok((paths(&[&[&["a"], &["b"]], &[&["c"]]]), paths(&[]))), label("a|b&c,T", false));
+        assert_eq!(Ok((paths(&[&[&["Tom"]]]), paths(&[&[&["b c", "é"]]]))), label(r" Tom , b c/\xc3\xa9 ", true));
+        assert_eq!(Ok((paths(&[&[&["a-b"]]]), None)), label(r"a\-b,F", true));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(Err(0), label("", true));
+        assert_eq!(Err(2), label("a&,b", true));
+        assert_eq!(Err(2), label("a/b,c", false));
+        assert_eq!(Err(3), label("a,b,c", true));
+        assert_eq!(Err(3), label(r"a,\", true));
+        assert_eq!(Err(0), label(r"\xff,T", true));
+    }
+}
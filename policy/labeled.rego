@@ -0,0 +1,50 @@
+# Flow checks over labels exported by the `opa` module.
+#
+# Components are `false`, or arrays of clauses, each an array of principal
+# paths (arrays of strings). A clause implies another if each of its paths is
+# a prefix of one of the other's, and a component implies another if each of
+# the other's clauses is implied by one of its own.
+package labeled
+
+import rego.v1
+
+default allow := false
+
+# Whether `input.source` can flow to `input.target`, exercising
+# `input.privilege` if given.
+allow if {
+	implies(conjoin(input.target.secrecy, privilege), input.source.secrecy)
+	implies(conjoin(input.source.integrity, privilege), input.target.integrity)
+}
+
+privilege := object.get(input, "privilege", [])
+
+conjoin(a, b) := false if false in [a, b]
+
+conjoin(a, b) := array.concat(a, b) if {
+	is_array(a)
+	is_array(b)
+}
+
+implies(premise, _) if premise == false
+
+implies(premise, conclusion) if {
+	is_array(premise)
+	is_array(conclusion)
+	every clause in conclusion {
+		some other in premise
+		clause_implies(other, clause)
+	}
+}
+
+clause_implies(premise, conclusion) if {
+	every path in premise {
+		some other in conclusion
+		prefix(path, other)
+	}
+}
+
+prefix(path, other) if {
+	count(path) <= count(other)
+	array.slice(other, 0, count(path)) == path
+}
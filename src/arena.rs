@@ -0,0 +1,273 @@
+//! A bump arena for short-lived labels.
+//!
+//! Servers labeling each request build, combine and drop a handful of labels
+//! per request, and each clause and component is a separate heap allocation.
+//! A `LabelArena` hands out memory for `Buckle2` labels from large chunks it
+//! takes from the global heap, never frees individual allocations, and makes
+//! all of its memory available again at once with `reset`. After the first
+//! few requests the chunks are large enough for a whole request, and
+//! labeling it no longer touches the global allocator.
+//!
+//! Only `Buckle2` labels are built in an arena: `Buckle` keeps its clauses
+//! in the global heap, behind `Arc`s, and has no allocator parameter to
+//! build them elsewhere. A `Buckle` is instead copied into the arena as a
+//! `Buckle2` with `LabelArena::buckle`, and compared with the same results.
+//!
+//! Labels in an arena borrow it, so `reset` can only be called once they are
+//! all dropped. An arena is meant to be owned by one worker and isn't `Sync`.
+//! Combining labels of two arenas copies the second operand's clauses into
+//! the first's arena, and `Buckle2::clone_in` copies a label out of one,
+//! e.g. into the global heap to keep it past a reset.
+//!
+//! The arena and `buckle2` unit tests run clean under Miri with strict
+//! provenance (the quickcheck properties are far too slow to interpret):
+//! `MIRIFLAGS=-Zmiri-strict-provenance cargo +nightly miri test --features arena --lib -- arena::tests::test buckle2::clause::tests::test buckle2::component::tests::test buckle2::flatset`.
+
+use crate::buckle2::Buckle2;
+#[cfg(feature = "buckle")]
+use crate::buckle2::{clause::principal_in, Clause, Component, FlatSet};
+use alloc::alloc::{alloc, dealloc};
+use alloc::vec::Vec;
+use allocator_api2::alloc::{AllocError, Allocator, Layout};
+use core::cell::{Cell, RefCell};
+use core::ptr::NonNull;
+
+/// The default size of the first chunk, in bytes.
+pub const DEFAULT_CHUNK_SIZE: usize = 4096;
+
+/// The alignment of every chunk, enough for any label type.
+const CHUNK_ALIGN: usize = 16;
+
+pub struct LabelArena {
+    chunks: RefCell<Vec<(NonNull<u8>, usize)>>,
+    /// The chunk allocations are currently served from.
+    current: Cell<usize>,
+    /// The offset of the free space in the current chunk.
+    offset: Cell<usize>,
+    allocated: Cell<usize>,
+    chunk_size: usize,
+}
+
+impl Default for LabelArena {
+    fn default() -> Self {
+        Self::with_chunk_size(DEFAULT_CHUNK_SIZE)
+    }
+}
+
+impl core::fmt::Debug for LabelArena {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("LabelArena")
+            .field("allocated", &self.allocated())
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+impl LabelArena {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns an arena taking at least `chunk_size` bytes from the global
+    /// heap whenever it runs out of memory.
+    pub fn with_chunk_size(chunk_size: usize) -> Self {
+        LabelArena {
+            chunks: RefCell::new(Vec::new()),
+            current: Cell::new(0),
+            offset: Cell::new(0),
+            allocated: Cell::new(0),
+            chunk_size: chunk_size.max(CHUNK_ALIGN),
+        }
+    }
+
+    /// Returns the number of bytes handed out since the last reset.
+    pub fn allocated(&self) -> usize {
+        self.allocated.get()
+    }
+
+    /// Returns the number of bytes taken from the global heap.
+    pub fn capacity(&self) -> usize {
+        self.chunks.borrow().iter().map(|(_, size)| size).sum()
+    }
+
+    /// Frees every allocation at once, keeping the memory for reuse.
+    pub fn reset(&mut self) {
+        self.current.set(0);
+        self.offset.set(0);
+        self.allocated.set(0);
+    }
+
+    fn bump(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let mut chunks = self.chunks.borrow_mut();
+        let (mut index, mut offset) = (self.current.get(), self.offset.get());
+        loop {
+            if index == chunks.len() {
+                // Each chunk is at least as large as all before it, so the
+                // arena needs few chunks once it has seen a busy request.
+                let size = layout.size().checked_add(layout.align()).ok_or(AllocError)?;
+                let size = size.max(self.chunk_size).max(chunks.last().map_or(0, |c| c.1));
+                let chunk_layout = Layout::from_size_align(size, CHUNK_ALIGN).map_err(|_| AllocError)?;
+                // Safe since `size` is nonzero.
+                let chunk = NonNull::new(unsafe { alloc(chunk_layout) }).ok_or(AllocError)?;
+                chunks.push((chunk, size));
+            }
+            let (chunk, size) = chunks[index];
+            // Aligns by address alone, so the pointer keeps the chunk's
+            // provenance.
+            let start = (chunk.addr().get() + offset)
+                .checked_next_multiple_of(layout.align())
+                .map(|address| address - chunk.addr().get());
+            if let Some(start) = start.filter(|start| start.checked_add(layout.size()).is_some_and(|end| end <= size)) {
+                self.current.set(index);
+                self.offset.set(start + layout.size());
+                self.allocated.set(self.allocated.get() + layout.size());
+                // Safe since `start` is within the chunk.
+                let ptr = unsafe { NonNull::new_unchecked(chunk.as_ptr().add(start)) };
+                return Ok(NonNull::slice_from_raw_parts(ptr, layout.size()));
+            }
+            index += 1;
+            offset = 0;
+        }
+    }
+
+    /// Parses a label into the arena, in the syntax of `Buckle2::parse`.
+    pub fn parse(&self, input: &str) -> Result<Buckle2<&Self>, crate::Error> {
+        Buckle2::parse_in(input, self)
+    }
+
+    pub fn public(&self) -> Buckle2<&Self> {
+        Buckle2::public_in(self)
+    }
+
+    pub fn top(&self) -> Buckle2<&Self> {
+        Buckle2::top_in(self)
+    }
+
+    pub fn bottom(&self) -> Buckle2<&Self> {
+        Buckle2::bottom_in(self)
+    }
+
+    /// Copies a `Buckle` label into the arena, as a `Buckle2`. Like
+    /// `differential::to_buckle2`, the components are copied as-is.
+    #[cfg(feature = "buckle")]
+    pub fn buckle(&self, label: &crate::buckle::Buckle) -> Buckle2<&Self> {
+        Buckle2::new_in(self.component(&label.secrecy), self.component(&label.integrity), self)
+    }
+
+    /// Copies a `Buckle` component into the arena.
+    #[cfg(feature = "buckle")]
+    pub fn component(&self, component: &crate::buckle::Component) -> Component<&Self> {
+        match component {
+            crate::buckle::Component::DCFalse => Component::DCFalse,
+            crate::buckle::Component::DCFormula(clauses) => {
+                let mut result = FlatSet::with_capacity_in(clauses.len(), self);
+                for clause in clauses.iter() {
+                    let mut paths = FlatSet::with_capacity_in(clause.0.len(), self);
+                    for path in clause.0.iter() {
+                        let mut principals = allocator_api2::vec::Vec::with_capacity_in(path.len(), self);
+                        principals.extend(path.iter().map(|p| principal_in(p.as_bytes(), self)));
+                        paths.insert(principals);
+                    }
+                    result.insert(Clause(paths));
+                }
+                Component::DCFormula(result, self)
+            }
+        }
+    }
+}
+
+impl Drop for LabelArena {
+    fn drop(&mut self) {
+        for (chunk, size) in self.chunks.get_mut().drain(..) {
+            // Safe since the chunk was allocated with this layout, and no
+            // label borrowing the arena outlives it.
+            unsafe { dealloc(chunk.as_ptr(), Layout::from_size_align_unchecked(size, CHUNK_ALIGN)) }
+        }
+    }
+}
+
+// Safe since memory handed out stays valid until the arena is reset or
+// dropped, both of which require that no label borrows it.
+unsafe impl Allocator for &LabelArena {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.bump(layout)
+    }
+
+    unsafe fn deallocate(&self, _ptr: NonNull<u8>, _layout: Layout) {
+        // Memory is freed in bulk by `reset`.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HasPrivilege, Label};
+
+    #[test]
+    fn test_arena() {
+        let mut arena = LabelArena::with_chunk_size(64);
+        {
+            let amit = arena.parse("amit,T").unwrap();
+            let both = arena.parse("amit&yue,T").unwrap();
+            assert!(amit.can_flow_to(&both));
+            assert!(!both.can_flow_to(&amit));
+            assert_eq!(both, amit.clone().lub(both.clone()));
+            let privilege = Buckle2::parse_component("yue", &arena).unwrap();
+            assert!(both.can_flow_to_with_privilege(&amit, &privilege));
+            assert!(arena.public().can_flow_to(&arena.top()));
+            assert!(arena.bottom().can_flow_to(&arena.public()));
+        }
+        assert!(arena.allocated() > 0);
+        let capacity = arena.capacity();
+        assert!(capacity >= arena.allocated());
+
+        arena.reset();
+        assert_eq!(0, arena.allocated());
+        {
+            let amit = arena.parse("amit,T").unwrap();
+            let both = arena.parse("amit&yue,T").unwrap();
+            assert!(amit.can_flow_to(&both));
+        }
+        assert_eq!(capacity, arena.capacity());
+    }
+
+    #[test]
+    fn test_queries_do_not_allocate() {
+        let arena = LabelArena::new();
+        let amit = arena.parse("amit|yue&david,amit").unwrap();
+        let both = arena.parse("amit&yue&david,T").unwrap();
+        let allocated = arena.allocated();
+        for _ in 0..100 {
+            assert!(amit.can_flow_to(&both));
+            assert!(!both.can_flow_to(&amit));
+            assert!(amit.secrecy().is_reduced());
+        }
+        assert_eq!(allocated, arena.allocated());
+    }
+
+    #[test]
+    fn test_huge_layout() {
+        let arena = LabelArena::new();
+        let layout = Layout::from_size_align(isize::MAX as usize - 15, 16).unwrap();
+        assert!((&arena).allocate(layout).is_err());
+        assert!((&arena).allocate(Layout::new::<u64>()).is_ok());
+    }
+
+    #[test]
+    fn test_large_allocation() {
+        let arena = LabelArena::with_chunk_size(16);
+        let principal = "a".repeat(1000);
+        let label = arena.parse(&alloc::format!("{},T", principal)).unwrap();
+        assert!(label.can_flow_to(&arena.top()));
+        assert!(arena.capacity() >= 1000);
+    }
+
+    #[cfg(feature = "buckle")]
+    quickcheck! {
+        fn buckle_agrees(lbl1: crate::buckle::Buckle, lbl2: crate::buckle::Buckle) -> bool {
+            let arena = LabelArena::new();
+            let (a1, a2) = (arena.buckle(&lbl1), arena.buckle(&lbl2));
+            lbl1.can_flow_to(&lbl2) == a1.can_flow_to(&a2)
+        }
+    }
+}
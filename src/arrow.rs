@@ -0,0 +1,161 @@
+//! Labels in Arrow schema and Parquet file metadata.
+//!
+//! A dataset's label is stored under the `labeled.label` key of the Arrow
+//! schema's metadata, or of the Parquet file's key-value metadata, and a
+//! column's label under the same key of its field's metadata, all in the
+//! label's textual syntax. A reader computes the label of the data it reads
+//! with `read_label`, which joins the dataset label with the labels of the
+//! projected columns, and checks that it flows to the reader's label.
+//! Missing labels are public.
+
+extern crate std;
+
+use crate::Label;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use arrow_schema::{Field, Schema};
+use parquet::file::metadata::KeyValue;
+use std::collections::HashMap;
+
+/// The metadata key holding a label.
+pub const METADATA_KEY: &str = "labeled.label";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The metadata value isn't a label in the textual syntax.
+    InvalidLabel(String),
+    /// The projection refers to a column the schema doesn't have.
+    NoSuchColumn(usize),
+}
+
+/// A label type with a textual syntax.
+pub trait MetadataLabel: Label + Sized + ToString {
+    fn parse_label(input: &str) -> Option<Self>;
+
+    fn public_label() -> Self;
+}
+
+macro_rules! impl_metadata_label {
+    ($label:path, $parser:path) => {
+        impl MetadataLabel for $label {
+            fn parse_label(input: &str) -> Option<Self> {
+                nom::combinator::all_consuming($parser)(input).ok().map(|(_, label)| label)
+            }
+
+            fn public_label() -> Self {
+                <$label>::public()
+            }
+        }
+    };
+}
+
+#[cfg(feature = "dclabel")]
+impl_metadata_label!(crate::dclabel::DCLabel, crate::dclabel::DCLabel::parse);
+#[cfg(feature = "buckle")]
+impl_metadata_label!(crate::buckle::Buckle, crate::buckle::Buckle::parser);
+
+fn parse<L: MetadataLabel>(value: Option<&String>) -> Result<Option<L>, Error> {
+    value
+        .map(|text| L::parse_label(text).ok_or_else(|| Error::InvalidLabel(text.clone())))
+        .transpose()
+}
+
+fn with_label<L: MetadataLabel>(metadata: &HashMap<String, String>, label: &L) -> HashMap<String, String> {
+    let mut metadata = metadata.clone();
+    metadata.insert(METADATA_KEY.into(), label.to_string());
+    metadata
+}
+
+/// Sets the dataset label in `schema`'s metadata.
+pub fn with_schema_label<L: MetadataLabel>(schema: Schema, label: &L) -> Schema {
+    let metadata = with_label(schema.metadata(), label);
+    schema.with_metadata(metadata)
+}
+
+/// Reads the dataset label from `schema`'s metadata.
+pub fn schema_label<L: MetadataLabel>(schema: &Schema) -> Result<Option<L>, Error> {
+    parse(schema.metadata().get(METADATA_KEY))
+}
+
+/// Sets the column label in `field`'s metadata.
+pub fn with_field_label<L: MetadataLabel>(field: Field, label: &L) -> Field {
+    let metadata = with_label(field.metadata(), label);
+    field.with_metadata(metadata)
+}
+
+/// Reads the column label from `field`'s metadata.
+pub fn field_label<L: MetadataLabel>(field: &Field) -> Result<Option<L>, Error> {
+    parse(field.metadata().get(METADATA_KEY))
+}
+
+/// Returns the label of the data read from the columns of `schema` in
+/// `projection`, or from all columns if it's `None`.
+pub fn read_label<L: MetadataLabel>(schema: &Schema, projection: Option<&[usize]>) -> Result<L, Error> {
+    let mut label = schema_label(schema)?.unwrap_or_else(L::public_label);
+    let all: Vec<usize>;
+    let projection = match projection {
+        Some(projection) => projection,
+        None => {
+            all = (0..schema.fields().len()).collect();
+            &all
+        }
+    };
+    for &i in projection {
+        let field = schema.fields().get(i).ok_or(Error::NoSuchColumn(i))?;
+        if let Some(field_label) = field_label(field)? {
+            label = label.lub(field_label);
+        }
+    }
+    Ok(label)
+}
+
+/// Returns the Parquet key-value entry for the file label.
+pub fn key_value<L: MetadataLabel>(label: &L) -> KeyValue {
+    KeyValue::new(METADATA_KEY.into(), label.to_string())
+}
+
+/// Reads the file label from Parquet key-value metadata.
+pub fn key_value_label<L: MetadataLabel>(metadata: &[KeyValue]) -> Result<Option<L>, Error> {
+    let value = metadata.iter().find(|kv| kv.key == METADATA_KEY).and_then(|kv| kv.value.as_ref());
+    parse(value)
+}
+
+#[cfg(all(test, feature = "dclabel"))]
+mod tests {
+    use super::*;
+    use crate::dclabel::DCLabel;
+    use arrow_schema::DataType;
+
+    fn label(s: &str) -> DCLabel {
+        DCLabel::parse(s).unwrap().1
+    }
+
+    #[test]
+    fn test_read_label() {
+        let schema = Schema::new(alloc::vec![
+            Field::new("id", DataType::Int64, false),
+            with_field_label(Field::new("grade", DataType::Utf8, false), &label("yue,T")),
+        ]);
+        let schema = with_schema_label(schema, &label("amit,T"));
+
+        assert_eq!(Ok(Some(label("amit,T"))), schema_label(&schema));
+        assert_eq!(Ok(label("amit,T")), read_label(&schema, Some(&[0])));
+        assert_eq!(Ok(label("amit&yue,T")), read_label(&schema, None));
+        assert_eq!(Err(Error::NoSuchColumn(2)), read_label::<DCLabel>(&schema, Some(&[2])));
+
+        let unlabeled = Schema::new(alloc::vec![Field::new("id", DataType::Int64, false)]);
+        assert_eq!(Ok(DCLabel::public()), read_label(&unlabeled, None));
+    }
+
+    #[test]
+    fn test_key_value() {
+        let kv = key_value(&label("amit,yue"));
+        assert_eq!(METADATA_KEY, kv.key);
+        assert_eq!(Ok(Some(label("amit,yue"))), key_value_label(&[KeyValue::new("other".into(), None), kv]));
+        assert_eq!(Ok(None), key_value_label::<DCLabel>(&[]));
+        assert_eq!(
+            Err(Error::InvalidLabel("amit".into())),
+            key_value_label::<DCLabel>(&[KeyValue::new(METADATA_KEY.into(), "amit".to_string())])
+        );
+    }
+}
@@ -0,0 +1,328 @@
+//! Labels over a closed universe of principals, represented as bitsets.
+//!
+//! When every principal is known up front, e.g. a few hundred users and
+//! services, a `Universe` assigns each an index and a clause becomes the set
+//! of its principals' bits. Clause implication is then a subset test over
+//! machine words rather than a search over strings. `BitLabel` has the
+//! semantics of `DCLabel`, and `Universe` converts between the two.
+//!
+//! Clauses of labels from different universes must not be mixed.
+
+use crate::{HasPrivilege, Label};
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// A principal is registered twice.
+    DuplicatePrincipal(String),
+    /// A principal isn't in the universe.
+    UnknownPrincipal(String),
+}
+
+/// The principals labels can mention.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Universe {
+    names: Vec<String>,
+    indices: BTreeMap<String, usize>,
+}
+
+impl Universe {
+    pub fn new<I, S>(principals: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut universe = Universe::default();
+        for name in principals {
+            let name = name.into();
+            if universe.indices.insert(name.clone(), universe.names.len()).is_some() {
+                return Err(Error::DuplicatePrincipal(name));
+            }
+            universe.names.push(name);
+        }
+        Ok(universe)
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn index(&self, principal: &str) -> Option<usize> {
+        self.indices.get(principal).copied()
+    }
+
+    pub fn principal(&self, index: usize) -> Option<&str> {
+        self.names.get(index).map(String::as_str)
+    }
+
+    fn words(&self) -> usize {
+        self.names.len().div_ceil(64)
+    }
+
+    /// Returns the clause of the given principals.
+    pub fn clause<'a, I: IntoIterator<Item = &'a str>>(&self, principals: I) -> Result<Clause, Error> {
+        let mut words = alloc::vec![0u64; self.words()].into_boxed_slice();
+        for principal in principals {
+            let i = self.index(principal).ok_or_else(|| Error::UnknownPrincipal(principal.into()))?;
+            words[i / 64] |= 1 << (i % 64);
+        }
+        Ok(Clause(words))
+    }
+
+    /// Converts a `DCLabel` whose principals are all in the universe.
+    #[cfg(feature = "dclabel")]
+    pub fn dclabel(&self, label: &crate::dclabel::DCLabel) -> Result<BitLabel, Error> {
+        use crate::dclabel::Component as C;
+
+        let component = |c: &C| match c {
+            C::DCFalse => Ok(Component::DCFalse),
+            C::DCFormula(clauses) => clauses
+                .iter()
+                .map(|c| self.clause(c.0.iter().map(|p| p.as_str())))
+                .collect::<Result<_, _>>()
+                .map(Component::DCFormula),
+        };
+        Ok(BitLabel { secrecy: component(&label.secrecy)?, integrity: component(&label.integrity)? })
+    }
+
+    /// Converts a label of this universe to a `DCLabel`.
+    #[cfg(feature = "dclabel")]
+    pub fn to_dclabel(&self, label: &BitLabel) -> crate::dclabel::DCLabel {
+        use crate::dclabel::{Clause as C, Component as K};
+
+        let component = |c: &Component| match c {
+            Component::DCFalse => K::DCFalse,
+            Component::DCFormula(clauses) => K::from(
+                clauses
+                    .iter()
+                    .map(|c| C::from(c.principals().filter_map(|i| self.principal(i)).map(Into::into).collect::<BTreeSet<_>>()))
+                    .collect::<BTreeSet<_>>(),
+            ),
+        };
+        crate::dclabel::DCLabel::new(component(&label.secrecy), component(&label.integrity))
+    }
+}
+
+/// A disjunction of principals, as a bitset of their indices.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Clause(Box<[u64]>);
+
+impl Clause {
+    /// A clause implies another if its principals are a subset of the
+    /// other's.
+    pub fn implies(&self, other: &Self) -> bool {
+        self.0.iter().zip(other.0.iter()).all(|(s, o)| s & !o == 0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|w| *w == 0)
+    }
+
+    /// Iterates over the indices of the clause's principals.
+    pub fn principals(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0
+            .iter()
+            .enumerate()
+            .flat_map(|(i, w)| (0..64).filter(move |b| w & (1 << b) != 0).map(move |b| i * 64 + b))
+    }
+
+    fn union(&self, other: &Self) -> Clause {
+        Clause(self.0.iter().zip(other.0.iter()).map(|(s, o)| s | o).collect())
+    }
+}
+
+/// A conjunction of clauses, or false.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Component {
+    DCFalse,
+    DCFormula(BTreeSet<Clause>),
+}
+
+impl Component {
+    pub fn dc_true() -> Self {
+        Component::DCFormula(BTreeSet::new())
+    }
+
+    pub fn implies(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Component::DCFalse, _) => true,
+            (_, Component::DCFalse) => false,
+            (Component::DCFormula(s), Component::DCFormula(o)) => {
+                o.iter().all(|oclause| s.iter().any(|sclause| sclause.implies(oclause)))
+            }
+        }
+    }
+
+    /// Removes the clauses implied by other clauses.
+    pub fn reduce(&mut self) {
+        if let Component::DCFormula(clauses) = self {
+            let redundant: Vec<_> =
+                clauses.iter().filter(|c| clauses.iter().any(|o| o != *c && o.implies(c))).cloned().collect();
+            for clause in redundant.iter() {
+                clauses.remove(clause);
+            }
+        }
+    }
+}
+
+impl core::ops::BitAnd for Component {
+    type Output = Component;
+    fn bitand(self, rhs: Self) -> Component {
+        match (self, rhs) {
+            (Component::DCFormula(mut s), Component::DCFormula(mut o)) => {
+                s.append(&mut o);
+                Component::DCFormula(s)
+            }
+            _ => Component::DCFalse,
+        }
+    }
+}
+
+/// Distributes the disjunction over both conjunctions.
+impl core::ops::BitOr for Component {
+    type Output = Component;
+    fn bitor(self, rhs: Self) -> Component {
+        match (self, rhs) {
+            (s, Component::DCFalse) => s,
+            (Component::DCFalse, o) => o,
+            (Component::DCFormula(s), Component::DCFormula(o)) => {
+                Component::DCFormula(s.iter().flat_map(|sclause| o.iter().map(move |oclause| sclause.union(oclause))).collect())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BitLabel {
+    pub secrecy: Component,
+    pub integrity: Component,
+}
+
+impl BitLabel {
+    pub fn reduce(&mut self) {
+        self.secrecy.reduce();
+        self.integrity.reduce();
+    }
+}
+
+impl Label for BitLabel {
+    fn lub(self, rhs: Self) -> Self {
+        let mut res = BitLabel { secrecy: self.secrecy & rhs.secrecy, integrity: self.integrity | rhs.integrity };
+        res.reduce();
+        res
+    }
+
+    fn glb(self, rhs: Self) -> Self {
+        let mut res = BitLabel { secrecy: self.secrecy | rhs.secrecy, integrity: self.integrity & rhs.integrity };
+        res.reduce();
+        res
+    }
+
+    fn can_flow_to(&self, rhs: &Self) -> bool {
+        rhs.secrecy.implies(&self.secrecy) && self.integrity.implies(&rhs.integrity)
+    }
+}
+
+impl HasPrivilege for BitLabel {
+    type Privilege = Component;
+
+    fn downgrade(mut self, privilege: &Component) -> BitLabel {
+        self.secrecy = match (self.secrecy, privilege) {
+            (_, Component::DCFalse) => Component::dc_true(),
+            (Component::DCFalse, _) => Component::DCFalse,
+            (Component::DCFormula(mut sec), Component::DCFormula(p)) => {
+                sec.retain(|c| !p.iter().any(|pclause| pclause.implies(c)));
+                Component::DCFormula(sec)
+            }
+        };
+        self.integrity = privilege.clone() & self.integrity;
+        self
+    }
+
+    fn downgrade_to(self, target: Self, privilege: &Component) -> Self {
+        if self.can_flow_to_with_privilege(&target, privilege) {
+            target
+        } else {
+            self
+        }
+    }
+
+    fn can_flow_to_with_privilege(&self, rhs: &Self, privilege: &Component) -> bool {
+        (rhs.secrecy.clone() & privilege.clone()).implies(&self.secrecy)
+            && (self.integrity.clone() & privilege.clone()).implies(&rhs.integrity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn universe() -> Universe {
+        // More than one word of principals.
+        Universe::new((0..100).map(|i| alloc::format!("p{}", i)).chain(["amit".into(), "yue".into()])).unwrap()
+    }
+
+    #[test]
+    fn test_universe() {
+        let universe = universe();
+        assert_eq!(102, universe.len());
+        assert_eq!(Some(101), universe.index("yue"));
+        assert_eq!(Some("amit"), universe.principal(100));
+        assert_eq!(Err(Error::DuplicatePrincipal("a".into())), Universe::new(["a", "b", "a"].iter().copied()));
+        assert_eq!(Err(Error::UnknownPrincipal("deian".into())), universe.clause(["amit", "deian"].iter().copied()));
+
+        let clause = universe.clause(["yue", "p3", "amit"].iter().copied()).unwrap();
+        assert_eq!(alloc::vec![3, 100, 101], clause.principals().collect::<Vec<_>>());
+        assert!(universe.clause(["amit"].iter().copied()).unwrap().implies(&clause));
+        assert!(!clause.implies(&universe.clause(["amit"].iter().copied()).unwrap()));
+    }
+
+    #[cfg(feature = "dclabel")]
+    #[test]
+    fn test_dclabel() {
+        use crate::dclabel::DCLabel;
+
+        let universe = universe();
+        let lbl = DCLabel::parse("amit|p7&yue,p99").unwrap().1;
+        let bits = universe.dclabel(&lbl).unwrap();
+        assert_eq!(lbl, universe.to_dclabel(&bits));
+        assert_eq!(
+            Err(Error::UnknownPrincipal("deian".into())),
+            universe.dclabel(&DCLabel::parse("deian,T").unwrap().1)
+        );
+
+        let amit = universe.dclabel(&DCLabel::parse("amit,T").unwrap().1).unwrap();
+        let both = universe.dclabel(&DCLabel::parse("amit&yue,T").unwrap().1).unwrap();
+        assert!(amit.can_flow_to(&both));
+        assert!(!both.can_flow_to(&amit));
+        let privilege = universe.dclabel(&DCLabel::parse("yue,T").unwrap().1).unwrap().secrecy;
+        assert!(both.can_flow_to_with_privilege(&amit, &privilege));
+    }
+
+    #[cfg(feature = "dclabel")]
+    quickcheck! {
+        fn agrees_with_dclabel(lbl1: crate::dclabel::DCLabel, lbl2: crate::dclabel::DCLabel) -> bool {
+            let principals = |l: &crate::dclabel::DCLabel| -> BTreeSet<crate::dclabel::Principal> {
+                [&l.secrecy, &l.integrity]
+                    .iter()
+                    .flat_map(|c| match c {
+                        crate::dclabel::Component::DCFalse => Vec::new(),
+                        crate::dclabel::Component::DCFormula(clauses) => {
+                            clauses.iter().flat_map(|c| c.0.iter().cloned()).collect()
+                        }
+                    })
+                    .collect()
+            };
+            let universe = Universe::new(principals(&lbl1).union(&principals(&lbl2)).cloned()).unwrap();
+            let (b1, b2) = (universe.dclabel(&lbl1).unwrap(), universe.dclabel(&lbl2).unwrap());
+            b1.can_flow_to(&b2) == lbl1.can_flow_to(&lbl2)
+        }
+    }
+}
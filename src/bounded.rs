@@ -0,0 +1,506 @@
+//! Deserialization of labels from untrusted input, within resource limits.
+//!
+//! The `Deserialize` impls of `DCLabel` and `Buckle` accept labels of any
+//! size, so hostile input can make them allocate without bound. `BoundedSeed`
+//! deserializes a label while enforcing `Limits` on the number of clauses and
+//! principals, the length of principals and the depth of principal paths,
+//! failing as soon as a limit is exceeded rather than after the label is
+//! built. It should be used wherever labels cross a trust boundary; `Bounded`
+//! wraps a label to apply the default limits from derived impls.
+//!
+//! Both of the serde representations of labels are supported: for
+//! human-readable formats the text is limited to `Limits::max_text_len` bytes
+//! before it is parsed, and for binary formats every collection is counted
+//! while it is read.
+
+use alloc::collections::BTreeSet;
+#[cfg(feature = "dclabel")]
+use crate::dclabel::Principal;
+#[cfg(all(feature = "buckle", not(feature = "dclabel")))]
+use crate::buckle::Principal;
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+use serde::de::{self, DeserializeSeed, Deserializer, EnumAccess, SeqAccess, VariantAccess, Visitor};
+
+/// Resource limits for deserializing a label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum number of clauses in each component.
+    pub max_clauses: usize,
+    /// The maximum number of principals in the label, counting each path of
+    /// a hierarchical label once, as `LabelStats` does.
+    pub max_principals: usize,
+    /// The maximum length of a principal, or of each element of a path, in
+    /// bytes.
+    pub max_principal_len: usize,
+    /// The maximum length of a principal path.
+    pub max_path_depth: usize,
+    /// The maximum length of a label's text, in bytes.
+    pub max_text_len: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_clauses: 256,
+            max_principals: 1024,
+            max_principal_len: 256,
+            max_path_depth: 16,
+            max_text_len: 16 * 1024,
+        }
+    }
+}
+
+impl Limits {
+    /// Returns a seed deserializing an `L` within these limits.
+    pub fn seed<L: BoundedLabel>(self) -> BoundedSeed<L> {
+        BoundedSeed { limits: self, _label: PhantomData }
+    }
+}
+
+/// The limit a label exceeds. Each variant holds the limit's value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitError {
+    TooManyClauses(usize),
+    TooManyPrincipals(usize),
+    PrincipalTooLong(usize),
+    PathTooDeep(usize),
+    TextTooLong(usize),
+}
+
+impl fmt::Display for LimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitError::TooManyClauses(n) => write!(f, "label component has more than {} clauses", n),
+            LimitError::TooManyPrincipals(n) => write!(f, "label has more than {} principals", n),
+            LimitError::PrincipalTooLong(n) => write!(f, "principal is longer than {} bytes", n),
+            LimitError::PathTooDeep(n) => write!(f, "principal path is longer than {} principals", n),
+            LimitError::TextTooLong(n) => write!(f, "label text is longer than {} bytes", n),
+        }
+    }
+}
+
+/// A label type that can be deserialized within limits.
+pub trait BoundedLabel: Sized {
+    fn deserialize_bounded<'de, D: Deserializer<'de>>(deserializer: D, limits: &Limits) -> Result<Self, D::Error>;
+}
+
+/// Deserializes an `L` within `Limits`.
+pub struct BoundedSeed<L> {
+    limits: Limits,
+    _label: PhantomData<L>,
+}
+
+impl<'de, L: BoundedLabel> DeserializeSeed<'de> for BoundedSeed<L> {
+    type Value = L;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<L, D::Error> {
+        L::deserialize_bounded(deserializer, &self.limits)
+    }
+}
+
+/// A label deserialized within the default `Limits`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bounded<L>(pub L);
+
+impl<'de, L: BoundedLabel> serde::Deserialize<'de> for Bounded<L> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        L::deserialize_bounded(deserializer, &Limits::default()).map(Bounded)
+    }
+}
+
+/// An element of a clause: a principal, or a principal path.
+trait Element: Ord + Sized {
+    fn deserialize<'de, D: Deserializer<'de>>(deserializer: D, limits: &Limits) -> Result<Self, D::Error>;
+
+    /// The length of the longest principal in the element.
+    fn max_len(&self) -> usize;
+}
+
+#[derive(Clone, Copy)]
+struct PrincipalSeed<'a>(&'a Limits);
+
+impl<'de> DeserializeSeed<'de> for PrincipalSeed<'_> {
+    type Value = Principal;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Principal, D::Error> {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl<'de> Visitor<'de> for PrincipalSeed<'_> {
+    type Value = Principal;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a principal")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Principal, E> {
+        if v.len() > self.0.max_principal_len {
+            return Err(E::custom(LimitError::PrincipalTooLong(self.0.max_principal_len)));
+        }
+        Ok(v.into())
+    }
+}
+
+impl Element for Principal {
+    fn deserialize<'de, D: Deserializer<'de>>(deserializer: D, limits: &Limits) -> Result<Self, D::Error> {
+        PrincipalSeed(limits).deserialize(deserializer)
+    }
+
+    fn max_len(&self) -> usize {
+        self.len()
+    }
+}
+
+struct PathVisitor<'a>(&'a Limits);
+
+impl<'de> Visitor<'de> for PathVisitor<'_> {
+    type Value = Vec<Principal>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a principal path")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<Principal>, A::Error> {
+        let mut path = Vec::new();
+        while let Some(principal) = seq.next_element_seed(PrincipalSeed(self.0))? {
+            if path.len() == self.0.max_path_depth {
+                return Err(de::Error::custom(LimitError::PathTooDeep(self.0.max_path_depth)));
+            }
+            path.push(principal);
+        }
+        Ok(path)
+    }
+}
+
+impl Element for Vec<Principal> {
+    fn deserialize<'de, D: Deserializer<'de>>(deserializer: D, limits: &Limits) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(PathVisitor(limits))
+    }
+
+    fn max_len(&self) -> usize {
+        self.iter().map(Principal::len).max().unwrap_or(0)
+    }
+}
+
+/// A component's clauses, or `None` for `DCFalse`.
+type Clauses<E> = Option<BTreeSet<BTreeSet<E>>>;
+
+/// The principals read so far, shared by both components.
+struct Budget {
+    limits: Limits,
+    principals: usize,
+}
+
+struct ElementSeed<'a, E>(&'a Limits, PhantomData<E>);
+
+impl<'de, E: Element> DeserializeSeed<'de> for ElementSeed<'_, E> {
+    type Value = E;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<E, D::Error> {
+        E::deserialize(deserializer, self.0)
+    }
+}
+
+/// Reads a `Clause`, a newtype around a set of elements.
+struct ClauseSeed<'a, E>(&'a mut Budget, PhantomData<E>);
+
+impl<'de, E: Element> DeserializeSeed<'de> for ClauseSeed<'_, E> {
+    type Value = BTreeSet<E>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<BTreeSet<E>, D::Error> {
+        deserializer.deserialize_newtype_struct("Clause", self)
+    }
+}
+
+impl<'de, E: Element> Visitor<'de> for ClauseSeed<'_, E> {
+    type Value = BTreeSet<E>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a clause")
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(self, deserializer: D) -> Result<BTreeSet<E>, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<BTreeSet<E>, A::Error> {
+        let mut clause = BTreeSet::new();
+        while let Some(element) = seq.next_element_seed(ElementSeed(&self.0.limits, PhantomData))? {
+            self.0.principals += 1;
+            if self.0.principals > self.0.limits.max_principals {
+                return Err(de::Error::custom(LimitError::TooManyPrincipals(self.0.limits.max_principals)));
+            }
+            clause.insert(element);
+        }
+        Ok(clause)
+    }
+}
+
+struct ClausesSeed<'a, E>(&'a mut Budget, PhantomData<E>);
+
+impl<'de, E: Element> DeserializeSeed<'de> for ClausesSeed<'_, E> {
+    type Value = BTreeSet<BTreeSet<E>>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, E: Element> Visitor<'de> for ClausesSeed<'_, E> {
+    type Value = BTreeSet<BTreeSet<E>>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a set of clauses")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut clauses = BTreeSet::new();
+        while let Some(clause) = seq.next_element_seed(ClauseSeed(&mut *self.0, PhantomData))? {
+            if clauses.len() == self.0.limits.max_clauses {
+                return Err(de::Error::custom(LimitError::TooManyClauses(self.0.limits.max_clauses)));
+            }
+            clauses.insert(clause);
+        }
+        Ok(clauses)
+    }
+}
+
+/// The variants of `Component`, as identified by its derived impls.
+enum Variant {
+    DCFalse,
+    DCFormula,
+}
+
+const VARIANTS: &[&str] = &["DCFalse", "DCFormula"];
+
+impl<'de> serde::Deserialize<'de> for Variant {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct VariantVisitor;
+
+        impl<'de> Visitor<'de> for VariantVisitor {
+            type Value = Variant;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a component variant")
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Variant, E> {
+                match v {
+                    0 => Ok(Variant::DCFalse),
+                    1 => Ok(Variant::DCFormula),
+                    _ => Err(E::invalid_value(de::Unexpected::Unsigned(v), &self)),
+                }
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Variant, E> {
+                match v {
+                    "DCFalse" => Ok(Variant::DCFalse),
+                    "DCFormula" => Ok(Variant::DCFormula),
+                    _ => Err(E::unknown_variant(v, VARIANTS)),
+                }
+            }
+        }
+
+        deserializer.deserialize_identifier(VariantVisitor)
+    }
+}
+
+/// Reads a `Component`, yielding `None` for `DCFalse` and the clauses of a
+/// `DCFormula`.
+struct ComponentSeed<'a, E>(&'a mut Budget, PhantomData<E>);
+
+impl<'de, E: Element> DeserializeSeed<'de> for ComponentSeed<'_, E> {
+    type Value = Clauses<E>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_enum("Component", VARIANTS, self)
+    }
+}
+
+impl<'de, E: Element> Visitor<'de> for ComponentSeed<'_, E> {
+    type Value = Clauses<E>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a component")
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Self::Value, A::Error> {
+        match data.variant()? {
+            (Variant::DCFalse, variant) => variant.unit_variant().map(|_| None),
+            (Variant::DCFormula, variant) => variant.newtype_variant_seed(ClausesSeed(self.0, PhantomData)).map(Some),
+        }
+    }
+}
+
+/// Reads a label in either serde representation.
+struct LabelVisitor<'a, L, E> {
+    limits: &'a Limits,
+    parse: fn(&str) -> Option<L>,
+    check: fn(&L, &Limits) -> Result<(), LimitError>,
+    build: fn(Clauses<E>, Clauses<E>) -> L,
+}
+
+impl<'de, L, E: Element> Visitor<'de> for LabelVisitor<'_, L, E> {
+    type Value = L;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a label")
+    }
+
+    fn visit_str<Er: de::Error>(self, v: &str) -> Result<L, Er> {
+        if v.len() > self.limits.max_text_len {
+            return Err(Er::custom(LimitError::TextTooLong(self.limits.max_text_len)));
+        }
+        let label = (self.parse)(v).ok_or_else(|| Er::invalid_value(de::Unexpected::Str(v), &self))?;
+        (self.check)(&label, self.limits).map_err(Er::custom)?;
+        Ok(label)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<L, A::Error> {
+        let mut budget = Budget { limits: *self.limits, principals: 0 };
+        let secrecy = seq
+            .next_element_seed(ComponentSeed(&mut budget, PhantomData))?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let integrity = seq
+            .next_element_seed(ComponentSeed(&mut budget, PhantomData))?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        Ok((self.build)(secrecy, integrity))
+    }
+}
+
+/// Implements `BoundedLabel` for `$label`, whose clauses hold `$element`s,
+/// parsing the textual syntax with the nom parser `$parser`.
+macro_rules! impl_bounded {
+    ($label:ident, $component:ident, $clause:path, $element:ty, $parser:path) => {
+        impl BoundedLabel for $label {
+            fn deserialize_bounded<'de, D: Deserializer<'de>>(
+                deserializer: D,
+                limits: &Limits,
+            ) -> Result<Self, D::Error> {
+                use $component as Component;
+
+                fn component(clauses: Clauses<$element>) -> Component {
+                    match clauses {
+                        None => Component::DCFalse,
+                        Some(clauses) => Component::from(clauses.into_iter().map(<$clause>::from).collect::<BTreeSet<_>>()),
+                    }
+                }
+
+                fn check(label: &$label, limits: &Limits) -> Result<(), LimitError> {
+                    let stats = label.stats();
+                    if stats.clauses_secrecy.max(stats.clauses_integrity) > limits.max_clauses {
+                        return Err(LimitError::TooManyClauses(limits.max_clauses));
+                    }
+                    if stats.principals > limits.max_principals {
+                        return Err(LimitError::TooManyPrincipals(limits.max_principals));
+                    }
+                    if stats.max_path_depth > limits.max_path_depth {
+                        return Err(LimitError::PathTooDeep(limits.max_path_depth));
+                    }
+                    for component in [&label.secrecy, &label.integrity] {
+                        if let Component::DCFormula(clauses) = component {
+                            let elements = clauses.iter().flat_map(|c| c.0.iter());
+                            if elements.map(Element::max_len).any(|len| len > limits.max_principal_len) {
+                                return Err(LimitError::PrincipalTooLong(limits.max_principal_len));
+                            }
+                        }
+                    }
+                    Ok(())
+                }
+
+                let visitor = LabelVisitor {
+                    limits,
+                    parse: |input| nom::combinator::all_consuming($parser)(input).ok().map(|(_, label)| label),
+                    check,
+                    build: |secrecy, integrity| $label::new(component(secrecy), component(integrity)),
+                };
+                if deserializer.is_human_readable() {
+                    deserializer.deserialize_str(visitor)
+                } else {
+                    deserializer.deserialize_tuple(2, visitor)
+                }
+            }
+        }
+    };
+}
+
+#[cfg(feature = "dclabel")]
+mod dclabel {
+    use super::*;
+    use crate::dclabel::{Clause, Component, DCLabel};
+
+    impl_bounded!(DCLabel, Component, Clause, Principal, DCLabel::parse);
+}
+
+#[cfg(feature = "buckle")]
+mod buckle {
+    use super::*;
+    use crate::buckle::{Buckle, Clause, Component};
+
+    impl_bounded!(Buckle, Component, Clause, Vec<Principal>, Buckle::parser);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    fn limits() -> Limits {
+        Limits { max_clauses: 2, max_principals: 4, max_principal_len: 8, max_path_depth: 2, max_text_len: 32 }
+    }
+
+    #[cfg(feature = "buckle")]
+    #[test]
+    fn test_json() {
+        use crate::buckle::Buckle;
+
+        let json = |s: &str| {
+            let mut deserializer = serde_json::Deserializer::from_str(s);
+            limits().seed::<Buckle>().deserialize(&mut deserializer).map_err(|e| e.to_string())
+        };
+        assert_eq!(Ok(Buckle::parse("amit/grades|yue,T").unwrap()), json(r#""amit/grades|yue,T""#));
+        assert!(json(r#""a&b&c,T""#).unwrap_err().starts_with("label component has more than 2 clauses"));
+        assert!(json(r#""a|b&c|d,e""#).unwrap_err().starts_with("label has more than 4 principals"));
+        assert!(json(r#""a/b/c,T""#).unwrap_err().starts_with("principal path is longer than 2"));
+        assert!(json(r#""abcdefghi,T""#).unwrap_err().starts_with("principal is longer than 8 bytes"));
+        assert!(json(r#""a|b|c|d|e|f|g|h|i|j|k|l|m|n|o|p,T""#).unwrap_err().starts_with("label text is longer"));
+
+        let bounded: Bounded<Buckle> = serde_json::from_str(r#""amit,T""#).unwrap();
+        assert_eq!(Buckle::parse("amit,T").unwrap(), bounded.0);
+    }
+
+    #[cfg(feature = "buckle")]
+    #[test]
+    fn test_postcard() {
+        use crate::buckle::Buckle;
+
+        let postcard = |lbl: &str| {
+            let bytes = postcard::to_allocvec(&Buckle::parse(lbl).unwrap()).unwrap();
+            let mut deserializer = postcard::Deserializer::from_bytes(&bytes);
+            limits().seed::<Buckle>().deserialize(&mut deserializer)
+        };
+        assert_eq!(Ok(Buckle::parse("amit/grades|yue,T").unwrap()), postcard("amit/grades|yue,T"));
+        assert_eq!(Ok(Buckle::top()), postcard("F,T"));
+        assert!(postcard("a&b&c,T").is_err());
+        assert!(postcard("a|b&c|d,e").is_err());
+        assert!(postcard("a/b/c,T").is_err());
+        assert!(postcard("abcdefghi,T").is_err());
+    }
+
+    #[cfg(feature = "dclabel")]
+    quickcheck! {
+        fn dclabel_postcard_within_limits(lbl: crate::dclabel::DCLabel) -> bool {
+            let limits = Limits { max_principal_len: usize::MAX, ..Limits::default() };
+            let bytes = postcard::to_allocvec(&lbl).unwrap();
+            let mut deserializer = postcard::Deserializer::from_bytes(&bytes);
+            let bounded = limits.seed::<crate::dclabel::DCLabel>().deserialize(&mut deserializer);
+            let stats = lbl.stats();
+            let within = stats.clauses_secrecy.max(stats.clauses_integrity) <= limits.max_clauses
+                && stats.principals <= limits.max_principals;
+            bounded.ok() == if within { Some(lbl) } else { None }
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! A builder for labels assembled clause by clause.
+//!
+//! Code that builds labels from runtime data, e.g. a list of groups per
+//! clause, rather than from literals, would otherwise collect nested
+//! vectors to satisfy the `From` impls. `Buckle::builder` starts from the
+//! public label and conjoins one clause at a time.
+
+use super::{Buckle, Clause, Component};
+
+/// Builds a `Buckle` label; see `Buckle::builder`.
+#[derive(Debug, Clone)]
+pub struct BuckleBuilder {
+    secrecy: Component,
+    integrity: Component,
+}
+
+impl Default for BuckleBuilder {
+    fn default() -> Self {
+        BuckleBuilder { secrecy: Component::dc_true(), integrity: Component::dc_true() }
+    }
+}
+
+impl BuckleBuilder {
+    /// Conjoins a clause to the secrecy component, e.g. `["amit", "staff"]`
+    /// for `amit|staff`, or a `Clause` built from paths.
+    pub fn secrecy_clause<C: Into<Clause>>(mut self, clause: C) -> Self {
+        self.secrecy.insert_clause(clause.into());
+        self
+    }
+
+    /// Conjoins a clause to the integrity component.
+    pub fn integrity_clause<C: Into<Clause>>(mut self, clause: C) -> Self {
+        self.integrity.insert_clause(clause.into());
+        self
+    }
+
+    /// Makes the secrecy component false, as in the top label. Later
+    /// secrecy clauses are ignored.
+    pub fn secrecy_false(mut self) -> Self {
+        self.secrecy = Component::dc_false();
+        self
+    }
+
+    /// Makes the integrity component false, as in the bottom label. Later
+    /// integrity clauses are ignored.
+    pub fn integrity_false(mut self) -> Self {
+        self.integrity = Component::dc_false();
+        self
+    }
+
+    /// Returns the label, reduced, since `insert_clause` keeps each
+    /// component reduced.
+    pub fn build(self) -> Buckle {
+        Buckle { secrecy: self.secrecy, integrity: self.integrity }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_builder() {
+        let label = Buckle::builder()
+            .secrecy_clause(["amit", "staff"])
+            .secrecy_clause(["bob"])
+            .integrity_clause(["grader"])
+            .build();
+        assert_eq!(Buckle::parse("amit|staff&bob,grader").unwrap(), label);
+
+        // Reduced as it's built, from runtime data.
+        let groups = vec![vec!["amit", "staff"], vec!["amit"]];
+        let label = groups.into_iter().fold(Buckle::builder(), BuckleBuilder::secrecy_clause).build();
+        assert!(label.is_reduced());
+        assert_eq!(Buckle::parse("amit,T").unwrap(), label);
+
+        let path = Clause::new_from_vec(vec![vec!["amit", "grades"]]);
+        assert_eq!(Buckle::parse("amit/grades,T").unwrap(), Buckle::builder().secrecy_clause(path).build());
+        assert_eq!(Buckle::top(), Buckle::builder().secrecy_false().secrecy_clause(["amit"]).build());
+        assert_eq!(Buckle::public(), Buckle::builder().build());
+    }
+}
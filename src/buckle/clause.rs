@@ -1,25 +1,53 @@
-#[cfg(test)]
+#[cfg(any(test, feature = "quickcheck"))]
 use alloc::boxed::Box;
-#[cfg(test)]
+#[cfg(any(test, feature = "quickcheck"))]
+use alloc::string::String;
+#[cfg(any(test, feature = "quickcheck"))]
+use crate::generate::{GenConfig, Generate};
+#[cfg(any(test, feature = "quickcheck"))]
 use quickcheck::Arbitrary;
 
 use serde::{Deserialize, Serialize};
 
 use super::Principal;
+use crate::subsume::Subsumes;
+use crate::{fingerprint, ClauseStats, MalformedClause};
 use alloc::vec;
 use alloc::{collections::BTreeSet, vec::Vec};
 
+/// The principal paths of a clause. Under the `hashcons` feature they are
+/// shared between clones of a clause and copied on write.
+#[cfg(feature = "hashcons")]
+pub type Paths = alloc::sync::Arc<BTreeSet<Vec<Principal>>>;
+#[cfg(not(feature = "hashcons"))]
+pub type Paths = BTreeSet<Vec<Principal>>;
+
 #[derive(Eq, PartialEq, PartialOrd, Ord, Debug, Clone, Serialize, Deserialize)]
-pub struct Clause(pub BTreeSet<Vec<Principal>>);
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Clause(pub(crate) Paths);
 
-#[cfg(test)]
+#[cfg(any(test, feature = "quickcheck"))]
+impl Generate for Clause {
+    fn generate(g: &mut quickcheck::Gen, config: &GenConfig) -> Self {
+        let len = config.len(g, config.max_principals);
+        let paths = (0..len).map(|_| {
+            let depth = config.len(g, config.max_depth);
+            (0..depth).map(|_| config.principal(g)).collect()
+        });
+        Clause::new_from_vec(paths.collect())
+    }
+}
+
+#[cfg(any(test, feature = "quickcheck"))]
 impl Arbitrary for Clause {
     fn arbitrary(g: &mut quickcheck::Gen) -> Self {
-        Clause(BTreeSet::arbitrary(g))
+        GenConfig::default().generate(g)
     }
 
     fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
-        Box::new(self.0.shrink().map(|x| Clause(x)))
+        let paths: BTreeSet<Vec<String>> =
+            self.0.iter().map(|path| path.iter().map(|p| p.as_str().into()).collect()).collect();
+        Box::new(paths.shrink().map(|x| Clause::new_from_vec(x.into_iter().collect())))
     }
 }
 
@@ -33,7 +61,7 @@ impl Clause {
         for p in principals.iter() {
             result.insert(vec![p.clone().into()]);
         }
-        Self(result)
+        Self::from(result)
     }
 
     pub fn new_from_vec<P: Into<Principal> + Clone>(principals: Vec<Vec<P>>) -> Clause {
@@ -41,10 +69,57 @@ impl Clause {
         for p in principals.iter() {
             result.insert(p.clone().drain(..).map(Into::into).collect());
         }
-        Self(result)
+        Self::from(result)
+    }
+
+    /// Returns a clause of the given paths, e.g. from slices built by the
+    /// `buckle!` macro.
+    pub fn from_paths<P: Into<Principal> + Clone>(paths: &[&[P]]) -> Clause {
+        Self::from(paths.iter().map(|path| path.iter().cloned().map(Into::into).collect()).collect::<BTreeSet<_>>())
+    }
+
+    /// Checks that no path, or principal in one, is empty.
+    pub fn check(&self) -> Result<(), MalformedClause> {
+        for path in self.0.iter() {
+            if path.is_empty() {
+                return Err(MalformedClause::EmptyPath);
+            } else if path.iter().any(|p| p.is_empty()) {
+                return Err(MalformedClause::EmptyPrincipal);
+            }
+        }
+        Ok(())
+    }
+
+    /// Iterates over the paths, in order.
+    pub fn paths(&self) -> impl ExactSizeIterator<Item = &[Principal]> + DoubleEndedIterator + '_ {
+        self.0.iter().map(Vec::as_slice)
+    }
+
+    /// Returns the number of paths.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns whether the clause has no paths, i.e. is false.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the paths for mutation, first copying them if they are
+    /// shared.
+    pub(crate) fn paths_mut(&mut self) -> &mut BTreeSet<Vec<Principal>> {
+        #[cfg(feature = "hashcons")]
+        let paths = alloc::sync::Arc::make_mut(&mut self.0);
+        #[cfg(not(feature = "hashcons"))]
+        let paths = &mut self.0;
+        paths
     }
 
     pub fn implies(&self, other: &Self) -> bool {
+        #[cfg(feature = "hashcons")]
+        if alloc::sync::Arc::ptr_eq(&self.0, &other.0) {
+            return true;
+        }
         // self is subset of other
         if self.0.is_empty() {
             true
@@ -62,6 +137,52 @@ impl Clause {
             //    }))
         }
     }
+
+    /// The fingerprint of its paths' leading principals (see the `fingerprint` module).
+    pub(crate) fn fingerprint(&self) -> u64 {
+        self.0.iter().filter_map(|path| path.first()).fold(0, |f, p| f | fingerprint::bit(p.as_bytes()))
+    }
+}
+
+impl ClauseStats for Clause {
+    const PATH_SIZE: usize = core::mem::size_of::<Vec<Principal>>();
+    const PRINCIPAL_SIZE: usize = core::mem::size_of::<Principal>();
+
+    fn paths(&self) -> impl Iterator<Item = impl Iterator<Item = usize> + '_> {
+        self.0.iter().map(|path| path.iter().map(|p| p.len()))
+    }
+}
+
+impl crate::heap::Heap for Clause {
+    fn heap_size(&self) -> usize {
+        self.0.heap_size()
+    }
+
+    fn compact(&mut self) {
+        self.0.compact()
+    }
+}
+
+/// A clause only implies clauses with a path starting with the first
+/// principal of its least path.
+impl Subsumes for Clause {
+    type Key = Principal;
+
+    fn key(&self) -> Option<&Principal> {
+        self.0.first().and_then(|path| path.first())
+    }
+
+    fn keys(&self) -> impl Iterator<Item = &Principal> {
+        self.0.iter().filter_map(|path| path.first())
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn implies(&self, other: &Self) -> bool {
+        Clause::implies(self, other)
+    }
 }
 
 impl<P: Into<Principal> + Clone, const N: usize> From<[P; N]> for Clause {
@@ -78,6 +199,8 @@ impl<P: Into<Principal> + Clone> From<Vec<P>> for Clause {
 
 impl From<BTreeSet<Vec<Principal>>> for Clause {
     fn from(principals: BTreeSet<Vec<Principal>>) -> Clause {
+        #[cfg(feature = "hashcons")]
+        let principals = alloc::sync::Arc::new(principals);
         Clause(principals)
     }
 }
@@ -116,6 +239,16 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_accessors() {
+        let clause = Clause::from_paths(&[&["amit", "grades"][..], &["yue"][..]]);
+        let paths: alloc::vec::Vec<_> = clause.paths().collect();
+        assert_eq!(alloc::vec![&["amit", "grades"][..], &["yue"][..]], paths);
+        assert_eq!(2, clause.len());
+        assert!(!clause.is_empty());
+        assert!(Clause::empty().is_empty());
+    }
+
     quickcheck! {
         fn empty_clause_implies_all(clause: Clause) -> bool {
             let empty = Clause::empty();
@@ -124,7 +257,7 @@ mod tests {
 
         fn subset_implies_superset(clause1: Clause, clause2: Clause) -> bool {
             let mut clause1 = clause1.clone();
-            clause1.0.append(&mut clause2.0.clone());
+            clause1.paths_mut().extend(clause2.0.iter().cloned());
             clause2.implies(&clause1)
         }
     }
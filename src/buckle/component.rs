@@ -1,32 +1,88 @@
-#[cfg(test)]
+#[cfg(any(test, feature = "quickcheck"))]
 use alloc::boxed::Box;
-#[cfg(test)]
+#[cfg(any(test, feature = "quickcheck"))]
+use crate::generate::{GenConfig, Generate};
+#[cfg(any(test, feature = "quickcheck"))]
 use quickcheck::{empty_shrinker, Arbitrary};
 use serde::{Deserialize, Serialize};
 
 use super::clause::Clause;
+use crate::heap::Heap;
+use crate::proof::{self, ImplicationProof};
+use crate::subsume::{self, Entry};
+use crate::{fingerprint, MalformedClause};
 use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+
+/// The clauses of a formula. Under the `arc` feature they are shared between
+/// clones of a component and copied on write.
+#[cfg(feature = "arc")]
+pub type Clauses = crate::shared::Shared<BTreeSet<Clause>>;
+#[cfg(not(feature = "arc"))]
+pub type Clauses = BTreeSet<Clause>;
+
+#[cfg(feature = "arc")]
+const NO_CLAUSES: Clauses = crate::shared::Shared::new_const(BTreeSet::new());
+#[cfg(not(feature = "arc"))]
+const NO_CLAUSES: Clauses = BTreeSet::new();
 
 #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum Component {
     DCFalse,
-    DCFormula(BTreeSet<Clause>),
+    DCFormula(Clauses),
 }
 
-#[cfg(test)]
-impl Arbitrary for Component {
-    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
-        if !bool::arbitrary(g) {
+pub(crate) fn make_mut(clauses: &mut Clauses) -> &mut BTreeSet<Clause> {
+    #[cfg(feature = "arc")]
+    let clauses = crate::shared::Shared::make_mut(clauses);
+    clauses
+}
+
+/// The principals or paths of each clause, as `proof` identifies them.
+fn elements<'a>(clauses: impl Iterator<Item = &'a Clause>) -> Vec<Vec<&'a Vec<super::Principal>>> {
+    clauses.map(|c| c.0.iter().collect()).collect()
+}
+
+/// Returns whether a clause of `sources`, paired with its fingerprint,
+/// implies `target`.
+fn implied(sources: &[(u64, &Clause)], target: &Clause) -> bool {
+    let fingerprint = target.fingerprint();
+    sources.iter().any(|(f, source)| fingerprint::may_imply(*f, fingerprint) && source.implies(target))
+}
+
+/// Inserts a clause no clause of `clauses` implies, dropping the clauses it
+/// implies.
+fn insert_reduced(clauses: &mut BTreeSet<Clause>, clause: Clause) {
+    clauses.retain(|c| !clause.implies(c));
+    clauses.insert(clause);
+}
+
+#[cfg(any(test, feature = "quickcheck"))]
+impl Generate for Component {
+    fn generate(g: &mut quickcheck::Gen, config: &GenConfig) -> Self {
+        // Clauses nest three collections deep, so quickcheck's default size
+        // yields components far too large to test in reasonable time.
+        let g = &mut quickcheck::Gen::new(g.size().min(10));
+        if config.is_false(g) {
             Component::DCFalse
         } else {
-            Component::DCFormula(BTreeSet::arbitrary(g))
+            let len = config.len(g, config.max_clauses);
+            Component::from((0..len).map(|_| Clause::generate(g, config)).collect::<BTreeSet<_>>())
         }
     }
+}
+
+#[cfg(any(test, feature = "quickcheck"))]
+impl Arbitrary for Component {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        GenConfig::default().generate(g)
+    }
 
     fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
         match self {
             Component::DCFalse => empty_shrinker(),
-            Component::DCFormula(clauses) => Box::new(clauses.shrink().map(Component::DCFormula)),
+            Component::DCFormula(clauses) => Box::new(BTreeSet::shrink(clauses).map(Component::from)),
         }
     }
 }
@@ -37,15 +93,56 @@ impl Component {
         for c in clauses.iter() {
             result.insert(c.clone().into());
         }
-        Component::DCFormula(result)
+        Component::from(result)
+    }
+
+    /// Returns the reduced formula of `clauses`, or why one is malformed.
+    /// Unlike `from`, which trusts its input, this suits clauses built from
+    /// untrusted data.
+    pub fn new_checked<I: IntoIterator<Item = Clause>>(clauses: I) -> Result<Component, MalformedClause> {
+        let mut result = BTreeSet::new();
+        for clause in clauses {
+            clause.check()?;
+            result.insert(clause);
+        }
+        let mut component = Component::from(result);
+        component.reduce();
+        Ok(component)
     }
 
-    pub fn dc_false() -> Self {
+    pub const fn dc_false() -> Self {
         Component::DCFalse
     }
 
-    pub fn dc_true() -> Self {
-        Component::DCFormula(BTreeSet::new())
+    /// The empty formula.
+    pub const fn dc_true() -> Self {
+        Component::DCFormula(NO_CLAUSES)
+    }
+
+    /// Returns the clauses of a formula, or `None` for `DCFalse`, copying
+    /// them if they are shared.
+    pub fn into_clauses(self) -> Option<BTreeSet<Clause>> {
+        match self {
+            Component::DCFalse => None,
+            #[cfg(feature = "arc")]
+            Component::DCFormula(clauses) => Some(crate::shared::Shared::unwrap_or_clone(clauses)),
+            #[cfg(not(feature = "arc"))]
+            Component::DCFormula(clauses) => Some(clauses),
+        }
+    }
+
+    /// Conjoins `clause`, keeping a reduced component reduced: the clause
+    /// is skipped if already implied, and the clauses it implies are
+    /// dropped. Returns whether the clause was inserted.
+    pub fn insert_clause(&mut self, clause: Clause) -> bool {
+        match self {
+            Component::DCFalse => false,
+            Component::DCFormula(clauses) if clauses.iter().any(|c| c.implies(&clause)) => false,
+            Component::DCFormula(clauses) => {
+                insert_reduced(make_mut(clauses), clause);
+                true
+            }
+        }
     }
 
     pub fn is_false(&self) -> bool {
@@ -62,37 +159,167 @@ impl Component {
         }
     }
 
+    /// Returns whether no clause implies another, as `reduce` leaves a
+    /// formula. `==` only decides equivalence between reduced components.
+    pub fn is_reduced(&self) -> bool {
+        match self {
+            Component::DCFalse => true,
+            Component::DCFormula(clauses) => {
+                let sources: Vec<(u64, &Clause)> = clauses.iter().map(|c| (c.fingerprint(), c)).collect();
+                clauses.iter().all(|target| {
+                    let fingerprint = target.fingerprint();
+                    sources.iter().all(|(f, source)| {
+                        *source == target || !fingerprint::may_imply(*f, fingerprint) || !source.implies(target)
+                    })
+                })
+            }
+        }
+    }
+
     pub fn implies(&self, other: &Self) -> bool {
         match (self, other) {
             (Component::DCFalse, _) => true,
             (_, Component::DCFalse) => false,
             (_, o) if o.is_true() => true,
             (s, _) if s.is_true() => false,
+            // The empty clause sorts first and implies every clause.
+            (Component::DCFormula(s), _) if s.first().is_some_and(|c| c.0.is_empty()) => true,
             (Component::DCFormula(s), Component::DCFormula(o)) => {
-                // for all clauses in other there must be at least one in self that implies it
-                o.iter()
-                    .all(|oclause| s.iter().any(|sclause| sclause.implies(oclause)))
+                // for all clauses in other there must be at least one in self that implies it.
+                // Clauses with fewer paths are implied by fewer clauses, so
+                // checking them first finds an unimplied clause sooner.
+                let mut targets = Vec::new();
+                targets.extend(o.iter());
+                targets.sort_by_key(|c| c.0.len());
+                let mut sources = Vec::new();
+                sources.extend(s.iter().map(|c| (c.fingerprint(), c)));
+                #[cfg(feature = "rayon")]
+                if crate::parallel::is_large(s.len(), o.len()) {
+                    return crate::parallel::all(&targets, |oclause| implied(&sources, oclause));
+                }
+                targets.iter().all(|oclause| implied(&sources, oclause))
+            }
+        }
+    }
+
+    /// Like `(self & assumed).implies(other)`, but evaluated in place without
+    /// cloning either component: every clause of `other` must be implied by
+    /// a clause of `self` or of `assumed`.
+    pub fn implies_with(&self, assumed: &Self, other: &Self) -> bool {
+        match (self, assumed, other) {
+            (Component::DCFalse, _, _) | (_, Component::DCFalse, _) => true,
+            (_, _, Component::DCFalse) => false,
+            (Component::DCFormula(s), Component::DCFormula(a), Component::DCFormula(o)) => {
+                let sources: Vec<(u64, &Clause)> = s.iter().chain(a.iter()).map(|c| (c.fingerprint(), c)).collect();
+                o.iter().all(|oclause| implied(&sources, oclause))
+            }
+        }
+    }
+
+    /// Like `implies`, but returns why `self` implies `other`, as clauses of
+    /// `self` implying each of `other`'s (see the `proof` module), or `None`
+    /// if it doesn't.
+    pub fn implies_with_proof(&self, other: &Self) -> Option<ImplicationProof> {
+        match (self, other) {
+            (Component::DCFalse, _) => Some(ImplicationProof::False),
+            (_, Component::DCFalse) => None,
+            (Component::DCFormula(s), Component::DCFormula(o)) => proof::prove(&elements(s.iter()), &elements(o.iter()), |s, t| t.starts_with(s)),
+        }
+    }
+
+    /// Returns a clause of `other` that `self` doesn't imply, as a component
+    /// of that clause alone, or `DCFalse` if `other` is false and `self`
+    /// isn't, or `None` if `self` implies `other`.
+    pub fn unimplied_clause(&self, other: &Self) -> Option<Self> {
+        match (self, other) {
+            (Component::DCFalse, _) => None,
+            (_, Component::DCFalse) => Some(Component::DCFalse),
+            (Component::DCFormula(s), Component::DCFormula(o)) => o.iter().find(|oc| !s.iter().any(|sc| sc.implies(oc))).map(|c| Component::from([c.clone()])),
+        }
+    }
+
+    /// Returns whether `proof` shows that `self` implies `other`.
+    pub fn check_proof(&self, other: &Self, proof: &ImplicationProof) -> bool {
+        match (self, other, proof) {
+            (Component::DCFalse, _, ImplicationProof::False) => true,
+            (Component::DCFormula(s), Component::DCFormula(o), ImplicationProof::Clauses(steps)) => {
+                proof::check(&elements(s.iter()), &elements(o.iter()), steps, |s, t| t.starts_with(s))
             }
+            _ => false,
+        }
+    }
+
+    /// Returns the clauses of a formula, or `None` for `DCFalse`.
+    pub(crate) fn clauses(&self) -> Option<impl Iterator<Item = &Clause>> {
+        match self {
+            Component::DCFalse => None,
+            Component::DCFormula(clauses) => Some(clauses.iter()),
+        }
+    }
+
+    /// Returns the approximate number of bytes the component owns on the
+    /// heap (see the `heap` module).
+    pub fn approx_heap_size(&self) -> usize {
+        match self {
+            Component::DCFalse => 0,
+            Component::DCFormula(clauses) => clauses.heap_size(),
+        }
+    }
+
+    /// Releases excess capacity, e.g. left by a burst of `lub`s.
+    pub fn shrink_to_fit(&mut self) {
+        if let Component::DCFormula(clauses) = self {
+            clauses.compact()
         }
     }
 
     pub fn reduce(&mut self) {
-        let mut rmlist = BTreeSet::new();
         match self {
             Component::DCFalse => {}
+            #[cfg(feature = "rayon")]
+            Component::DCFormula(clauses) if crate::parallel::is_large(clauses.len(), clauses.len()) => {
+                let removed = crate::parallel::redundant(clauses);
+                if removed.contains(&true) {
+                    // `retain` visits the clauses in ascending order.
+                    let mut removed = removed.into_iter();
+                    make_mut(clauses).retain(|_| !removed.next().unwrap_or(false));
+                }
+            }
+            Component::DCFormula(_) => {
+                self.reduce_with_budget(usize::MAX);
+            }
+        }
+    }
+
+    /// Reduces the component as `reduce` does, but gives up after checking
+    /// `max_comparisons` pairs of clauses for implication, bounding the work
+    /// on adversarially large components. Returns whether it finished; if
+    /// not, the component is still equivalent, with the redundant clauses
+    /// found so far removed.
+    pub fn reduce_with_budget(&mut self, max_comparisons: usize) -> bool {
+        let mut budget = max_comparisons;
+        self.reduce_within(&mut budget)
+    }
+
+    /// Reduces the component within `budget` comparisons, deducting those
+    /// made, and returns whether it finished.
+    pub(crate) fn reduce_within(&mut self, budget: &mut usize) -> bool {
+        match self {
+            Component::DCFalse => true,
             Component::DCFormula(clauses) => {
-                for (i, clausef) in clauses.iter().enumerate() {
-                    for clauser in clauses.iter().skip(i + 1) {
-                        if clausef.implies(clauser) {
-                            rmlist.insert(clauser.clone());
-                        } else if clauser.implies(clausef) {
-                            rmlist.insert(clausef.clone());
-                        }
+                let mut keep = alloc::vec![true; clauses.len()];
+                let mut index: Vec<_> = clauses.iter().enumerate().map(|(i, c)| Entry::new(i, c)).collect();
+                let finished = subsume::redundant(&mut index, clauses.iter(), budget, |i| {
+                    if let Some(keep) = keep.get_mut(i) {
+                        *keep = false;
                     }
+                });
+                if keep.contains(&false) {
+                    // `retain` visits the clauses in ascending order.
+                    let mut keep = keep.into_iter();
+                    make_mut(clauses).retain(|_| keep.next().unwrap_or(true));
                 }
-                for rmclause in rmlist.iter() {
-                    clauses.remove(rmclause);
-                }
+                finished
             }
         }
     }
@@ -114,21 +341,106 @@ impl From<bool> for Component {
     }
 }
 
+/// Parses a component alone, e.g. a privilege, in the syntax of either side
+/// of `Buckle::parse`'s comma, and reduces it. Unlike `parse`, this rejects
+/// trailing input.
+impl core::convert::TryFrom<&str> for Component {
+    type Error = crate::Error;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        match super::component(input) {
+            Ok(("", mut component)) => {
+                component.reduce();
+                Ok(component)
+            }
+            Ok((rest, _)) => Err(crate::Error::Parse(input.len() - rest.len())),
+            Err(e) => Err(crate::error::from_nom(input, e)),
+        }
+    }
+}
+
+impl core::str::FromStr for Component {
+    type Err = crate::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        core::convert::TryFrom::try_from(input)
+    }
+}
+
 impl From<BTreeSet<Clause>> for Component {
     fn from(clauses: BTreeSet<Clause>) -> Component {
+        #[cfg(feature = "arc")]
+        let clauses = crate::shared::Shared::new(clauses);
         Component::DCFormula(clauses)
     }
 }
 
+/// Iterates over the clauses of a formula. `DCFalse` yields the empty
+/// clause, so collecting its clauses gives `{∅}`, which is false but not
+/// `DCFalse`.
+impl IntoIterator for Component {
+    type Item = Clause;
+    type IntoIter = alloc::collections::btree_set::IntoIter<Clause>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_clauses().unwrap_or_else(|| BTreeSet::from([Clause::empty()])).into_iter()
+    }
+}
+
+/// Conjoins the clauses as `insert_clause` does, keeping a reduced
+/// component reduced.
+impl Extend<Clause> for Component {
+    fn extend<I: IntoIterator<Item = Clause>>(&mut self, clauses: I) {
+        for clause in clauses {
+            self.insert_clause(clause);
+        }
+    }
+}
+
+/// Collects the clauses into a reduced component, as `extend` conjoins them
+/// with `T`.
+impl core::iter::FromIterator<Clause> for Component {
+    fn from_iter<I: IntoIterator<Item = Clause>>(clauses: I) -> Component {
+        let mut component = Component::dc_true();
+        component.extend(clauses);
+        component
+    }
+}
+
+/// Formats the component in the canonical text syntax of `crate::text`: `T`,
+/// `F`, or `&`-separated clauses of `|`-separated principals, whose paths are
+/// `/`-separated. Empty paths and principals, which have no textual form, are
+/// written as nothing.
+impl core::fmt::Display for Component {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        crate::text::buckle::write(f, self, true)
+    }
+}
+
+/// Conjoins the clauses of both sides in order as `insert_clause` does, so
+/// the conjunction is reduced without a separate `reduce`. Like
+/// `insert_clause`, an empty clause leaves the formula of just the empty
+/// clause, which is false but not `DCFalse`: making it `DCFalse` would break
+/// `lub`'s leastness, as only `DCFalse` implies `DCFalse`.
 impl core::ops::BitAnd for Component {
     type Output = Component;
     fn bitand(self, rhs: Self) -> Component {
         match (self, rhs) {
             (Component::DCFalse, _) => Component::DCFalse,
             (_, Component::DCFalse) => Component::DCFalse,
-            (Component::DCFormula(mut s), Component::DCFormula(mut o)) => {
-                s.append(&mut o);
-                Component::DCFormula(s)
+            // Keeps shared clauses shared when conjoining with true.
+            (s, o) if o.is_true() => s,
+            (s, o) if s.is_true() => o,
+            (s, o) => {
+                let mut all = s.into_clauses().unwrap_or_default();
+                all.append(&mut o.into_clauses().unwrap_or_default());
+                let mut clauses = BTreeSet::new();
+                for clause in all {
+                    if !clauses.iter().any(|c: &Clause| c.implies(&clause)) {
+                        insert_reduced(&mut clauses, clause);
+                    }
+                }
+                Component::from(clauses)
             }
         }
     }
@@ -143,24 +455,92 @@ impl core::ops::BitOr for Component {
             (Component::DCFormula(s), Component::DCFormula(o)) if s.is_empty() || o.is_empty() => {
                 Component::dc_true()
             }
+            (s, o) => &s | &o,
+        }
+    }
+}
+
+/// Conjoins as the owned `&` does, cloning only the clauses the result
+/// keeps.
+impl core::ops::BitAnd for &Component {
+    type Output = Component;
+    fn bitand(self, rhs: Self) -> Component {
+        match (self, rhs) {
+            (Component::DCFalse, _) => Component::DCFalse,
+            (_, Component::DCFalse) => Component::DCFalse,
+            (s, o) if o.is_true() => s.clone(),
+            (s, o) if s.is_true() => o.clone(),
             (Component::DCFormula(s), Component::DCFormula(o)) => {
+                let mut clauses: Vec<&Clause> = Vec::new();
+                for clause in s.union(o) {
+                    if !clauses.iter().any(|c| c.implies(clause)) {
+                        clauses.retain(|c| !clause.implies(c));
+                        clauses.push(clause);
+                    }
+                }
+                Component::from(clauses.into_iter().cloned().collect::<BTreeSet<_>>())
+            }
+        }
+    }
+}
+
+impl core::ops::BitOr for &Component {
+    type Output = Component;
+    fn bitor(self, rhs: Self) -> Component {
+        match (self, rhs) {
+            (s, Component::DCFalse) => s.clone(),
+            (Component::DCFalse, o) => o.clone(),
+            (Component::DCFormula(s), Component::DCFormula(o)) if s.is_empty() || o.is_empty() => {
+                Component::dc_true()
+            }
+            (Component::DCFormula(s), Component::DCFormula(o)) => {
+                // Distributes the disjunction over both conjunctions.
                 let mut result = BTreeSet::new();
-                for mut clauses in s.iter().cloned() {
-                    for mut clauseo in o.iter().cloned() {
-                        clauses.0.append(&mut clauseo.0);
+                for sclause in s.iter() {
+                    for oclause in o.iter() {
+                        let mut clause = sclause.clone();
+                        clause.paths_mut().extend(oclause.0.iter().cloned());
+                        result.insert(clause);
                     }
-                    result.insert(clauses);
                 }
-                Component::DCFormula(result)
+                Component::from(result)
             }
         }
     }
 }
 
+/// Conjoins `rhs` in place, with the same result as `&`, whose clauses it
+/// moves rather than clones.
+impl core::ops::BitAndAssign for Component {
+    fn bitand_assign(&mut self, rhs: Self) {
+        *self = core::mem::replace(self, Component::DCFalse) & rhs;
+    }
+}
+
+/// Disjoins `rhs` in place, with the same result as `|`.
+impl core::ops::BitOrAssign for Component {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = core::mem::replace(self, Component::DCFalse) | rhs;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_try_from_str() {
+        use core::convert::TryFrom;
+
+        let privilege = Component::try_from("amit/grader|staff&amit").unwrap();
+        assert_eq!(Component::try_from("amit"), Ok(privilege.clone()));
+        assert_eq!(Ok(privilege), "amit/grader|staff&amit".parse());
+        assert_eq!(Ok(Component::dc_false()), Component::try_from("F"));
+        assert_eq!(Ok(Component::dc_true()), Component::try_from("T"));
+        assert_eq!(Err(crate::Error::Parse(4)), Component::try_from("amit,T"));
+        assert_eq!(Err(crate::Error::Parse(0)), Component::try_from(""));
+    }
+
     #[test]
     fn test_x_implies_x() {
         assert!(Component::from(false).implies(&Component::from(false)));
@@ -168,6 +548,26 @@ mod tests {
         assert!(Component::from([["Amit"]]).implies(&Component::from([["Amit"]])));
     }
 
+    #[cfg(feature = "arc")]
+    #[test]
+    fn test_copy_on_write() {
+        let clauses = |c: &Component| match c {
+            Component::DCFormula(clauses) => clauses.clone(),
+            Component::DCFalse => unreachable!(),
+        };
+        let component = Component::from([["Amit"], ["Yue"]]);
+        let mut copy = component.clone();
+        assert!(crate::shared::Shared::ptr_eq(&clauses(&component), &clauses(&copy)));
+        let conjoined = component.clone() & Component::dc_true();
+        assert!(crate::shared::Shared::ptr_eq(&clauses(&component), &clauses(&conjoined)));
+
+        if let Component::DCFormula(clauses) = &mut copy {
+            make_mut(clauses).clear();
+        }
+        assert!(copy.is_true());
+        assert_eq!(Component::from([["Amit"], ["Yue"]]), component);
+    }
+
     #[test]
     fn test_true_not_implies_not_true() {
         assert_eq!(
@@ -181,6 +581,22 @@ mod tests {
         assert_eq!(false, Component::dc_true().implies(&Component::dc_false()));
     }
 
+    #[test]
+    fn test_new_checked() {
+        let a = Clause::new_from_vec(alloc::vec![alloc::vec!["a"]]);
+        let ab = Clause::new_from_vec(alloc::vec![alloc::vec!["a", "b"]]);
+        assert_eq!(Ok(Component::formula([a.clone()])), Component::new_checked([a.clone(), ab.clone()]));
+        assert!(!Component::formula([a, ab]).is_reduced());
+        assert_eq!(
+            Err(MalformedClause::EmptyPrincipal),
+            Component::new_checked([Clause::new_from_vec(alloc::vec![alloc::vec!["a", ""]])])
+        );
+        assert_eq!(
+            Err(MalformedClause::EmptyPath),
+            Component::new_checked([Clause::new_from_vec::<&str>(alloc::vec![alloc::vec![]])])
+        );
+    }
+
     #[test]
     fn test_false_implies_everything() {
         assert!(Component::dc_false().implies(&Component::dc_false()));
@@ -188,6 +604,27 @@ mod tests {
         assert!(Component::dc_false().implies(&Component::from([["Amit"]])));
     }
 
+    #[test]
+    fn test_implies_with_proof() {
+        use crate::buckle::Buckle;
+        use crate::proof::ClauseProof;
+
+        // amit & (yue | staff) implies (amit/grades | bob) & yue/x/y|staff.
+        let source = Buckle::parse("amit&staff|yue,T").unwrap().secrecy;
+        let target = Buckle::parse("amit/grades|bob&staff|yue/x/y,T").unwrap().secrecy;
+        let proof = source.implies_with_proof(&target).unwrap();
+        let expected = ImplicationProof::Clauses(alloc::vec![
+            ClauseProof { target: 0, source: 0, prefixes: alloc::vec![0] },
+            ClauseProof { target: 1, source: 1, prefixes: alloc::vec![0, 1] },
+        ]);
+        assert_eq!(expected, proof);
+        assert!(source.check_proof(&target, &proof));
+        assert!(!target.check_proof(&source, &proof));
+        assert_eq!(None, target.implies_with_proof(&source));
+        assert_eq!(Some(ImplicationProof::False), Component::dc_false().implies_with_proof(&target));
+        assert!(!source.check_proof(&target, &ImplicationProof::False));
+    }
+
     #[test]
     fn test_everything_implies_true() {
         assert!(Component::dc_false().implies(&Component::dc_true()));
@@ -227,12 +664,99 @@ mod tests {
         assert_eq!(true, component_sup.implies(&component_sub));
     }
 
+    #[test]
+    fn test_insert_clause() {
+        let mut component = Component::from([["Amit", "Yue"]]);
+        assert!(component.insert_clause(Clause::from(["Yue"])));
+        assert_eq!(Component::from([["Yue"]]), component);
+        assert!(!component.insert_clause(Clause::from(["David", "Yue"])));
+        assert_eq!(Component::from([["Yue"]]), component);
+        assert!(!Component::dc_false().insert_clause(Clause::from(["Yue"])));
+    }
+
+    #[test]
+    fn test_and_empty_clause() {
+        // Conjoins the empty clause as `insert_clause` does: it implies every
+        // clause, but is only false, not `DCFalse`.
+        let component = Component::from([["Amit", "Yue"]]);
+        let empty = Component::from([Clause::empty()]);
+        let mut inserted = component.clone();
+        assert!(inserted.insert_clause(Clause::empty()));
+        assert_eq!(empty, inserted);
+        assert_eq!(inserted, component.clone() & empty.clone());
+        assert_eq!(inserted, empty & component);
+    }
+
+    #[test]
+    fn test_iter() {
+        let component = Component::from([["Amit", "Yue"], ["David", "Yue"]]);
+        let clauses: alloc::vec::Vec<Clause> = component.clone().into_iter().collect();
+        assert_eq!(alloc::vec![Clause::from(["Amit", "Yue"]), Clause::from(["David", "Yue"])], clauses);
+        assert_eq!(component, clauses.into_iter().collect());
+
+        // Reduced as it's collected.
+        let reduced: Component = alloc::vec![Clause::from(["Amit", "Yue"]), Clause::from(["Yue"])].into_iter().collect();
+        assert_eq!(Component::from([["Yue"]]), reduced);
+
+        assert_eq!(0, Component::dc_true().into_iter().count());
+        assert_eq!(Component::dc_true(), core::iter::empty().collect());
+        assert_eq!(Component::from([Clause::empty()]), Component::dc_false().into_iter().collect());
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut component = Component::dc_true();
+        for clause in alloc::vec![Clause::from(["Amit", "Yue"]), Clause::from(["David"]), Clause::from(["Yue"])] {
+            component.extend(Some(clause));
+        }
+        assert!(component.is_reduced());
+        assert_eq!(Component::from([["David"], ["Yue"]]), component);
+
+        let expected = component.clone() & Component::from([Clause::empty()]);
+        component.extend(alloc::vec![Clause::empty(), Clause::from(["Amit"])]);
+        assert_eq!(expected, component);
+        assert_ne!(Component::dc_false(), component);
+
+        let mut component = Component::dc_false();
+        component.extend(Some(Clause::from(["Amit"])));
+        assert_eq!(Component::dc_false(), component);
+    }
+
+    #[test]
+    fn test_assign_ops() {
+        let mut component = Component::dc_true();
+        component &= Component::from([["Amit", "Yue"]]);
+        component &= Component::from([["Yue"]]);
+        assert_eq!(Component::from([["Yue"]]), component);
+        component |= Component::from([["Ziv"]]);
+        assert_eq!(Component::from([["Yue", "Ziv"]]), component);
+        component |= Component::dc_false();
+        assert_eq!(Component::from([["Yue", "Ziv"]]), component);
+        component &= Component::dc_false();
+        assert!(component.is_false());
+    }
+
     #[test]
     fn test_or() {
         assert_eq!(
             Component::from([["Amit", "Yue"], ["David", "Yue"]]),
             Component::from([["Amit"], ["David"]]) | Component::from([["Yue"]])
         );
+        assert_eq!(
+            Component::from([["Amit", "Yue"], ["Amit", "Ziv"], ["David", "Yue"], ["David", "Ziv"]]),
+            Component::from([["Amit"], ["David"]]) | Component::from([["Yue"], ["Ziv"]])
+        );
+    }
+
+    #[test]
+    fn test_ref_ops() {
+        let a = Component::from([Clause::from(["Amit", "Yue"]), Clause::from(["David"])]);
+        let b = Component::from([["Yue"], ["Ziv"]]);
+        for (s, o) in [(&a, &b), (&a, &Component::dc_true()), (&Component::dc_false(), &b), (&a, &a)].iter() {
+            assert_eq!((*s).clone() & (*o).clone(), *s & *o);
+            assert_eq!((*s).clone() | (*o).clone(), *s | *o);
+        }
+        assert_eq!(Component::from([["David"], ["Yue"], ["Ziv"]]), &a & &b);
     }
 
     quickcheck! {
@@ -241,6 +765,13 @@ mod tests {
             component.implies(&other) && other.implies(&component)
         }
 
+        fn budgeted_reduce_is_equivalent(component: Component, budget: u8) -> bool {
+            let (mut budgeted, mut reduced) = (component.clone(), component.clone());
+            let finished = budgeted.reduce_with_budget(budget as usize);
+            reduced.reduce();
+            budgeted.implies(&component) && component.implies(&budgeted) && (!finished || budgeted == reduced)
+        }
+
         fn true_not_implies_not_true(component: Component) -> bool {
             if component.is_true() {
                 true
@@ -270,6 +801,53 @@ mod tests {
             component1.implies(&component2)
         }
 
+        fn and_stays_reduced(component1: Component, component2: Component) -> bool {
+            let (mut component1, mut component2) = (component1, component2);
+            component1.reduce();
+            component2.reduce();
+            let conjoined = component1.clone() & component2.clone();
+            let mut reduced = conjoined.clone();
+            reduced.reduce();
+            reduced == conjoined && conjoined.implies(&component1) && conjoined.implies(&component2)
+        }
+
+        fn proof_agrees_with_implies(component1: Component, component2: Component) -> bool {
+            let weaker = component1.clone() | component2.clone();
+            [(&component1, &component2), (&component1, &weaker)].iter().all(|(a, b)| match a.implies_with_proof(b) {
+                Some(proof) => a.implies(b) && a.check_proof(b, &proof),
+                None => !a.implies(b),
+            })
+        }
+
+        fn extend_agrees_with_and(component: Component, clauses: Vec<Clause>) -> bool {
+            let mut component = component;
+            component.reduce();
+            let mut extended = component.clone();
+            extended.extend(clauses.iter().cloned());
+            let conjoined = clauses.into_iter().fold(component, |c, clause| c & Component::from([clause]));
+            extended.is_reduced() && extended.implies(&conjoined) && conjoined.implies(&extended)
+        }
+
+        fn implies_with_agrees(component: Component, assumed: Component, other: Component) -> bool {
+            component.implies_with(&assumed, &other) == (component & assumed).implies(&other)
+        }
+
+        fn implies_agrees_with_pairwise(component1: Component, component2: Component) -> bool {
+            let expected = match (&component1, &component2) {
+                (Component::DCFalse, _) => true,
+                (_, Component::DCFalse) => false,
+                (Component::DCFormula(s), Component::DCFormula(o)) => o.iter().all(|oclause| s.iter().any(|sclause| sclause.implies(oclause))),
+            };
+            component1.implies(&component2) == expected
+        }
+
+        fn reduce_leaves_reduced(component: Component, budget: u8) -> bool {
+            let (mut reduced, mut budgeted) = (component.clone(), component);
+            reduced.reduce();
+            let finished = budgeted.reduce_with_budget(budget as usize);
+            reduced.is_reduced() && (!finished || budgeted.is_reduced())
+        }
+
         fn reduce_simplifies(component: Component) -> bool {
             let mut component = component.clone();
             component.reduce();
@@ -5,97 +5,128 @@
 //! unlike DCLabels, Buckle principals are not strings, but rather ordered
 //! lists, where prefixes imply longer lists.
 
-#[cfg(test)]
+#[cfg(any(test, feature = "quickcheck"))]
 use alloc::boxed::Box;
-use alloc::vec::Vec;
-#[cfg(test)]
+#[cfg(any(test, feature = "quickcheck"))]
+use crate::generate::{GenConfig, Generate};
+#[cfg(any(test, feature = "quickcheck"))]
 use quickcheck::Arbitrary;
-use serde::{Deserialize, Serialize};
 
-use super::{HasPrivilege, Label};
+use super::{HasPrivilege, Label, LabelStats};
+use crate::proof::FlowCounterexample;
 
+pub mod builder;
 pub mod clause;
 pub mod component;
+pub mod privilege;
 
+pub use builder::BuckleBuilder;
 pub use clause::*;
 pub use component::*;
+pub use privilege::CompiledPrivilege;
 
+#[cfg(not(feature = "smolstr"))]
 pub type Principal = alloc::string::String;
-
-#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+/// Principals are short and repeated across clauses, so with `smolstr` they
+/// are stored inline up to 23 bytes and share longer names by reference
+/// count.
+#[cfg(feature = "smolstr")]
+pub type Principal = smol_str::SmolStr;
+
+/// A Buckle label: secrecy and integrity components, kept reduced. Read them
+/// with `secrecy` and `integrity`, and replace them with `set_secrecy` and
+/// `set_integrity`, which reduce them as `new` does, so that the reduced
+/// form `downgrade` relies on holds.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "diesel", derive(diesel::expression::AsExpression, diesel::deserialize::FromSqlRow))]
+#[cfg_attr(feature = "diesel", diesel(sql_type = diesel::sql_types::Text))]
 pub struct Buckle {
-    pub secrecy: Component,
-    pub integrity: Component,
+    pub(crate) secrecy: Component,
+    pub(crate) integrity: Component,
 }
 
 impl Buckle {
-    /// Parses a string into a DCLabel.
+    /// Parses a string into a Buckle label.
     ///
-    /// The string separates secrecy and integrity with a comma, clauses
-    /// separated with a '&' and principle vectors with a '|', and delegated
-    /// principles with '/'. The backslash character ('\') allows escaping these
-    /// special characters (including itself).
-    pub fn parse(input: &str) -> Result<Buckle, nom::Err<nom::error::Error<&str>>> {
-        Self::parser(input).map(|r| r.1)
+    /// The string is in the canonical text syntax of `crate::text`, read
+    /// leniently.
+    pub fn parse(input: &str) -> Result<Buckle, crate::Error> {
+        Self::parser(input).map(|r| r.1).map_err(|e| crate::error::from_nom(input, e))
     }
 
+    /// Parses a label as `parse` does, stopping at the end of the label.
     pub fn parser(input: &str) -> nom::IResult<&str, Buckle> {
-        use alloc::collections::BTreeSet;
-        use nom::{
-            bytes::complete::{escaped_transform, tag},
-            character::complete::{alphanumeric1, one_of},
-            multi::separated_list1,
-            sequence::tuple,
-            Parser,
-        };
+        use crate::text::{buckle::ComponentBuilder, label_prefix, nom_error, Mode};
 
-        fn component(input: &str) -> nom::IResult<&str, Component> {
-            tag("T")
-                .map(|_| Component::dc_true())
-                .or(tag("F").map(|_| Component::dc_false()))
-                .or(nom::combinator::map(
-                    separated_list1(
-                        tag("&"),
-                        separated_list1(
-                            tag("|"),
-                            separated_list1(
-                                tag("/"),
-                                escaped_transform(alphanumeric1, '\\', one_of(r#",|&/\"#)),
-                            ),
-                        ),
-                    ),
-                    |mut c| {
-                        Component::DCFormula(
-                            c.iter_mut()
-                                .map(|c| c.drain(..).collect::<BTreeSet<Vec<Principal>>>().into())
-                                .collect::<BTreeSet<Clause>>(),
-                        )
-                    },
-                ))
-                .parse(input)
+        match label_prefix(input, Mode::Lenient, ComponentBuilder::default(), ComponentBuilder::default()) {
+            Ok(((secrecy, integrity), end)) => Ok((&input[end..], Buckle::new(secrecy, integrity))),
+            Err((_, offset)) => nom_error(input, offset),
         }
+    }
+}
 
-        let (input, (secrecy, _, integrity)) =
-            tuple((component, tag(","), component)).parse(input)?;
+/// Parses a single component: `T`, `F`, or clauses in the syntax accepted by
+/// `Buckle::parse`.
+pub(crate) fn component(input: &str) -> nom::IResult<&str, Component> {
+    use crate::text::{buckle::ComponentBuilder, component_prefix, nom_error, Mode};
 
-        Ok((input, Buckle::new(secrecy, integrity)))
+    match component_prefix(input, Mode::Lenient, ComponentBuilder::default()) {
+        Ok((component, end)) => Ok((&input[end..], component)),
+        Err((_, offset)) => nom_error(input, offset),
     }
 }
 
-#[cfg(test)]
+crate::serde_label::impl_serde!(Buckle, Buckle::parser, "a Buckle label, e.g. \"amit/grades|yue,T\"");
+
+/// Parses a label in the strict canonical text syntax, rejecting trailing
+/// input, so that it's the inverse of `Display`. Syntax errors are
+/// `crate::Error::Parse`.
+impl core::convert::TryFrom<&str> for Buckle {
+    type Error = crate::Error;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        use crate::text::{Error, Mode};
+
+        crate::text::parse(input, Mode::Strict).map_err(|e| match e {
+            Error::Syntax(offset) => crate::Error::Parse(offset),
+            e => crate::Error::Text(e),
+        })
+    }
+}
+
+impl core::str::FromStr for Buckle {
+    type Err = crate::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        core::convert::TryFrom::try_from(input)
+    }
+}
+
+/// Formats the label in the syntax accepted by `Buckle::parse`.
+impl core::fmt::Display for Buckle {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{},{}", self.secrecy, self.integrity)
+    }
+}
+
+#[cfg(any(test, feature = "quickcheck"))]
+impl Generate for Buckle {
+    fn generate(g: &mut quickcheck::Gen, config: &GenConfig) -> Self {
+        Buckle::new(Component::generate(g, config), Component::generate(g, config))
+    }
+}
+
+#[cfg(any(test, feature = "quickcheck"))]
 impl Arbitrary for Buckle {
     fn arbitrary(g: &mut quickcheck::Gen) -> Self {
-        Buckle {
-            secrecy: Component::arbitrary(g),
-            integrity: Component::arbitrary(g),
-        }
+        GenConfig::default().generate(g)
     }
 
     fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
         Box::new(
             (self.secrecy.clone(), self.integrity.clone())
                 .shrink()
-                .map(|(secrecy, integrity)| Buckle { secrecy, integrity }),
+                .map(|(secrecy, integrity)| Buckle::new(secrecy, integrity)),
         )
     }
 }
@@ -109,16 +140,79 @@ impl Buckle {
         Buckle { secrecy, integrity }
     }
 
-    pub fn public() -> Buckle {
-        Self::new(Component::dc_true(), Component::dc_true())
+    /// Returns a builder that starts from the public label and conjoins
+    /// clauses one at a time.
+    pub fn builder() -> BuckleBuilder {
+        BuckleBuilder::default()
     }
 
-    pub fn top() -> Buckle {
-        Self::new(Component::dc_false(), Component::dc_true())
+    /// The secrecy component.
+    pub fn secrecy(&self) -> &Component {
+        &self.secrecy
     }
 
-    pub fn bottom() -> Buckle {
-        Self::new(Component::dc_true(), Component::dc_false())
+    /// The integrity component.
+    pub fn integrity(&self) -> &Component {
+        &self.integrity
+    }
+
+    /// Replaces the secrecy component, reducing it as `new` does.
+    pub fn set_secrecy<S: Into<Component>>(&mut self, secrecy: S) {
+        self.secrecy = secrecy.into();
+        self.secrecy.reduce();
+    }
+
+    /// Replaces the integrity component, reducing it as `new` does.
+    pub fn set_integrity<I: Into<Component>>(&mut self, integrity: I) {
+        self.integrity = integrity.into();
+        self.integrity.reduce();
+    }
+
+    /// Returns the secrecy and integrity components.
+    pub fn into_parts(self) -> (Component, Component) {
+        (self.secrecy, self.integrity)
+    }
+
+    pub const fn public() -> Buckle {
+        Buckle { secrecy: Component::dc_true(), integrity: Component::dc_true() }
+    }
+
+    pub const fn top() -> Buckle {
+        Buckle { secrecy: Component::dc_false(), integrity: Component::dc_true() }
+    }
+
+    pub const fn bottom() -> Buckle {
+        Buckle { secrecy: Component::dc_true(), integrity: Component::dc_false() }
+    }
+
+    /// Returns whether the label is public, i.e. both components are true.
+    pub fn is_public(&self) -> bool {
+        self.secrecy.is_true() && self.integrity.is_true()
+    }
+
+    /// Returns whether the label is the top label, i.e. false secrecy and
+    /// true integrity.
+    pub fn is_top(&self) -> bool {
+        self.secrecy.is_false() && self.integrity.is_true()
+    }
+
+    /// Returns whether the label is the bottom label, i.e. true secrecy and
+    /// false integrity.
+    pub fn is_bottom(&self) -> bool {
+        self.secrecy.is_true() && self.integrity.is_false()
+    }
+
+    /// Returns the approximate number of bytes the label owns on the heap,
+    /// e.g. to measure the memory held by stored labels.
+    pub fn approx_heap_size(&self) -> usize {
+        self.secrecy.approx_heap_size() + self.integrity.approx_heap_size()
+    }
+
+    /// Releases excess capacity, e.g. to compact labels kept after a burst
+    /// of `lub`s.
+    pub fn shrink_to_fit(&mut self) {
+        self.secrecy.shrink_to_fit();
+        self.integrity.shrink_to_fit();
     }
 
     pub fn reduce(&mut self) {
@@ -126,51 +220,130 @@ impl Buckle {
         self.integrity.reduce();
     }
 
+    /// Reduces both components as `Component::reduce_with_budget` does,
+    /// sharing `max_comparisons` between them, and returns whether both
+    /// finished.
+    pub fn reduce_with_budget(&mut self, max_comparisons: usize) -> bool {
+        let mut budget = max_comparisons;
+        let secrecy = self.secrecy.reduce_within(&mut budget);
+        let integrity = self.integrity.reduce_within(&mut budget);
+        secrecy && integrity
+    }
+
+    /// Returns whether both components are reduced.
+    pub fn is_reduced(&self) -> bool {
+        self.secrecy.is_reduced() && self.integrity.is_reduced()
+    }
+
+    /// Computes size and shape statistics for the label, e.g. to monitor
+    /// label growth or reject labels exceeding a budget.
+    pub fn stats(&self) -> LabelStats {
+        LabelStats::of::<Self, _>(self.secrecy.clauses(), self.integrity.clauses())
+    }
+
     pub fn endorse(mut self, privilege: &Component) -> Buckle {
+        #[cfg(feature = "trace")]
+        let before = crate::telemetry::digest_before(&self);
+        self.integrity &= privilege.clone();
+        #[cfg(feature = "trace")]
+        crate::telemetry::trace_privileged("endorse", before, &self);
+        self
+    }
+
+    /// Returns why `self` can't flow to `rhs`: a clause of its secrecy that
+    /// `rhs` doesn't uphold, or of `rhs`'s integrity that it lacks, found as
+    /// `can_flow_to` would find them (see the `proof` module). Returns
+    /// `None` if `self` can flow to `rhs`.
+    pub fn flow_counterexample(&self, rhs: &Self) -> Option<FlowCounterexample<Component>> {
+        rhs.secrecy
+            .unimplied_clause(&self.secrecy)
+            .map(FlowCounterexample::Secrecy)
+            .or_else(|| self.integrity.unimplied_clause(&rhs.integrity).map(FlowCounterexample::Integrity))
+    }
+
+    /// `downgrade`, without its decision event.
+    fn downgraded(mut self, privilege: &Component) -> Buckle {
+        // An empty privilege downgrades nothing.
+        if privilege.is_true() {
+            return self;
+        }
+        // Dropping secrecy clauses and conjoining the privilege keep reduced
+        // components reduced.
+        #[cfg(debug_assertions)]
+        let reduced = self.is_reduced() && privilege.is_reduced();
+        self.secrecy = match (self.secrecy, privilege) {
+            //not real (DCTrue, _) => DCTrue, // can't go lower than true
+            (_, Component::DCFalse) => Component::dc_true(), // false can downgrade _anything_ to true
+            (Component::DCFalse, _) => Component::dc_false(), // only false can downgrade false
+            (Component::DCFormula(mut sec), Component::DCFormula(p)) => {
+                if sec.iter().any(|c| p.iter().any(|pclause| pclause.implies(c))) {
+                    component::make_mut(&mut sec).retain(|c| !p.iter().any(|pclause| pclause.implies(c)));
+                }
+                Component::DCFormula(sec)
+            }
+        };
         self.integrity = privilege.clone() & self.integrity;
+        #[cfg(debug_assertions)]
+        debug_assert!(!reduced || self.is_reduced());
         self
     }
 }
 
 impl Label for Buckle {
     fn lub(self, rhs: Self) -> Self {
-        let mut res = Buckle {
-            secrecy: self.secrecy & rhs.secrecy,
-            integrity: self.integrity | rhs.integrity,
+        // Top absorbs every label and bottom is the identity, so neither
+        // needs the other label's clauses.
+        let mut res = if self.is_top() || rhs.is_bottom() {
+            self
+        } else if rhs.is_top() || self.is_bottom() {
+            rhs
+        } else {
+            let mut label = self;
+            label.secrecy &= rhs.secrecy;
+            label.integrity |= rhs.integrity;
+            label
         };
         res.reduce();
         res
     }
 
     fn glb(self, rhs: Self) -> Self {
-        let mut res = Buckle {
-            secrecy: self.secrecy | rhs.secrecy,
-            integrity: self.integrity & rhs.integrity,
+        // Bottom absorbs every label and top is the identity.
+        let mut res = if self.is_bottom() || rhs.is_top() {
+            self
+        } else if rhs.is_bottom() || self.is_top() {
+            rhs
+        } else {
+            let mut label = self;
+            label.secrecy |= rhs.secrecy;
+            label.integrity &= rhs.integrity;
+            label
         };
         res.reduce();
         res
     }
 
     fn can_flow_to(&self, rhs: &Self) -> bool {
-        rhs.secrecy.implies(&self.secrecy) && self.integrity.implies(&rhs.integrity)
+        // Bottom flows to every label, and every label flows to top.
+        let allowed = self.is_bottom()
+            || rhs.is_top()
+            || (rhs.secrecy.implies(&self.secrecy) && self.integrity.implies(&rhs.integrity));
+        #[cfg(feature = "trace")]
+        crate::telemetry::trace_flow(self, rhs, allowed);
+        allowed
     }
 }
 
 impl HasPrivilege for Buckle {
     type Privilege = Component;
 
-    fn downgrade(mut self, privilege: &Component) -> Buckle {
-        self.secrecy = match (self.secrecy, privilege) {
-            //not real (DCTrue, _) => DCTrue, // can't go lower than true
-            (_, Component::DCFalse) => Component::dc_true(), // false can downgrade _anything_ to true
-            (Component::DCFalse, _) => Component::dc_false(), // only false can downgrade false
-            (Component::DCFormula(mut sec), Component::DCFormula(p)) => {
-                sec.retain(|c| !p.iter().any(|pclause| pclause.implies(c)));
-                Component::DCFormula(sec)
-            }
-        };
-        self.integrity = privilege.clone() & self.integrity;
-        self
+    fn downgrade(self, privilege: &Component) -> Buckle {
+        #[cfg(feature = "trace")]
+        let before = crate::telemetry::digest_before(&self);
+        let after = self.downgraded(privilege);
+        #[cfg(feature = "trace")]
+        crate::telemetry::trace_privileged("downgrade", before, &after);
+        after
     }
 
     fn downgrade_to(self, target: Self, privilege: &Self::Privilege) -> Self {
@@ -182,8 +355,14 @@ impl HasPrivilege for Buckle {
     }
 
     fn can_flow_to_with_privilege(&self, rhs: &Self, privilege: &Component) -> bool {
-        (rhs.secrecy.clone() & privilege.clone()).implies(&self.secrecy)
-            && (self.integrity.clone() & privilege.clone()).implies(&rhs.integrity)
+        // An empty privilege permits no more flows, and false permits all.
+        if privilege.is_true() {
+            return self.can_flow_to(rhs);
+        } else if privilege.is_false() {
+            return true;
+        }
+        rhs.secrecy.implies_with(privilege, &self.secrecy)
+            && self.integrity.implies_with(privilege, &rhs.integrity)
     }
 }
 
@@ -349,6 +528,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_accessors() {
+        let mut label = Buckle::public();
+        label.set_secrecy([Clause::from(["amit"]), Clause::from(["amit", "yue"])]);
+        label.set_integrity(Component::from([["staff"]]));
+        assert!(label.is_reduced());
+        assert_eq!(&Component::from([["amit"]]), label.secrecy());
+        assert_eq!(Buckle::parse("amit,staff").unwrap(), label);
+        assert_eq!((Component::from([["amit"]]), Component::from([["staff"]])), label.into_parts());
+    }
+
+    #[cfg(feature = "macros")]
+    #[test]
+    fn test_macro() {
+        assert_eq!(Buckle::parse("amit/grades,amit").unwrap(), crate::buckle!("amit/grades,amit"));
+        assert_eq!(Buckle::parse("T,F").unwrap(), crate::buckle!("T,F"));
+        assert_eq!(Buckle::parse("F,T").unwrap(), crate::buckle!("F,T"));
+        // Reduced, as by `parse`.
+        assert_eq!(Buckle::parse("amit,a/b").unwrap(), crate::buckle!("amit/grades|yue&amit,a/b&a/b/c"));
+        assert_eq!(Buckle::parse(r"\T/\,\/x|\é,T").unwrap(), crate::buckle!(r"\T/\,\/x|\é,T"));
+    }
+
+    #[test]
+    fn test_flow_counterexample() {
+        use alloc::string::ToString;
+
+        let record = Buckle::parse("amit/grades&yue,staff").unwrap();
+        let counterexample = record.flow_counterexample(&Buckle::parse("amit,staff").unwrap()).unwrap();
+        assert_eq!(FlowCounterexample::Secrecy(Component::from([["yue"]])), counterexample);
+        assert_eq!("the target doesn't protect secrecy clause yue", counterexample.to_string());
+        let counterexample = record.flow_counterexample(&Buckle::parse("amit/grades&yue,staff&audit").unwrap()).unwrap();
+        assert_eq!("the source lacks integrity clause audit", counterexample.to_string());
+        assert_eq!(
+            Some(FlowCounterexample::Secrecy(Component::DCFalse)),
+            Buckle::top().flow_counterexample(&Buckle::public())
+        );
+        assert_eq!(None, record.flow_counterexample(&Buckle::parse("amit/grades&yue&bob,T").unwrap()));
+    }
+
     #[test]
     fn test_basic_can_flow_to_secrecy() {
         assert_eq!(
@@ -384,6 +602,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_trivial() {
+        let lbl = Buckle::parse("Amit/Grades&Yue,Amit").unwrap();
+        assert!(Buckle::public().is_public());
+        assert!(Buckle::top().is_top() && Buckle::bottom().is_bottom());
+        assert!(!lbl.is_public() && !lbl.is_top() && !lbl.is_bottom());
+        assert_eq!(Buckle::top(), lbl.clone().lub(Buckle::top()));
+        assert_eq!(lbl, Buckle::bottom().lub(lbl.clone()));
+        assert_eq!(Buckle::bottom(), lbl.clone().glb(Buckle::bottom()));
+        assert_eq!(lbl, Buckle::top().glb(lbl.clone()));
+        assert_eq!(lbl, lbl.clone().downgrade(&Component::dc_true()));
+        assert!(lbl.can_flow_to_with_privilege(&Buckle::bottom(), &Component::dc_false()));
+        assert!(!lbl.can_flow_to_with_privilege(&Buckle::bottom(), &Component::dc_true()));
+    }
+
+    #[test]
+    fn test_from_str() {
+        use alloc::string::ToString;
+        use core::convert::TryFrom;
+
+        let label: Buckle = "amit/grades|yue,T".parse().unwrap();
+        assert_eq!(Buckle::parse("amit/grades|yue,T").unwrap(), label);
+        assert_eq!(Ok(label.clone()), Buckle::try_from(label.to_string().as_str()));
+        assert_eq!(Err(crate::Error::Parse(17)), "amit/grades|yue,T,x".parse::<Buckle>());
+        assert_eq!(Err(crate::Error::Parse(4)), Buckle::try_from("amit"));
+        assert_eq!(label, Buckle::parse(" amit/grades | yue , T ").unwrap());
+        assert_eq!(Err(crate::Error::Parse(0)), " amit/grades | yue , T ".parse::<Buckle>());
+        assert_eq!(Buckle::parse("amit,T").unwrap(), Buckle::parse("\\amit,T").unwrap());
+        assert_eq!(Err(crate::Error::Parse(1)), Buckle::try_from("\\amit,T"));
+    }
+
+    #[test]
+    fn test_const() {
+        const PUBLIC: Buckle = Buckle::public();
+        static TOP: Buckle = Buckle::top();
+        assert_eq!(Buckle::parse("T,T").unwrap(), PUBLIC);
+        assert!(TOP.is_top() && Buckle::bottom().is_bottom());
+    }
+
     #[test]
     fn test_lub() {
         assert_eq!(Buckle::top(), Buckle::public().lub(Buckle::top()));
@@ -462,6 +719,49 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_display() {
+        use alloc::string::ToString;
+
+        assert_eq!("T,T", Buckle::public().to_string());
+        assert_eq!("F,T", Buckle::top().to_string());
+        assert_eq!("T,F", Buckle::bottom().to_string());
+        let lbl = Buckle::parse(r"amit/grades|yue&a\/\\,F").unwrap();
+        assert_eq!(r"a\/\\&amit/grades|yue,F", lbl.to_string());
+        assert_eq!(Ok(lbl.clone()), Buckle::parse(&lbl.to_string()));
+        let lbl = Buckle::parse("b|T&F/x,1").unwrap();
+        assert_eq!(r"\F/x&\T|b,1", lbl.to_string());
+        assert_eq!(Ok(lbl.clone()), Buckle::parse(&lbl.to_string()));
+    }
+
+    fn assert_postcard_round_trip(lbl: Buckle) {
+        let bytes = postcard::to_allocvec(&lbl).unwrap();
+        assert_eq!(Ok(lbl), postcard::from_bytes(&bytes));
+    }
+
+    #[test]
+    fn test_postcard_round_trip() {
+        assert_postcard_round_trip(Buckle::public());
+        assert_postcard_round_trip(Buckle::top());
+        assert_postcard_round_trip(Buckle::bottom());
+        assert_postcard_round_trip(Buckle::new([vec![""], vec!["a", ""]], [Clause::new_from_vec::<&str>(vec![vec![]])]));
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn test_json_schema() {
+        let schema = schemars::schema_for!(Buckle);
+        assert_eq!(Some(&"string".into()), schema.get("type"));
+    }
+
+    #[test]
+    fn test_json() {
+        let lbl = Buckle::parse("amit/grades|yue,T").unwrap();
+        assert_eq!(r#""amit/grades|yue,T""#, serde_json::to_string(&lbl).unwrap());
+        assert_eq!(lbl, serde_json::from_str::<Buckle>(r#""amit/grades|yue,T""#).unwrap());
+        assert!(serde_json::from_str::<Buckle>(r#""amit,T,T""#).is_err());
+    }
+
     quickcheck! {
         fn everything_can_flow_to_top(lbl: Buckle) -> bool {
             let top = Buckle::top();
@@ -483,9 +783,22 @@ mod tests {
             result.can_flow_to(&lbl1) && result.can_flow_to(&lbl2)
         }
 
+        fn counterexample_iff_denied(lbl1: Buckle, lbl2: Buckle) -> bool {
+            match lbl1.flow_counterexample(&lbl2) {
+                None => lbl1.can_flow_to(&lbl2),
+                Some(FlowCounterexample::Secrecy(clause)) => !lbl2.secrecy.implies(&clause) && !lbl1.can_flow_to(&lbl2),
+                Some(FlowCounterexample::Integrity(clause)) => !lbl1.integrity.implies(&clause) && !lbl1.can_flow_to(&lbl2),
+            }
+        }
+
         fn endorse_equiv_downgrade_to(lbl: Buckle, privilege: Component) -> bool {
             let target = Buckle { secrecy: lbl.secrecy.clone(), integrity: lbl.integrity.clone() & privilege.clone() };
             lbl.clone().downgrade_to(target, &privilege) == lbl.endorse(&privilege)
         }
+
+        fn postcard_round_trip(lbl: Buckle) -> bool {
+            let bytes = postcard::to_allocvec(&lbl).unwrap();
+            postcard::from_bytes::<Buckle>(&bytes) == Ok(lbl)
+        }
     }
 }
@@ -1,18 +1,22 @@
-#[cfg(test)]
+#[cfg(any(test, feature = "quickcheck"))]
 use alloc::boxed::Box;
-#[cfg(test)]
+#[cfg(any(test, feature = "quickcheck"))]
+use crate::generate::{GenConfig, Generate};
+#[cfg(any(test, feature = "quickcheck"))]
 use quickcheck::Arbitrary;
 
 // use serde::{Deserialize, Serialize};
 
-use super::Principal;
-use alloc::{collections::BTreeSet, vec::Vec};
+use super::{FlatSet, Principal};
+use crate::subsume::Subsumes;
+use crate::{fingerprint, ClauseStats, MalformedClause};
+use allocator_api2::vec::Vec;
 
-use core::alloc::Allocator;
-use alloc::alloc::Global;
+use allocator_api2::alloc::Allocator;
+use allocator_api2::alloc::Global;
 
 #[derive(Debug, Clone)]
-pub struct Clause<A: Allocator + Clone = Global>(pub BTreeSet<Vec<Principal<A>, A>, A>);
+pub struct Clause<A: Allocator + Clone = Global>(pub FlatSet<Vec<Principal<A>, A>, A>);
 
 impl<A: Allocator + Clone> PartialEq for Clause<A> {
     fn eq(&self, other: &Self) -> bool {
@@ -24,7 +28,7 @@ impl<A: Allocator + Clone> Eq for Clause<A> {}
 
 impl<A: Allocator + Clone> PartialOrd for Clause<A> {
     fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
-        self.0.partial_cmp(&other.0)
+        Some(self.cmp(other))
     }
 }
 
@@ -34,14 +38,33 @@ impl<A: Allocator + Clone> Ord for Clause<A> {
     }
 }
 
-#[cfg(test)]
+#[cfg(any(test, feature = "quickcheck"))]
+impl Generate for Clause {
+    fn generate(g: &mut quickcheck::Gen, config: &GenConfig) -> Self {
+        let len = config.len(g, config.max_principals);
+        let paths: Vec<Vec<Principal<Global>>> = (0..len)
+            .map(|_| {
+                let depth = config.len(g, config.max_depth);
+                (0..depth).map(|_| Principal::from(&config.principal_bytes(g)[..])).collect()
+            })
+            .collect();
+        Clause(FlatSet::from(paths))
+    }
+}
+
+#[cfg(any(test, feature = "quickcheck"))]
 impl Arbitrary for Clause {
     fn arbitrary(g: &mut quickcheck::Gen) -> Self {
-        Clause(BTreeSet::arbitrary(g))
+        GenConfig::default().generate(g)
     }
 
     fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
-        Box::new(self.0.shrink().map(|x| Clause(x)))
+        // Shrinks standard vectors, which, unlike principals, are `Arbitrary`.
+        let paths: alloc::vec::Vec<alloc::vec::Vec<alloc::vec::Vec<u8>>> =
+            self.0.iter().map(|path| path.iter().map(|p| p.to_vec()).collect()).collect();
+        Box::new(paths.shrink().map(|paths| {
+            Clause(paths.iter().map(|path| path.iter().map(|p| Principal::from(&p[..])).collect()).collect())
+        }))
     }
 }
 
@@ -52,10 +75,9 @@ impl<P: Into<Principal<Global>> + Clone, const N: usize> From<[P; N]> for Clause
     }
 }
 
-impl<P: Into<Principal<Global>> + Clone> From<Vec<P>> for Clause {
-    fn from(mut principals: Vec<P>) -> Clause {
-        use alloc::vec;
-        Clause::new_from_vec(principals.drain(..).map(|p| vec![p]).collect())
+impl<P: Into<Principal<Global>> + Clone> From<alloc::vec::Vec<P>> for Clause {
+    fn from(principals: alloc::vec::Vec<P>) -> Clause {
+        Clause::new_from_vec(principals.into_iter().map(|p| alloc::vec![p]).collect())
     }
 }
 
@@ -74,16 +96,20 @@ impl Clause {
         Self::new_in(principals, Global)
     }
 
-    pub fn new_from_vec<P: Into<Principal<Global>> + Clone>(principals: Vec<Vec<P>>) -> Clause {
-        Self::new_from_vec_in(principals, Global)
+    pub fn new_from_vec<P: Into<Principal<Global>> + Clone>(principals: alloc::vec::Vec<alloc::vec::Vec<P>>) -> Clause {
+        Clause(principals.into_iter().map(|path| path.into_iter().map(Into::into).collect()).collect())
+    }
+
+    pub fn with_capacity(capacity: usize) -> Clause {
+        Self::with_capacity_in(capacity, Global)
     }
 }
 
 impl<A: Allocator + Clone, P: Into<Principal<A>> + Clone> From<(Vec<P, A>, A)> for Clause<A> {
     fn from((mut principals, alloc): (Vec<P, A>, A)) -> Clause<A> {
-        let mut v = Vec::new_in(alloc.clone());
+        let mut v = Vec::with_capacity_in(principals.len(), alloc.clone());
         principals.drain(..).for_each(|p| {
-            let mut vv = Vec::new_in(alloc.clone());
+            let mut vv = Vec::with_capacity_in(1, alloc.clone());
             vv.push(p);
             v.push(vv);
         });
@@ -92,22 +118,73 @@ impl<A: Allocator + Clone, P: Into<Principal<A>> + Clone> From<(Vec<P, A>, A)> f
     }
 }
 
-impl<A: Allocator + Clone> From<BTreeSet<Vec<Principal<A>, A>, A>> for Clause<A> {
-    fn from(principals: BTreeSet<Vec<Principal<A>, A>, A>) -> Clause<A> {
+impl<A: Allocator + Clone> From<FlatSet<Vec<Principal<A>, A>, A>> for Clause<A> {
+    fn from(principals: FlatSet<Vec<Principal<A>, A>, A>) -> Clause<A> {
         Clause(principals)
     }
 }
 
+impl<A: Allocator + Clone> crate::heap::Heap for Clause<A> {
+    fn heap_size(&self) -> usize {
+        self.0.heap_size()
+    }
+
+    fn compact(&mut self) {
+        self.0.compact()
+    }
+}
+
+/// A clause only implies clauses with a path starting with the first
+/// principal of its least path.
+impl<A: Allocator + Clone> Subsumes for Clause<A> {
+    type Key = Principal<A>;
+
+    fn key(&self) -> Option<&Principal<A>> {
+        self.0.first().and_then(|path| path.first())
+    }
+
+    fn keys(&self) -> impl Iterator<Item = &Principal<A>> {
+        self.0.iter().filter_map(|path| path.first())
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn implies(&self, other: &Self) -> bool {
+        Clause::implies(self, other)
+    }
+}
+
+/// Copies a principal into `alloc`.
+pub(crate) fn principal_in<A: Allocator>(principal: &[u8], alloc: A) -> Principal<A> {
+    let mut p = Vec::with_capacity_in(principal.len(), alloc);
+    p.extend_from_slice(principal);
+    p
+}
+
+/// Copies a path into `alloc`.
+pub(crate) fn path_in<A: Allocator, B: Allocator + Clone>(path: &[Principal<A>], alloc: &B) -> Vec<Principal<B>, B> {
+    let mut result = Vec::with_capacity_in(path.len(), alloc.clone());
+    result.extend(path.iter().map(|principal| principal_in(principal, alloc.clone())));
+    result
+}
+
 impl<A: Allocator + Clone> Clause<A> {
     pub fn empty_in(alloc: A) -> Clause<A> {
         Self::new_in([] as [Principal<A>; 0], alloc)
     }
 
+    /// Returns the empty clause with room for `capacity` paths.
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Clause<A> {
+        Clause(FlatSet::with_capacity_in(capacity, alloc))
+    }
+
     pub fn new_in<P: Into<Principal<A>> + Clone, const N: usize>(principals: [P; N], alloc: A) -> Clause<A>
     {
-        let mut result = BTreeSet::new_in(alloc.clone());
+        let mut result = FlatSet::with_capacity_in(N, alloc.clone());
         for p in principals.iter() {
-            let mut v = Vec::new_in(alloc.clone());
+            let mut v = Vec::with_capacity_in(1, alloc.clone());
             v.push(p.clone().into());
             result.insert(v);
         }
@@ -115,10 +192,10 @@ impl<A: Allocator + Clone> Clause<A> {
     }
 
     pub fn new_from_vec_in<P: Into<Principal<A>> + Clone>(principals: Vec<Vec<P, A>, A>, alloc: A) -> Clause<A> {
-        let mut result = BTreeSet::new_in(alloc.clone());
+        let mut result = FlatSet::with_capacity_in(principals.len(), alloc.clone());
         for p in principals.iter() {
 
-            let mut v = Vec::new_in(alloc.clone());
+            let mut v = Vec::with_capacity_in(p.len(), alloc.clone());
             p.clone().drain(..).for_each(|e| v.push(e.into()));
             result.insert(v);
 
@@ -129,6 +206,41 @@ impl<A: Allocator + Clone> Clause<A> {
         Self(result)
     }
 
+    /// Returns a copy of the clause allocated entirely in `alloc`, unlike
+    /// `clone`, which allocates each path in its own allocator.
+    pub fn clone_in<B: Allocator + Clone>(&self, alloc: B) -> Clause<B> {
+        let mut paths = FlatSet::with_capacity_in(self.0.len(), alloc.clone());
+        paths.extend(self.0.iter().map(|path| path_in(path, &alloc)));
+        Clause(paths)
+    }
+
+    /// Iterates over the paths, in order.
+    pub fn paths(&self) -> impl ExactSizeIterator<Item = &[Principal<A>]> + DoubleEndedIterator + '_ {
+        self.0.iter().map(Vec::as_slice)
+    }
+
+    /// Returns the number of paths.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns whether the clause has no paths, i.e. is false.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Checks that no path, or principal in one, is empty.
+    pub fn check(&self) -> Result<(), MalformedClause> {
+        for path in self.0.iter() {
+            if path.is_empty() {
+                return Err(MalformedClause::EmptyPath);
+            } else if path.iter().any(|p| p.is_empty()) {
+                return Err(MalformedClause::EmptyPrincipal);
+            }
+        }
+        Ok(())
+    }
+
     pub fn implies(&self, other: &Self) -> bool {
         // self is subset of other
         if self.0.is_empty() {
@@ -147,12 +259,27 @@ impl<A: Allocator + Clone> Clause<A> {
             //    }))
         }
     }
+
+    /// The fingerprint of its paths' leading principals (see the `fingerprint` module).
+    pub(crate) fn fingerprint(&self) -> u64 {
+        self.0.iter().filter_map(|path| path.first()).fold(0, |f, p| f | fingerprint::bit(p))
+    }
+}
+
+impl<A: Allocator + Clone> ClauseStats for Clause<A> {
+    const PATH_SIZE: usize = core::mem::size_of::<Vec<Principal<A>, A>>();
+    const PRINCIPAL_SIZE: usize = core::mem::size_of::<Principal<A>>();
+
+    fn paths(&self) -> impl Iterator<Item = impl Iterator<Item = usize> + '_> {
+        self.0.iter().map(|path| path.iter().map(|p| p.len()))
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
-    use alloc::alloc::Global;
+    use allocator_api2::alloc::Global;
 
     #[test]
     fn test_x_implies_x() {
@@ -185,6 +312,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_accessors() {
+        let clause = Clause::new_from_vec(alloc::vec![alloc::vec!["amit", "grades"], alloc::vec!["yue"]]);
+        let paths: alloc::vec::Vec<alloc::vec::Vec<&[u8]>> =
+            clause.paths().map(|path| path.iter().map(|p| p.as_slice()).collect()).collect();
+        assert_eq!(alloc::vec![alloc::vec![&b"amit"[..], b"grades"], alloc::vec![b"yue"]], paths);
+        assert_eq!(2, clause.len());
+        assert!(!clause.is_empty());
+        assert!(Clause::empty().is_empty());
+    }
+
     quickcheck! {
         fn empty_clause_implies_all(clause: Clause) -> bool {
             let empty = Clause::empty();
@@ -1,25 +1,37 @@
-#[cfg(test)]
+#[cfg(any(test, feature = "quickcheck"))]
 use alloc::boxed::Box;
-#[cfg(test)]
+#[cfg(any(test, feature = "quickcheck"))]
+use crate::generate::{GenConfig, Generate};
+#[cfg(any(test, feature = "quickcheck"))]
 use quickcheck::{empty_shrinker, Arbitrary};
 // use serde::{Deserialize, Serialize};
 
 use super::clause::Clause;
-use alloc::collections::BTreeSet;
+use super::FlatSet;
+use crate::heap::Heap;
+use crate::proof::{self, ImplicationProof};
+use crate::subsume::{self, Entry};
+use crate::{fingerprint, MalformedClause};
+use allocator_api2::vec::Vec;
 
-use core::alloc::Allocator;
-use alloc::alloc::Global;
+use allocator_api2::alloc::Allocator;
+use allocator_api2::alloc::Global;
 
 #[derive(Debug, Clone)]
 pub enum Component<A: Allocator + Clone = Global> {
     DCFalse,
-    DCFormula(BTreeSet<Clause<A>, A>, A),
+    DCFormula(FlatSet<Clause<A>, A>, A),
+}
+
+/// The principals or paths of each clause, as `proof` identifies them.
+fn elements<'a, A: Allocator + Clone + 'a>(clauses: impl Iterator<Item = &'a Clause<A>>) -> alloc::vec::Vec<alloc::vec::Vec<&'a Vec<super::Principal<A>, A>>> {
+    clauses.map(|c| c.0.iter().collect()).collect()
 }
 
 impl<A: Allocator + Clone> PartialEq for Component<A> {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
-            (Component::DCFormula(e1, _), Component::DCFormula(e2, _)) => e1.eq(&e2),
+            (Component::DCFormula(e1, _), Component::DCFormula(e2, _)) => e1.eq(e2),
             (Component::DCFalse, Component::DCFalse) => true,
             _ => false,
         }
@@ -29,15 +41,69 @@ impl<A: Allocator + Clone> PartialEq for Component<A> {
 impl<A: Allocator + Clone> Eq for Component<A> {}
 
 
-#[cfg(test)]
-impl Arbitrary for Component {
-    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
-        if !bool::arbitrary(g) {
+/// Returns whether a clause of `sources`, paired with its fingerprint,
+/// implies `target`.
+fn implied<A: Allocator + Clone>(sources: &[(u64, &Clause<A>)], target: &Clause<A>) -> bool {
+    let fingerprint = target.fingerprint();
+    sources.iter().any(|(f, source)| fingerprint::may_imply(*f, fingerprint) && source.implies(target))
+}
+
+/// Inserts a clause no clause of `clauses` implies, dropping the clauses it
+/// implies.
+fn insert_reduced<A: Allocator + Clone>(clauses: &mut FlatSet<Clause<A>, A>, clause: Clause<A>) {
+    clauses.retain(|c| !clause.implies(c));
+    clauses.insert(clause);
+}
+
+/// Whether every value of `A` allocates the same memory, as stateless
+/// allocators like `Global` do. Otherwise two components of the same type
+/// may be in different allocators, e.g. different arenas.
+const fn interchangeable<A>() -> bool {
+    core::mem::size_of::<A>() == 0
+}
+
+/// Moves `clauses` into `into`, first copying them into `alloc` if they may
+/// be in another allocator, so a component only owns memory of its own.
+fn append_in<A: Allocator + Clone>(into: &mut FlatSet<Clause<A>, A>, mut clauses: FlatSet<Clause<A>, A>, alloc: &A) {
+    if interchangeable::<A>() {
+        into.append(&mut clauses);
+    } else {
+        into.extend(clauses.iter().map(|c| c.clone_in(alloc.clone())));
+    }
+}
+
+/// Conjoins `all` in order as `insert_clause` does.
+fn conjoined<A: Allocator + Clone>(all: FlatSet<Clause<A>, A>, alloc: A) -> FlatSet<Clause<A>, A> {
+    let mut clauses = FlatSet::new_in(alloc);
+    for clause in all {
+        if !clauses.iter().any(|c: &Clause<A>| c.implies(&clause)) {
+            insert_reduced(&mut clauses, clause);
+        }
+    }
+    clauses
+}
+
+#[cfg(any(test, feature = "quickcheck"))]
+impl Generate for Component {
+    fn generate(g: &mut quickcheck::Gen, config: &GenConfig) -> Self {
+        // Clauses nest three collections deep, so quickcheck's default size
+        // yields components far too large to test in reasonable time.
+        let g = &mut quickcheck::Gen::new(g.size().min(10));
+        if config.is_false(g) {
             Component::DCFalse
         } else {
-            Component::DCFormula(BTreeSet::arbitrary(g), Global)
+            let len = config.len(g, config.max_clauses);
+            let clauses: Vec<Clause> = (0..len).map(|_| Clause::generate(g, config)).collect();
+            Component::DCFormula(FlatSet::from(clauses), Global)
         }
     }
+}
+
+#[cfg(any(test, feature = "quickcheck"))]
+impl Arbitrary for Component {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        GenConfig::default().generate(g)
+    }
 
     fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
         match self {
@@ -48,37 +114,59 @@ impl Arbitrary for Component {
 }
 
 impl Component {
-    pub fn dc_true() -> Self {
-        Component::dc_true_in(Global)
+    pub const fn dc_true() -> Self {
+        Component::DCFormula(FlatSet::new_in(Global), Global)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Component::with_capacity_in(capacity, Global)
     }
 
-    // pub fn dc_false() -> Self {
-        // Component::DCFalse
-    // }
+    /// Returns the reduced formula of `clauses`, or why one is malformed.
+    pub fn new_checked<I: IntoIterator<Item = Clause>>(clauses: I) -> Result<Self, MalformedClause> {
+        Component::new_checked_in(clauses, Global)
+    }
 }
 
 impl<A: Allocator + Clone> Component<A> {
     pub fn formula<C: Into<Clause<A>> + Clone, const N: usize>(clauses: [C; N], alloc: A) -> Component<A> {
-        let mut result = BTreeSet::new_in(alloc.clone());
+        let mut result = FlatSet::with_capacity_in(N, alloc.clone());
         for c in clauses.iter() {
             result.insert(c.clone().into());
         }
         Component::DCFormula(result, alloc)
     }
 
-    pub fn dc_false() -> Self {
+    /// Returns the reduced formula of `clauses`, or why one is malformed.
+    /// Unlike `from`, which trusts its input, this suits clauses built from
+    /// untrusted data.
+    pub fn new_checked_in<I: IntoIterator<Item = Clause<A>>>(clauses: I, alloc: A) -> Result<Self, MalformedClause> {
+        let mut result = FlatSet::new_in(alloc.clone());
+        for clause in clauses {
+            clause.check()?;
+            result.insert(clause);
+        }
+        let mut component = Component::DCFormula(result, alloc);
+        component.reduce();
+        Ok(component)
+    }
+
+    pub const fn dc_false() -> Self {
         Component::DCFalse
     }
 
     pub fn dc_true_in(alloc: A) -> Self {
-        Component::DCFormula(BTreeSet::new_in(alloc.clone()), alloc)
+        Component::DCFormula(FlatSet::new_in(alloc.clone()), alloc)
+    }
+
+    /// Returns true, with room for `capacity` clauses to be conjoined with
+    /// `insert_clause`.
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        Component::DCFormula(FlatSet::with_capacity_in(capacity, alloc.clone()), alloc)
     }
 
     pub fn is_false(&self) -> bool {
-        match self {
-            Component::DCFalse => true,
-            _ => false,
-        }
+        matches!(self, Component::DCFalse)
     }
 
     pub fn is_true(&self) -> bool {
@@ -88,37 +176,197 @@ impl<A: Allocator + Clone> Component<A> {
         }
     }
 
+    /// Returns a copy of the component allocated entirely in `alloc`, e.g.
+    /// to keep a label built in an arena past the arena's reset.
+    pub fn clone_in<B: Allocator + Clone>(&self, alloc: B) -> Component<B> {
+        match self {
+            Component::DCFalse => Component::DCFalse,
+            Component::DCFormula(clauses, _) => {
+                let mut result = FlatSet::with_capacity_in(clauses.len(), alloc.clone());
+                result.extend(clauses.iter().map(|c| c.clone_in(alloc.clone())));
+                Component::DCFormula(result, alloc)
+            }
+        }
+    }
+
+    /// Returns whether no clause implies another, as `reduce` leaves a
+    /// formula. `==` only decides equivalence between reduced components.
+    pub fn is_reduced(&self) -> bool {
+        match self {
+            Component::DCFalse => true,
+            Component::DCFormula(clauses, _) => {
+                let sources: alloc::vec::Vec<_> = clauses.iter().map(|c| (c.fingerprint(), c)).collect();
+                clauses.iter().all(|target| {
+                    let fingerprint = target.fingerprint();
+                    sources.iter().all(|(f, source)| {
+                        *source == target || !fingerprint::may_imply(*f, fingerprint) || !source.implies(target)
+                    })
+                })
+            }
+        }
+    }
+
     pub fn implies(&self, other: &Self) -> bool {
         match (self, other) {
             (Component::DCFalse, _) => true,
             (_, Component::DCFalse) => false,
             (_, o) if o.is_true() => true,
             (s, _) if s.is_true() => false,
+            // The empty clause sorts first and implies every clause.
+            (Component::DCFormula(s, _), _) if s.first().is_some_and(|c| c.0.is_empty()) => true,
             (Component::DCFormula(s, _), Component::DCFormula(o, _)) => {
-                // for all clauses in other there must be at least one in self that implies it
-                o.iter()
-                    .all(|oclause| s.iter().any(|sclause| sclause.implies(oclause)))
+                // for all clauses in other there must be at least one in self that implies it.
+                // Clauses with fewer paths are implied by fewer clauses, so
+                // checking them first finds an unimplied clause sooner.
+                // The scratch space is on the global heap, since a query
+                // must not grow the label's allocator, e.g. an arena.
+                let mut targets: alloc::vec::Vec<_> = o.iter().collect();
+                targets.sort_by_key(|c| c.0.len());
+                let sources: alloc::vec::Vec<_> = s.iter().map(|c| (c.fingerprint(), c)).collect();
+                targets.iter().all(|oclause| implied(&sources, oclause))
+            }
+        }
+    }
+
+    /// Like `(self & assumed).implies(other)`, but evaluated in place without
+    /// cloning either component.
+    pub fn implies_with(&self, assumed: &Self, other: &Self) -> bool {
+        match (self, assumed, other) {
+            (Component::DCFalse, _, _) | (_, Component::DCFalse, _) => true,
+            (_, _, Component::DCFalse) => false,
+            (Component::DCFormula(s, _), Component::DCFormula(a, _), Component::DCFormula(o, _)) => {
+                o.iter().all(|oclause| {
+                    s.iter().chain(a.iter()).any(|sclause| sclause.implies(oclause))
+                })
+            }
+        }
+    }
+
+    /// Like `implies`, but returns why `self` implies `other`, as clauses of
+    /// `self` implying each of `other`'s (see the `proof` module), or `None`
+    /// if it doesn't.
+    pub fn implies_with_proof(&self, other: &Self) -> Option<ImplicationProof> {
+        match (self, other) {
+            (Component::DCFalse, _) => Some(ImplicationProof::False),
+            (_, Component::DCFalse) => None,
+            (Component::DCFormula(s, _), Component::DCFormula(o, _)) => proof::prove(&elements(s.iter()), &elements(o.iter()), |s, t| t.starts_with(s)),
+        }
+    }
+
+    /// Returns a clause of `other` that `self` doesn't imply, as a component
+    /// of that clause alone, or `DCFalse` if `other` is false and `self`
+    /// isn't, or `None` if `self` implies `other`.
+    pub fn unimplied_clause(&self, other: &Self) -> Option<Self> {
+        match (self, other) {
+            (Component::DCFalse, _) => None,
+            (_, Component::DCFalse) => Some(Component::DCFalse),
+            (Component::DCFormula(s, _), Component::DCFormula(o, a)) => o.iter().find(|oc| !s.iter().any(|sc| sc.implies(oc))).map(|c| Component::from(([c.clone()], a.clone()))),
+        }
+    }
+
+    /// Returns whether `proof` shows that `self` implies `other`.
+    pub fn check_proof(&self, other: &Self, proof: &ImplicationProof) -> bool {
+        match (self, other, proof) {
+            (Component::DCFalse, _, ImplicationProof::False) => true,
+            (Component::DCFormula(s, _), Component::DCFormula(o, _), ImplicationProof::Clauses(steps)) => {
+                proof::check(&elements(s.iter()), &elements(o.iter()), steps, |s, t| t.starts_with(s))
+            }
+            _ => false,
+        }
+    }
+
+    /// Conjoins `other` onto `self` in place as `&` does, cloning only
+    /// `other`'s clauses.
+    pub fn conjoin(&mut self, other: &Self) {
+        match (&mut *self, other) {
+            (Component::DCFalse, _) => {}
+            (_, Component::DCFalse) => *self = Component::DCFalse,
+            (_, o) if o.is_true() => {}
+            (Component::DCFormula(s, a), o) if s.is_empty() => *self = o.clone_in(a.clone()),
+            (Component::DCFormula(s, a), Component::DCFormula(o, _)) => {
+                let mut all = core::mem::replace(s, FlatSet::new_in(a.clone()));
+                all.extend(o.iter().map(|c| c.clone_in(a.clone())));
+                *s = conjoined(all, a.clone());
             }
         }
     }
 
+    /// Conjoins `clause`, keeping a reduced component reduced: the clause
+    /// is skipped if already implied, and the clauses it implies are
+    /// dropped. Returns whether the clause was inserted.
+    pub fn insert_clause(&mut self, clause: Clause<A>) -> bool {
+        match self {
+            Component::DCFalse => false,
+            Component::DCFormula(clauses, _) if clauses.iter().any(|c| c.implies(&clause)) => false,
+            Component::DCFormula(clauses, _) => {
+                insert_reduced(clauses, clause);
+                true
+            }
+        }
+    }
+
+    /// Returns the clauses of a formula, or `None` for `DCFalse`.
+    pub(crate) fn clauses(&self) -> Option<impl Iterator<Item = &Clause<A>>> {
+        match self {
+            Component::DCFalse => None,
+            Component::DCFormula(clauses, _) => Some(clauses.iter()),
+        }
+    }
+
+    /// Returns the approximate number of bytes the component owns on the
+    /// heap (see the `heap` module).
+    pub fn approx_heap_size(&self) -> usize {
+        match self {
+            Component::DCFalse => 0,
+            Component::DCFormula(clauses, _) => clauses.heap_size(),
+        }
+    }
+
+    /// Releases excess capacity, e.g. left by a burst of `lub`s.
+    pub fn shrink_to_fit(&mut self) {
+        if let Component::DCFormula(clauses, _) = self {
+            clauses.compact()
+        }
+    }
+
     pub fn reduce(&mut self) {
+        self.reduce_with_budget(usize::MAX);
+    }
+
+    /// Reduces the component as `reduce` does, but gives up after checking
+    /// `max_comparisons` pairs of clauses for implication, bounding the work
+    /// on adversarially large components. Returns whether it finished; if
+    /// not, the component is still equivalent, with the redundant clauses
+    /// found so far removed.
+    pub fn reduce_with_budget(&mut self, max_comparisons: usize) -> bool {
+        let mut budget = max_comparisons;
+        self.reduce_within(&mut budget)
+    }
+
+    /// Reduces the component within `budget` comparisons, deducting those
+    /// made, and returns whether it finished.
+    pub(crate) fn reduce_within(&mut self, budget: &mut usize) -> bool {
         match self {
-            Component::DCFalse => {}
+            Component::DCFalse => true,
             Component::DCFormula(clauses, a) => {
-                let mut rmlist = BTreeSet::new_in(a.clone());
-                for (i, clausef) in clauses.iter().enumerate() {
-                    for clauser in clauses.iter().skip(i + 1) {
-                        if clausef.implies(clauser) {
-                            rmlist.insert(clauser.clone());
-                        } else if clauser.implies(clausef) {
-                            rmlist.insert(clausef.clone());
-                        }
+                let mut keep = Vec::new_in(a.clone());
+                keep.resize(clauses.len(), true);
+                let mut index = Vec::new_in(a.clone());
+                index.extend(clauses.iter().enumerate().map(|(i, c)| Entry::new(i, c)));
+                let finished = subsume::redundant(&mut index, clauses.iter(), budget, |i| {
+                    if let Some(keep) = keep.get_mut(i) {
+                        *keep = false;
                     }
+                });
+                // Ends the borrow of `clauses`, which `index` would hold until
+                // dropped without the standard `Vec`'s `may_dangle`.
+                drop(index);
+                if keep.contains(&false) {
+                    // `retain` visits the clauses in ascending order.
+                    let mut keep = keep.into_iter();
+                    clauses.retain(|_| keep.next().unwrap_or(true));
                 }
-                for rmclause in rmlist.iter() {
-                    clauses.remove(rmclause);
-                }
+                finished
             }
         }
     }
@@ -140,12 +388,56 @@ impl From<bool> for Component {
     }
 }
 
-impl From<BTreeSet<Clause>> for Component {
-    fn from(clauses: BTreeSet<Clause>) -> Component {
+impl From<FlatSet<Clause>> for Component {
+    fn from(clauses: FlatSet<Clause>) -> Component {
         Component::DCFormula(clauses, Global)
     }
 }
 
+impl From<alloc::collections::BTreeSet<Clause>> for Component {
+    fn from(clauses: alloc::collections::BTreeSet<Clause>) -> Component {
+        Component::DCFormula(clauses.into_iter().collect(), Global)
+    }
+}
+
+/// Iterates over the clauses of a formula. `DCFalse` yields the empty
+/// clause, so collecting its clauses gives `{∅}`, which is false but not
+/// `DCFalse`.
+impl IntoIterator for Component {
+    type Item = Clause;
+    type IntoIter = allocator_api2::vec::IntoIter<Clause>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        match self {
+            Component::DCFalse => {
+                let mut clauses = FlatSet::new();
+                clauses.insert(Clause::empty());
+                clauses.into_iter()
+            }
+            Component::DCFormula(clauses, _) => clauses.into_iter(),
+        }
+    }
+}
+
+/// Conjoins the clauses as `insert_clause` does, keeping a reduced
+/// component reduced.
+impl<A: Allocator + Clone> Extend<Clause<A>> for Component<A> {
+    fn extend<I: IntoIterator<Item = Clause<A>>>(&mut self, clauses: I) {
+        for clause in clauses {
+            self.insert_clause(clause);
+        }
+    }
+}
+
+/// Collects the clauses into a reduced component, as `extend` conjoins them
+/// with `T`.
+impl core::iter::FromIterator<Clause> for Component {
+    fn from_iter<I: IntoIterator<Item = Clause>>(clauses: I) -> Component {
+        let mut component = Component::dc_true();
+        component.extend(clauses);
+        component
+    }
+}
 
 impl<A: Allocator + Clone, C: Into<Clause<A>> + Clone, const N: usize> From<([C; N], A)> for Component<A> {
     fn from((clauses, alloc): ([C; N], A)) -> Component<A> {
@@ -163,21 +455,43 @@ impl<A: Allocator + Clone> From<(bool, A)> for Component<A> {
     }
 }
 
-impl<A: Allocator + Clone> From<(BTreeSet<Clause<A>, A>, A)> for Component<A> {
-    fn from((clauses, alloc): (BTreeSet<Clause<A>, A>, A)) -> Component<A> {
+impl<A: Allocator + Clone> From<(FlatSet<Clause<A>, A>, A)> for Component<A> {
+    fn from((clauses, alloc): (FlatSet<Clause<A>, A>, A)) -> Component<A> {
         Component::DCFormula(clauses, alloc)
     }
 }
 
+/// Formats the component in the canonical text syntax of `crate::text`, as
+/// `Buckle` does. Empty paths and principals, which have no textual form, are
+/// written as nothing.
+impl<A: Allocator + Clone> core::fmt::Display for Component<A> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        crate::text::buckle2::write(f, self, true)
+    }
+}
+
+/// Conjoins the clauses of both sides in order as `insert_clause` does, so
+/// the conjunction is reduced without a separate `reduce`. Like
+/// `insert_clause`, an empty clause leaves the formula of just the empty
+/// clause, which is false but not `DCFalse`: making it `DCFalse` would break
+/// `lub`'s leastness, as only `DCFalse` implies `DCFalse`.
 impl<A: Allocator + Clone> core::ops::BitAnd for Component<A> {
     type Output = Component<A>;
     fn bitand(self, rhs: Self) -> Component<A> {
         match (self, rhs) {
             (Component::DCFalse, _) => Component::DCFalse,
             (_, Component::DCFalse) => Component::DCFalse,
-            (Component::DCFormula(mut s, a), Component::DCFormula(mut o, _)) => {
-                s.append(&mut o);
-                Component::DCFormula(s, a)
+            (s, o) if o.is_true() => s,
+            (Component::DCFormula(s, a), o) if s.is_empty() => {
+                if interchangeable::<A>() {
+                    o
+                } else {
+                    o.clone_in(a)
+                }
+            }
+            (Component::DCFormula(mut s, a), Component::DCFormula(o, _)) => {
+                append_in(&mut s, o, &a);
+                Component::DCFormula(conjoined(s, a.clone()), a)
             }
         }
     }
@@ -193,12 +507,14 @@ impl<A: Allocator + Clone> core::ops::BitOr for Component<A> {
                 Component::dc_true_in(a)
             }
             (Component::DCFormula(s, a), Component::DCFormula(o, _)) => {
-                let mut result = BTreeSet::new_in(a.clone());
-                for mut clauses in s.iter().cloned() {
-                    for mut clauseo in o.iter().cloned() {
-                        clauses.0.append(&mut clauseo.0);
+                let mut result = FlatSet::new_in(a.clone());
+                // Distributes the disjunction over both conjunctions.
+                for sclause in s.iter() {
+                    for oclause in o.iter() {
+                        let mut clause = sclause.clone_in(a.clone());
+                        clause.0.extend(oclause.0.iter().map(|path| super::clause::path_in(path, &a)));
+                        result.insert(clause);
                     }
-                    result.insert(clauses);
                 }
                 Component::DCFormula(result, a)
             }
@@ -206,10 +522,26 @@ impl<A: Allocator + Clone> core::ops::BitOr for Component<A> {
     }
 }
 
+/// Conjoins `rhs` in place, with the same result as `&`, whose clauses it
+/// moves rather than clones.
+impl<A: Allocator + Clone> core::ops::BitAndAssign for Component<A> {
+    fn bitand_assign(&mut self, rhs: Self) {
+        *self = core::mem::replace(self, Component::DCFalse) & rhs;
+    }
+}
+
+/// Disjoins `rhs` in place, with the same result as `|`.
+impl<A: Allocator + Clone> core::ops::BitOrAssign for Component<A> {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = core::mem::replace(self, Component::DCFalse) | rhs;
+    }
+}
+
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
-    use alloc::alloc::Global;
+    use allocator_api2::alloc::Global;
 
     #[test]
     fn test_x_implies_x() {
@@ -231,6 +563,22 @@ mod tests {
         assert_eq!(false, Component::dc_true().implies(&Component::dc_false()));
     }
 
+    #[test]
+    fn test_new_checked() {
+        let a = Clause::new_from_vec(alloc::vec![alloc::vec!["a"]]);
+        let ab = Clause::new_from_vec(alloc::vec![alloc::vec!["a", "b"]]);
+        assert_eq!(Ok(Component::from([a.clone()])), Component::new_checked([a.clone(), ab.clone()]));
+        assert!(!Component::from([a, ab]).is_reduced());
+        assert_eq!(
+            Err(MalformedClause::EmptyPrincipal),
+            Component::new_checked([Clause::new_from_vec(alloc::vec![alloc::vec!["a", ""]])])
+        );
+        assert_eq!(
+            Err(MalformedClause::EmptyPath),
+            Component::new_checked([Clause::new_from_vec::<&str>(alloc::vec![alloc::vec![]])])
+        );
+    }
+
     #[test]
     fn test_false_implies_everything() {
         assert!(Component::<Global>::dc_false().implies(&Component::dc_false()));
@@ -249,6 +597,16 @@ mod tests {
         assert!(Component::from([["Amit"], ["Yue"]]).implies(&Component::from([["Amit"]])));
     }
 
+    #[test]
+    fn test_reduce_with_budget() {
+        let unreduced = Component::from([Clause::from(["Amit", "Yue"]), Clause::from(["Yue"])]);
+        let mut component = unreduced.clone();
+        assert!(!component.reduce_with_budget(0));
+        assert_eq!(unreduced, component);
+        assert!(component.reduce_with_budget(2));
+        assert_eq!(Component::from([["Yue"]]), component);
+    }
+
     #[test]
     fn test_reduce_simplifies() {
         {
@@ -265,8 +623,8 @@ mod tests {
 
     #[test]
     fn test_yue_implies_yue_sub_hello() {
-        use alloc::{vec, string::String};
-        let clause_sup = Clause::new_from_vec(vec![vec![String::from("Yue")]]);
+        use alloc::vec;
+        let clause_sup = Clause::new_from_vec(vec![vec!["Yue"]]);
         let clause_sub = Clause::new_from_vec(vec![vec!["Yue", "hello"]]);
 
         assert_eq!(true, clause_sup.implies(&clause_sub));
@@ -277,12 +635,126 @@ mod tests {
         assert_eq!(true, component_sup.implies(&component_sub));
     }
 
+    #[test]
+    fn test_insert_clause() {
+        let mut component = Component::from([["Amit", "Yue"]]);
+        assert!(component.insert_clause(Clause::from(["Yue"])));
+        assert_eq!(Component::from([["Yue"]]), component);
+        assert!(!component.insert_clause(Clause::from(["David", "Yue"])));
+        assert_eq!(Component::from([["Yue"]]), component);
+        assert!(!Component::dc_false().insert_clause(Clause::from(["Yue"])));
+    }
+
+    #[test]
+    fn test_and_empty_clause() {
+        // Conjoins the empty clause as `insert_clause` does: it implies every
+        // clause, but is only false, not `DCFalse`.
+        let component = Component::from([["Amit", "Yue"]]);
+        let empty = Component::from([Clause::empty()]);
+        let mut inserted = component.clone();
+        assert!(inserted.insert_clause(Clause::empty()));
+        assert_eq!(empty, inserted);
+        assert_eq!(inserted, component.clone() & empty.clone());
+        assert_eq!(inserted, empty & component);
+    }
+
+    #[test]
+    fn test_iter() {
+        let component = Component::from([["Amit", "Yue"], ["David", "Yue"]]);
+        let clauses: alloc::vec::Vec<Clause> = component.clone().into_iter().collect();
+        assert_eq!(alloc::vec![Clause::from(["Amit", "Yue"]), Clause::from(["David", "Yue"])], clauses);
+        assert_eq!(component, clauses.into_iter().collect());
+
+        // Reduced as it's collected.
+        let reduced: Component = alloc::vec![Clause::from(["Amit", "Yue"]), Clause::from(["Yue"])].into_iter().collect();
+        assert_eq!(Component::from([["Yue"]]), reduced);
+
+        assert_eq!(0, Component::dc_true().into_iter().count());
+        assert_eq!(Component::dc_true(), core::iter::empty().collect());
+        assert_eq!(Component::from([Clause::empty()]), Component::dc_false().into_iter().collect());
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut component = Component::dc_true();
+        for clause in alloc::vec![Clause::from(["Amit", "Yue"]), Clause::from(["David"]), Clause::from(["Yue"])] {
+            component.extend(Some(clause));
+        }
+        assert!(component.is_reduced());
+        assert_eq!(Component::from([["David"], ["Yue"]]), component);
+
+        let expected = component.clone() & Component::from([Clause::empty()]);
+        component.extend(alloc::vec![Clause::empty(), Clause::from(["Amit"])]);
+        assert_eq!(expected, component);
+        assert_ne!(Component::dc_false(), component);
+
+        let mut component = Component::dc_false();
+        component.extend(Some(Clause::from(["Amit"])));
+        assert_eq!(Component::dc_false(), component);
+    }
+
+    #[test]
+    fn test_assign_ops() {
+        let mut component = Component::dc_true();
+        component &= Component::from([["Amit", "Yue"]]);
+        component &= Component::from([["Yue"]]);
+        assert_eq!(Component::from([["Yue"]]), component);
+        component |= Component::from([["Ziv"]]);
+        assert_eq!(Component::from([["Yue", "Ziv"]]), component);
+        component |= Component::dc_false();
+        assert_eq!(Component::from([["Yue", "Ziv"]]), component);
+        component &= Component::dc_false();
+        assert!(component.is_false());
+    }
+
+    /// Counts the bytes it has live, to tell which allocator owns what.
+    #[derive(Debug, Clone, Copy)]
+    struct Counting<'a>(&'a core::cell::Cell<usize>);
+
+    // Safe since it forwards to `Global`.
+    unsafe impl Allocator for Counting<'_> {
+        fn allocate(&self, layout: core::alloc::Layout) -> Result<core::ptr::NonNull<[u8]>, allocator_api2::alloc::AllocError> {
+            self.0.set(self.0.get() + layout.size());
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: core::ptr::NonNull<u8>, layout: core::alloc::Layout) {
+            self.0.set(self.0.get() - layout.size());
+            unsafe { Global.deallocate(ptr, layout) }
+        }
+    }
+
+    #[test]
+    fn test_operands_in_other_allocator() {
+        let (live1, live2) = (core::cell::Cell::new(0), core::cell::Cell::new(0));
+        let component = |input, live| crate::buckle2::Buckle2::parse_component(input, Counting(live)).unwrap();
+        let results = [
+            component("a&b", &live1) & component("b|c&d", &live2),
+            component("T", &live1) & component("b|c&d", &live2),
+            component("a&b", &live1) | component("b|c&d", &live2),
+            {
+                let mut conjoined = component("T", &live1);
+                conjoined.conjoin(&component("b|c&d", &live2));
+                conjoined
+            },
+        ];
+        // The results own nothing of the second allocator.
+        assert_eq!(0, live2.get());
+        assert_eq!(results[1], results[3].clone_in(Global).clone_in(Counting(&live1)));
+        drop(results);
+        assert_eq!(0, live1.get());
+    }
+
     #[test]
     fn test_or() {
         assert_eq!(
             Component::from([["Amit", "Yue"], ["David", "Yue"]]),
             Component::from([["Amit"], ["David"]]) | Component::from([["Yue"]])
         );
+        assert_eq!(
+            Component::from([["Amit", "Yue"], ["Amit", "Ziv"], ["David", "Yue"], ["David", "Ziv"]]),
+            Component::from([["Amit"], ["David"]]) | Component::from([["Yue"], ["Ziv"]])
+        );
     }
 
     quickcheck! {
@@ -291,6 +763,13 @@ mod tests {
             component.implies(&other) && other.implies(&component)
         }
 
+        fn budgeted_reduce_is_equivalent(component: Component, budget: u8) -> bool {
+            let (mut budgeted, mut reduced) = (component.clone(), component.clone());
+            let finished = budgeted.reduce_with_budget(budget as usize);
+            reduced.reduce();
+            budgeted.implies(&component) && component.implies(&budgeted) && (!finished || budgeted == reduced)
+        }
+
         fn true_not_implies_not_true(component: Component) -> bool {
             if component.is_true() {
                 true
@@ -320,6 +799,53 @@ mod tests {
             component1.implies(&component2)
         }
 
+        fn and_stays_reduced(component1: Component, component2: Component) -> bool {
+            let (mut component1, mut component2) = (component1, component2);
+            component1.reduce();
+            component2.reduce();
+            let conjoined = component1.clone() & component2.clone();
+            let mut reduced = conjoined.clone();
+            reduced.reduce();
+            reduced == conjoined && conjoined.implies(&component1) && conjoined.implies(&component2)
+        }
+
+        fn proof_agrees_with_implies(component1: Component, component2: Component) -> bool {
+            let weaker = component1.clone() | component2.clone();
+            [(&component1, &component2), (&component1, &weaker)].iter().all(|(a, b)| match a.implies_with_proof(b) {
+                Some(proof) => a.implies(b) && a.check_proof(b, &proof),
+                None => !a.implies(b),
+            })
+        }
+
+        fn extend_agrees_with_and(component: Component, clauses: alloc::vec::Vec<Clause>) -> bool {
+            let mut component = component;
+            component.reduce();
+            let mut extended = component.clone();
+            extended.extend(clauses.iter().cloned());
+            let conjoined = clauses.into_iter().fold(component, |c, clause| c & Component::from([clause]));
+            extended.is_reduced() && extended.implies(&conjoined) && conjoined.implies(&extended)
+        }
+
+        fn implies_with_agrees(component: Component, assumed: Component, other: Component) -> bool {
+            component.implies_with(&assumed, &other) == (component & assumed).implies(&other)
+        }
+
+        fn implies_agrees_with_pairwise(component1: Component, component2: Component) -> bool {
+            let expected = match (&component1, &component2) {
+                (Component::DCFalse, _) => true,
+                (_, Component::DCFalse) => false,
+                (Component::DCFormula(s, _), Component::DCFormula(o, _)) => o.iter().all(|oclause| s.iter().any(|sclause| sclause.implies(oclause))),
+            };
+            component1.implies(&component2) == expected
+        }
+
+        fn reduce_leaves_reduced(component: Component, budget: u8) -> bool {
+            let (mut reduced, mut budgeted) = (component.clone(), component);
+            reduced.reduce();
+            let finished = budgeted.reduce_with_budget(budget as usize);
+            reduced.is_reduced() && (!finished || budgeted.is_reduced())
+        }
+
         fn reduce_simplifies(component: Component) -> bool {
             let mut component = component.clone();
             component.reduce();
@@ -0,0 +1,277 @@
+//! A sorted set stored in a single allocation.
+//!
+//! An allocator-aware `BTreeSet` allocates a node for every handful of
+//! entries, which quickly exhausts small bump arenas. `FlatSet` keeps its
+//! entries sorted and unique in one `Vec`, so a clause or component costs a
+//! single allocation, lookups are binary searches and iteration walks a
+//! slice. Insertion and removal shift the entries after them, which is cheap
+//! at the sizes labels have. It supports the parts of the `BTreeSet` API
+//! buckle2 uses and orders like a `BTreeSet` with the same entries.
+
+#[cfg(any(test, feature = "quickcheck"))]
+use alloc::boxed::Box;
+use allocator_api2::alloc::Global;
+use allocator_api2::vec::Vec;
+use allocator_api2::alloc::Allocator;
+#[cfg(any(test, feature = "quickcheck"))]
+use quickcheck::Arbitrary;
+
+#[derive(Clone)]
+pub struct FlatSet<T, A: Allocator = Global> {
+    items: Vec<T, A>,
+}
+
+// Implemented by hand, as deriving would require `A` to implement them.
+
+impl<T: PartialEq, A: Allocator> PartialEq for FlatSet<T, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.items == other.items
+    }
+}
+
+impl<T: Eq, A: Allocator> Eq for FlatSet<T, A> {}
+
+impl<T: Ord, A: Allocator> PartialOrd for FlatSet<T, A> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Ord, A: Allocator> Ord for FlatSet<T, A> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.items.iter().cmp(other.items.iter())
+    }
+}
+
+impl<T: core::hash::Hash, A: Allocator> core::hash::Hash for FlatSet<T, A> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.items.hash(state)
+    }
+}
+
+impl<T: core::fmt::Debug, A: Allocator> core::fmt::Debug for FlatSet<T, A> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_set().entries(self.items.iter()).finish()
+    }
+}
+
+impl<T> FlatSet<T> {
+    pub const fn new() -> Self {
+        FlatSet { items: Vec::new() }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        FlatSet { items: Vec::with_capacity(capacity) }
+    }
+}
+
+impl<T> Default for FlatSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, A: Allocator> FlatSet<T, A> {
+    pub const fn new_in(alloc: A) -> Self {
+        FlatSet { items: Vec::new_in(alloc) }
+    }
+
+    /// Returns an empty set with room for `capacity` entries.
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        FlatSet { items: Vec::with_capacity_in(capacity, alloc) }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.items.capacity()
+    }
+
+    /// Reserves room for at least `additional` more entries.
+    pub fn reserve(&mut self, additional: usize) {
+        self.items.reserve(additional)
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn iter(&self) -> core::slice::Iter<'_, T> {
+        self.items.iter()
+    }
+
+    /// The entries in ascending order.
+    pub fn as_slice(&self) -> &[T] {
+        &self.items
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.items.first()
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.items.last()
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear()
+    }
+
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, f: F) {
+        self.items.retain(f)
+    }
+}
+
+impl<T: Ord, A: Allocator> FlatSet<T, A> {
+    /// Adds `value`, returning whether it wasn't already present.
+    pub fn insert(&mut self, value: T) -> bool {
+        match self.items.binary_search(&value) {
+            Ok(_) => false,
+            Err(i) => {
+                self.items.insert(i, value);
+                true
+            }
+        }
+    }
+
+    /// Removes `value`, returning whether it was present.
+    pub fn remove(&mut self, value: &T) -> bool {
+        match self.items.binary_search(value) {
+            Ok(i) => {
+                self.items.remove(i);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.items.binary_search(value).is_ok()
+    }
+
+    pub fn is_subset<B: Allocator>(&self, other: &FlatSet<T, B>) -> bool {
+        self.len() <= other.len() && self.iter().all(|v| other.contains(v))
+    }
+
+    /// Moves all entries of `other` into `self`.
+    pub fn append(&mut self, other: &mut Self) {
+        self.items.append(&mut other.items);
+        self.normalize();
+    }
+
+    fn normalize(&mut self) {
+        self.items.sort();
+        self.items.dedup();
+    }
+}
+
+impl<T: Ord, A: Allocator> Extend<T> for FlatSet<T, A> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.items.extend(iter);
+        self.normalize();
+    }
+}
+
+impl<'a, T: Ord + Copy + 'a, A: Allocator> Extend<&'a T> for FlatSet<T, A> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied())
+    }
+}
+
+impl<T: Ord> core::iter::FromIterator<T> for FlatSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = FlatSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T, A: Allocator> IntoIterator for FlatSet<T, A> {
+    type Item = T;
+    type IntoIter = allocator_api2::vec::IntoIter<T, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for &'a FlatSet<T, A> {
+    type Item = &'a T;
+    type IntoIter = core::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+impl<T: Ord, A: Allocator> From<Vec<T, A>> for FlatSet<T, A> {
+    fn from(items: Vec<T, A>) -> Self {
+        let mut set = FlatSet { items };
+        set.normalize();
+        set
+    }
+}
+
+impl<T: crate::heap::Heap, A: Allocator> crate::heap::Heap for FlatSet<T, A> {
+    fn heap_size(&self) -> usize {
+        self.items.heap_size()
+    }
+
+    fn compact(&mut self) {
+        self.items.compact()
+    }
+}
+
+#[cfg(any(test, feature = "quickcheck"))]
+impl<T: Arbitrary + Ord> Arbitrary for FlatSet<T> {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        alloc::vec::Vec::<T>::arbitrary(g).into_iter().collect()
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        let items: alloc::vec::Vec<T> = self.items.to_vec();
+        Box::new(items.shrink().map(|items| items.into_iter().collect()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::collections::BTreeSet;
+
+    #[test]
+    fn test_insert_remove() {
+        let mut set = FlatSet::new();
+        assert!(set.insert(3));
+        assert!(set.insert(1));
+        assert!(!set.insert(3));
+        assert_eq!(&[1, 3], set.as_slice());
+        assert!(set.contains(&1));
+        assert!(set.remove(&1));
+        assert!(!set.remove(&1));
+        assert_eq!(&[3], set.as_slice());
+    }
+
+    #[test]
+    fn test_with_capacity() {
+        let mut set = FlatSet::with_capacity(4);
+        let capacity = set.capacity();
+        assert!(capacity >= 4);
+        (0..4).for_each(|i| assert!(set.insert(i)));
+        assert_eq!(capacity, set.capacity());
+        set.reserve(8);
+        assert!(set.capacity() >= 12);
+    }
+
+    quickcheck! {
+        fn matches_btreeset(v1: alloc::vec::Vec<u8>, v2: alloc::vec::Vec<u8>) -> bool {
+            let (f1, f2): (FlatSet<_>, FlatSet<_>) = (v1.iter().copied().collect(), v2.iter().copied().collect());
+            let (b1, b2): (BTreeSet<_>, BTreeSet<_>) = (v1.into_iter().collect(), v2.into_iter().collect());
+            f1.iter().eq(b1.iter())
+                && f1.cmp(&f2) == b1.cmp(&b2)
+                && f1.is_subset(&f2) == b1.is_subset(&b2)
+        }
+    }
+}
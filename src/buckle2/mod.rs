@@ -4,24 +4,34 @@
 //! components which are conjunctions of disjunctions of principals. However,
 //! unlike DCLabels, Buckle principals are not strings, but rather ordered
 //! lists, where prefixes imply longer lists.
+//!
+//! The allocator types are `allocator_api2`'s, which build on stable Rust.
+//! With the `nightly` feature they're the standard library's own, so
+//! `Principal<A>` is `alloc::vec::Vec<u8, A>` and any `core::alloc::Allocator`
+//! works.
 
-#[cfg(test)]
+#[cfg(any(test, feature = "quickcheck"))]
 use alloc::boxed::Box;
-use alloc::vec::Vec;
-#[cfg(test)]
+use allocator_api2::vec::Vec;
+#[cfg(any(test, feature = "quickcheck"))]
+use crate::generate::{GenConfig, Generate};
+#[cfg(any(test, feature = "quickcheck"))]
 use quickcheck::Arbitrary;
 // use serde::{Deserialize, Serialize};
 
-use core::alloc::Allocator;
-use alloc::alloc::Global;
+use allocator_api2::alloc::Allocator;
+use allocator_api2::alloc::Global;
 
-use super::{HasPrivilege, Label};
+use super::{HasPrivilege, Label, LabelStats};
+use crate::proof::FlowCounterexample;
 
 pub mod clause;
 pub mod component;
+pub mod flatset;
 
 pub use clause::*;
 pub use component::*;
+pub use flatset::FlatSet;
 
 pub type Principal<A> = Vec<u8, A>;
 
@@ -38,65 +48,65 @@ impl<A: Allocator + Clone> PartialEq for Buckle2<A> {
     }
 }
 
+impl<A: Allocator + Clone> Eq for Buckle2<A> {}
+
 impl Buckle2 {
-    pub fn parse(input: &str) -> Result<Buckle2, ()> {
+    pub fn parse(input: &str) -> Result<Buckle2, crate::Error> {
         Self::parse_in(input, Global)
     }
 }
 
 impl<A: Allocator + Clone> Buckle2<A> {
-    /// Parses a string into a DCLabel.
+    /// Parses a string into a Buckle2 label.
+    ///
+    /// The string is in the canonical text syntax of `crate::text`, read
+    /// leniently. The label isn't reduced.
     ///
-    /// principles with '/'. The backslash character ('\') allows escaping these
-    /// special characters (including itself).
-    pub fn parse_in(input: &str, alloc: A) -> Result<Buckle2<A>, ()> {
-        let mut s = input.split(',');
-        match (s.next(), s.next(), s.next()) {
-            (Some(s), Some(i), None) => Ok(Buckle2 {
-                    secrecy: Self::parse_component(s, alloc.clone()),
-                    integrity: Self::parse_component(i, alloc.clone()),
-                    alloc,
-            }),
-            _ => Err(()),
+    /// Fails with the offset of the first syntax error.
+    pub fn parse_in(input: &str, alloc: A) -> Result<Buckle2<A>, crate::Error> {
+        use crate::text::{buckle2::ComponentBuilder, label, Mode};
+
+        let builder = || ComponentBuilder::new_in(alloc.clone());
+        match label(input, Mode::Lenient, builder(), builder()) {
+            Ok((secrecy, integrity)) => Ok(Buckle2 { secrecy, integrity, alloc }),
+            Err((_, offset)) => Err(crate::Error::Parse(offset)),
         }
     }
 
-    fn parse_component(input: &str, alloc: A) -> Component<A> {
-        use alloc::collections::BTreeSet;
+    /// Parses a single component in the syntax of `parse_in`.
+    #[cfg(any(test, feature = "ffi"))]
+    pub(crate) fn parse_component(input: &str, alloc: A) -> Result<Component<A>, crate::Error> {
+        use crate::text::{buckle2::ComponentBuilder, component_prefix, Mode};
 
-        if let Some(_) = input.find('T') {
-            Component::dc_true_in(alloc)
-        } else if let Some(_) =  input.find('F') {
-            Component::dc_false()
-        } else {
-            let mut formula = BTreeSet::new_in(alloc.clone());
-            let alloc_dup = alloc.clone();
-            input.split('&')
-                .for_each(|t| {
-                    let mut clause_vec = Vec::new_in(alloc_dup.clone());
-                    t.split('|').for_each(|t| {
-                        let mut clause_inner = Vec::new_in(alloc_dup.clone());
-                        t.split('/').for_each(|t| {
-                            clause_inner.push(t.as_bytes().to_vec_in(alloc_dup.clone()))
-                        });
-                        clause_vec.push(clause_inner)
-                    });
-                    formula.insert(Clause::new_from_vec_in(clause_vec, alloc_dup.clone()));
-                });
-            Component::DCFormula(formula, alloc)
+        match component_prefix(input, Mode::Lenient, ComponentBuilder::new_in(alloc)) {
+            Ok((component, end)) if end == input.len() => Ok(component),
+            Ok((_, offset)) | Err((_, offset)) => Err(crate::Error::Parse(offset)),
         }
     }
 }
 
-#[cfg(test)]
-impl Arbitrary for Buckle2 {
-    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+impl<A: Allocator + Clone> core::fmt::Display for Buckle2<A> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{},{}", self.secrecy, self.integrity)
+    }
+}
+
+#[cfg(any(test, feature = "quickcheck"))]
+impl Generate for Buckle2 {
+    fn generate(g: &mut quickcheck::Gen, config: &GenConfig) -> Self {
         Buckle2 {
-            secrecy: Component::arbitrary(g),
-            integrity: Component::arbitrary(g),
+            secrecy: Component::generate(g, config),
+            integrity: Component::generate(g, config),
             alloc: Global,
         }
     }
+}
+
+#[cfg(any(test, feature = "quickcheck"))]
+impl Arbitrary for Buckle2 {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        GenConfig::default().generate(g)
+    }
 
     fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
         Box::new(
@@ -112,16 +122,16 @@ impl Buckle2 {
         Self::new_in(secrecy, integrity, Global)
     }
 
-    pub fn public() -> Buckle2 {
-        Self::public_in(Global)
+    pub const fn public() -> Buckle2 {
+        Buckle2 { secrecy: Component::dc_true(), integrity: Component::dc_true(), alloc: Global }
     }
 
-    pub fn top() -> Buckle2 {
-        Self::top_in(Global)
+    pub const fn top() -> Buckle2 {
+        Buckle2 { secrecy: Component::dc_false(), integrity: Component::dc_true(), alloc: Global }
     }
 
-    pub fn bottom() -> Buckle2 {
-        Self::bottom_in(Global)
+    pub const fn bottom() -> Buckle2 {
+        Buckle2 { secrecy: Component::dc_true(), integrity: Component::dc_false(), alloc: Global }
     }
 }
 
@@ -146,79 +156,212 @@ impl<A: Allocator + Clone> Buckle2<A> {
         Self::new_in(Component::dc_true_in(alloc.clone()), Component::dc_false(), alloc)
     }
 
+    /// The secrecy component, as `DCLabel::secrecy` and `Buckle::secrecy`
+    /// return it.
+    pub fn secrecy(&self) -> &Component<A> {
+        &self.secrecy
+    }
+
+    /// The integrity component.
+    pub fn integrity(&self) -> &Component<A> {
+        &self.integrity
+    }
+
+    /// Returns a copy of the label allocated entirely in `alloc`.
+    pub fn clone_in<B: Allocator + Clone>(&self, alloc: B) -> Buckle2<B> {
+        Buckle2 {
+            secrecy: self.secrecy.clone_in(alloc.clone()),
+            integrity: self.integrity.clone_in(alloc.clone()),
+            alloc,
+        }
+    }
+
+    /// Returns whether the label is public, i.e. both components are true.
+    pub fn is_public(&self) -> bool {
+        self.secrecy.is_true() && self.integrity.is_true()
+    }
+
+    /// Returns whether the label is the top label, i.e. false secrecy and
+    /// true integrity.
+    pub fn is_top(&self) -> bool {
+        self.secrecy.is_false() && self.integrity.is_true()
+    }
+
+    /// Returns whether the label is the bottom label, i.e. true secrecy and
+    /// false integrity.
+    pub fn is_bottom(&self) -> bool {
+        self.secrecy.is_true() && self.integrity.is_false()
+    }
+
+    /// Returns the approximate number of bytes the label owns on the heap,
+    /// e.g. to measure the memory held by stored labels.
+    pub fn approx_heap_size(&self) -> usize {
+        self.secrecy.approx_heap_size() + self.integrity.approx_heap_size()
+    }
+
+    /// Releases excess capacity, e.g. to compact labels kept after a burst
+    /// of `lub`s.
+    pub fn shrink_to_fit(&mut self) {
+        self.secrecy.shrink_to_fit();
+        self.integrity.shrink_to_fit();
+    }
+
     pub fn reduce(&mut self) {
         self.secrecy.reduce();
         self.integrity.reduce();
     }
 
+    /// Reduces both components as `Component::reduce_with_budget` does,
+    /// sharing `max_comparisons` between them, and returns whether both
+    /// finished.
+    pub fn reduce_with_budget(&mut self, max_comparisons: usize) -> bool {
+        let mut budget = max_comparisons;
+        let secrecy = self.secrecy.reduce_within(&mut budget);
+        let integrity = self.integrity.reduce_within(&mut budget);
+        secrecy && integrity
+    }
+
+    /// Returns whether both components are reduced.
+    pub fn is_reduced(&self) -> bool {
+        self.secrecy.is_reduced() && self.integrity.is_reduced()
+    }
+
+    /// Computes size and shape statistics for the label, e.g. to monitor
+    /// label growth or reject labels exceeding a budget.
+    pub fn stats(&self) -> LabelStats {
+        LabelStats::of::<Self, _>(self.secrecy.clauses(), self.integrity.clauses())
+    }
+
     pub fn endorse(mut self, privilege: &Component<A>) -> Buckle2<A> {
-        self.integrity = self.integrity & privilege.clone();
+        #[cfg(feature = "trace")]
+        let before = crate::telemetry::digest_before(&self);
+        self.integrity &= privilege.clone();
+        #[cfg(feature = "trace")]
+        crate::telemetry::trace_privileged("endorse", before, &self);
+        self
+    }
+
+    /// Returns why `self` can't flow to `rhs`: a clause of its secrecy that
+    /// `rhs` doesn't uphold, or of `rhs`'s integrity that it lacks, found as
+    /// `can_flow_to` would find them (see the `proof` module). Returns
+    /// `None` if `self` can flow to `rhs`.
+    pub fn flow_counterexample(&self, rhs: &Self) -> Option<FlowCounterexample<Component<A>>> {
+        rhs.secrecy
+            .unimplied_clause(&self.secrecy)
+            .map(FlowCounterexample::Secrecy)
+            .or_else(|| self.integrity.unimplied_clause(&rhs.integrity).map(FlowCounterexample::Integrity))
+    }
+
+    /// `downgrade`, without its decision event.
+    fn downgraded(mut self, privilege: &Component<A>) -> Buckle2<A> {
+        // An empty privilege downgrades nothing.
+        if privilege.is_true() {
+            return self;
+        }
+        // Dropping secrecy clauses and conjoining the privilege keep reduced
+        // components reduced.
+        #[cfg(debug_assertions)]
+        let reduced = self.is_reduced() && privilege.is_reduced();
+        match privilege {
+            // false can downgrade _anything_ to true
+            Component::DCFalse => self.secrecy = Component::dc_true_in(self.alloc.clone()),
+            // only false can downgrade false
+            Component::DCFormula(p, _) => {
+                if let Component::DCFormula(sec, _) = &mut self.secrecy {
+                    sec.retain(|c| !p.iter().any(|pclause| pclause.implies(c)));
+                }
+            }
+        }
+        self.integrity.conjoin(privilege);
+        #[cfg(debug_assertions)]
+        debug_assert!(!reduced || self.is_reduced());
         self
     }
 }
 
 impl<A: Allocator + Clone> Label for Buckle2<A> {
     fn lub(self, rhs: Self) -> Self {
-        let mut res = Buckle2 {
-            secrecy: self.secrecy & rhs.secrecy,
-            integrity: self.integrity | rhs.integrity,
-            alloc: self.alloc,
+        // Top absorbs every label and bottom is the identity, so neither
+        // needs the other label's clauses.
+        let mut res = if self.is_top() || rhs.is_bottom() {
+            self
+        } else if rhs.is_top() || self.is_bottom() {
+            rhs
+        } else {
+            let mut label = self;
+            label.secrecy &= rhs.secrecy;
+            label.integrity |= rhs.integrity;
+            label
         };
         res.reduce();
         res
     }
 
     fn glb(self, rhs: Self) -> Self {
-        let mut res = Buckle2 {
-            secrecy: self.secrecy | rhs.secrecy,
-            integrity: self.integrity & rhs.integrity,
-            alloc: self.alloc
+        // Bottom absorbs every label and top is the identity.
+        let mut res = if self.is_bottom() || rhs.is_top() {
+            self
+        } else if rhs.is_bottom() || self.is_top() {
+            rhs
+        } else {
+            let mut label = self;
+            label.secrecy |= rhs.secrecy;
+            label.integrity &= rhs.integrity;
+            label
         };
         res.reduce();
         res
     }
 
     fn can_flow_to(&self, rhs: &Self) -> bool {
-        rhs.secrecy.implies(&self.secrecy) && self.integrity.implies(&rhs.integrity)
+        // Bottom flows to every label, and every label flows to top.
+        let allowed = self.is_bottom()
+            || rhs.is_top()
+            || (rhs.secrecy.implies(&self.secrecy) && self.integrity.implies(&rhs.integrity));
+        #[cfg(feature = "trace")]
+        crate::telemetry::trace_flow(self, rhs, allowed);
+        allowed
     }
 }
 
 impl<A: Allocator + Clone> HasPrivilege for Buckle2<A> {
     type Privilege = Component<A>;
 
-    fn downgrade(mut self, privilege: &Component<A>) -> Buckle2<A> {
-        self.secrecy = match (self.secrecy, privilege) {
-            //not real (DCTrue, _) => DCTrue, // can't go lower than true
-            (_, Component::DCFalse) => Component::dc_true_in(self.alloc.clone()), // false can downgrade _anything_ to true
-            (Component::DCFalse, _) => Component::dc_false(), // only false can downgrade false
-            (Component::DCFormula(mut sec, a), Component::DCFormula(p, _)) => {
-                sec.retain(|c| !p.iter().any(|pclause| pclause.implies(c)));
-                Component::DCFormula(sec, a)
-            }
-        };
-        self.integrity = privilege.clone() & self.integrity;
-        self
+    fn downgrade(self, privilege: &Component<A>) -> Buckle2<A> {
+        #[cfg(feature = "trace")]
+        let before = crate::telemetry::digest_before(&self);
+        let after = self.downgraded(privilege);
+        #[cfg(feature = "trace")]
+        crate::telemetry::trace_privileged("downgrade", before, &after);
+        after
     }
 
     fn downgrade_to(self, target: Self, privilege: &Self::Privilege) -> Self {
         if self.can_flow_to_with_privilege(&target, privilege) {
-            return target;
+            target
         } else {
-            return self;
+            self
         }
     }
 
     fn can_flow_to_with_privilege(&self, rhs: &Self, privilege: &Component<A>) -> bool {
-        (rhs.secrecy.clone() & privilege.clone()).implies(&self.secrecy)
-            && (self.integrity.clone() & privilege.clone()).implies(&rhs.integrity)
+        // An empty privilege permits no more flows, and false permits all.
+        if privilege.is_true() {
+            return self.can_flow_to(rhs);
+        } else if privilege.is_false() {
+            return true;
+        }
+        rhs.secrecy.implies_with(privilege, &self.secrecy)
+            && self.integrity.implies_with(privilege, &rhs.integrity)
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
     use alloc::vec;
-    use alloc::alloc::Global;
+    use allocator_api2::alloc::Global;
 
     #[test]
     fn test_can_flow_to_with_privilege() {
@@ -412,6 +555,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_trivial() {
+        let lbl = Buckle2::parse("Amit/Grades&Yue,Amit").unwrap();
+        assert!(Buckle2::public().is_public());
+        assert!(Buckle2::top().is_top() && Buckle2::bottom().is_bottom());
+        assert!(!lbl.is_public() && !lbl.is_top() && !lbl.is_bottom());
+        assert_eq!(Buckle2::top(), lbl.clone().lub(Buckle2::top()));
+        assert_eq!(lbl, Buckle2::bottom().lub(lbl.clone()));
+        assert_eq!(Buckle2::bottom(), lbl.clone().glb(Buckle2::bottom()));
+        assert_eq!(lbl, Buckle2::top().glb(lbl.clone()));
+        assert_eq!(lbl, lbl.clone().downgrade(&Component::dc_true()));
+        assert!(lbl.can_flow_to_with_privilege(&Buckle2::bottom(), &Component::dc_false()));
+        assert!(!lbl.can_flow_to_with_privilege(&Buckle2::bottom(), &Component::dc_true()));
+    }
+
+    #[test]
+    fn test_const() {
+        const PUBLIC: Buckle2 = Buckle2::public();
+        static TOP: Buckle2 = Buckle2::top();
+        assert_eq!(Buckle2::parse("T,T").unwrap(), PUBLIC);
+        assert!(TOP.is_top() && Buckle2::bottom().is_bottom());
+    }
+
     #[test]
     fn test_lub() {
         assert_eq!(Buckle2::top(), Buckle2::public().lub(Buckle2::top()));
@@ -448,6 +614,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_shrink_to_fit() {
+        assert_eq!(0, Buckle2::top().approx_heap_size());
+        let mut label = Buckle2::parse("Amit/staff|Yue,T").unwrap();
+        if let Component::DCFormula(clauses, _) = &mut label.secrecy {
+            clauses.reserve(16);
+        }
+        let size = label.approx_heap_size();
+        label.shrink_to_fit();
+        assert!(label.approx_heap_size() < size);
+        assert_eq!(Buckle2::parse("Amit/staff|Yue,T").unwrap(), label);
+    }
+
     #[test]
     fn test_parse() {
         assert_eq!(Buckle2::parse("T,T"), Ok(Buckle2::public()));
@@ -511,9 +690,28 @@ mod tests {
             result.can_flow_to(&lbl1) && result.can_flow_to(&lbl2)
         }
 
+        fn counterexample_iff_denied(lbl1: Buckle2, lbl2: Buckle2) -> bool {
+            match lbl1.flow_counterexample(&lbl2) {
+                None => lbl1.can_flow_to(&lbl2),
+                Some(FlowCounterexample::Secrecy(clause)) => !lbl2.secrecy.implies(&clause) && !lbl1.can_flow_to(&lbl2),
+                Some(FlowCounterexample::Integrity(clause)) => !lbl1.integrity.implies(&clause) && !lbl1.can_flow_to(&lbl2),
+            }
+        }
+
         fn endorse_equiv_downgrade_to(lbl: Buckle2, privilege: Component) -> bool {
             let target = Buckle2 { secrecy: lbl.secrecy.clone(), integrity: lbl.integrity.clone() & privilege.clone(), alloc: Global };
             lbl.clone().downgrade_to(target, &privilege) == lbl.endorse(&privilege)
         }
+
+        fn can_flow_to_with_privilege_equiv_conjunction(lbl1: Buckle2, lbl2: Buckle2, privilege: Component) -> bool {
+            let expected = (lbl2.secrecy.clone() & privilege.clone()).implies(&lbl1.secrecy)
+                && (lbl1.integrity.clone() & privilege.clone()).implies(&lbl2.integrity);
+            lbl1.can_flow_to_with_privilege(&lbl2, &privilege) == expected
+        }
+
+        fn downgrade_integrity_equiv_conjunction(lbl: Buckle2, privilege: Component) -> bool {
+            let expected = privilege.clone() & lbl.integrity.clone();
+            lbl.downgrade(&privilege).integrity == expected
+        }
     }
 }
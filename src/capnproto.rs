@@ -0,0 +1,387 @@
+//! Cap'n Proto codec for labels.
+//!
+//! The wire format is described by `schema/label.capnp`, from which the build
+//! script generates `label_capnp` with `capnpc`, so building the `capnp`
+//! feature needs the `capnp` schema compiler. `dclabel` and `buckle` re-export
+//! the generated `Reader` and `Builder` types, which implement the `capnp`
+//! pointer traits, so labels can be used as message roots or stored in
+//! `AnyPointer` fields of other messages (e.g. RPC headers) via
+//! `get_as`/`init_as`, and add methods to convert them to and from labels.
+//!
+//! Readers can evaluate `can_flow_to` directly on the wire representation,
+//! without materializing owned labels.
+//!
+//! `DCFalse` is written as an empty component with its `IsFalse` flag set, so
+//! a component holding the empty clause round-trips as itself. A component
+//! with the flag set and clauses is rejected.
+
+use ::capnp::{list_list, Result};
+
+::capnp::generated_code!(pub mod label_capnp);
+
+/// Returns a component's clauses, or `None` for `DCFalse`.
+fn component<T: ::capnp::traits::Owned>(
+    is_false: bool,
+    clauses: list_list::Reader<'_, T>,
+) -> Result<Option<list_list::Reader<'_, T>>> {
+    match (is_false, clauses.is_empty()) {
+        (false, _) => Ok(Some(clauses)),
+        (true, true) => Ok(None),
+        (true, false) => Err(::capnp::Error::failed("a false component has clauses".into())),
+    }
+}
+
+/// Evaluates `s.implies(o)` over two wire components, with `None` for
+/// `DCFalse`, given a clause-level implication.
+fn component_implies<'a, T, F>(
+    s: Option<list_list::Reader<'a, T>>,
+    o: Option<list_list::Reader<'a, T>>,
+    clause_implies: F,
+) -> Result<bool>
+where
+    T: ::capnp::traits::Owned,
+    T::Reader<'a>: Copy,
+    F: Fn(T::Reader<'a>, T::Reader<'a>) -> Result<bool>,
+{
+    let (s, o) = match (s, o) {
+        (None, _) => return Ok(true),
+        (_, None) => return Ok(false),
+        (Some(s), Some(o)) => (s, o),
+    };
+    for oclause in o.iter() {
+        let oclause = oclause?;
+        let mut implied = false;
+        for sclause in s.iter() {
+            if clause_implies(sclause?, oclause)? {
+                implied = true;
+                break;
+            }
+        }
+        if !implied {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(feature = "dclabel")]
+pub mod dclabel {
+    use super::*;
+    use crate::dclabel::{Clause, Component, DCLabel};
+    use ::capnp::text_list;
+    use alloc::collections::BTreeSet;
+
+    pub use super::label_capnp::d_c_label::{Builder, Owned, Reader};
+
+    type Clauses<'a> = list_list::Reader<'a, text_list::Owned>;
+
+    fn clause_implies(s: text_list::Reader, o: text_list::Reader) -> Result<bool> {
+        for sp in s.iter() {
+            let sp = sp?.as_bytes();
+            let mut found = false;
+            for op in o.iter() {
+                if op?.as_bytes() == sp {
+                    found = true;
+                    break;
+                }
+            }
+            if !found {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn read_component(c: Option<Clauses>) -> Result<Component> {
+        let c = match c {
+            None => return Ok(Component::DCFalse),
+            Some(c) => c,
+        };
+        let mut clauses = BTreeSet::new();
+        for clause in c.iter() {
+            let mut principals = BTreeSet::new();
+            for p in clause?.iter() {
+                principals.insert(p?.to_str()?.into());
+            }
+            clauses.insert(Clause::from(principals));
+        }
+        Ok(Component::from(clauses))
+    }
+
+    impl<'a> Reader<'a> {
+        fn secrecy(&self) -> Result<Option<Clauses<'a>>> {
+            component(self.get_secrecy_is_false(), self.get_secrecy()?)
+        }
+
+        fn integrity(&self) -> Result<Option<Clauses<'a>>> {
+            component(self.get_integrity_is_false(), self.get_integrity()?)
+        }
+
+        /// Decodes the label into an owned `DCLabel`.
+        pub fn get(&self) -> Result<DCLabel> {
+            Ok(DCLabel::new(read_component(self.secrecy()?)?, read_component(self.integrity()?)?))
+        }
+
+        /// Equivalent to `DCLabel::can_flow_to`, evaluated on the wire.
+        pub fn can_flow_to(&self, rhs: &Reader) -> Result<bool> {
+            Ok(component_implies(rhs.secrecy()?, self.secrecy()?, clause_implies)?
+                && component_implies(self.integrity()?, rhs.integrity()?, clause_implies)?)
+        }
+    }
+
+    /// Writes the clauses of `component`, returning whether it is `DCFalse`.
+    fn write_component<'a>(
+        list: impl FnOnce(u32) -> list_list::Builder<'a, text_list::Owned>,
+        component: &Component,
+    ) -> bool {
+        let clauses = match component {
+            Component::DCFalse => {
+                list(0);
+                return true;
+            }
+            Component::DCFormula(clauses) => clauses,
+        };
+        let mut list = list(clauses.len() as u32);
+        for (i, clause) in clauses.iter().enumerate() {
+            let mut principals = list.reborrow().init(i as u32, clause.0.len() as u32);
+            for (j, p) in clause.0.iter().enumerate() {
+                principals.set(j as u32, p.as_str());
+            }
+        }
+        false
+    }
+
+    impl Builder<'_> {
+        /// Overwrites the label with `label`.
+        pub fn set(&mut self, label: &DCLabel) {
+            let is_false = write_component(|len| self.reborrow().init_secrecy(len), &label.secrecy);
+            self.set_secrecy_is_false(is_false);
+            let is_false = write_component(|len| self.reborrow().init_integrity(len), &label.integrity);
+            self.set_integrity_is_false(is_false);
+        }
+    }
+}
+
+#[cfg(feature = "buckle")]
+pub mod buckle {
+    use super::*;
+    use crate::buckle::{Buckle, Clause, Component};
+    use ::capnp::text_list;
+    use alloc::collections::BTreeSet;
+    use alloc::vec::Vec;
+
+    pub use super::label_capnp::buckle::{Builder, Owned, Reader};
+
+    type PathList = list_list::Owned<text_list::Owned>;
+    type Clauses<'a> = list_list::Reader<'a, PathList>;
+
+    /// Whether path `s` is a prefix of path `o`.
+    fn path_prefixes(s: text_list::Reader, o: text_list::Reader) -> Result<bool> {
+        if s.len() > o.len() {
+            return Ok(false);
+        }
+        for i in 0..s.len() {
+            if s.get(i)?.as_bytes() != o.get(i)?.as_bytes() {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn clause_implies(s: list_list::Reader<text_list::Owned>, o: list_list::Reader<text_list::Owned>) -> Result<bool> {
+        for spath in s.iter() {
+            let spath = spath?;
+            let mut found = false;
+            for opath in o.iter() {
+                if path_prefixes(spath, opath?)? {
+                    found = true;
+                    break;
+                }
+            }
+            if !found {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn read_component(c: Option<Clauses>) -> Result<Component> {
+        let c = match c {
+            None => return Ok(Component::DCFalse),
+            Some(c) => c,
+        };
+        let mut clauses = BTreeSet::new();
+        for clause in c.iter() {
+            let mut paths = BTreeSet::new();
+            for path in clause?.iter() {
+                let mut principals = Vec::new();
+                for p in path?.iter() {
+                    principals.push(p?.to_str()?.into());
+                }
+                paths.insert(principals);
+            }
+            clauses.insert(Clause::from(paths));
+        }
+        Ok(Component::from(clauses))
+    }
+
+    impl<'a> Reader<'a> {
+        fn secrecy(&self) -> Result<Option<Clauses<'a>>> {
+            component(self.get_secrecy_is_false(), self.get_secrecy()?)
+        }
+
+        fn integrity(&self) -> Result<Option<Clauses<'a>>> {
+            component(self.get_integrity_is_false(), self.get_integrity()?)
+        }
+
+        /// Decodes the label into an owned `Buckle`.
+        pub fn get(&self) -> Result<Buckle> {
+            Ok(Buckle::new(read_component(self.secrecy()?)?, read_component(self.integrity()?)?))
+        }
+
+        /// Equivalent to `Buckle::can_flow_to`, evaluated on the wire.
+        pub fn can_flow_to(&self, rhs: &Reader) -> Result<bool> {
+            Ok(component_implies(rhs.secrecy()?, self.secrecy()?, clause_implies)?
+                && component_implies(self.integrity()?, rhs.integrity()?, clause_implies)?)
+        }
+    }
+
+    /// Writes the clauses of `component`, returning whether it is `DCFalse`.
+    fn write_component<'a>(
+        list: impl FnOnce(u32) -> list_list::Builder<'a, PathList>,
+        component: &Component,
+    ) -> bool {
+        let clauses = match component {
+            Component::DCFalse => {
+                list(0);
+                return true;
+            }
+            Component::DCFormula(clauses) => clauses,
+        };
+        let mut list = list(clauses.len() as u32);
+        for (i, clause) in clauses.iter().enumerate() {
+            let mut paths = list.reborrow().init(i as u32, clause.0.len() as u32);
+            for (j, path) in clause.0.iter().enumerate() {
+                let mut principals = paths.reborrow().init(j as u32, path.len() as u32);
+                for (k, p) in path.iter().enumerate() {
+                    principals.set(k as u32, p.as_str());
+                }
+            }
+        }
+        false
+    }
+
+    impl Builder<'_> {
+        /// Overwrites the label with `label`.
+        pub fn set(&mut self, label: &Buckle) {
+            let is_false = write_component(|len| self.reborrow().init_secrecy(len), &label.secrecy);
+            self.set_secrecy_is_false(is_false);
+            let is_false = write_component(|len| self.reborrow().init_integrity(len), &label.integrity);
+            self.set_integrity_is_false(is_false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::capnp::message;
+
+    #[cfg(feature = "dclabel")]
+    quickcheck! {
+        fn dclabel_round_trip(lbl1: crate::dclabel::DCLabel, lbl2: crate::dclabel::DCLabel) -> bool {
+            use crate::Label;
+
+            let mut m1 = message::Builder::new_default();
+            m1.init_root::<super::dclabel::Builder>().set(&lbl1);
+            let mut m2 = message::Builder::new_default();
+            m2.init_root::<super::dclabel::Builder>().set(&lbl2);
+
+            let r1 = m1.get_root_as_reader::<super::dclabel::Reader>().unwrap();
+            let r2 = m2.get_root_as_reader::<super::dclabel::Reader>().unwrap();
+            let (d1, d2) = (r1.get().unwrap(), r2.get().unwrap());
+            d1 == lbl1 && d2 == lbl2 && r1.can_flow_to(&r2).unwrap() == lbl1.can_flow_to(&lbl2)
+        }
+    }
+
+    #[cfg(feature = "buckle")]
+    quickcheck! {
+        fn buckle_round_trip(lbl1: crate::buckle::Buckle, lbl2: crate::buckle::Buckle) -> bool {
+            use crate::Label;
+
+            let mut m1 = message::Builder::new_default();
+            m1.init_root::<super::buckle::Builder>().set(&lbl1);
+            let mut m2 = message::Builder::new_default();
+            m2.init_root::<super::buckle::Builder>().set(&lbl2);
+
+            let r1 = m1.get_root_as_reader::<super::buckle::Reader>().unwrap();
+            let r2 = m2.get_root_as_reader::<super::buckle::Reader>().unwrap();
+            let (d1, d2) = (r1.get().unwrap(), r2.get().unwrap());
+            d1 == lbl1 && d2 == lbl2 && r1.can_flow_to(&r2).unwrap() == lbl1.can_flow_to(&lbl2)
+        }
+    }
+
+    #[cfg(feature = "buckle")]
+    #[test]
+    fn test_buckle_any_pointer() {
+        use crate::buckle::Buckle;
+
+        let lbl = Buckle::new([["amit/grades"]], false);
+        let mut message = message::Builder::new_default();
+        message
+            .init_root::<::capnp::any_pointer::Builder>()
+            .init_as::<super::buckle::Builder>()
+            .set(&lbl);
+        let reader = message
+            .get_root_as_reader::<::capnp::any_pointer::Reader>()
+            .unwrap()
+            .get_as::<super::buckle::Reader>()
+            .unwrap();
+        assert_eq!(lbl, reader.get().unwrap());
+    }
+
+    #[cfg(feature = "buckle")]
+    #[test]
+    fn test_buckle_can_flow_to_on_wire() {
+        use crate::buckle::Buckle;
+
+        let mut m1 = message::Builder::new_default();
+        m1.init_root::<super::buckle::Builder>().set(&Buckle::parse("amit/grades,amit/grader").unwrap());
+        let mut m2 = message::Builder::new_default();
+        m2.init_root::<super::buckle::Builder>().set(&Buckle::parse("amit&yue,T").unwrap());
+        let r1 = m1.get_root_as_reader::<super::buckle::Reader>().unwrap();
+        let r2 = m2.get_root_as_reader::<super::buckle::Reader>().unwrap();
+
+        assert!(r1.can_flow_to(&r2).unwrap());
+        assert!(!r2.can_flow_to(&r1).unwrap());
+    }
+
+    #[cfg(feature = "buckle")]
+    #[test]
+    fn test_buckle_empty_clause() {
+        use crate::buckle::{Buckle, Clause, Component};
+
+        let empty = Buckle::new(Component::from([Clause::empty()]), true);
+        let dc_false = Buckle::new(Component::dc_false(), true);
+        let mut m1 = message::Builder::new_default();
+        m1.init_root::<super::buckle::Builder>().set(&empty);
+        let mut m2 = message::Builder::new_default();
+        m2.init_root::<super::buckle::Builder>().set(&dc_false);
+        let r1 = m1.get_root_as_reader::<super::buckle::Reader>().unwrap();
+        let r2 = m2.get_root_as_reader::<super::buckle::Reader>().unwrap();
+
+        assert_eq!(empty, r1.get().unwrap());
+        assert_eq!(dc_false, r2.get().unwrap());
+        // Only `DCFalse` implies `DCFalse`.
+        assert!(r1.can_flow_to(&r2).unwrap());
+        assert!(!r2.can_flow_to(&r1).unwrap());
+
+        // Overwriting a label clears its flags.
+        m2.get_root::<super::buckle::Builder>().unwrap().set(&empty);
+        assert_eq!(empty, m2.get_root_as_reader::<super::buckle::Reader>().unwrap().get().unwrap());
+
+        // A false component with clauses is malformed.
+        let mut builder = m1.get_root::<super::buckle::Builder>().unwrap();
+        builder.set_secrecy_is_false(true);
+        assert!(m1.get_root_as_reader::<super::buckle::Reader>().unwrap().get().is_err());
+    }
+}
@@ -0,0 +1,345 @@
+//! CBOR encoding of labels via `minicbor`.
+//!
+//! All label types share the same item layout, which is considered stable:
+//!
+//! ```text
+//! label     = [secrecy, integrity]
+//! component = false            ; DCFalse
+//!           / [* clause]       ; DCFormula, the empty array being DCTrue
+//! ```
+//!
+//! Clauses differ by label type:
+//!
+//! - `dclabel`: `clause = [* tstr]`, one text string per principal.
+//! - `buckle`: `clause = [* [* tstr]]`, one array of text strings per
+//!   principal path.
+//! - `buckle2`: `clause = [* [* bstr]]`, one array of byte strings per
+//!   principal path.
+//!
+//! Only definite-length arrays are produced or accepted. Decoded `DCLabel`
+//! and `Buckle` labels are reduced, so a label that wasn't round-trips to its
+//! reduced form; `Buckle2` components are decoded as written.
+//!
+//! `Buckle2` decoding takes the allocator to build the label in as the decode
+//! context, e.g. `minicbor::decode_with(bytes, &mut alloc)`.
+
+#[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2"))]
+use minicbor::{
+    decode::{self, Decoder},
+    encode::{self, Encoder, Write},
+    Decode, Encode,
+};
+
+#[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2"))]
+fn array_len(d: &mut Decoder<'_>) -> Result<u64, decode::Error> {
+    d.array()?
+        .ok_or_else(|| decode::Error::message("indefinite-length arrays are not supported"))
+}
+
+/// Decodes a component, calling `clause` once per clause array.
+#[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2"))]
+fn decode_component<'b, F>(d: &mut Decoder<'b>, mut clause: F) -> Result<bool, decode::Error>
+where
+    F: FnMut(&mut Decoder<'b>) -> Result<(), decode::Error>,
+{
+    if d.datatype()? == minicbor::data::Type::Bool {
+        if d.bool()? {
+            return Err(decode::Error::message("expected `false` or a clause array"));
+        }
+        return Ok(false);
+    }
+    for _ in 0..array_len(d)? {
+        clause(d)?;
+    }
+    Ok(true)
+}
+
+#[cfg(feature = "dclabel")]
+mod dclabel {
+    use super::*;
+    use crate::dclabel::{Clause, Component, DCLabel};
+    use alloc::collections::BTreeSet;
+
+    impl<C> Encode<C> for Clause {
+        fn encode<W: Write>(&self, e: &mut Encoder<W>, _: &mut C) -> Result<(), encode::Error<W::Error>> {
+            e.array(self.0.len() as u64)?;
+            for p in self.0.iter() {
+                e.str(p)?;
+            }
+            Ok(())
+        }
+    }
+
+    impl<'b, C> Decode<'b, C> for Clause {
+        fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, decode::Error> {
+            let mut result = BTreeSet::new();
+            for _ in 0..array_len(d)? {
+                result.insert(d.str()?.into());
+            }
+            Ok(Clause::from(result))
+        }
+    }
+
+    impl<C> Encode<C> for Component {
+        fn encode<W: Write>(&self, e: &mut Encoder<W>, ctx: &mut C) -> Result<(), encode::Error<W::Error>> {
+            match self {
+                Component::DCFalse => {
+                    e.bool(false)?;
+                }
+                Component::DCFormula(clauses) => {
+                    e.array(clauses.len() as u64)?;
+                    for c in clauses.iter() {
+                        c.encode(e, ctx)?;
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl<'b, C> Decode<'b, C> for Component {
+        fn decode(d: &mut Decoder<'b>, ctx: &mut C) -> Result<Self, decode::Error> {
+            let mut clauses = BTreeSet::new();
+            if decode_component(d, |d| {
+                clauses.insert(Clause::decode(d, ctx)?);
+                Ok(())
+            })? {
+                Ok(Component::from(clauses))
+            } else {
+                Ok(Component::DCFalse)
+            }
+        }
+    }
+
+    impl<C> Encode<C> for DCLabel {
+        fn encode<W: Write>(&self, e: &mut Encoder<W>, ctx: &mut C) -> Result<(), encode::Error<W::Error>> {
+            e.array(2)?;
+            self.secrecy.encode(e, ctx)?;
+            self.integrity.encode(e, ctx)
+        }
+    }
+
+    impl<'b, C> Decode<'b, C> for DCLabel {
+        fn decode(d: &mut Decoder<'b>, ctx: &mut C) -> Result<Self, decode::Error> {
+            if array_len(d)? != 2 {
+                return Err(decode::Error::message("expected a two-element label array"));
+            }
+            Ok(DCLabel::new(Component::decode(d, ctx)?, Component::decode(d, ctx)?))
+        }
+    }
+}
+
+#[cfg(feature = "buckle")]
+mod buckle {
+    use super::*;
+    use crate::buckle::{Buckle, Clause, Component};
+    use alloc::collections::BTreeSet;
+    use alloc::vec::Vec;
+
+    impl<C> Encode<C> for Clause {
+        fn encode<W: Write>(&self, e: &mut Encoder<W>, _: &mut C) -> Result<(), encode::Error<W::Error>> {
+            e.array(self.0.len() as u64)?;
+            for path in self.0.iter() {
+                e.array(path.len() as u64)?;
+                for p in path.iter() {
+                    e.str(p)?;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl<'b, C> Decode<'b, C> for Clause {
+        fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, decode::Error> {
+            let mut result = BTreeSet::new();
+            for _ in 0..array_len(d)? {
+                let len = array_len(d)?;
+                let mut path = Vec::new();
+                for _ in 0..len {
+                    path.push(d.str()?.into());
+                }
+                result.insert(path);
+            }
+            Ok(Clause::from(result))
+        }
+    }
+
+    impl<C> Encode<C> for Component {
+        fn encode<W: Write>(&self, e: &mut Encoder<W>, ctx: &mut C) -> Result<(), encode::Error<W::Error>> {
+            match self {
+                Component::DCFalse => {
+                    e.bool(false)?;
+                }
+                Component::DCFormula(clauses) => {
+                    e.array(clauses.len() as u64)?;
+                    for c in clauses.iter() {
+                        c.encode(e, ctx)?;
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl<'b, C> Decode<'b, C> for Component {
+        fn decode(d: &mut Decoder<'b>, ctx: &mut C) -> Result<Self, decode::Error> {
+            let mut clauses = BTreeSet::new();
+            if decode_component(d, |d| {
+                clauses.insert(Clause::decode(d, ctx)?);
+                Ok(())
+            })? {
+                Ok(Component::from(clauses))
+            } else {
+                Ok(Component::DCFalse)
+            }
+        }
+    }
+
+    impl<C> Encode<C> for Buckle {
+        fn encode<W: Write>(&self, e: &mut Encoder<W>, ctx: &mut C) -> Result<(), encode::Error<W::Error>> {
+            e.array(2)?;
+            self.secrecy.encode(e, ctx)?;
+            self.integrity.encode(e, ctx)
+        }
+    }
+
+    impl<'b, C> Decode<'b, C> for Buckle {
+        fn decode(d: &mut Decoder<'b>, ctx: &mut C) -> Result<Self, decode::Error> {
+            if array_len(d)? != 2 {
+                return Err(decode::Error::message("expected a two-element label array"));
+            }
+            Ok(Buckle::new(Component::decode(d, ctx)?, Component::decode(d, ctx)?))
+        }
+    }
+}
+
+#[cfg(feature = "buckle2")]
+mod buckle2 {
+    use super::*;
+    use crate::buckle2::clause::principal_in;
+    use allocator_api2::vec::Vec;
+    use crate::buckle2::{Buckle2, Clause, Component};
+    use crate::buckle2::FlatSet;
+    use allocator_api2::alloc::Allocator;
+
+    impl<A: Allocator + Clone, C> Encode<C> for Clause<A> {
+        fn encode<W: Write>(&self, e: &mut Encoder<W>, _: &mut C) -> Result<(), encode::Error<W::Error>> {
+            e.array(self.0.len() as u64)?;
+            for path in self.0.iter() {
+                e.array(path.len() as u64)?;
+                for p in path.iter() {
+                    e.bytes(p)?;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl<'b, A: Allocator + Clone> Decode<'b, A> for Clause<A> {
+        fn decode(d: &mut Decoder<'b>, alloc: &mut A) -> Result<Self, decode::Error> {
+            let mut result = FlatSet::new_in(alloc.clone());
+            for _ in 0..array_len(d)? {
+                let len = array_len(d)?;
+                let mut path = Vec::new_in(alloc.clone());
+                for _ in 0..len {
+                    path.push(principal_in(d.bytes()?, alloc.clone()));
+                }
+                result.insert(path);
+            }
+            Ok(Clause(result))
+        }
+    }
+
+    impl<A: Allocator + Clone, C> Encode<C> for Component<A> {
+        fn encode<W: Write>(&self, e: &mut Encoder<W>, ctx: &mut C) -> Result<(), encode::Error<W::Error>> {
+            match self {
+                Component::DCFalse => {
+                    e.bool(false)?;
+                }
+                Component::DCFormula(clauses, _) => {
+                    e.array(clauses.len() as u64)?;
+                    for c in clauses.iter() {
+                        c.encode(e, ctx)?;
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl<'b, A: Allocator + Clone> Decode<'b, A> for Component<A> {
+        fn decode(d: &mut Decoder<'b>, alloc: &mut A) -> Result<Self, decode::Error> {
+            let mut clauses = FlatSet::new_in(alloc.clone());
+            if decode_component(d, |d| {
+                clauses.insert(Clause::decode(d, alloc)?);
+                Ok(())
+            })? {
+                Ok(Component::DCFormula(clauses, alloc.clone()))
+            } else {
+                Ok(Component::DCFalse)
+            }
+        }
+    }
+
+    impl<A: Allocator + Clone, C> Encode<C> for Buckle2<A> {
+        fn encode<W: Write>(&self, e: &mut Encoder<W>, ctx: &mut C) -> Result<(), encode::Error<W::Error>> {
+            e.array(2)?;
+            self.secrecy.encode(e, ctx)?;
+            self.integrity.encode(e, ctx)
+        }
+    }
+
+    impl<'b, A: Allocator + Clone> Decode<'b, A> for Buckle2<A> {
+        fn decode(d: &mut Decoder<'b>, alloc: &mut A) -> Result<Self, decode::Error> {
+            if array_len(d)? != 2 {
+                return Err(decode::Error::message("expected a two-element label array"));
+            }
+            let mut result = Buckle2::public_in(alloc.clone());
+            result.secrecy = Component::decode(d, alloc)?;
+            result.integrity = Component::decode(d, alloc)?;
+            Ok(result)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "dclabel")]
+    quickcheck! {
+        fn dclabel_round_trip(lbl: crate::dclabel::DCLabel) -> bool {
+            let bytes = minicbor::to_vec(&lbl).unwrap();
+            minicbor::decode::<crate::dclabel::DCLabel>(&bytes).unwrap() == lbl
+        }
+    }
+
+    #[cfg(feature = "buckle")]
+    quickcheck! {
+        fn buckle_round_trip(lbl: crate::buckle::Buckle) -> bool {
+            let bytes = minicbor::to_vec(&lbl).unwrap();
+            minicbor::decode::<crate::buckle::Buckle>(&bytes).unwrap() == lbl
+        }
+    }
+
+    #[cfg(feature = "buckle2")]
+    quickcheck! {
+        fn buckle2_round_trip(lbl: crate::buckle2::Buckle2) -> bool {
+            let bytes = minicbor::to_vec(&lbl).unwrap();
+            minicbor::decode_with::<_, crate::buckle2::Buckle2>(&bytes, &mut allocator_api2::alloc::Global).unwrap() == lbl
+        }
+    }
+
+    #[cfg(feature = "buckle2")]
+    #[test]
+    fn test_buckle2_layout() {
+        use crate::buckle2::Buckle2;
+
+        // [false, []]
+        assert_eq!(alloc::vec![0x82, 0xf4, 0x80], minicbor::to_vec(Buckle2::top()).unwrap());
+        // [[[[h'41']]], [[[h'42', h'43']]]]
+        assert_eq!(
+            alloc::vec![0x82, 0x81, 0x81, 0x81, 0x41, b'A', 0x81, 0x81, 0x82, 0x41, b'B', 0x41, b'C'],
+            minicbor::to_vec(Buckle2::parse("A,B/C").unwrap()).unwrap()
+        );
+        assert!(minicbor::decode_with::<_, Buckle2>(&[0x82, 0xf5, 0x80], &mut allocator_api2::alloc::Global).is_err());
+    }
+}
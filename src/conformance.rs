@@ -0,0 +1,169 @@
+//! Round trips between each label type's `parse` and `Display`.
+//!
+//! Every label the text syntax can represent must print to text that parses
+//! back to it, and printing whatever a parser accepts must give canonical
+//! text, which reads back to the same label and prints unchanged. The
+//! syntax can't represent empty principals or paths, whose text is empty,
+//! nor the empty clause, which prints as `F`, so labels with them are
+//! discarded.
+//!
+//! Quickcheck's arbitrary strings seldom contain separators or escapes, so
+//! the text checks also draw strings from the syntax's own characters.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+#[cfg(any(feature = "dclabel", feature = "buckle"))]
+use quickcheck::TestResult;
+
+/// Characters the text checks draw from: separators, constants, escapes and
+/// characters that must be escaped.
+const ALPHABET: [char; 12] = ['a', 'b', '1', '|', '&', ',', '\\', 'T', 'F', '/', '-', 'é'];
+
+fn syntax(input: Vec<u8>) -> String {
+    input.into_iter().map(|b| ALPHABET[b as usize % ALPHABET.len()]).collect()
+}
+
+/// Checks that `label` prints to text that parses back to it and prints
+/// unchanged.
+fn canonical<L: PartialEq + core::fmt::Display>(label: L, parse: impl Fn(&str) -> Option<L>) -> bool {
+    let printed = label.to_string();
+    match parse(&printed) {
+        Some(reparsed) => reparsed == label && reparsed.to_string() == printed,
+        None => false,
+    }
+}
+
+#[cfg(feature = "dclabel")]
+mod dclabel {
+    use super::*;
+    use crate::dclabel::{Component, DCLabel};
+
+    fn parse(input: &str) -> Option<DCLabel> {
+        match DCLabel::parse(input) {
+            Ok(("", label)) => Some(label),
+            _ => None,
+        }
+    }
+
+    fn representable(component: &Component) -> bool {
+        match component {
+            Component::DCFalse => true,
+            Component::DCFormula(clauses) => clauses.iter().all(|c| !c.0.is_empty() && c.check().is_ok()),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for input in ["T,F", "Tom,a|b&c", r"a-b|é,\\"] {
+            let label = parse(input).unwrap();
+            assert_eq!(input, label.to_string());
+        }
+        assert_eq!(Some(r"a\x20b,T"), parse(r"a\ b,T").map(|l| l.to_string()).as_deref());
+        assert_eq!(None, parse("T,a|"));
+    }
+
+    quickcheck! {
+        fn round_trips(label: DCLabel) -> TestResult {
+            if !representable(&label.secrecy) || !representable(&label.integrity) {
+                return TestResult::discard();
+            }
+            TestResult::from_bool(parse(&label.to_string()) == Some(label))
+        }
+
+        fn prints_canonical(input: Vec<u8>) -> bool {
+            parse(&syntax(input)).is_none_or(|label| canonical(label, parse))
+        }
+    }
+}
+
+#[cfg(feature = "buckle")]
+mod buckle {
+    use super::*;
+    use crate::buckle::{Buckle, Component};
+
+    fn parse(input: &str) -> Option<Buckle> {
+        match Buckle::parser(input) {
+            Ok(("", label)) => Some(label),
+            _ => None,
+        }
+    }
+
+    pub(super) fn representable(component: &Component) -> bool {
+        match component {
+            Component::DCFalse => true,
+            Component::DCFormula(clauses) => clauses.iter().all(|c| !c.0.is_empty() && c.check().is_ok()),
+        }
+    }
+
+    quickcheck! {
+        fn round_trips(label: Buckle) -> TestResult {
+            if !representable(&label.secrecy) || !representable(&label.integrity) {
+                return TestResult::discard();
+            }
+            TestResult::from_bool(parse(&label.to_string()) == Some(label))
+        }
+
+        fn prints_canonical(input: Vec<u8>) -> bool {
+            parse(&syntax(input)).is_none_or(|label| canonical(label, parse))
+        }
+    }
+}
+
+#[cfg(feature = "buckle2")]
+mod buckle2 {
+    use super::*;
+    use crate::buckle2::{Buckle2, Component};
+
+    fn parse(input: &str) -> Option<Buckle2> {
+        Buckle2::parse(input).ok()
+    }
+
+    #[test]
+    fn test_constants() {
+        assert_eq!(Some(Buckle2::new(Component::from([["Tom"]]), true)), parse("Tom,T"));
+        assert_eq!(Some(Buckle2::new(Component::from([["aF"]]), false)), parse("aF,F"));
+        assert_eq!(Some("Tom,T"), parse("Tom,T").map(|l| l.to_string()).as_deref());
+        assert_eq!(Some(r"a\,b,T"), parse(r"a\,b,T").map(|l| l.to_string()).as_deref());
+        assert_eq!(Some(r"\T,T"), parse(r"\T,T").map(|l| l.to_string()).as_deref());
+    }
+
+    /// Returns the `Buckle2` version of a `Buckle` component.
+    #[cfg(feature = "buckle")]
+    fn component(component: &crate::buckle::Component) -> Component {
+        match component {
+            crate::buckle::Component::DCFalse => Component::DCFalse,
+            crate::buckle::Component::DCFormula(clauses) => Component::from(
+                clauses
+                    .iter()
+                    .map(|c| {
+                        crate::buckle2::Clause::new_from_vec(
+                            c.0.iter().map(|path| path.iter().map(|p| p.as_bytes()).collect()).collect(),
+                        )
+                    })
+                    .collect::<alloc::collections::BTreeSet<_>>(),
+            ),
+        }
+    }
+
+    #[cfg(feature = "buckle")]
+    quickcheck! {
+        fn round_trips(label: crate::buckle::Buckle) -> TestResult {
+            if !super::buckle::representable(&label.secrecy) || !super::buckle::representable(&label.integrity) {
+                return TestResult::discard();
+            }
+            let label = Buckle2::new(component(&label.secrecy), component(&label.integrity));
+            TestResult::from_bool(parse(&label.to_string()) == Some(label))
+        }
+
+        fn prints_like_buckle(label: crate::buckle::Buckle) -> bool {
+            let label = crate::buckle::Buckle::new(label.secrecy, label.integrity);
+            Buckle2::new(component(&label.secrecy), component(&label.integrity)).to_string() == label.to_string()
+        }
+    }
+
+    quickcheck! {
+        fn prints_canonical(input: Vec<u8>) -> bool {
+            parse(&syntax(input)).is_none_or(|label| canonical(label, parse))
+        }
+    }
+}
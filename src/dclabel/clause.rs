@@ -1,24 +1,47 @@
-#[cfg(test)]
+#[cfg(any(test, feature = "quickcheck"))]
 use alloc::boxed::Box;
-#[cfg(test)]
+#[cfg(any(test, feature = "quickcheck"))]
+use alloc::string::String;
+#[cfg(any(test, feature = "quickcheck"))]
+use crate::generate::{GenConfig, Generate};
+#[cfg(any(test, feature = "quickcheck"))]
 use quickcheck::Arbitrary;
 
 use serde::{Deserialize, Serialize};
 
 use super::Principal;
+use crate::subsume::Subsumes;
+use crate::{fingerprint, ClauseStats, MalformedClause};
 use alloc::{collections::BTreeSet, vec::Vec};
 
+/// The principals of a clause. Under the `hashcons` feature they are shared
+/// between clones of a clause and copied on write.
+#[cfg(feature = "hashcons")]
+pub type Principals = alloc::sync::Arc<BTreeSet<Principal>>;
+#[cfg(not(feature = "hashcons"))]
+pub type Principals = BTreeSet<Principal>;
+
 #[derive(Eq, PartialEq, PartialOrd, Ord, Debug, Clone, Serialize, Deserialize)]
-pub struct Clause(pub BTreeSet<Principal>);
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Clause(pub(crate) Principals);
+
+#[cfg(any(test, feature = "quickcheck"))]
+impl Generate for Clause {
+    fn generate(g: &mut quickcheck::Gen, config: &GenConfig) -> Self {
+        let len = config.len(g, config.max_principals);
+        Clause::new_from_vec((0..len).map(|_| config.principal(g)).collect())
+    }
+}
 
-#[cfg(test)]
+#[cfg(any(test, feature = "quickcheck"))]
 impl Arbitrary for Clause {
     fn arbitrary(g: &mut quickcheck::Gen) -> Self {
-        Clause(BTreeSet::arbitrary(g))
+        GenConfig::default().generate(g)
     }
 
     fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
-        Box::new(self.0.shrink().map(|x| Clause(x)))
+        let principals: BTreeSet<String> = self.0.iter().map(|p| p.as_str().into()).collect();
+        Box::new(principals.shrink().map(|x| Clause::new_from_vec(x.into_iter().collect())))
     }
 }
 
@@ -32,7 +55,7 @@ impl Clause {
         for p in principals.iter() {
             result.insert(p.clone().into());
         }
-        Self(result)
+        Self::from(result)
     }
 
     pub fn new_from_vec<P: Into<Principal> + Clone>(principals: Vec<P>) -> Clause {
@@ -40,13 +63,95 @@ impl Clause {
         for p in principals.iter() {
             result.insert(p.clone().into());
         }
-        Self(result)
+        Self::from(result)
+    }
+
+    /// Checks that no principal is empty.
+    pub fn check(&self) -> Result<(), MalformedClause> {
+        if self.0.iter().any(|p| p.is_empty()) {
+            return Err(MalformedClause::EmptyPrincipal);
+        }
+        Ok(())
+    }
+
+    /// Iterates over the principals, in order.
+    pub fn principals(&self) -> impl ExactSizeIterator<Item = &Principal> + DoubleEndedIterator + '_ {
+        self.0.iter()
+    }
+
+    /// Returns the number of principals.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns whether the clause has no principals, i.e. is false.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the principals for mutation, first copying them if they are
+    /// shared.
+    pub(crate) fn principals_mut(&mut self) -> &mut BTreeSet<Principal> {
+        #[cfg(feature = "hashcons")]
+        let principals = alloc::sync::Arc::make_mut(&mut self.0);
+        #[cfg(not(feature = "hashcons"))]
+        let principals = &mut self.0;
+        principals
     }
 
     pub fn implies(&self, other: &Self) -> bool {
+        #[cfg(feature = "hashcons")]
+        if alloc::sync::Arc::ptr_eq(&self.0, &other.0) {
+            return true;
+        }
         // self is subset of other
         self.0.is_subset(&other.0)
     }
+
+    /// The fingerprint of its principals (see the `fingerprint` module).
+    pub(crate) fn fingerprint(&self) -> u64 {
+        self.0.iter().fold(0, |f, p| f | fingerprint::bit(p.as_bytes()))
+    }
+}
+
+impl ClauseStats for Clause {
+    const PATH_SIZE: usize = 0;
+    const PRINCIPAL_SIZE: usize = core::mem::size_of::<Principal>();
+
+    fn paths(&self) -> impl Iterator<Item = impl Iterator<Item = usize> + '_> {
+        self.0.iter().map(|p| core::iter::once(p.len()))
+    }
+}
+
+impl crate::heap::Heap for Clause {
+    fn heap_size(&self) -> usize {
+        self.0.heap_size()
+    }
+
+    fn compact(&mut self) {
+        self.0.compact()
+    }
+}
+
+/// A clause only implies clauses containing its least principal.
+impl Subsumes for Clause {
+    type Key = Principal;
+
+    fn key(&self) -> Option<&Principal> {
+        self.0.first()
+    }
+
+    fn keys(&self) -> impl Iterator<Item = &Principal> {
+        self.0.iter()
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn implies(&self, other: &Self) -> bool {
+        Clause::implies(self, other)
+    }
 }
 
 impl<P: Into<Principal> + Clone, const N: usize> From<[P; N]> for Clause {
@@ -63,6 +168,8 @@ impl<P: Into<Principal> + Clone> From<Vec<P>> for Clause {
 
 impl From<BTreeSet<Principal>> for Clause {
     fn from(principals: BTreeSet<Principal>) -> Clause {
+        #[cfg(feature = "hashcons")]
+        let principals = alloc::sync::Arc::new(principals);
         Clause(principals)
     }
 }
@@ -101,6 +208,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_accessors() {
+        let clause = Clause::from(["Yue", "Amit"]);
+        assert_eq!(alloc::vec!["Amit", "Yue"], clause.principals().collect::<alloc::vec::Vec<_>>());
+        assert_eq!(2, clause.len());
+        assert!(!clause.is_empty());
+        assert!(Clause::empty().is_empty());
+    }
+
     quickcheck! {
         fn empty_clause_implies_all(clause: Clause) -> bool {
             let empty = Clause::empty();
@@ -109,7 +225,7 @@ mod tests {
 
         fn subset_implies_superset(clause1: Clause, clause2: Clause) -> bool {
             let mut clause1 = clause1.clone();
-            clause1.0.append(&mut clause2.0.clone());
+            clause1.principals_mut().extend(clause2.0.iter().cloned());
             clause2.implies(&clause1)
         }
     }
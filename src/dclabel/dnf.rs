@@ -0,0 +1,256 @@
+use super::clause::Clause;
+use super::component::Component;
+use super::Principal;
+use alloc::collections::BTreeSet;
+
+/// A conjunction of principals.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
+pub struct Term(pub BTreeSet<Principal>);
+
+impl Term {
+    pub fn new<P: Into<Principal> + Clone, const N: usize>(principals: [P; N]) -> Term {
+        Term(principals.iter().cloned().map(Into::into).collect())
+    }
+
+    /// A term implies another if its principals are a superset of the
+    /// other's.
+    pub fn implies(&self, other: &Self) -> bool {
+        other.0.is_subset(&self.0)
+    }
+}
+
+impl<P: Into<Principal> + Clone, const N: usize> From<[P; N]> for Term {
+    fn from(principals: [P; N]) -> Term {
+        Term::new(principals)
+    }
+}
+
+/// A component in disjunctive normal form, a disjunction of terms.
+///
+/// `Component`'s conjunctive form makes `&` cheap and `|` expensive, and its
+/// `|` over-approximates the disjunction to stay small. Formulas with few
+/// conjuncts but wide disjunctions, e.g. "any of these 200 team members",
+/// are better kept here, where `|` is a union of terms and exact, and `&`
+/// takes the product of both sides' terms. Converting to and from
+/// `Component` is exact, but can grow exponentially with the number of
+/// terms or clauses.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DnfComponent {
+    /// Reduced: no term implies another. Empty for false, and the empty term
+    /// alone for true.
+    terms: BTreeSet<Term>,
+}
+
+impl DnfComponent {
+    pub fn dc_false() -> Self {
+        DnfComponent { terms: BTreeSet::new() }
+    }
+
+    pub fn dc_true() -> Self {
+        let mut terms = BTreeSet::new();
+        terms.insert(Term(BTreeSet::new()));
+        DnfComponent { terms }
+    }
+
+    pub fn is_false(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    pub fn is_true(&self) -> bool {
+        self.terms.first().is_some_and(|t| t.0.is_empty())
+    }
+
+    pub fn terms(&self) -> &BTreeSet<Term> {
+        &self.terms
+    }
+
+    /// Disjoins `term`, returning whether it was inserted, i.e. no term
+    /// already implied by it was present. Terms implying `term` are dropped.
+    pub fn insert_term(&mut self, term: Term) -> bool {
+        if self.terms.iter().any(|t| term.implies(t)) {
+            return false;
+        }
+        self.terms.retain(|t| !t.implies(&term));
+        self.terms.insert(term)
+    }
+
+    /// Every term must imply a term of `other`, which for terms of
+    /// principals means containing it.
+    pub fn implies(&self, other: &Self) -> bool {
+        self.terms.iter().all(|sterm| other.terms.iter().any(|oterm| sterm.implies(oterm)))
+    }
+}
+
+impl core::iter::FromIterator<Term> for DnfComponent {
+    fn from_iter<I: IntoIterator<Item = Term>>(terms: I) -> Self {
+        let mut result = DnfComponent::dc_false();
+        for term in terms {
+            result.insert_term(term);
+        }
+        result
+    }
+}
+
+impl From<bool> for DnfComponent {
+    fn from(clause: bool) -> Self {
+        if clause {
+            DnfComponent::dc_true()
+        } else {
+            DnfComponent::dc_false()
+        }
+    }
+}
+
+/// Distributes the clauses over each other, one term per choice of a
+/// principal from every clause.
+impl From<&Component> for DnfComponent {
+    fn from(component: &Component) -> Self {
+        match component {
+            Component::DCFalse => DnfComponent::dc_false(),
+            Component::DCFormula(clauses) => clauses.iter().fold(DnfComponent::dc_true(), |dnf, clause| {
+                dnf & clause.0.iter().map(|p| Term(core::iter::once(p.clone()).collect())).collect()
+            }),
+        }
+    }
+}
+
+impl From<Component> for DnfComponent {
+    fn from(component: Component) -> Self {
+        DnfComponent::from(&component)
+    }
+}
+
+/// Distributes the terms over each other, one clause per choice of a
+/// principal from every term.
+impl From<&DnfComponent> for Component {
+    fn from(component: &DnfComponent) -> Self {
+        let mut terms = component.terms.iter();
+        let first = match terms.next() {
+            None => return Component::DCFalse,
+            Some(term) => term,
+        };
+        let mut result = Component::from(first.0.iter().map(|p| Clause::new([p.clone()])).collect::<BTreeSet<_>>());
+        for term in terms {
+            let mut clauses = Component::dc_true();
+            for clause in result.into_clauses().into_iter().flatten() {
+                for p in term.0.iter() {
+                    let mut clause = clause.clone();
+                    clause.principals_mut().insert(p.clone());
+                    clauses.insert_clause(clause);
+                }
+            }
+            result = clauses;
+        }
+        result
+    }
+}
+
+impl From<DnfComponent> for Component {
+    fn from(component: DnfComponent) -> Self {
+        Component::from(&component)
+    }
+}
+
+impl core::ops::BitOr for DnfComponent {
+    type Output = DnfComponent;
+    fn bitor(mut self, rhs: Self) -> DnfComponent {
+        for term in rhs.terms {
+            self.insert_term(term);
+        }
+        self
+    }
+}
+
+impl core::ops::BitAnd for DnfComponent {
+    type Output = DnfComponent;
+    fn bitand(self, rhs: Self) -> DnfComponent {
+        match (self, rhs) {
+            (s, o) if s.is_false() || o.is_false() => DnfComponent::dc_false(),
+            (s, o) if o.is_true() => s,
+            (s, o) if s.is_true() => o,
+            (s, o) => s
+                .terms
+                .iter()
+                .flat_map(|sterm| o.terms.iter().map(move |oterm| Term(sterm.0.union(&oterm.0).cloned().collect())))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+    use alloc::vec::Vec;
+
+    /// A component of at most three clauses of three of four principals, so
+    /// converting it stays small.
+    fn small(clauses: Vec<Vec<u8>>) -> Component {
+        Component::from(
+            clauses
+                .iter()
+                .take(3)
+                .map(|c| Clause::new_from_vec(c.iter().take(3).map(|p| format!("p{}", p % 4)).collect()))
+                .collect::<BTreeSet<_>>(),
+        )
+    }
+
+    /// Reduces `component`, making it `DCFalse` if it has the empty clause
+    /// as the DNF does.
+    fn reduced(mut component: Component) -> Component {
+        component.reduce();
+        match component {
+            Component::DCFormula(clauses) if clauses.iter().any(|c| c.0.is_empty()) => Component::DCFalse,
+            c => c,
+        }
+    }
+
+    #[test]
+    fn test_or() {
+        let team = (0..200).map(|i| Term::new([format!("p{}", i)])).collect::<DnfComponent>();
+        let either = team.clone() | IntoIterator::into_iter([Term::new(["Amit", "Yue"])]).collect();
+        assert_eq!(201, either.terms().len());
+        assert!(team.implies(&either));
+        assert!(!either.implies(&team));
+        assert!(DnfComponent::dc_false().implies(&team));
+        assert!(team.implies(&DnfComponent::dc_true()));
+    }
+
+    #[test]
+    fn test_convert() {
+        let cnf = Component::from([Clause::from(["Amit", "Yue"]), Clause::from(["Amit", "David"])]);
+        let dnf = DnfComponent::from(&cnf);
+        // (Amit | Yue) & (Amit | David) = Amit | Yue & David
+        let expected: DnfComponent = IntoIterator::into_iter([Term::new(["Amit"]), Term::new(["David", "Yue"])]).collect();
+        assert_eq!(expected, dnf);
+        assert_eq!(cnf, Component::from(dnf));
+        assert_eq!(Component::dc_true(), Component::from(DnfComponent::dc_true()));
+        assert_eq!(Component::DCFalse, Component::from(DnfComponent::dc_false()));
+    }
+
+    quickcheck! {
+        fn implies_agrees(c1: Vec<Vec<u8>>, c2: Vec<Vec<u8>>) -> bool {
+            let (c1, c2) = (small(c1), small(c2));
+            DnfComponent::from(&c1).implies(&DnfComponent::from(&c2)) == c1.implies(&c2)
+        }
+
+        fn round_trip(clauses: Vec<Vec<u8>>) -> bool {
+            let component = small(clauses);
+            Component::from(DnfComponent::from(&component)) == reduced(component)
+        }
+
+        fn and_agrees(c1: Vec<Vec<u8>>, c2: Vec<Vec<u8>>) -> bool {
+            let (c1, c2) = (small(c1), small(c2));
+            Component::from(DnfComponent::from(&c1) & DnfComponent::from(&c2)) == reduced(c1 & c2)
+        }
+
+        // The disjunction is exact, so it implies `Component`'s.
+        fn or_implies(c1: Vec<Vec<u8>>, c2: Vec<Vec<u8>>) -> bool {
+            let (c1, c2) = (small(c1), small(c2));
+            let dnf = DnfComponent::from(&c1) | DnfComponent::from(&c2);
+            dnf.implies(&DnfComponent::from(&(c1.clone() | c2.clone())))
+                && DnfComponent::from(&c1).implies(&dnf)
+                && DnfComponent::from(&c2).implies(&dnf)
+        }
+    }
+}
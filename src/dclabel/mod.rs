@@ -1,75 +1,130 @@
-#[cfg(test)]
+#[cfg(any(test, feature = "quickcheck"))]
 use alloc::boxed::Box;
-#[cfg(test)]
+#[cfg(any(test, feature = "quickcheck"))]
+use crate::generate::{GenConfig, Generate};
+#[cfg(any(test, feature = "quickcheck"))]
 use quickcheck::Arbitrary;
-use serde::{Deserialize, Serialize};
 
-use super::{HasPrivilege, Label};
+use super::{HasPrivilege, Label, LabelStats};
+use crate::proof::FlowCounterexample;
 
 pub mod clause;
 pub mod component;
+pub mod dnf;
+pub mod privilege;
+pub mod sorted;
 
 pub use clause::*;
 pub use component::*;
+pub use dnf::{DnfComponent, Term};
+pub use privilege::CompiledPrivilege;
+pub use sorted::SortedComponent;
 
+#[cfg(not(feature = "smolstr"))]
 pub type Principal = alloc::string::String;
-
-#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+/// Principals are short and repeated across clauses, so with `smolstr` they
+/// are stored inline up to 23 bytes and share longer names by reference
+/// count.
+#[cfg(feature = "smolstr")]
+pub type Principal = smol_str::SmolStr;
+
+/// A DCLabel: secrecy and integrity components, kept reduced. Read them
+/// with `secrecy` and `integrity`, and replace them with `set_secrecy` and
+/// `set_integrity`, which reduce them as `new` does, so that the reduced
+/// form `downgrade` relies on holds.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "diesel", derive(diesel::expression::AsExpression, diesel::deserialize::FromSqlRow))]
+#[cfg_attr(feature = "diesel", diesel(sql_type = diesel::sql_types::Text))]
 pub struct DCLabel {
-    pub secrecy: Component,
-    pub integrity: Component,
+    pub(crate) secrecy: Component,
+    pub(crate) integrity: Component,
 }
 
 impl DCLabel {
     /// Parses a string into a DCLabel.
     ///
-    /// The string separates secrecy and integrity with a comma, clauses
-    /// separated with a '&' and principles with a '|'. The backslash character
-    /// ('\') allows escaping these special characters (including itself).
+    /// The string is in the canonical text syntax of `crate::text`, read
+    /// leniently, and the parser stops at the end of the label. Paths of
+    /// several principals are rejected.
+    ///
+    /// A component of just `T` or `F` is the constant true or false, and the
+    /// principals named `T` and `F` are written `\T` and `\F`. Before
+    /// canonical text v1 the syntax had no constants, so text persisted by
+    /// older releases with such a principal, e.g. `F,T`, now reads as a
+    /// different label; `migrate::read_dclabel_text` reads it as it was
+    /// written.
     pub fn parse(input: &str) -> nom::IResult<&str, DCLabel> {
-        use alloc::collections::BTreeSet;
-        use nom::{
-            bytes::complete::{escaped_transform, tag},
-            character::complete::{alphanumeric1, one_of},
-            multi::separated_list1,
-            Parser,
-        };
+        use crate::text::{dclabel::ComponentBuilder, label_prefix, nom_error, Mode};
 
-        let mut component = separated_list1(
-            tag("&"),
-            separated_list1(
-                tag("|"),
-                escaped_transform(alphanumeric1, '\\', one_of(r#",|&\"#)),
-            ),
-        )
-        .map(|mut c| {
-            c.iter_mut()
-                .map(|c| c.drain(..).collect::<BTreeSet<Principal>>().into())
-                .collect::<BTreeSet<Clause>>()
-        });
+        match label_prefix(input, Mode::Lenient, ComponentBuilder::default(), ComponentBuilder::default()) {
+            Ok(((secrecy, integrity), end)) => Ok((&input[end..], DCLabel::new(secrecy, integrity))),
+            Err((_, offset)) => nom_error(input, offset),
+        }
+    }
+}
 
-        let (input, secrecy) = component.parse(input)?;
-        let (input, _) = tag(",")(input)?;
-        let (input, integrity) = component.parse(input)?;
+/// Parses a single component: `T`, `F`, or clauses in the syntax accepted by
+/// `DCLabel::parse`.
+pub(crate) fn component(input: &str) -> nom::IResult<&str, Component> {
+    use crate::text::{component_prefix, dclabel::ComponentBuilder, nom_error, Mode};
 
-        Ok((input, DCLabel::new(secrecy, integrity)))
+    match component_prefix(input, Mode::Lenient, ComponentBuilder::default()) {
+        Ok((component, end)) => Ok((&input[end..], component)),
+        Err((_, offset)) => nom_error(input, offset),
     }
 }
 
-#[cfg(test)]
+crate::serde_label::impl_serde!(DCLabel, DCLabel::parse, "a DCLabel, e.g. \"amit|yue,T\"");
+
+/// Parses a label in the strict canonical text syntax, rejecting trailing
+/// input, so that it's the inverse of `Display`. Syntax errors are
+/// `crate::Error::Parse`.
+impl core::convert::TryFrom<&str> for DCLabel {
+    type Error = crate::Error;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        use crate::text::{Error, Mode};
+
+        crate::text::parse(input, Mode::Strict).map_err(|e| match e {
+            Error::Syntax(offset) => crate::Error::Parse(offset),
+            e => crate::Error::Text(e),
+        })
+    }
+}
+
+impl core::str::FromStr for DCLabel {
+    type Err = crate::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        core::convert::TryFrom::try_from(input)
+    }
+}
+
+/// Formats the label in the syntax accepted by `DCLabel::parse`.
+impl core::fmt::Display for DCLabel {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{},{}", self.secrecy, self.integrity)
+    }
+}
+
+#[cfg(any(test, feature = "quickcheck"))]
+impl Generate for DCLabel {
+    fn generate(g: &mut quickcheck::Gen, config: &GenConfig) -> Self {
+        DCLabel::new(Component::generate(g, config), Component::generate(g, config))
+    }
+}
+
+#[cfg(any(test, feature = "quickcheck"))]
 impl Arbitrary for DCLabel {
     fn arbitrary(g: &mut quickcheck::Gen) -> Self {
-        DCLabel {
-            secrecy: Component::arbitrary(g),
-            integrity: Component::arbitrary(g),
-        }
+        GenConfig::default().generate(g)
     }
 
     fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
         Box::new(
             (self.secrecy.clone(), self.integrity.clone())
                 .shrink()
-                .map(|(secrecy, integrity)| DCLabel { secrecy, integrity }),
+                .map(|(secrecy, integrity)| DCLabel::new(secrecy, integrity)),
         )
     }
 }
@@ -83,16 +138,73 @@ impl DCLabel {
         DCLabel { secrecy, integrity }
     }
 
-    pub fn public() -> DCLabel {
-        Self::new(Component::dc_true(), Component::dc_true())
+    /// The secrecy component.
+    pub fn secrecy(&self) -> &Component {
+        &self.secrecy
     }
 
-    pub fn top() -> DCLabel {
-        Self::new(Component::dc_false(), Component::dc_true())
+    /// The integrity component.
+    pub fn integrity(&self) -> &Component {
+        &self.integrity
     }
 
-    pub fn bottom() -> DCLabel {
-        Self::new(Component::dc_true(), Component::dc_false())
+    /// Replaces the secrecy component, reducing it as `new` does.
+    pub fn set_secrecy<S: Into<Component>>(&mut self, secrecy: S) {
+        self.secrecy = secrecy.into();
+        self.secrecy.reduce();
+    }
+
+    /// Replaces the integrity component, reducing it as `new` does.
+    pub fn set_integrity<I: Into<Component>>(&mut self, integrity: I) {
+        self.integrity = integrity.into();
+        self.integrity.reduce();
+    }
+
+    /// Returns the secrecy and integrity components.
+    pub fn into_parts(self) -> (Component, Component) {
+        (self.secrecy, self.integrity)
+    }
+
+    pub const fn public() -> DCLabel {
+        DCLabel { secrecy: Component::dc_true(), integrity: Component::dc_true() }
+    }
+
+    pub const fn top() -> DCLabel {
+        DCLabel { secrecy: Component::dc_false(), integrity: Component::dc_true() }
+    }
+
+    pub const fn bottom() -> DCLabel {
+        DCLabel { secrecy: Component::dc_true(), integrity: Component::dc_false() }
+    }
+
+    /// Returns whether the label is public, i.e. both components are true.
+    pub fn is_public(&self) -> bool {
+        self.secrecy.is_true() && self.integrity.is_true()
+    }
+
+    /// Returns whether the label is the top label, i.e. false secrecy and
+    /// true integrity.
+    pub fn is_top(&self) -> bool {
+        self.secrecy.is_false() && self.integrity.is_true()
+    }
+
+    /// Returns whether the label is the bottom label, i.e. true secrecy and
+    /// false integrity.
+    pub fn is_bottom(&self) -> bool {
+        self.secrecy.is_true() && self.integrity.is_false()
+    }
+
+    /// Returns the approximate number of bytes the label owns on the heap,
+    /// e.g. to measure the memory held by stored labels.
+    pub fn approx_heap_size(&self) -> usize {
+        self.secrecy.approx_heap_size() + self.integrity.approx_heap_size()
+    }
+
+    /// Releases excess capacity, e.g. to compact labels kept after a burst
+    /// of `lub`s.
+    pub fn shrink_to_fit(&mut self) {
+        self.secrecy.shrink_to_fit();
+        self.integrity.shrink_to_fit();
     }
 
     pub fn reduce(&mut self) {
@@ -100,51 +212,130 @@ impl DCLabel {
         self.integrity.reduce();
     }
 
+    /// Reduces both components as `Component::reduce_with_budget` does,
+    /// sharing `max_comparisons` between them, and returns whether both
+    /// finished.
+    pub fn reduce_with_budget(&mut self, max_comparisons: usize) -> bool {
+        let mut budget = max_comparisons;
+        let secrecy = self.secrecy.reduce_within(&mut budget);
+        let integrity = self.integrity.reduce_within(&mut budget);
+        secrecy && integrity
+    }
+
+    /// Returns whether both components are reduced.
+    pub fn is_reduced(&self) -> bool {
+        self.secrecy.is_reduced() && self.integrity.is_reduced()
+    }
+
+    /// Computes size and shape statistics for the label, e.g. to monitor
+    /// label growth or reject labels exceeding a budget.
+    pub fn stats(&self) -> LabelStats {
+        LabelStats::of::<Self, _>(self.secrecy.clauses(), self.integrity.clauses())
+    }
+
     pub fn endorse(mut self, privilege: &Component) -> DCLabel {
+        #[cfg(feature = "trace")]
+        let before = crate::telemetry::digest_before(&self);
+        self.integrity &= privilege.clone();
+        #[cfg(feature = "trace")]
+        crate::telemetry::trace_privileged("endorse", before, &self);
+        self
+    }
+
+    /// Returns why `self` can't flow to `rhs`: a clause of its secrecy that
+    /// `rhs` doesn't uphold, or of `rhs`'s integrity that it lacks, found as
+    /// `can_flow_to` would find them (see the `proof` module). Returns
+    /// `None` if `self` can flow to `rhs`.
+    pub fn flow_counterexample(&self, rhs: &Self) -> Option<FlowCounterexample<Component>> {
+        rhs.secrecy
+            .unimplied_clause(&self.secrecy)
+            .map(FlowCounterexample::Secrecy)
+            .or_else(|| self.integrity.unimplied_clause(&rhs.integrity).map(FlowCounterexample::Integrity))
+    }
+
+    /// `downgrade`, without its decision event.
+    fn downgraded(mut self, privilege: &Component) -> DCLabel {
+        // An empty privilege downgrades nothing.
+        if privilege.is_true() {
+            return self;
+        }
+        // Dropping secrecy clauses and conjoining the privilege keep reduced
+        // components reduced.
+        #[cfg(debug_assertions)]
+        let reduced = self.is_reduced() && privilege.is_reduced();
+        self.secrecy = match (self.secrecy, privilege) {
+            //not real (DCTrue, _) => DCTrue, // can't go lower than true
+            (_, Component::DCFalse) => Component::dc_true(), // false can downgrade _anything_ to true
+            (Component::DCFalse, _) => Component::dc_false(), // only false can downgrade false
+            (Component::DCFormula(mut sec), Component::DCFormula(p)) => {
+                if sec.iter().any(|c| p.iter().any(|pclause| pclause.implies(c))) {
+                    component::make_mut(&mut sec).retain(|c| !p.iter().any(|pclause| pclause.implies(c)));
+                }
+                Component::DCFormula(sec)
+            }
+        };
         self.integrity = privilege.clone() & self.integrity;
+        #[cfg(debug_assertions)]
+        debug_assert!(!reduced || self.is_reduced());
         self
     }
 }
 
 impl Label for DCLabel {
     fn lub(self, rhs: Self) -> Self {
-        let mut res = DCLabel {
-            secrecy: self.secrecy & rhs.secrecy,
-            integrity: self.integrity | rhs.integrity,
+        // Top absorbs every label and bottom is the identity, so neither
+        // needs the other label's clauses.
+        let mut res = if self.is_top() || rhs.is_bottom() {
+            self
+        } else if rhs.is_top() || self.is_bottom() {
+            rhs
+        } else {
+            let mut label = self;
+            label.secrecy &= rhs.secrecy;
+            label.integrity |= rhs.integrity;
+            label
         };
         res.reduce();
         res
     }
 
     fn glb(self, rhs: Self) -> Self {
-        let mut res = DCLabel {
-            secrecy: self.secrecy | rhs.secrecy,
-            integrity: self.integrity & rhs.integrity,
+        // Bottom absorbs every label and top is the identity.
+        let mut res = if self.is_bottom() || rhs.is_top() {
+            self
+        } else if rhs.is_bottom() || self.is_top() {
+            rhs
+        } else {
+            let mut label = self;
+            label.secrecy |= rhs.secrecy;
+            label.integrity &= rhs.integrity;
+            label
         };
         res.reduce();
         res
     }
 
     fn can_flow_to(&self, rhs: &Self) -> bool {
-        rhs.secrecy.implies(&self.secrecy) && self.integrity.implies(&rhs.integrity)
+        // Bottom flows to every label, and every label flows to top.
+        let allowed = self.is_bottom()
+            || rhs.is_top()
+            || (rhs.secrecy.implies(&self.secrecy) && self.integrity.implies(&rhs.integrity));
+        #[cfg(feature = "trace")]
+        crate::telemetry::trace_flow(self, rhs, allowed);
+        allowed
     }
 }
 
 impl HasPrivilege for DCLabel {
     type Privilege = Component;
 
-    fn downgrade(mut self, privilege: &Component) -> DCLabel {
-        self.secrecy = match (self.secrecy, privilege) {
-            //not real (DCTrue, _) => DCTrue, // can't go lower than true
-            (_, Component::DCFalse) => Component::dc_true(), // false can downgrade _anything_ to true
-            (Component::DCFalse, _) => Component::dc_false(), // only false can downgrade false
-            (Component::DCFormula(mut sec), Component::DCFormula(p)) => {
-                sec.retain(|c| !p.iter().any(|pclause| pclause.implies(c)));
-                Component::DCFormula(sec)
-            }
-        };
-        self.integrity = privilege.clone() & self.integrity;
-        self
+    fn downgrade(self, privilege: &Component) -> DCLabel {
+        #[cfg(feature = "trace")]
+        let before = crate::telemetry::digest_before(&self);
+        let after = self.downgraded(privilege);
+        #[cfg(feature = "trace")]
+        crate::telemetry::trace_privileged("downgrade", before, &after);
+        after
     }
 
     fn downgrade_to(self, target: Self, privilege: &Self::Privilege) -> Self {
@@ -156,8 +347,14 @@ impl HasPrivilege for DCLabel {
     }
 
     fn can_flow_to_with_privilege(&self, rhs: &Self, privilege: &Component) -> bool {
-        (rhs.secrecy.clone() & privilege.clone()).implies(&self.secrecy)
-            && (self.integrity.clone() & privilege.clone()).implies(&rhs.integrity)
+        // An empty privilege permits no more flows, and false permits all.
+        if privilege.is_true() {
+            return self.can_flow_to(rhs);
+        } else if privilege.is_false() {
+            return true;
+        }
+        rhs.secrecy.implies_with(privilege, &self.secrecy)
+            && self.integrity.implies_with(privilege, &rhs.integrity)
     }
 }
 
@@ -166,6 +363,15 @@ mod tests {
     use super::*;
     use alloc::vec;
 
+    #[cfg(feature = "macros")]
+    #[test]
+    fn test_macro() {
+        assert_eq!(DCLabel::parse("amit|yue,T").unwrap().1, crate::dclabel!("amit|yue,T"));
+        assert_eq!(DCLabel::parse("F,T").unwrap().1, crate::dclabel!("F,T"));
+        assert_eq!(DCLabel::parse("amit,bob").unwrap().1, crate::dclabel!("amit|yue&amit,bob"));
+        assert_eq!(DCLabel::parse(r"\T|\,,T").unwrap().1, crate::dclabel!(r"\T|\,,T"));
+    }
+
     #[test]
     fn test_can_flow_to_with_privilege() {
         let privilege = &Component::formula([["go_grader"]]);
@@ -358,6 +564,54 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_trivial() {
+        let lbl = DCLabel::parse("Amit&Yue,Amit").unwrap().1;
+        assert!(DCLabel::public().is_public());
+        assert!(DCLabel::top().is_top() && DCLabel::bottom().is_bottom());
+        assert!(!lbl.is_public() && !lbl.is_top() && !lbl.is_bottom());
+        assert_eq!(DCLabel::top(), lbl.clone().lub(DCLabel::top()));
+        assert_eq!(lbl, DCLabel::bottom().lub(lbl.clone()));
+        assert_eq!(DCLabel::bottom(), lbl.clone().glb(DCLabel::bottom()));
+        assert_eq!(lbl, DCLabel::top().glb(lbl.clone()));
+        assert_eq!(lbl, lbl.clone().downgrade(&Component::dc_true()));
+        assert!(lbl.can_flow_to_with_privilege(&DCLabel::bottom(), &Component::dc_false()));
+        assert!(!lbl.can_flow_to_with_privilege(&DCLabel::bottom(), &Component::dc_true()));
+    }
+
+    #[test]
+    fn test_from_str() {
+        use alloc::string::ToString;
+        use core::convert::TryFrom;
+
+        let label: DCLabel = "amit|yue,T".parse().unwrap();
+        assert_eq!(DCLabel::parse("amit|yue,T").unwrap().1, label);
+        assert_eq!(Ok(label.clone()), DCLabel::try_from(label.to_string().as_str()));
+        assert_eq!(Err(crate::Error::Parse(10)), "amit|yue,T,x".parse::<DCLabel>());
+        assert_eq!(Err(crate::Error::Parse(4)), DCLabel::try_from("amit"));
+        assert_eq!(label, DCLabel::parse(" amit | yue , T ").unwrap().1);
+        assert_eq!(Err(crate::Error::Parse(0)), " amit | yue , T ".parse::<DCLabel>());
+        assert_eq!(Err(crate::Error::Text(crate::text::Error::PathNotSupported)), DCLabel::try_from("amit/grades,T"));
+    }
+
+    #[test]
+    fn test_const() {
+        const PUBLIC: DCLabel = DCLabel::public();
+        static TOP: DCLabel = DCLabel::top();
+        assert_eq!(DCLabel::parse("T,T").unwrap().1, PUBLIC);
+        assert!(TOP.is_top() && DCLabel::bottom().is_bottom());
+    }
+
+    #[cfg(feature = "smolstr")]
+    #[test]
+    fn test_smolstr() {
+        let lbl = DCLabel::parse("Amit&Yue,Amit").unwrap().1;
+        assert_eq!(DCLabel::new([["Amit"], ["Yue"]], [[alloc::string::String::from("Amit")]]), lbl);
+        if let Component::DCFormula(clauses) = &lbl.secrecy {
+            assert!(clauses.iter().flat_map(|c| c.0.iter()).all(|p| !p.is_heap_allocated()));
+        }
+    }
+
     #[test]
     fn test_lub() {
         assert_eq!(DCLabel::top(), DCLabel::public().lub(DCLabel::top()));
@@ -394,6 +648,22 @@ mod tests {
         );
     }
 
+    #[cfg(not(feature = "smolstr"))]
+    #[test]
+    fn test_shrink_to_fit() {
+        let mut amit = alloc::string::String::with_capacity(64);
+        amit.push_str("Amit");
+        let clause = Clause::from(alloc::collections::BTreeSet::from([amit]));
+        let mut label = DCLabel {
+            secrecy: Component::from(alloc::collections::BTreeSet::from([clause])),
+            integrity: Component::dc_true(),
+        };
+        let size = label.approx_heap_size();
+        label.shrink_to_fit();
+        assert!(label.approx_heap_size() < size);
+        assert_eq!(DCLabel::new([["Amit"]], true), label);
+    }
+
     #[test]
     fn test_parse() {
         assert_eq!(
@@ -426,6 +696,59 @@ mod tests {
             DCLabel::parse(r#"Am\&it&Yue,Y\|ue"#),
             Ok(("", DCLabel::new([["Am&it"], ["Yue"]], [["Y|ue"]])))
         );
+        // A bare `T` or `F` component is a constant, and an escaped one a
+        // principal.
+        assert_eq!(DCLabel::parse("F,T"), Ok(("", DCLabel::top())));
+        assert_eq!(DCLabel::parse(r"\F,\T"), Ok(("", DCLabel::new([["F"]], [["T"]]))));
+        assert_eq!(DCLabel::parse(r"\T|a,\F"), Ok(("", DCLabel::new([["T", "a"]], [["F"]]))));
+    }
+
+    #[test]
+    fn test_display() {
+        use alloc::string::ToString;
+
+        assert_eq!("T,T", DCLabel::public().to_string());
+        assert_eq!("F,T", DCLabel::top().to_string());
+        assert_eq!("T,F", DCLabel::bottom().to_string());
+        let lbl = DCLabel::new([Clause::from(["Amit", "Yue"]), Clause::from(["a\\&,|"])], [["Yue"]]);
+        assert_eq!(r"Amit|Yue&a\\\&\,\|,Yue", lbl.to_string());
+        assert_eq!(Ok(("", lbl.clone())), DCLabel::parse(&lbl.to_string()));
+        assert_eq!(Ok(("", DCLabel::bottom())), DCLabel::parse("T,F"));
+        assert_eq!("F,T", DCLabel::new([Clause::empty()], true).to_string());
+        let lbl = DCLabel::parse("b|T&T,1").unwrap().1;
+        assert_eq!(r"\T,1", lbl.to_string());
+        assert_eq!(Ok(("", lbl.clone())), DCLabel::parse(&lbl.to_string()));
+    }
+
+    #[test]
+    fn test_json() {
+        let lbl = DCLabel::new([["Amit", "Yue"]], true);
+        assert_eq!(r#""Amit|Yue,T""#, serde_json::to_string(&lbl).unwrap());
+        assert_eq!(lbl, serde_json::from_str::<DCLabel>(r#""Amit|Yue,T""#).unwrap());
+        assert!(serde_json::from_str::<DCLabel>(r#"{"secrecy":"DCFalse"}"#).is_err());
+    }
+
+    fn assert_postcard_round_trip(lbl: DCLabel) {
+        let bytes = postcard::to_allocvec(&lbl).unwrap();
+        assert_eq!(Ok(lbl), postcard::from_bytes(&bytes));
+    }
+
+    #[test]
+    fn test_postcard_round_trip() {
+        assert_postcard_round_trip(DCLabel::public());
+        assert_postcard_round_trip(DCLabel::top());
+        assert_postcard_round_trip(DCLabel::bottom());
+        assert_postcard_round_trip(DCLabel::new([[""]], [["a", "b\\&,|"]]));
+    }
+
+    #[test]
+    fn test_postcard_reduces() {
+        let secrecy = Component::formula([Clause::new(["a"]), Clause::new(["a", "b"])]);
+        assert!(!secrecy.is_reduced());
+        let bytes = postcard::to_allocvec(&(secrecy, Component::dc_true())).unwrap();
+        let lbl: DCLabel = postcard::from_bytes(&bytes).unwrap();
+        assert!(lbl.is_reduced());
+        assert_eq!(DCLabel::new([["a"]], true), lbl);
     }
 
     quickcheck! {
@@ -449,9 +772,22 @@ mod tests {
             result.can_flow_to(&lbl1) && result.can_flow_to(&lbl2)
         }
 
+        fn counterexample_iff_denied(lbl1: DCLabel, lbl2: DCLabel) -> bool {
+            match lbl1.flow_counterexample(&lbl2) {
+                None => lbl1.can_flow_to(&lbl2),
+                Some(FlowCounterexample::Secrecy(clause)) => !lbl2.secrecy.implies(&clause) && !lbl1.can_flow_to(&lbl2),
+                Some(FlowCounterexample::Integrity(clause)) => !lbl1.integrity.implies(&clause) && !lbl1.can_flow_to(&lbl2),
+            }
+        }
+
         fn endorse_equiv_downgrade_to(lbl: DCLabel, privilege: Component) -> bool {
             let target = DCLabel { secrecy: lbl.secrecy.clone(), integrity: lbl.integrity.clone() & privilege.clone() };
             lbl.clone().downgrade_to(target, &privilege) == lbl.endorse(&privilege)
         }
+
+        fn postcard_round_trip(lbl: DCLabel) -> bool {
+            let bytes = postcard::to_allocvec(&lbl).unwrap();
+            postcard::from_bytes::<DCLabel>(&bytes) == Ok(lbl)
+        }
     }
 }
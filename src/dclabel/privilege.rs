@@ -0,0 +1,120 @@
+//! Privileges preprocessed for repeated use.
+//!
+//! Gateways apply one privilege to every message they relay. Compiling the
+//! privilege once reduces it and indexes its clauses by least principal with
+//! their fingerprints, so a downgrade or privileged flow check only
+//! compares each clause of the label against the few privilege clauses that
+//! could imply it.
+
+use super::{DCLabel, Clause, Component};
+use crate::subsume::Index;
+use crate::Label;
+use alloc::vec::Vec;
+
+/// A privilege compiled with `Component::compile`.
+#[derive(Debug, Clone)]
+pub struct CompiledPrivilege {
+    privilege: Component,
+    /// The privilege's reduced clauses, or `None` if it is false.
+    index: Option<Index<Clause>>,
+}
+
+impl Component {
+    /// Compiles the component for use as a privilege on many labels.
+    pub fn compile(&self) -> CompiledPrivilege {
+        let mut reduced = self.clone();
+        reduced.reduce();
+        let index = reduced
+            .into_clauses()
+            .map(|clauses| Index::new(clauses.into_iter().map(|c| (c.fingerprint(), c)).collect()));
+        CompiledPrivilege { privilege: self.clone(), index }
+    }
+}
+
+impl CompiledPrivilege {
+    /// Returns the privilege as compiled.
+    pub fn privilege(&self) -> &Component {
+        &self.privilege
+    }
+
+    /// Returns whether the privilege implies `clause`.
+    pub fn implies(&self, clause: &Clause) -> bool {
+        self.index.as_ref().is_none_or(|index| index.implies(clause.fingerprint(), clause))
+    }
+
+    /// Like `component.implies_with(self.privilege(), other)`.
+    pub fn implies_with(&self, component: &Component, other: &Component) -> bool {
+        match (component, &self.index, other) {
+            (Component::DCFalse, _, _) | (_, None, _) => true,
+            (_, _, Component::DCFalse) => false,
+            (Component::DCFormula(s), Some(index), Component::DCFormula(o)) => {
+                let sources: Vec<(u64, &Clause)> = s.iter().map(|c| (c.fingerprint(), c)).collect();
+                o.iter().all(|oclause| {
+                    let fingerprint = oclause.fingerprint();
+                    index.implies(fingerprint, oclause)
+                        || sources.iter().any(|(f, c)| crate::fingerprint::may_imply(*f, fingerprint) && c.implies(oclause))
+                })
+            }
+        }
+    }
+}
+
+impl DCLabel {
+    /// Like `downgrade`, with a compiled privilege.
+    pub fn downgrade_compiled(mut self, privilege: &CompiledPrivilege) -> DCLabel {
+        if privilege.privilege.is_true() {
+            return self;
+        }
+        #[cfg(debug_assertions)]
+        let reduced = self.is_reduced() && privilege.privilege.is_reduced();
+        self.secrecy = match (self.secrecy, &privilege.index) {
+            (_, None) => Component::dc_true(),
+            (Component::DCFalse, _) => Component::dc_false(),
+            (Component::DCFormula(mut sec), Some(_)) => {
+                if sec.iter().any(|c| privilege.implies(c)) {
+                    super::component::make_mut(&mut sec).retain(|c| !privilege.implies(c));
+                }
+                Component::DCFormula(sec)
+            }
+        };
+        self.integrity = privilege.privilege.clone() & self.integrity;
+        #[cfg(debug_assertions)]
+        debug_assert!(!reduced || self.is_reduced());
+        self
+    }
+
+    /// Like `can_flow_to_with_privilege`, with a compiled privilege.
+    pub fn can_flow_to_with_compiled(&self, rhs: &Self, privilege: &CompiledPrivilege) -> bool {
+        if privilege.privilege.is_true() {
+            return self.can_flow_to(rhs);
+        }
+        privilege.implies_with(&rhs.secrecy, &self.secrecy) && privilege.implies_with(&self.integrity, &rhs.integrity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HasPrivilege;
+
+    #[test]
+    fn test_compiled() {
+        let privilege = Component::formula([["go_grader"]]).compile();
+        assert!(DCLabel::new([["go_grader"], ["bob"]], [["go_grader"]])
+            .can_flow_to_with_compiled(&DCLabel::new([["bob"]], [["go_grader"]]), &privilege));
+        assert!(!DCLabel::new([["alice"], ["bob"]], true)
+            .can_flow_to_with_compiled(&DCLabel::new([["bob"]], true), &privilege));
+        assert_eq!(
+            DCLabel::new([["bob"]], [["go_grader"]]),
+            DCLabel::new([["go_grader"], ["bob"]], true).downgrade_compiled(&privilege)
+        );
+    }
+
+    quickcheck! {
+        fn agrees_with_privilege(lbl1: DCLabel, lbl2: DCLabel, privilege: Component) -> bool {
+            let compiled = privilege.compile();
+            lbl1.can_flow_to_with_compiled(&lbl2, &compiled) == lbl1.can_flow_to_with_privilege(&lbl2, &privilege)
+                && lbl1.clone().downgrade_compiled(&compiled) == lbl1.downgrade(&privilege)
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use super::clause::Clause;
+use super::component::Component;
+use alloc::vec::Vec;
+
+/// A component whose clauses are kept in a sorted `Vec`.
+///
+/// Clauses are ordered by their least principal first, so the clauses whose
+/// least principal is `p` are contiguous and found by binary search. A
+/// clause is only implied by clauses whose least principal it contains, so
+/// `implies` and `reduce` search those ranges instead of comparing every pair
+/// of clauses. The slice is also cheaper to walk than a `BTreeSet`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SortedComponent {
+    /// `None` for `DCFalse`, otherwise sorted and without duplicates.
+    clauses: Option<Vec<Clause>>,
+}
+
+impl SortedComponent {
+    pub fn dc_false() -> Self {
+        SortedComponent { clauses: None }
+    }
+
+    pub fn dc_true() -> Self {
+        SortedComponent { clauses: Some(Vec::new()) }
+    }
+
+    /// Returns true, with room for `capacity` clauses to be conjoined with
+    /// `insert_clause`.
+    pub fn with_capacity(capacity: usize) -> Self {
+        SortedComponent { clauses: Some(Vec::with_capacity(capacity)) }
+    }
+
+    pub fn is_false(&self) -> bool {
+        self.clauses.is_none()
+    }
+
+    pub fn is_true(&self) -> bool {
+        self.clauses.as_ref().is_some_and(Vec::is_empty)
+    }
+
+    /// Returns the sorted clauses of a formula, or `None` for `DCFalse`.
+    pub fn clauses(&self) -> Option<&[Clause]> {
+        self.clauses.as_deref()
+    }
+
+    pub fn implies(&self, other: &Self) -> bool {
+        match (&self.clauses, &other.clauses) {
+            (None, _) => true,
+            (_, None) => false,
+            (Some(s), Some(o)) => o.iter().all(|oclause| implied(s, oclause, false)),
+        }
+    }
+
+    /// Conjoins `clause` as `Component::insert_clause` does, returning
+    /// whether it was inserted.
+    pub fn insert_clause(&mut self, clause: Clause) -> bool {
+        match &mut self.clauses {
+            Some(clauses) if !implied(clauses, &clause, false) => {
+                clauses.retain(|c| !clause.implies(c));
+                let i = clauses.partition_point(|c| *c < clause);
+                clauses.insert(i, clause);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn reduce(&mut self) {
+        if let Some(clauses) = &mut self.clauses {
+            // A clause implied by a redundant clause is implied by whatever
+            // made that clause redundant, so checking against the unreduced
+            // clauses gives the same result.
+            let keep: Vec<bool> = clauses.iter().map(|c| !implied(clauses, c, true)).collect();
+            if keep.contains(&false) {
+                let mut keep = keep.into_iter();
+                clauses.retain(|_| keep.next().unwrap_or(true));
+            }
+        }
+    }
+}
+
+/// Returns whether a clause of `clauses` implies `target`, ignoring `target`
+/// itself if `strict`.
+fn implied(clauses: &[Clause], target: &Clause, strict: bool) -> bool {
+    // The empty clause sorts first and implies every clause.
+    if clauses.first().is_some_and(|c| c.0.is_empty()) && !(strict && target.0.is_empty()) {
+        return true;
+    }
+    target.0.iter().any(|p| {
+        let start = clauses.partition_point(|c| c.0.first().is_none_or(|q| q < p));
+        clauses
+            .iter()
+            .skip(start)
+            .take_while(|c| c.0.first() == Some(p))
+            .any(|c| !(strict && c == target) && c.implies(target))
+    })
+}
+
+impl From<Component> for SortedComponent {
+    fn from(component: Component) -> Self {
+        // A `BTreeSet` iterates in order, so the clauses are already sorted.
+        SortedComponent { clauses: component.into_clauses().map(|c| c.into_iter().collect()) }
+    }
+}
+
+impl From<SortedComponent> for Component {
+    fn from(component: SortedComponent) -> Self {
+        match component.clauses {
+            None => Component::DCFalse,
+            Some(clauses) => Component::from(clauses.into_iter().collect::<alloc::collections::BTreeSet<_>>()),
+        }
+    }
+}
+
+impl From<bool> for SortedComponent {
+    fn from(clause: bool) -> Self {
+        if clause {
+            SortedComponent::dc_true()
+        } else {
+            SortedComponent::dc_false()
+        }
+    }
+}
+
+impl core::ops::BitAnd for SortedComponent {
+    type Output = SortedComponent;
+    fn bitand(self, rhs: Self) -> SortedComponent {
+        match (self, rhs) {
+            (s, o) if s.is_false() || o.is_false() => SortedComponent::dc_false(),
+            (s, o) if o.is_true() => s,
+            (s, o) if s.is_true() => o,
+            (s, o) => {
+                let mut all: Vec<Clause> = s.clauses.into_iter().chain(o.clauses).flatten().collect();
+                all.sort_unstable();
+                all.dedup();
+                let mut result = SortedComponent::with_capacity(all.len());
+                for clause in all {
+                    result.insert_clause(clause);
+                }
+                result
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_implies() {
+        let amit = SortedComponent::from(Component::from([["Amit"]]));
+        let either = SortedComponent::from(Component::from([["Amit", "Yue"]]));
+        let both = SortedComponent::from(Component::from([["Amit"], ["Yue"]]));
+        assert!(amit.implies(&either));
+        assert!(!either.implies(&amit));
+        assert!(both.implies(&amit));
+        assert!(!amit.implies(&both));
+        assert!(SortedComponent::dc_false().implies(&amit));
+        assert!(amit.implies(&SortedComponent::dc_true()));
+        assert!(!SortedComponent::dc_true().implies(&amit));
+    }
+
+    #[test]
+    fn test_reduce() {
+        let mut component = SortedComponent::from(Component::from([["Amit", "Yue"]]))
+            & SortedComponent::from(Component::from([Clause::from(["Yue"]), Clause::from(["David", "Yue"])]));
+        component.reduce();
+        assert_eq!(SortedComponent::from(Component::from([["Yue"]])), component);
+    }
+
+    quickcheck! {
+        fn implies_agrees(c1: Component, c2: Component) -> bool {
+            SortedComponent::from(c1.clone()).implies(&SortedComponent::from(c2.clone())) == c1.implies(&c2)
+        }
+
+        fn reduce_agrees(component: Component) -> bool {
+            let mut sorted = SortedComponent::from(component.clone());
+            let mut component = component;
+            sorted.reduce();
+            component.reduce();
+            Component::from(sorted) == component
+        }
+
+        fn and_agrees(c1: Component, c2: Component) -> bool {
+            Component::from(SortedComponent::from(c1.clone()) & SortedComponent::from(c2.clone())) == c1 & c2
+        }
+    }
+}
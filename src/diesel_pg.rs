@@ -0,0 +1,89 @@
+//! Diesel support for storing labels in Postgres.
+//!
+//! Mirrors `sqlx_pg`: `DCLabel` and `Buckle` map to `Text` columns holding
+//! the label's textual syntax, while `Binary` maps a label to a `Binary`
+//! (`BYTEA`) column holding its envelope (see `envelope`).
+
+extern crate std;
+
+use crate::envelope::{self, Enveloped};
+use diesel::deserialize::{self, FromSql};
+use diesel::pg::{Pg, PgValue};
+use diesel::serialize::{self, IsNull, Output, ToSql};
+use diesel::sql_types;
+use std::io::Write;
+
+/// Implements the text mapping for `$label`, parsing with the nom parser
+/// `$parser`.
+#[cfg(any(feature = "dclabel", feature = "buckle"))]
+macro_rules! impl_text {
+    ($label:path, $parser:path) => {
+        impl ToSql<sql_types::Text, Pg> for $label {
+            fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+                write!(out, "{}", self)?;
+                Ok(IsNull::No)
+            }
+        }
+
+        impl FromSql<sql_types::Text, Pg> for $label {
+            fn from_sql(value: PgValue<'_>) -> deserialize::Result<Self> {
+                let text = core::str::from_utf8(value.as_bytes())?;
+                nom::combinator::all_consuming($parser)(text)
+                    .map(|(_, label)| label)
+                    .map_err(|_| alloc::format!("invalid label `{}`", text).into())
+            }
+        }
+    };
+}
+
+#[cfg(feature = "dclabel")]
+impl_text!(crate::dclabel::DCLabel, crate::dclabel::DCLabel::parse);
+#[cfg(feature = "buckle")]
+impl_text!(crate::buckle::Buckle, crate::buckle::Buckle::parser);
+
+/// A label stored in a `Binary` column in its envelope encoding.
+#[derive(Debug, Clone, PartialEq, diesel::expression::AsExpression, diesel::deserialize::FromSqlRow)]
+#[diesel(sql_type = sql_types::Binary)]
+pub struct Binary<L>(pub L);
+
+impl<L: Enveloped + core::fmt::Debug> ToSql<sql_types::Binary, Pg> for Binary<L> {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        out.write_all(&envelope::encode(&self.0))?;
+        Ok(IsNull::No)
+    }
+}
+
+impl<L: Enveloped> FromSql<sql_types::Binary, Pg> for Binary<L> {
+    fn from_sql(value: PgValue<'_>) -> deserialize::Result<Self> {
+        envelope::decode(value.as_bytes())
+            .map(Binary)
+            .map_err(|e| alloc::format!("invalid label envelope: {:?}", e).into())
+    }
+}
+
+#[cfg(all(test, feature = "buckle", feature = "buckle2"))]
+mod tests {
+    use super::*;
+    use diesel::prelude::*;
+
+    diesel::table! {
+        events (id) {
+            id -> Integer,
+            label -> Text,
+            envelope -> Binary,
+        }
+    }
+
+    #[test]
+    fn test_insert() {
+        use crate::buckle::Buckle;
+        use crate::buckle2::Buckle2;
+
+        let query = diesel::insert_into(events::table).values((
+            events::label.eq(Buckle::parse("amit/grades,T").unwrap()),
+            events::envelope.eq(Binary(Buckle2::top())),
+        ));
+        let sql = alloc::format!("{}", diesel::debug_query::<Pg, _>(&query));
+        assert!(sql.starts_with(r#"INSERT INTO "events" ("label", "envelope") VALUES ($1, $2)"#));
+    }
+}
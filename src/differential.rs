@@ -0,0 +1,119 @@
+//! Differential consistency checking between `buckle` and `buckle2`.
+//!
+//! The two modules implement the same label model over different
+//! representations (`String` principals on the global heap versus byte-vector
+//! principals in an arbitrary allocator). This module converts `Buckle` labels
+//! into `Buckle2` labels and checks that both implementations agree on the
+//! outcome of each label operation, so that the two can serve as an executable
+//! oracle for one another.
+
+use alloc::collections::BTreeSet;
+
+use crate::buckle::{self, Buckle};
+use crate::buckle2::{self, Buckle2};
+use crate::{HasPrivilege, Label};
+
+/// A label operation on which `buckle` and `buckle2` disagreed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    CanFlowTo,
+    Lub,
+    Glb,
+    Downgrade,
+}
+
+pub fn clause_to_buckle2(clause: &buckle::Clause) -> buckle2::Clause {
+    buckle2::Clause(
+        clause
+            .0
+            .iter()
+            .map(|path| path.iter().map(|p| buckle2::Principal::from(p.as_bytes())).collect())
+            .collect(),
+    )
+}
+
+pub fn component_to_buckle2(component: &buckle::Component) -> buckle2::Component {
+    match component {
+        buckle::Component::DCFalse => buckle2::Component::dc_false(),
+        buckle::Component::DCFormula(clauses) => clauses
+            .iter()
+            .map(clause_to_buckle2)
+            .collect::<BTreeSet<_>>()
+            .into(),
+    }
+}
+
+/// Converts a `Buckle` label into the equivalent `Buckle2` label.
+///
+/// The components are converted as-is and are not reduced, so an unreduced
+/// input yields an equally unreduced output.
+pub fn to_buckle2(label: &Buckle) -> Buckle2 {
+    let mut result = Buckle2::public();
+    result.secrecy = component_to_buckle2(&label.secrecy);
+    result.integrity = component_to_buckle2(&label.integrity);
+    result
+}
+
+/// Runs every operation on both implementations and reports the first one
+/// whose results differ.
+pub fn check(lbl1: &Buckle, lbl2: &Buckle, privilege: &buckle::Component) -> Result<(), Operation> {
+    let (b1, b2) = (to_buckle2(lbl1), to_buckle2(lbl2));
+    let bprivilege = component_to_buckle2(privilege);
+
+    if lbl1.can_flow_to(lbl2) != b1.can_flow_to(&b2) {
+        return Err(Operation::CanFlowTo);
+    }
+    if to_buckle2(&lbl1.clone().lub(lbl2.clone())) != b1.clone().lub(b2.clone()) {
+        return Err(Operation::Lub);
+    }
+    if to_buckle2(&lbl1.clone().glb(lbl2.clone())) != b1.clone().glb(b2) {
+        return Err(Operation::Glb);
+    }
+    if to_buckle2(&lbl1.clone().downgrade(privilege)) != b1.downgrade(&bprivilege) {
+        return Err(Operation::Downgrade);
+    }
+    Ok(())
+}
+
+/// Like `check`, but panics with the offending inputs on disagreement.
+pub fn assert_consistent(lbl1: &Buckle, lbl2: &Buckle, privilege: &buckle::Component) {
+    if let Err(op) = check(lbl1, lbl2, privilege) {
+        panic!(
+            "buckle and buckle2 disagree on {:?} for {:?}, {:?} with privilege {:?}",
+            op, lbl1, lbl2, privilege
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversion() {
+        assert_eq!(Buckle2::public(), to_buckle2(&Buckle::public()));
+        assert_eq!(Buckle2::top(), to_buckle2(&Buckle::top()));
+        assert_eq!(Buckle2::bottom(), to_buckle2(&Buckle::bottom()));
+        assert_eq!(
+            Buckle2::new([["Amit"], ["Yue"]], [["Amit", "Yue"]]),
+            to_buckle2(&Buckle::new([["Amit"], ["Yue"]], [["Amit", "Yue"]]))
+        );
+    }
+
+    #[test]
+    fn test_consistent() {
+        let privilege = buckle::Component::formula([["go_grader"]]);
+        assert_consistent(
+            &Buckle::new([["go_grader"], ["bob"]], [["go_grader"]]),
+            &Buckle::new([["bob"]], [["go_grader"]]),
+            &privilege,
+        );
+        assert_consistent(&Buckle::top(), &Buckle::bottom(), &false.into());
+    }
+
+    quickcheck! {
+        fn buckle_and_buckle2_agree(lbl1: Buckle, lbl2: Buckle, privilege: buckle::Component) -> bool {
+            check(&lbl1, &lbl2, &privilege).is_ok()
+        }
+    }
+}
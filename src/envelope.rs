@@ -0,0 +1,913 @@
+//! Versioned, self-describing binary format for persisting labels.
+//!
+//! An envelope is a version byte, a label-kind tag and a payload:
+//!
+//! ```text
+//! envelope  = version kind payload
+//! payload   = component component        ; secrecy, then integrity
+//! component = %x00                       ; DCFalse
+//!           / %x01 uvarint *clause       ; DCFormula with that many clauses
+//! clause    = uvarint *principal         ; dclabel
+//!           / uvarint *path              ; buckle, buckle2
+//! path      = uvarint *principal
+//! principal = uvarint *OCTET             ; length-prefixed bytes
+//! ```
+//!
+//! `uvarint` is an unsigned LEB128 integer. Principals of `dclabel` and
+//! `buckle` labels must be valid UTF-8.
+//!
+//! `decode` accepts every version up to and including `VERSION`, so labels
+//! written by older releases remain readable. Versions newer than `VERSION`
+//! are rejected rather than guessed at. Decoded `DCLabel` and `Buckle`
+//! labels are reduced, so a label that wasn't round-trips to its reduced
+//! form; `Buckle2` components are decoded as written.
+//!
+//! `decode_any` decodes an envelope of any kind into an `AnyLabel`, for
+//! readers that don't know in advance what they will find, e.g. a service
+//! reading a store shared by a fleet mid-upgrade. An envelope written by a
+//! newer release fails with `Error::UnsupportedVersion`, carrying its
+//! version, so the reader can tell it apart from corrupt input and retry
+//! once upgraded.
+//!
+//! # Canonical form
+//!
+//! `Enveloped::canonical_bytes` encodes a label's canonical form, which is
+//! the same for all semantically equal labels: paths subsumed by another path
+//! of the same clause are dropped and the component is reduced. A formula
+//! holding the empty clause reduces to `{∅}`, which is kept apart from
+//! `DCFalse` as it is in memory, since only `DCFalse` implies `DCFalse`.
+//! Together with the sorted order
+//! of clauses and principals, this makes the bytes suitable for signing,
+//! hashing and comparing labels across processes. The canonical encoding is
+//! stable: it is the version 1 envelope of the canonical form, and won't
+//! change without a new `VERSION`.
+//!
+//! With the `digest` feature, `Enveloped::label_digest` hashes the canonical
+//! bytes into a `LabelDigest`, a compact key for caches, deduplication and
+//! audit logs.
+//!
+//! # Flow checks on encoded labels
+//!
+//! `can_flow_to_encoded` checks whether one enveloped label can flow to
+//! another by reading both payloads in place, without decoding them into
+//! labels, e.g. for proxies that only gate messages on their labels.
+
+use alloc::vec::Vec;
+
+/// The version written by `encode`.
+pub const VERSION: u8 = 1;
+
+/// The kind of label held by an envelope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum LabelKind {
+    DCLabel = 1,
+    Buckle = 2,
+    Buckle2 = 3,
+}
+
+impl LabelKind {
+    fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            1 => Some(LabelKind::DCLabel),
+            2 => Some(LabelKind::Buckle),
+            3 => Some(LabelKind::Buckle2),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The input ended before the label did.
+    Truncated,
+    /// The envelope was written by a newer version of this format.
+    UnsupportedVersion(u8),
+    /// The kind tag is not a known `LabelKind`.
+    UnknownKind(u8),
+    /// The envelope holds a different kind of label than requested.
+    KindMismatch { expected: LabelKind, found: LabelKind },
+    /// The payload is malformed, e.g. has a bad component tag or a
+    /// non-UTF-8 principal.
+    InvalidPayload,
+    /// Bytes remain after the label.
+    TrailingBytes,
+    /// The input is not unpadded URL-safe base64.
+    #[cfg(feature = "b64url")]
+    InvalidBase64,
+}
+
+/// The header of an envelope, with its still-undecoded payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LabelEnvelope<'a> {
+    pub version: u8,
+    pub kind: LabelKind,
+    pub payload: &'a [u8],
+}
+
+impl<'a> LabelEnvelope<'a> {
+    /// Splits `bytes` into header and payload, without decoding the payload.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, Error> {
+        match bytes {
+            [version, ..] if *version == 0 || *version > VERSION => {
+                Err(Error::UnsupportedVersion(*version))
+            }
+            [version, kind, payload @ ..] => Ok(LabelEnvelope {
+                version: *version,
+                kind: LabelKind::from_tag(*kind).ok_or(Error::UnknownKind(*kind))?,
+                payload,
+            }),
+            _ => Err(Error::Truncated),
+        }
+    }
+}
+
+/// A label that can be stored in an envelope.
+pub trait Enveloped: Sized {
+    const KIND: LabelKind;
+
+    /// Writes the payload in the current `VERSION`.
+    fn write_payload(&self, w: &mut Writer);
+
+    /// Reads a payload written in `version`.
+    fn read_payload(r: &mut Reader, version: u8) -> Result<Self, Error>;
+
+    /// Returns the label's canonical form (see the module documentation).
+    fn canonical(&self) -> Self;
+
+    /// Encodes the label's canonical form in an envelope, so that equal
+    /// labels produce identical bytes.
+    fn canonical_bytes(&self) -> Vec<u8> {
+        encode(&self.canonical())
+    }
+
+    /// Hashes the canonical bytes with SHA-256.
+    #[cfg(feature = "digest")]
+    fn label_digest(&self) -> LabelDigest {
+        self.label_digest_with::<sha2::Sha256>()
+    }
+
+    /// Hashes the canonical bytes with `D`, any hash with 32-byte output.
+    #[cfg(feature = "digest")]
+    fn label_digest_with<D>(&self) -> LabelDigest
+    where
+        D: digest::Digest + digest::OutputSizeUser<OutputSize = digest::consts::U32>,
+    {
+        LabelDigest(D::digest(self.canonical_bytes()).into())
+    }
+
+    /// Encodes the label's envelope as unpadded URL-safe base64, e.g. for
+    /// query parameters, cookies or object-store keys.
+    #[cfg(feature = "b64url")]
+    fn to_b64url(&self) -> alloc::string::String {
+        use base64::Engine;
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(encode(self))
+    }
+
+    /// Decodes a label encoded with `to_b64url`.
+    #[cfg(feature = "b64url")]
+    fn from_b64url(input: &str) -> Result<Self, Error> {
+        use base64::Engine;
+        let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(input)
+            .map_err(|_| Error::InvalidBase64)?;
+        decode(&bytes)
+    }
+}
+
+/// The hash of a label's canonical bytes, equal for equal labels.
+#[cfg(feature = "digest")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LabelDigest(pub [u8; 32]);
+
+#[cfg(feature = "digest")]
+impl LabelDigest {
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+/// Formats the digest as lowercase hex.
+#[cfg(feature = "digest")]
+impl core::fmt::Display for LabelDigest {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.0.iter().try_for_each(|b| write!(f, "{:02x}", b))
+    }
+}
+
+/// Encodes `label` in an envelope of the current `VERSION`.
+pub fn encode<L: Enveloped>(label: &L) -> Vec<u8> {
+    let mut w = Writer(alloc::vec![VERSION, L::KIND as u8]);
+    label.write_payload(&mut w);
+    w.0
+}
+
+/// Decodes an envelope holding a label of kind `L::KIND`, written in any
+/// supported version.
+pub fn decode<L: Enveloped>(bytes: &[u8]) -> Result<L, Error> {
+    let envelope = LabelEnvelope::parse(bytes)?;
+    if envelope.kind != L::KIND {
+        return Err(Error::KindMismatch { expected: L::KIND, found: envelope.kind });
+    }
+    let mut r = Reader(envelope.payload);
+    let label = L::read_payload(&mut r, envelope.version)?;
+    if !r.0.is_empty() {
+        return Err(Error::TrailingBytes);
+    }
+    Ok(label)
+}
+
+/// A label of any kind this build supports, as returned by `decode_any`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnyLabel {
+    #[cfg(feature = "dclabel")]
+    DCLabel(crate::dclabel::DCLabel),
+    #[cfg(feature = "buckle")]
+    Buckle(crate::buckle::Buckle),
+    #[cfg(feature = "buckle2")]
+    Buckle2(crate::buckle2::Buckle2),
+}
+
+impl AnyLabel {
+    /// Returns the kind of the label.
+    pub fn kind(&self) -> LabelKind {
+        match self {
+            #[cfg(feature = "dclabel")]
+            AnyLabel::DCLabel(_) => LabelKind::DCLabel,
+            #[cfg(feature = "buckle")]
+            AnyLabel::Buckle(_) => LabelKind::Buckle,
+            #[cfg(feature = "buckle2")]
+            AnyLabel::Buckle2(_) => LabelKind::Buckle2,
+        }
+    }
+}
+
+/// Decodes an envelope holding any kind of label, written in any supported
+/// version.
+///
+/// Fails with `Error::UnsupportedVersion` if the envelope is of a newer
+/// version, and `Error::UnknownKind` if it holds a kind of label this build
+/// lacks the feature for.
+pub fn decode_any(bytes: &[u8]) -> Result<AnyLabel, Error> {
+    let envelope = LabelEnvelope::parse(bytes)?;
+    match envelope.kind {
+        #[cfg(feature = "dclabel")]
+        LabelKind::DCLabel => decode(bytes).map(AnyLabel::DCLabel),
+        #[cfg(feature = "buckle")]
+        LabelKind::Buckle => decode(bytes).map(AnyLabel::Buckle),
+        #[cfg(feature = "buckle2")]
+        LabelKind::Buckle2 => decode(bytes).map(AnyLabel::Buckle2),
+        #[allow(unreachable_patterns)]
+        kind => Err(Error::UnknownKind(kind as u8)),
+    }
+}
+
+/// Returns whether the label in envelope `a` can flow to the label in
+/// envelope `b`, as `Label::can_flow_to` on the decoded labels would, while
+/// reading the payloads in place and allocating nothing.
+///
+/// Both envelopes must hold the same kind of label, and are validated as
+/// `decode` would validate them.
+pub fn can_flow_to_encoded(a: &[u8], b: &[u8]) -> Result<bool, Error> {
+    let (a, b) = (EncodedLabel::parse(a)?, EncodedLabel::parse(b)?);
+    if a.kind != b.kind {
+        return Err(Error::KindMismatch { expected: a.kind, found: b.kind });
+    }
+    Ok(component_implies(b.secrecy, a.secrecy, a.kind) && component_implies(a.integrity, b.integrity, a.kind))
+}
+
+pub struct Writer(Vec<u8>);
+
+impl Writer {
+    fn uvarint(&mut self, mut n: usize) {
+        while n >= 0x80 {
+            self.0.push(n as u8 | 0x80);
+            n >>= 7;
+        }
+        self.0.push(n as u8);
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.uvarint(bytes.len());
+        self.0.extend_from_slice(bytes);
+    }
+
+    /// Writes a component given its clauses, or `None` for `DCFalse`.
+    fn component<'c, C: 'c, I, F>(&mut self, clauses: Option<I>, mut clause: F)
+    where
+        I: ExactSizeIterator<Item = &'c C>,
+        F: FnMut(&mut Self, &'c C),
+    {
+        match clauses {
+            None => self.0.push(0),
+            Some(clauses) => {
+                self.0.push(1);
+                self.uvarint(clauses.len());
+                clauses.for_each(|c| clause(self, c));
+            }
+        }
+    }
+}
+
+pub struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<u8, Error> {
+        let (b, rest) = self.0.split_first().ok_or(Error::Truncated)?;
+        self.0 = rest;
+        Ok(*b)
+    }
+
+    fn uvarint(&mut self) -> Result<usize, Error> {
+        let mut n = 0usize;
+        for shift in (0..usize::BITS).step_by(7) {
+            let b = self.byte()?;
+            n |= ((b & 0x7f) as usize).checked_shl(shift).ok_or(Error::InvalidPayload)?;
+            if b & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err(Error::InvalidPayload)
+    }
+
+    /// Reads a length prefix, rejecting lengths that can't possibly fit in
+    /// the remaining input so that a corrupt prefix can't cause a large
+    /// allocation.
+    fn len(&mut self) -> Result<usize, Error> {
+        let len = self.uvarint()?;
+        if len > self.0.len() {
+            return Err(Error::Truncated);
+        }
+        Ok(len)
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], Error> {
+        let len = self.len()?;
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    #[cfg(any(feature = "dclabel", feature = "buckle"))]
+    fn string<S: From<&'a str>>(&mut self) -> Result<S, Error> {
+        let bytes = self.bytes()?;
+        core::str::from_utf8(bytes).map(Into::into).map_err(|_| Error::InvalidPayload)
+    }
+
+    /// Reads a component, calling `clause` once per clause. Returns `false`
+    /// for `DCFalse`.
+    fn component<F>(&mut self, mut clause: F) -> Result<bool, Error>
+    where
+        F: FnMut(&mut Self) -> Result<(), Error>,
+    {
+        match self.byte()? {
+            0 => Ok(false),
+            1 => {
+                for _ in 0..self.len()? {
+                    clause(self)?;
+                }
+                Ok(true)
+            }
+            _ => Err(Error::InvalidPayload),
+        }
+    }
+}
+
+/// A validated label payload, read in place.
+#[derive(Clone, Copy)]
+struct EncodedLabel<'a> {
+    kind: LabelKind,
+    secrecy: Option<Encoded<'a>>,
+    integrity: Option<Encoded<'a>>,
+}
+
+impl<'a> EncodedLabel<'a> {
+    fn parse(bytes: &'a [u8]) -> Result<Self, Error> {
+        let envelope = LabelEnvelope::parse(bytes)?;
+        let mut r = Reader(envelope.payload);
+        let secrecy = r.encoded_component(envelope.kind)?;
+        let integrity = r.encoded_component(envelope.kind)?;
+        if !r.0.is_empty() {
+            return Err(Error::TrailingBytes);
+        }
+        Ok(EncodedLabel { kind: envelope.kind, secrecy, integrity })
+    }
+}
+
+/// A run of `len` encoded items: the clauses of a component, the paths of a
+/// clause or the principals of a path. A `dclabel` clause's principals are
+/// read as paths of one principal, as prefix and equality coincide for
+/// those.
+#[derive(Clone, Copy)]
+struct Encoded<'a> {
+    len: usize,
+    bytes: &'a [u8],
+}
+
+impl<'a> Encoded<'a> {
+    /// Reads the items with `read`. The run was validated while parsing, so
+    /// reading it again can't fail.
+    fn iter<T, F>(self, mut read: F) -> impl Iterator<Item = T> + 'a
+    where
+        F: FnMut(&mut Reader<'a>) -> Result<T, Error> + 'a,
+    {
+        let mut r = Reader(self.bytes);
+        (0..self.len).map_while(move |_| read(&mut r).ok())
+    }
+
+    fn starts_with(self, prefix: Encoded<'a>) -> bool {
+        prefix.len <= self.len && prefix.iter(Reader::bytes).zip(self.iter(Reader::bytes)).all(|(p, q)| p == q)
+    }
+
+    /// As `Clause::implies`: every path of `self` prefixes a path of `other`.
+    fn clause_implies(self, other: Encoded<'a>, kind: LabelKind) -> bool {
+        self.len == 0
+            || (other.len > 0
+                && self.iter(move |r| r.encoded_path(kind)).all(|p| other.iter(move |r| r.encoded_path(kind)).any(|q| q.starts_with(p))))
+    }
+}
+
+/// As `Component::implies`, with `None` for `DCFalse`.
+fn component_implies(s: Option<Encoded>, o: Option<Encoded>, kind: LabelKind) -> bool {
+    match (s, o) {
+        (None, _) => true,
+        (_, None) => false,
+        (Some(s), Some(o)) => o
+            .iter(move |r| r.encoded_clause(kind))
+            .all(|oclause| s.iter(move |r| r.encoded_clause(kind)).any(|sclause| sclause.clause_implies(oclause, kind))),
+    }
+}
+
+impl<'a> Reader<'a> {
+    /// Reads `len` items with `read`, returning the bytes they span.
+    fn encoded<F>(&mut self, len: usize, mut read: F) -> Result<Encoded<'a>, Error>
+    where
+        F: FnMut(&mut Self) -> Result<(), Error>,
+    {
+        let start = self.0;
+        for _ in 0..len {
+            read(self)?;
+        }
+        Ok(Encoded { len, bytes: &start[..start.len() - self.0.len()] })
+    }
+
+    fn encoded_path(&mut self, kind: LabelKind) -> Result<Encoded<'a>, Error> {
+        let len = if kind == LabelKind::DCLabel { 1 } else { self.len()? };
+        self.encoded(len, |r| {
+            let principal = r.bytes()?;
+            if kind != LabelKind::Buckle2 && core::str::from_utf8(principal).is_err() {
+                return Err(Error::InvalidPayload);
+            }
+            Ok(())
+        })
+    }
+
+    fn encoded_clause(&mut self, kind: LabelKind) -> Result<Encoded<'a>, Error> {
+        let len = self.len()?;
+        self.encoded(len, |r| r.encoded_path(kind).map(drop))
+    }
+
+    /// Reads a component, returning `None` for `DCFalse`.
+    fn encoded_component(&mut self, kind: LabelKind) -> Result<Option<Encoded<'a>>, Error> {
+        match self.byte()? {
+            0 => Ok(None),
+            1 => {
+                let len = self.len()?;
+                self.encoded(len, |r| r.encoded_clause(kind).map(drop)).map(Some)
+            }
+            _ => Err(Error::InvalidPayload),
+        }
+    }
+}
+
+#[cfg(feature = "dclabel")]
+mod dclabel {
+    use super::*;
+    use crate::dclabel::{Clause, Component, DCLabel};
+    use alloc::collections::BTreeSet;
+
+    fn write_component(w: &mut Writer, c: &Component) {
+        let clauses = match c {
+            Component::DCFalse => None,
+            Component::DCFormula(clauses) => Some(clauses.iter()),
+        };
+        w.component(clauses, |w, clause: &Clause| {
+            w.uvarint(clause.0.len());
+            clause.0.iter().for_each(|p| w.bytes(p.as_bytes()));
+        });
+    }
+
+    fn read_component(r: &mut Reader) -> Result<Component, Error> {
+        let mut clauses = BTreeSet::new();
+        let formula = r.component(|r| {
+            let mut clause = BTreeSet::new();
+            for _ in 0..r.len()? {
+                clause.insert(r.string()?);
+            }
+            clauses.insert(Clause::from(clause));
+            Ok(())
+        })?;
+        Ok(if formula { Component::from(clauses) } else { Component::DCFalse })
+    }
+
+    fn canonical_component(c: &Component) -> Component {
+        let mut c = c.clone();
+        c.reduce();
+        c
+    }
+
+    impl Enveloped for DCLabel {
+        const KIND: LabelKind = LabelKind::DCLabel;
+
+        fn write_payload(&self, w: &mut Writer) {
+            write_component(w, &self.secrecy);
+            write_component(w, &self.integrity);
+        }
+
+        fn read_payload(r: &mut Reader, _version: u8) -> Result<Self, Error> {
+            Ok(DCLabel::new(read_component(r)?, read_component(r)?))
+        }
+
+        fn canonical(&self) -> Self {
+            DCLabel { secrecy: canonical_component(&self.secrecy), integrity: canonical_component(&self.integrity) }
+        }
+    }
+}
+
+#[cfg(feature = "buckle")]
+mod buckle {
+    use super::*;
+    use crate::buckle::{Buckle, Clause, Component};
+    use alloc::collections::BTreeSet;
+
+    fn write_component(w: &mut Writer, c: &Component) {
+        let clauses = match c {
+            Component::DCFalse => None,
+            Component::DCFormula(clauses) => Some(clauses.iter()),
+        };
+        w.component(clauses, |w, clause: &Clause| {
+            w.uvarint(clause.0.len());
+            for path in clause.0.iter() {
+                w.uvarint(path.len());
+                path.iter().for_each(|p| w.bytes(p.as_bytes()));
+            }
+        });
+    }
+
+    fn read_component(r: &mut Reader) -> Result<Component, Error> {
+        let mut clauses = BTreeSet::new();
+        let formula = r.component(|r| {
+            let mut clause = BTreeSet::new();
+            for _ in 0..r.len()? {
+                let mut path = Vec::new();
+                for _ in 0..r.len()? {
+                    path.push(r.string()?);
+                }
+                clause.insert(path);
+            }
+            clauses.insert(Clause::from(clause));
+            Ok(())
+        })?;
+        Ok(if formula { Component::from(clauses) } else { Component::DCFalse })
+    }
+
+    fn canonical_component(c: &Component) -> Component {
+        let clauses = match c {
+            Component::DCFalse => return Component::DCFalse,
+            Component::DCFormula(clauses) => clauses,
+        };
+        let mut result = BTreeSet::new();
+        for clause in clauses.iter() {
+            // A path is subsumed by the paths extending it, e.g. `a | a/b` is
+            // equivalent to `a/b`.
+            let paths: BTreeSet<_> = clause
+                .0
+                .iter()
+                .filter(|p| !clause.0.iter().any(|q| q.len() > p.len() && q.starts_with(p)))
+                .cloned()
+                .collect();
+            result.insert(Clause::from(paths));
+        }
+        let mut result = Component::from(result);
+        result.reduce();
+        result
+    }
+
+    impl Enveloped for Buckle {
+        const KIND: LabelKind = LabelKind::Buckle;
+
+        fn write_payload(&self, w: &mut Writer) {
+            write_component(w, &self.secrecy);
+            write_component(w, &self.integrity);
+        }
+
+        fn read_payload(r: &mut Reader, _version: u8) -> Result<Self, Error> {
+            Ok(Buckle::new(read_component(r)?, read_component(r)?))
+        }
+
+        fn canonical(&self) -> Self {
+            Buckle { secrecy: canonical_component(&self.secrecy), integrity: canonical_component(&self.integrity) }
+        }
+    }
+}
+
+#[cfg(feature = "buckle2")]
+mod buckle2 {
+    use super::*;
+    use crate::buckle2::clause::principal_in;
+    use allocator_api2::vec::Vec;
+    use crate::buckle2::{Buckle2, Clause, Component};
+    use crate::buckle2::FlatSet;
+    use allocator_api2::alloc::Allocator;
+
+    fn write_component<A: Allocator + Clone>(w: &mut Writer, c: &Component<A>) {
+        let clauses = match c {
+            Component::DCFalse => None,
+            Component::DCFormula(clauses, _) => Some(clauses.iter()),
+        };
+        w.component(clauses, |w, clause: &Clause<A>| {
+            w.uvarint(clause.0.len());
+            for path in clause.0.iter() {
+                w.uvarint(path.len());
+                path.iter().for_each(|p| w.bytes(p));
+            }
+        });
+    }
+
+    fn read_component<A: Allocator + Clone>(r: &mut Reader, alloc: &A) -> Result<Component<A>, Error> {
+        let mut clauses = FlatSet::new_in(alloc.clone());
+        let formula = r.component(|r| {
+            let mut clause = FlatSet::new_in(alloc.clone());
+            for _ in 0..r.len()? {
+                let mut path = Vec::new_in(alloc.clone());
+                for _ in 0..r.len()? {
+                    path.push(principal_in(r.bytes()?, alloc.clone()));
+                }
+                clause.insert(path);
+            }
+            clauses.insert(Clause(clause));
+            Ok(())
+        })?;
+        Ok(if formula { Component::DCFormula(clauses, alloc.clone()) } else { Component::DCFalse })
+    }
+
+    fn canonical_component<A: Allocator + Clone>(c: &Component<A>) -> Component<A> {
+        let (clauses, alloc) = match c {
+            Component::DCFalse => return Component::DCFalse,
+            Component::DCFormula(clauses, alloc) => (clauses, alloc),
+        };
+        let mut result = FlatSet::new_in(alloc.clone());
+        for clause in clauses.iter() {
+            // A path is subsumed by the paths extending it, e.g. `a | a/b` is
+            // equivalent to `a/b`.
+            let mut paths = FlatSet::new_in(alloc.clone());
+            paths.extend(
+                clause.0.iter().filter(|p| !clause.0.iter().any(|q| q.len() > p.len() && q.starts_with(p))).cloned(),
+            );
+            result.insert(Clause(paths));
+        }
+        let mut result = Component::DCFormula(result, alloc.clone());
+        result.reduce();
+        result
+    }
+
+    /// Labels are decoded into `A::default()`.
+    impl<A: Allocator + Clone + Default> Enveloped for Buckle2<A> {
+        const KIND: LabelKind = LabelKind::Buckle2;
+
+        fn write_payload(&self, w: &mut Writer) {
+            write_component(w, &self.secrecy);
+            write_component(w, &self.integrity);
+        }
+
+        fn read_payload(r: &mut Reader, _version: u8) -> Result<Self, Error> {
+            let alloc = A::default();
+            let mut result = Buckle2::public_in(alloc.clone());
+            result.secrecy = read_component(r, &alloc)?;
+            result.integrity = read_component(r, &alloc)?;
+            Ok(result)
+        }
+
+        fn canonical(&self) -> Self {
+            let mut result = self.clone();
+            result.secrecy = canonical_component(&self.secrecy);
+            result.integrity = canonical_component(&self.integrity);
+            result
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "dclabel")]
+    quickcheck! {
+        fn dclabel_round_trip(lbl: crate::dclabel::DCLabel) -> bool {
+            decode::<crate::dclabel::DCLabel>(&encode(&lbl)) == Ok(lbl)
+        }
+    }
+
+    #[cfg(feature = "buckle")]
+    quickcheck! {
+        fn buckle_round_trip(lbl: crate::buckle::Buckle) -> bool {
+            decode::<crate::buckle::Buckle>(&encode(&lbl)) == Ok(lbl)
+        }
+    }
+
+    #[cfg(feature = "buckle2")]
+    quickcheck! {
+        fn buckle2_round_trip(lbl: crate::buckle2::Buckle2) -> bool {
+            decode::<crate::buckle2::Buckle2>(&encode(&lbl)) == Ok(lbl)
+        }
+    }
+
+    #[cfg(feature = "buckle2")]
+    quickcheck! {
+        fn buckle2_decode_any(lbl: crate::buckle2::Buckle2) -> bool {
+            decode_any(&encode(&lbl)) == Ok(AnyLabel::Buckle2(lbl))
+        }
+    }
+
+    #[cfg(all(feature = "dclabel", feature = "buckle"))]
+    #[test]
+    fn test_decode_any_kinds() {
+        use crate::buckle::Buckle;
+        use crate::dclabel::DCLabel;
+
+        let label = DCLabel::parse("a,b").unwrap().1;
+        assert_eq!(Ok(AnyLabel::DCLabel(label.clone())), decode_any(&encode(&label)));
+        let label = Buckle::parse("a/b,T").unwrap();
+        let decoded = decode_any(&encode(&label));
+        assert_eq!(Ok(LabelKind::Buckle), decoded.as_ref().map(AnyLabel::kind));
+        assert_eq!(Ok(AnyLabel::Buckle(label)), decoded);
+    }
+
+    #[cfg(feature = "buckle2")]
+    #[test]
+    fn test_decode_any_versions() {
+        let mut bytes = encode(&crate::buckle2::Buckle2::top());
+        bytes[0] = VERSION + 1;
+        assert_eq!(Err(Error::UnsupportedVersion(VERSION + 1)), decode_any(&bytes));
+        bytes[0] = 0;
+        assert_eq!(Err(Error::UnsupportedVersion(0)), decode_any(&bytes));
+        assert_eq!(Err(Error::UnknownKind(9)), decode_any(&[VERSION, 9]));
+        assert_eq!(Err(Error::Truncated), decode_any(&[VERSION]));
+    }
+
+    #[cfg(feature = "dclabel")]
+    quickcheck! {
+        fn dclabel_can_flow_to_encoded(lbl1: crate::dclabel::DCLabel, lbl2: crate::dclabel::DCLabel) -> bool {
+            use crate::Label;
+            can_flow_to_encoded(&encode(&lbl1), &encode(&lbl2)) == Ok(lbl1.can_flow_to(&lbl2))
+        }
+    }
+
+    #[cfg(feature = "buckle")]
+    quickcheck! {
+        fn buckle_can_flow_to_encoded(lbl1: crate::buckle::Buckle, lbl2: crate::buckle::Buckle) -> bool {
+            use crate::Label;
+            can_flow_to_encoded(&encode(&lbl1), &encode(&lbl2)) == Ok(lbl1.can_flow_to(&lbl2))
+        }
+    }
+
+    #[cfg(feature = "buckle2")]
+    quickcheck! {
+        fn buckle2_can_flow_to_encoded(lbl1: crate::buckle2::Buckle2, lbl2: crate::buckle2::Buckle2) -> bool {
+            use crate::Label;
+            can_flow_to_encoded(&encode(&lbl1), &encode(&lbl2)) == Ok(lbl1.can_flow_to(&lbl2))
+        }
+    }
+
+    #[cfg(feature = "buckle2")]
+    #[test]
+    fn test_can_flow_to_encoded() {
+        use crate::buckle2::Buckle2;
+
+        let (public, grades) = (encode(&Buckle2::public()), encode(&Buckle2::parse("amit/grades,T").unwrap()));
+        let amit = encode(&Buckle2::parse("amit,T").unwrap());
+        assert_eq!(Ok(true), can_flow_to_encoded(&public, &grades));
+        // `amit` implies `amit/grades`, so data for the latter may flow to
+        // the former but not back.
+        assert_eq!(Ok(true), can_flow_to_encoded(&grades, &amit));
+        assert_eq!(Ok(false), can_flow_to_encoded(&amit, &grades));
+        assert_eq!(Err(Error::TrailingBytes), can_flow_to_encoded(&[1, 3, 0, 1, 0, 0], &public));
+        assert_eq!(Err(Error::InvalidPayload), can_flow_to_encoded(&public, &[1, 3, 2, 1, 0]));
+        assert_eq!(Err(Error::Truncated), can_flow_to_encoded(&public, &[1, 3, 1, 0xff, 0xff, 0xff, 0x7f]));
+    }
+
+    #[cfg(all(feature = "dclabel", feature = "buckle2"))]
+    #[test]
+    fn test_can_flow_to_encoded_kinds() {
+        let (dclabel, buckle2) = (encode(&crate::dclabel::DCLabel::public()), encode(&crate::buckle2::Buckle2::public()));
+        assert_eq!(
+            Err(Error::KindMismatch { expected: LabelKind::DCLabel, found: LabelKind::Buckle2 }),
+            can_flow_to_encoded(&dclabel, &buckle2)
+        );
+        // Principals of `dclabel` labels must be UTF-8.
+        assert_eq!(Err(Error::InvalidPayload), can_flow_to_encoded(&[1, 1, 1, 1, 1, 1, 0xff, 1, 0], &dclabel));
+    }
+
+    #[cfg(feature = "buckle")]
+    quickcheck! {
+        fn buckle_canonical_bytes(lbl: crate::buckle::Buckle) -> bool {
+            use crate::Label;
+
+            let canonical = lbl.canonical();
+            canonical.can_flow_to(&lbl) && lbl.can_flow_to(&canonical) && encode(&canonical) == lbl.canonical_bytes()
+        }
+    }
+
+    #[cfg(feature = "buckle2")]
+    #[test]
+    fn test_canonical_bytes() {
+        use crate::buckle2::Buckle2;
+
+        let bytes = Buckle2::parse("a/b&a&c|c/d,T").unwrap().canonical_bytes();
+        assert_eq!(Buckle2::parse("c/d&a,T").unwrap().canonical_bytes(), bytes);
+        // Pins the encoding, which must stay stable.
+        assert_eq!(alloc::vec![1, 3, 1, 2, 1, 1, 1, b'a', 1, 2, 1, b'c', 1, b'd', 1, 0], bytes);
+        assert_ne!(Buckle2::parse("a/b,T").unwrap().canonical_bytes(), bytes);
+    }
+
+    #[cfg(feature = "buckle2")]
+    #[test]
+    fn test_canonical_bytes_empty_clause() {
+        use crate::buckle2::{Buckle2, Clause, Component};
+
+        let empty = Buckle2::new(Component::from([Clause::empty()]), true);
+        let dc_false = Buckle2::new(Component::dc_false(), true);
+        // `{∅}` absorbs every other clause but stays apart from `DCFalse`.
+        let absorbed = Buckle2::new(Component::from([Clause::empty(), Clause::from(["a"])]), true);
+        assert_eq!(empty.canonical_bytes(), absorbed.canonical_bytes());
+        assert_ne!(empty.canonical_bytes(), dc_false.canonical_bytes());
+        assert_eq!(alloc::vec![1, 3, 1, 1, 0, 1, 0], empty.canonical_bytes());
+    }
+
+    #[cfg(all(feature = "digest", feature = "buckle2"))]
+    #[test]
+    fn test_label_digest() {
+        use crate::buckle2::Buckle2;
+        use alloc::string::ToString;
+        use sha2::{Digest, Sha256};
+
+        let digest = Buckle2::parse("a/b&a,T").unwrap().label_digest();
+        assert_eq!(Buckle2::parse("a,T").unwrap().label_digest(), digest);
+        assert_ne!(Buckle2::parse("a/b,T").unwrap().label_digest(), digest);
+        assert_eq!(64, digest.to_string().len());
+        // `Buckle2::top()`'s canonical bytes are [1, 3, 0, 1, 0].
+        assert_eq!(LabelDigest(Sha256::digest([1, 3, 0, 1, 0]).into()), Buckle2::top().label_digest());
+        assert_ne!(Buckle2::top().label_digest_with::<sha2::Sha512_256>(), Buckle2::top().label_digest());
+    }
+
+    #[cfg(feature = "buckle2")]
+    #[test]
+    fn test_buckle2_layout() {
+        use crate::buckle2::Buckle2;
+
+        assert_eq!(alloc::vec![1, 3, 0, 1, 0], encode(&Buckle2::top()));
+        assert_eq!(
+            alloc::vec![1, 3, 1, 1, 1, 1, 1, b'A', 1, 1, 1, 2, 1, b'B', 1, b'C'],
+            encode(&Buckle2::parse("A,B/C").unwrap())
+        );
+        assert_eq!(Ok(Buckle2::top()), decode(&[1, 3, 0, 1, 0]));
+    }
+
+    #[cfg(feature = "buckle2")]
+    #[test]
+    fn test_rejects_malformed() {
+        use crate::buckle2::Buckle2;
+
+        assert_eq!(Err(Error::Truncated), decode::<Buckle2>(&[]));
+        assert_eq!(Err(Error::UnsupportedVersion(2)), decode::<Buckle2>(&[2, 3, 0, 1, 0]));
+        assert_eq!(Err(Error::UnknownKind(9)), decode::<Buckle2>(&[1, 9, 0, 1, 0]));
+        assert_eq!(Err(Error::Truncated), decode::<Buckle2>(&[1, 3, 0, 1]));
+        assert_eq!(Err(Error::TrailingBytes), decode::<Buckle2>(&[1, 3, 0, 1, 0, 0]));
+        assert_eq!(Err(Error::InvalidPayload), decode::<Buckle2>(&[1, 3, 2, 1, 0]));
+        // A clause count far larger than the input.
+        assert_eq!(Err(Error::Truncated), decode::<Buckle2>(&[1, 3, 1, 0xff, 0xff, 0xff, 0x7f]));
+    }
+
+    #[cfg(all(feature = "b64url", feature = "buckle2"))]
+    #[test]
+    fn test_b64url() {
+        use crate::buckle2::Buckle2;
+
+        let lbl = Buckle2::parse("amit/grades,T").unwrap();
+        let encoded = lbl.to_b64url();
+        assert!(encoded.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_'));
+        assert_eq!(Ok(lbl), <Buckle2>::from_b64url(&encoded));
+        assert_eq!("AQMAAQA", Buckle2::top().to_b64url());
+        assert_eq!(Err(Error::InvalidBase64), <Buckle2>::from_b64url("AQMAAQA="));
+    }
+
+    #[cfg(all(feature = "buckle", feature = "buckle2"))]
+    #[test]
+    fn test_kind_mismatch() {
+        let bytes = encode(&crate::buckle::Buckle::top());
+        assert_eq!(
+            Err(Error::KindMismatch { expected: LabelKind::Buckle2, found: LabelKind::Buckle }),
+            decode::<crate::buckle2::Buckle2>(&bytes)
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! The crate-wide error type.
+//!
+//! Each module reports failures in its own error type, which says exactly
+//! what went wrong there. `Error` wraps all of them, so that code using
+//! several modules can propagate their failures with `?` into a single
+//! type. Variants exist only for the modules a build enables, and more may
+//! be added with new modules, so matches need a wildcard arm.
+//!
+//! `fs::Error` and `vectors::Error` wrap I/O and JSON errors that can't be
+//! compared, and are left out to keep `Error` `PartialEq`.
+
+#[cfg(feature = "std")]
+extern crate std;
+
+use core::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// Text isn't a label in the syntax of the label type's `parse`. Holds
+    /// the byte offset parsing stopped at.
+    Parse(usize),
+    /// A clause has an empty principal or path.
+    #[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2"))]
+    MalformedClause(crate::MalformedClause),
+    /// A label exceeds a limit.
+    #[cfg(all(feature = "bounded", any(feature = "dclabel", feature = "buckle")))]
+    Limit(crate::bounded::LimitError),
+    /// Canonical text can't be parsed or written.
+    #[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2"))]
+    Text(crate::text::Error),
+    /// A binary envelope can't be decoded.
+    #[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2"))]
+    Envelope(crate::envelope::Error),
+    #[cfg(all(feature = "header", any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+    Header(crate::header::Error),
+    #[cfg(all(feature = "msgpack", any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+    MsgPack(crate::msgpack::Error),
+    #[cfg(all(feature = "registry", any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+    Registry(crate::registry::Error),
+    #[cfg(all(feature = "pki", any(feature = "dclabel", feature = "buckle")))]
+    Pki(crate::pki::Error),
+    #[cfg(all(feature = "jwt", any(feature = "dclabel", feature = "buckle")))]
+    Jwt(crate::jwt::Error),
+    #[cfg(feature = "macaroon")]
+    Macaroon(crate::macaroon::Error),
+    #[cfg(all(feature = "arrow", any(feature = "dclabel", feature = "buckle")))]
+    Arrow(crate::arrow::Error),
+    /// A label can't be converted to or from another label format.
+    #[cfg(feature = "migrate")]
+    Migrate(crate::migrate::Error),
+    #[cfg(feature = "selinux")]
+    Selinux(crate::selinux::Error),
+    #[cfg(feature = "tags")]
+    Tags(crate::tags::Error),
+    #[cfg(feature = "bitlabel")]
+    BitLabel(crate::bitlabel::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(offset) => write!(f, "invalid label syntax at byte {}", offset),
+            #[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2"))]
+            Error::MalformedClause(e) => write!(f, "{}", e),
+            #[cfg(all(feature = "bounded", any(feature = "dclabel", feature = "buckle")))]
+            Error::Limit(e) => write!(f, "{}", e),
+            #[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2"))]
+            Error::Text(e) => write!(f, "invalid label text: {:?}", e),
+            #[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2"))]
+            Error::Envelope(e) => write!(f, "invalid label envelope: {:?}", e),
+            #[cfg(all(feature = "header", any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+            Error::Header(e) => write!(f, "invalid label header: {:?}", e),
+            #[cfg(all(feature = "msgpack", any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+            Error::MsgPack(e) => write!(f, "invalid MessagePack label: {:?}", e),
+            #[cfg(all(feature = "registry", any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+            Error::Registry(e) => write!(f, "invalid label registry: {:?}", e),
+            #[cfg(all(feature = "pki", any(feature = "dclabel", feature = "buckle")))]
+            Error::Pki(e) => write!(f, "invalid label certificate extension: {:?}", e),
+            #[cfg(all(feature = "jwt", any(feature = "dclabel", feature = "buckle")))]
+            Error::Jwt(e) => write!(f, "invalid label claim: {:?}", e),
+            #[cfg(feature = "macaroon")]
+            Error::Macaroon(e) => write!(f, "invalid macaroon: {:?}", e),
+            #[cfg(all(feature = "arrow", any(feature = "dclabel", feature = "buckle")))]
+            Error::Arrow(e) => write!(f, "invalid Arrow label metadata: {:?}", e),
+            #[cfg(feature = "migrate")]
+            Error::Migrate(e) => write!(f, "can't migrate label: {:?}", e),
+            #[cfg(feature = "selinux")]
+            Error::Selinux(e) => write!(f, "can't convert SELinux context: {:?}", e),
+            #[cfg(feature = "tags")]
+            Error::Tags(e) => write!(f, "can't convert tags: {:?}", e),
+            #[cfg(feature = "bitlabel")]
+            Error::BitLabel(e) => write!(f, "can't convert bit label: {:?}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+macro_rules! impl_from {
+    ($(#[$cfg:meta])* $variant:ident, $error:ty) => {
+        $(#[$cfg])*
+        impl From<$error> for Error {
+            fn from(e: $error) -> Self {
+                Error::$variant(e)
+            }
+        }
+    };
+}
+
+impl_from!(#[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2"))] MalformedClause, crate::MalformedClause);
+impl_from!(#[cfg(all(feature = "bounded", any(feature = "dclabel", feature = "buckle")))] Limit, crate::bounded::LimitError);
+impl_from!(#[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2"))] Text, crate::text::Error);
+impl_from!(#[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2"))] Envelope, crate::envelope::Error);
+impl_from!(#[cfg(all(feature = "header", any(feature = "dclabel", feature = "buckle", feature = "buckle2")))] Header, crate::header::Error);
+impl_from!(#[cfg(all(feature = "msgpack", any(feature = "dclabel", feature = "buckle", feature = "buckle2")))] MsgPack, crate::msgpack::Error);
+impl_from!(#[cfg(all(feature = "registry", any(feature = "dclabel", feature = "buckle", feature = "buckle2")))] Registry, crate::registry::Error);
+impl_from!(#[cfg(all(feature = "pki", any(feature = "dclabel", feature = "buckle")))] Pki, crate::pki::Error);
+impl_from!(#[cfg(all(feature = "jwt", any(feature = "dclabel", feature = "buckle")))] Jwt, crate::jwt::Error);
+impl_from!(#[cfg(feature = "macaroon")] Macaroon, crate::macaroon::Error);
+impl_from!(#[cfg(all(feature = "arrow", any(feature = "dclabel", feature = "buckle")))] Arrow, crate::arrow::Error);
+impl_from!(#[cfg(feature = "migrate")] Migrate, crate::migrate::Error);
+impl_from!(#[cfg(feature = "selinux")] Selinux, crate::selinux::Error);
+impl_from!(#[cfg(feature = "tags")] Tags, crate::tags::Error);
+impl_from!(#[cfg(feature = "bitlabel")] BitLabel, crate::bitlabel::Error);
+
+/// Returns the parse error for a nom parser that stopped with `remaining`
+/// of `input` left.
+#[cfg(any(feature = "dclabel", feature = "buckle"))]
+pub(crate) fn from_nom(input: &str, error: nom::Err<nom::error::Error<&str>>) -> Error {
+    match error {
+        nom::Err::Error(e) | nom::Err::Failure(e) => Error::Parse(input.len() - e.input.len()),
+        nom::Err::Incomplete(_) => Error::Parse(input.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!("invalid label syntax at byte 3", alloc::format!("{}", Error::Parse(3)));
+    }
+
+    #[cfg(feature = "buckle2")]
+    #[test]
+    fn test_buckle2_parse() {
+        use crate::buckle2::Buckle2;
+
+        assert_eq!(Some(Error::Parse(3)), Buckle2::parse("a,b,c").err());
+        assert_eq!(Some(Error::Parse(1)), Buckle2::parse("a").err());
+    }
+
+    #[cfg(feature = "buckle")]
+    #[test]
+    fn test_buckle_parse() {
+        use crate::buckle::Buckle;
+
+        assert_eq!(Some(Error::Parse(1)), Buckle::parse("a").err());
+    }
+}
@@ -0,0 +1,353 @@
+//! FlatBuffers support for labels.
+//!
+//! The tables are described by `schema/label.fbs`. Rather than relying on
+//! `flatc`-generated code, this module provides equivalent hand-written table
+//! types, so labels can be embedded in other tables (e.g. event records) via
+//! `create`, and read back zero-copy from any verified buffer.
+//!
+//! `DCFalse` is written as an absent clause vector with the component's
+//! `_is_false` flag set, so a component holding the empty clause round-trips
+//! as itself. When the flag is set, the component's clauses are ignored.
+
+use alloc::vec::Vec;
+use flatbuffers::{
+    Allocator, FlatBufferBuilder, Follow, ForwardsUOffset, InvalidFlatbuffer, Table, UnionWIPOffset,
+    VOffsetT, Verifiable, Verifier, Vector, WIPOffset,
+};
+
+/// A vector of tables of type `T`.
+pub type Tables<'a, T> = Vector<'a, ForwardsUOffset<T>>;
+
+/// A vector of strings.
+pub type Strings<'a> = Vector<'a, ForwardsUOffset<&'a str>>;
+
+/// Declares a table with offset fields and then `bool` fields, along with
+/// its accessors and verifier.
+macro_rules! table {
+    ($name:ident<$a:lifetime> {
+        $($field:ident: $ty:ty = $vt:ident @ $slot:expr),* $(,)?
+        $(; $($flag:ident = $fvt:ident @ $fslot:expr),* $(,)?)?
+    }) => {
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        pub struct $name<$a> {
+            pub _tab: Table<$a>,
+        }
+
+        impl<$a> Follow<$a> for $name<$a> {
+            type Inner = $name<$a>;
+
+            unsafe fn follow(buf: &$a [u8], loc: usize) -> Self::Inner {
+                // Safety: upheld by the caller.
+                $name { _tab: unsafe { Table::new(buf, loc) } }
+            }
+        }
+
+        impl<$a> Verifiable for $name<$a> {
+            fn run_verifier(v: &mut Verifier, pos: usize) -> Result<(), InvalidFlatbuffer> {
+                v.visit_table(pos)?
+                    $(.visit_field::<ForwardsUOffset<$ty>>(stringify!($field), Self::$vt, false)?)*
+                    $($(.visit_field::<bool>(stringify!($flag), Self::$fvt, false)?)*)?
+                    .finish();
+                Ok(())
+            }
+        }
+
+        impl<$a> $name<$a> {
+            $(pub const $vt: VOffsetT = $slot;)*
+            $($(pub const $fvt: VOffsetT = $fslot;)*)?
+
+            $(pub fn $field(&self) -> Option<$ty> {
+                // Safety: tables are only reachable from verified buffers.
+                unsafe { self._tab.get::<ForwardsUOffset<$ty>>(Self::$vt, None) }
+            })*
+
+            $($(pub fn $flag(&self) -> bool {
+                // Safety: tables are only reachable from verified buffers.
+                unsafe { self._tab.get::<bool>(Self::$fvt, Some(false)) }.unwrap_or(false)
+            })*)?
+        }
+    };
+}
+
+fn create_strings<'fbb, 'p, A: Allocator + 'fbb>(
+    fbb: &mut FlatBufferBuilder<'fbb, A>,
+    strings: impl Iterator<Item = &'p str>,
+) -> WIPOffset<Strings<'fbb>> {
+    let offsets: Vec<_> = strings.map(|s| fbb.create_string(s)).collect();
+    fbb.create_vector(&offsets)
+}
+
+/// Writes a table whose fields are all offsets, given as `(slot, offset)`.
+fn create_table<'fbb, T, A: Allocator + 'fbb>(
+    fbb: &mut FlatBufferBuilder<'fbb, A>,
+    fields: &[(VOffsetT, WIPOffset<UnionWIPOffset>)],
+) -> WIPOffset<T> {
+    let start = fbb.start_table();
+    for &(slot, offset) in fields {
+        fbb.push_slot_always(slot, offset);
+    }
+    WIPOffset::new(fbb.end_table(start).value())
+}
+
+/// Writes a label table given the clause vectors of its secrecy and
+/// integrity, with `None` for `DCFalse`, and the slots of its fields in
+/// schema order.
+fn create_label<'fbb, T, A: Allocator + 'fbb>(
+    fbb: &mut FlatBufferBuilder<'fbb, A>,
+    slots: [VOffsetT; 4],
+    secrecy: Option<WIPOffset<UnionWIPOffset>>,
+    integrity: Option<WIPOffset<UnionWIPOffset>>,
+) -> WIPOffset<T> {
+    let start = fbb.start_table();
+    for (slot, is_false_slot, clauses) in [(slots[0], slots[2], secrecy), (slots[1], slots[3], integrity)] {
+        match clauses {
+            Some(clauses) => fbb.push_slot_always(slot, clauses),
+            None => fbb.push_slot_always(is_false_slot, true),
+        }
+    }
+    WIPOffset::new(fbb.end_table(start).value())
+}
+
+#[cfg(feature = "dclabel")]
+pub mod dclabel {
+    use super::*;
+    use crate::dclabel::{Clause as OwnedClause, Component, DCLabel as OwnedDCLabel, Principal};
+    use alloc::collections::BTreeSet;
+
+    table!(Clause<'a> { principals: Strings<'a> = VT_PRINCIPALS @ 4 });
+    table!(DCLabel<'a> {
+        secrecy: Tables<'a, Clause<'a>> = VT_SECRECY @ 4,
+        integrity: Tables<'a, Clause<'a>> = VT_INTEGRITY @ 6;
+        secrecy_is_false = VT_SECRECY_IS_FALSE @ 8,
+        integrity_is_false = VT_INTEGRITY_IS_FALSE @ 10,
+    });
+
+    fn create_clause<'fbb, 'p, A: Allocator + 'fbb>(
+        fbb: &mut FlatBufferBuilder<'fbb, A>,
+        principals: impl Iterator<Item = &'p str>,
+    ) -> WIPOffset<Clause<'fbb>> {
+        let principals = create_strings(fbb, principals);
+        create_table(fbb, &[(Clause::VT_PRINCIPALS, principals.as_union_value())])
+    }
+
+    /// Writes the clauses of `component`, or nothing for `DCFalse`.
+    fn create_component<'fbb, A: Allocator + 'fbb>(
+        fbb: &mut FlatBufferBuilder<'fbb, A>,
+        component: &Component,
+    ) -> Option<WIPOffset<UnionWIPOffset>> {
+        let offsets: Vec<_> = match component {
+            Component::DCFalse => return None,
+            Component::DCFormula(clauses) => clauses
+                .iter()
+                .map(|c| create_clause(fbb, c.0.iter().map(|p| p.as_str())))
+                .collect(),
+        };
+        Some(fbb.create_vector(&offsets).as_union_value())
+    }
+
+    /// Writes `label` as a `DCLabel` table, e.g. to be stored in a field of
+    /// an enclosing table.
+    pub fn create<'fbb, A: Allocator + 'fbb>(
+        fbb: &mut FlatBufferBuilder<'fbb, A>,
+        label: &OwnedDCLabel,
+    ) -> WIPOffset<DCLabel<'fbb>> {
+        let secrecy = create_component(fbb, &label.secrecy);
+        let integrity = create_component(fbb, &label.integrity);
+        let slots = [DCLabel::VT_SECRECY, DCLabel::VT_INTEGRITY, DCLabel::VT_SECRECY_IS_FALSE, DCLabel::VT_INTEGRITY_IS_FALSE];
+        create_label(fbb, slots, secrecy, integrity)
+    }
+
+    fn unpack_component(is_false: bool, clauses: Option<Tables<Clause>>) -> Component {
+        if is_false {
+            return Component::DCFalse;
+        }
+        let mut result = BTreeSet::new();
+        for clause in clauses.iter().flatten() {
+            let principals: BTreeSet<_> =
+                clause.principals().iter().flatten().map(Principal::from).collect();
+            result.insert(OwnedClause::from(principals));
+        }
+        Component::from(result)
+    }
+
+    impl DCLabel<'_> {
+        /// Decodes the table into an owned `DCLabel`.
+        pub fn unpack(&self) -> OwnedDCLabel {
+            OwnedDCLabel {
+                secrecy: unpack_component(self.secrecy_is_false(), self.secrecy()),
+                integrity: unpack_component(self.integrity_is_false(), self.integrity()),
+            }
+        }
+    }
+
+    /// Encodes `label` as a finished buffer with a `DCLabel` root.
+    pub fn encode(label: &OwnedDCLabel) -> Vec<u8> {
+        let mut fbb = FlatBufferBuilder::new();
+        let root = create(&mut fbb, label);
+        fbb.finish_minimal(root);
+        fbb.finished_data().to_vec()
+    }
+
+    /// Verifies `buf` and decodes its `DCLabel` root.
+    pub fn decode(buf: &[u8]) -> Result<OwnedDCLabel, InvalidFlatbuffer> {
+        Ok(flatbuffers::root::<DCLabel>(buf)?.unpack())
+    }
+}
+
+#[cfg(feature = "buckle")]
+pub mod buckle {
+    use super::*;
+    use crate::buckle::{Buckle as OwnedBuckle, Clause as OwnedClause, Component, Principal};
+    use alloc::collections::BTreeSet;
+
+    table!(Path<'a> { principals: Strings<'a> = VT_PRINCIPALS @ 4 });
+    table!(PathClause<'a> { paths: Tables<'a, Path<'a>> = VT_PATHS @ 4 });
+    table!(Buckle<'a> {
+        secrecy: Tables<'a, PathClause<'a>> = VT_SECRECY @ 4,
+        integrity: Tables<'a, PathClause<'a>> = VT_INTEGRITY @ 6;
+        secrecy_is_false = VT_SECRECY_IS_FALSE @ 8,
+        integrity_is_false = VT_INTEGRITY_IS_FALSE @ 10,
+    });
+
+    fn create_clause<'fbb, A: Allocator + 'fbb>(
+        fbb: &mut FlatBufferBuilder<'fbb, A>,
+        paths: &[&[Principal]],
+    ) -> WIPOffset<PathClause<'fbb>> {
+        let offsets: Vec<WIPOffset<Path>> = paths
+            .iter()
+            .map(|path| {
+                let principals = create_strings(fbb, path.iter().map(|p| p.as_str()));
+                create_table(fbb, &[(Path::VT_PRINCIPALS, principals.as_union_value())])
+            })
+            .collect();
+        let paths = fbb.create_vector(&offsets);
+        create_table(fbb, &[(PathClause::VT_PATHS, paths.as_union_value())])
+    }
+
+    /// Writes the clauses of `component`, or nothing for `DCFalse`.
+    fn create_component<'fbb, A: Allocator + 'fbb>(
+        fbb: &mut FlatBufferBuilder<'fbb, A>,
+        component: &Component,
+    ) -> Option<WIPOffset<UnionWIPOffset>> {
+        let offsets: Vec<_> = match component {
+            Component::DCFalse => return None,
+            Component::DCFormula(clauses) => clauses
+                .iter()
+                .map(|c| {
+                    let paths: Vec<&[_]> = c.0.iter().map(|p| p.as_slice()).collect();
+                    create_clause(fbb, &paths)
+                })
+                .collect(),
+        };
+        Some(fbb.create_vector(&offsets).as_union_value())
+    }
+
+    /// Writes `label` as a `Buckle` table, e.g. to be stored in a field of
+    /// an enclosing table.
+    pub fn create<'fbb, A: Allocator + 'fbb>(
+        fbb: &mut FlatBufferBuilder<'fbb, A>,
+        label: &OwnedBuckle,
+    ) -> WIPOffset<Buckle<'fbb>> {
+        let secrecy = create_component(fbb, &label.secrecy);
+        let integrity = create_component(fbb, &label.integrity);
+        let slots = [Buckle::VT_SECRECY, Buckle::VT_INTEGRITY, Buckle::VT_SECRECY_IS_FALSE, Buckle::VT_INTEGRITY_IS_FALSE];
+        create_label(fbb, slots, secrecy, integrity)
+    }
+
+    fn unpack_component(is_false: bool, clauses: Option<Tables<PathClause>>) -> Component {
+        if is_false {
+            return Component::DCFalse;
+        }
+        let mut result = BTreeSet::new();
+        for clause in clauses.iter().flatten() {
+            let paths: BTreeSet<_> = clause
+                .paths()
+                .iter()
+                .flatten()
+                .map(|path| path.principals().iter().flatten().map(Principal::from).collect())
+                .collect();
+            result.insert(OwnedClause::from(paths));
+        }
+        Component::from(result)
+    }
+
+    impl Buckle<'_> {
+        /// Decodes the table into an owned `Buckle`.
+        pub fn unpack(&self) -> OwnedBuckle {
+            OwnedBuckle {
+                secrecy: unpack_component(self.secrecy_is_false(), self.secrecy()),
+                integrity: unpack_component(self.integrity_is_false(), self.integrity()),
+            }
+        }
+    }
+
+    /// Encodes `label` as a finished buffer with a `Buckle` root.
+    pub fn encode(label: &OwnedBuckle) -> Vec<u8> {
+        let mut fbb = FlatBufferBuilder::new();
+        let root = create(&mut fbb, label);
+        fbb.finish_minimal(root);
+        fbb.finished_data().to_vec()
+    }
+
+    /// Verifies `buf` and decodes its `Buckle` root.
+    pub fn decode(buf: &[u8]) -> Result<OwnedBuckle, InvalidFlatbuffer> {
+        Ok(flatbuffers::root::<Buckle>(buf)?.unpack())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "dclabel")]
+    quickcheck! {
+        fn dclabel_round_trip(lbl: crate::dclabel::DCLabel) -> bool {
+            super::dclabel::decode(&super::dclabel::encode(&lbl)) == Ok(lbl)
+        }
+    }
+
+    #[cfg(feature = "buckle")]
+    quickcheck! {
+        fn buckle_round_trip(lbl: crate::buckle::Buckle) -> bool {
+            super::buckle::decode(&super::buckle::encode(&lbl)) == Ok(lbl)
+        }
+    }
+
+    #[cfg(feature = "buckle")]
+    #[test]
+    fn test_buckle_empty_clause() {
+        use crate::buckle::{Buckle, Clause, Component};
+
+        let empty = Buckle::new(Component::from([Clause::empty()]), Component::dc_false());
+        assert_eq!(Ok(empty.clone()), super::buckle::decode(&super::buckle::encode(&empty)));
+        let dc_false = Buckle::new(Component::dc_false(), Component::from([Clause::empty()]));
+        assert_eq!(Ok(dc_false.clone()), super::buckle::decode(&super::buckle::encode(&dc_false)));
+    }
+
+    #[cfg(feature = "buckle")]
+    #[test]
+    fn test_buckle_nested_table() {
+        use crate::buckle::Buckle;
+        use flatbuffers::FlatBufferBuilder;
+
+        // An enclosing table with the label in its first field.
+        let lbl = Buckle::parse("amit/grades,amit/grader").unwrap();
+        let mut fbb = FlatBufferBuilder::new();
+        let label = super::buckle::create(&mut fbb, &lbl);
+        let start = fbb.start_table();
+        fbb.push_slot_always(4, label);
+        let root = fbb.end_table(start);
+        fbb.finish_minimal(root);
+
+        let event = unsafe { flatbuffers::root_unchecked::<flatbuffers::Table>(fbb.finished_data()) };
+        let table = unsafe { event.get::<flatbuffers::ForwardsUOffset<super::buckle::Buckle>>(4, None) };
+        assert_eq!(lbl, table.unwrap().unpack());
+    }
+
+    #[cfg(feature = "buckle")]
+    #[test]
+    fn test_buckle_rejects_truncated() {
+        use crate::buckle::Buckle;
+
+        let buf = super::buckle::encode(&Buckle::parse("amit,yue").unwrap());
+        assert!(super::buckle::decode(&buf[..buf.len() - 4]).is_err());
+    }
+}
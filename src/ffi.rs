@@ -0,0 +1,200 @@
+//! C interface to `Buckle2` labels.
+//!
+//! Labels and privileges are passed across the boundary as opaque handles,
+//! allocated by this module and released with `labeled_free` and
+//! `labeled_privilege_free` respectively. Operations never take ownership of
+//! their arguments; those that produce a label return a fresh handle. Labels
+//! and privileges use the same string syntax as `Buckle2::parse`, with a
+//! privilege written as a single component (e.g. `"amit&yue"`).
+//!
+//! A C header can be generated with `cbindgen` using the `cbindgen.toml` at the
+//! root of the repository.
+
+use crate::buckle2::{Buckle2, Component};
+use crate::{HasPrivilege, Label};
+use allocator_api2::alloc::Global;
+use alloc::boxed::Box;
+use core::ffi::{c_char, CStr};
+
+/// An opaque label handle.
+pub struct LabeledLabel(Buckle2);
+
+/// An opaque privilege handle.
+pub struct LabeledPrivilege(Component);
+
+fn new_label(label: Buckle2) -> *mut LabeledLabel {
+    Box::into_raw(Box::new(LabeledLabel(label)))
+}
+
+/// Parses a label, returning NULL if `input` is not valid UTF-8 or not a
+/// valid label.
+///
+/// # Safety
+///
+/// `input` must be a valid, NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn labeled_parse(input: *const c_char) -> *mut LabeledLabel {
+    match unsafe { CStr::from_ptr(input) }.to_str().map(Buckle2::parse) {
+        Ok(Ok(label)) => new_label(label),
+        _ => core::ptr::null_mut(),
+    }
+}
+
+/// Parses a privilege, returning NULL if `input` is not valid UTF-8 or not a
+/// component.
+///
+/// # Safety
+///
+/// `input` must be a valid, NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn labeled_privilege_parse(input: *const c_char) -> *mut LabeledPrivilege {
+    match unsafe { CStr::from_ptr(input) }.to_str().map(|input| Buckle2::parse_component(input, Global)) {
+        Ok(Ok(mut privilege)) => {
+            privilege.reduce();
+            Box::into_raw(Box::new(LabeledPrivilege(privilege)))
+        }
+        _ => core::ptr::null_mut(),
+    }
+}
+
+/// Returns the least upper bound of `lhs` and `rhs`.
+///
+/// # Safety
+///
+/// Both arguments must be live handles returned by this module.
+#[no_mangle]
+pub unsafe extern "C" fn labeled_lub(lhs: *const LabeledLabel, rhs: *const LabeledLabel) -> *mut LabeledLabel {
+    let (lhs, rhs) = unsafe { (&(*lhs).0, &(*rhs).0) };
+    new_label(lhs.clone().lub(rhs.clone()))
+}
+
+/// Returns the greatest lower bound of `lhs` and `rhs`.
+///
+/// # Safety
+///
+/// Both arguments must be live handles returned by this module.
+#[no_mangle]
+pub unsafe extern "C" fn labeled_glb(lhs: *const LabeledLabel, rhs: *const LabeledLabel) -> *mut LabeledLabel {
+    let (lhs, rhs) = unsafe { (&(*lhs).0, &(*rhs).0) };
+    new_label(lhs.clone().glb(rhs.clone()))
+}
+
+/// Returns whether data labeled `lhs` may flow to `rhs`.
+///
+/// # Safety
+///
+/// Both arguments must be live handles returned by this module.
+#[no_mangle]
+pub unsafe extern "C" fn labeled_can_flow_to(lhs: *const LabeledLabel, rhs: *const LabeledLabel) -> bool {
+    let (lhs, rhs) = unsafe { (&(*lhs).0, &(*rhs).0) };
+    lhs.can_flow_to(rhs)
+}
+
+/// Returns whether data labeled `lhs` may flow to `rhs` when exercising
+/// `privilege`.
+///
+/// # Safety
+///
+/// All arguments must be live handles returned by this module.
+#[no_mangle]
+pub unsafe extern "C" fn labeled_can_flow_to_with_privilege(
+    lhs: *const LabeledLabel,
+    rhs: *const LabeledLabel,
+    privilege: *const LabeledPrivilege,
+) -> bool {
+    let (lhs, rhs, privilege) = unsafe { (&(*lhs).0, &(*rhs).0, &(*privilege).0) };
+    lhs.can_flow_to_with_privilege(rhs, privilege)
+}
+
+/// Returns `label` downgraded as far as `privilege` allows.
+///
+/// # Safety
+///
+/// Both arguments must be live handles returned by this module.
+#[no_mangle]
+pub unsafe extern "C" fn labeled_downgrade(
+    label: *const LabeledLabel,
+    privilege: *const LabeledPrivilege,
+) -> *mut LabeledLabel {
+    let (label, privilege) = unsafe { (&(*label).0, &(*privilege).0) };
+    new_label(label.clone().downgrade(privilege))
+}
+
+/// Releases a label handle. Passing NULL is a no-op.
+///
+/// # Safety
+///
+/// `label` must be NULL or a live handle returned by this module, and must
+/// not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn labeled_free(label: *mut LabeledLabel) {
+    if !label.is_null() {
+        drop(unsafe { Box::from_raw(label) });
+    }
+}
+
+/// Releases a privilege handle. Passing NULL is a no-op.
+///
+/// # Safety
+///
+/// `privilege` must be NULL or a live handle returned by this module, and
+/// must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn labeled_privilege_free(privilege: *mut LabeledPrivilege) {
+    if !privilege.is_null() {
+        drop(unsafe { Box::from_raw(privilege) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &[u8]) -> *mut LabeledLabel {
+        unsafe { labeled_parse(CStr::from_bytes_with_nul(input).unwrap().as_ptr()) }
+    }
+
+    #[test]
+    fn test_flow() {
+        let secret = parse(b"amit,T\0");
+        let public = parse(b"T,T\0");
+        unsafe {
+            assert!(labeled_can_flow_to(public, secret));
+            assert!(!labeled_can_flow_to(secret, public));
+
+            let lub = labeled_lub(public, secret);
+            assert_eq!(Buckle2::parse("amit,T").unwrap(), (*lub).0);
+            let glb = labeled_glb(public, secret);
+            assert_eq!(Buckle2::public(), (*glb).0);
+
+            labeled_free(lub);
+            labeled_free(glb);
+            labeled_free(secret);
+            labeled_free(public);
+        }
+    }
+
+    #[test]
+    fn test_downgrade() {
+        let secret = parse(b"amit,T\0");
+        let public = parse(b"T,T\0");
+        unsafe {
+            let privilege = labeled_privilege_parse(CStr::from_bytes_with_nul(b"amit\0").unwrap().as_ptr());
+            assert!(labeled_can_flow_to_with_privilege(secret, public, privilege));
+
+            let downgraded = labeled_downgrade(secret, privilege);
+            assert_eq!(Buckle2::parse("T,amit").unwrap(), (*downgraded).0);
+
+            labeled_free(downgraded);
+            labeled_privilege_free(privilege);
+            labeled_free(secret);
+            labeled_free(public);
+        }
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert!(parse(b"amit\0").is_null());
+        unsafe { labeled_free(core::ptr::null_mut()) };
+    }
+}
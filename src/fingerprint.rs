@@ -0,0 +1,65 @@
+//! Bloom-style fingerprints of clauses.
+//!
+//! A clause's fingerprint sets one of 64 bits for each principal it
+//! mentions, chosen by hashing the principal. A clause can only imply
+//! another whose principals include its own (for paths, their leading
+//! principals), so a fingerprint with a bit the other's lacks rules out the
+//! implication with a single AND, before any set or prefix comparison.
+
+/// The fingerprint bit of `principal`, from its 64-bit FNV-1a hash.
+pub(crate) fn bit(principal: &[u8]) -> u64 {
+    let hash = principal
+        .iter()
+        .fold(0xcbf29ce484222325u64, |h, b| (h ^ *b as u64).wrapping_mul(0x100000001b3));
+    1 << (hash >> 58)
+}
+
+/// Returns whether a clause with fingerprint `lhs` may imply one with
+/// fingerprint `rhs`.
+pub(crate) fn may_imply(lhs: u64, rhs: u64) -> bool {
+    lhs & !rhs == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bit() {
+        assert_eq!(1, bit(b"Amit").count_ones());
+        assert_eq!(bit(b"Amit"), bit(b"Amit"));
+    }
+
+    // A clause implying another must have a subset fingerprint, or `implies`
+    // would wrongly reject it.
+    #[cfg(feature = "dclabel")]
+    quickcheck! {
+        fn dclabel_implication_fingerprints(c1: crate::dclabel::Clause, c2: crate::dclabel::Clause) -> bool {
+            let c2 = crate::dclabel::Clause::new_from_vec(c1.0.union(&c2.0).cloned().collect());
+            may_imply(c1.fingerprint(), c2.fingerprint())
+        }
+    }
+
+    #[cfg(feature = "buckle")]
+    quickcheck! {
+        fn buckle_implication_fingerprints(c1: crate::buckle::Clause, c2: crate::buckle::Clause) -> bool {
+            // Extending every path keeps the implication.
+            let c2 = crate::buckle::Clause::from(
+                c1.0.iter()
+                    .map(|p| p.iter().chain(c2.0.iter().flatten()).cloned().collect())
+                    .chain(c2.0.iter().cloned())
+                    .collect::<alloc::collections::BTreeSet<_>>(),
+            );
+            c1.implies(&c2) && may_imply(c1.fingerprint(), c2.fingerprint())
+        }
+    }
+
+    #[cfg(feature = "buckle2")]
+    quickcheck! {
+        fn buckle2_implication_fingerprints(c1: crate::buckle2::Clause, c2: crate::buckle2::Clause) -> bool {
+            let mut c2 = c2;
+            c2.0.extend(c1.0.iter().cloned());
+            c1.implies(&c2) && may_imply(c1.fingerprint(), c2.fingerprint())
+        }
+    }
+}
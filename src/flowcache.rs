@@ -0,0 +1,253 @@
+//! A bounded cache of flow decisions.
+//!
+//! Enforcement points often evaluate the same label pairs over and over. A
+//! `FlowCache` memoizes `can_flow_to` on labels and `implies` on components,
+//! keyed by the `LabelDigest` of each side, so equal labels share entries no
+//! matter how they're written, while labels that differ, even just as `{∅}`
+//! and `DCFalse` do, never share one. Once the cache holds `capacity` decisions, the
+//! oldest is evicted for each new one.
+//!
+//! Digesting a label costs about as much as a flow check on a small label, so
+//! callers that check the same label repeatedly should compute its digest
+//! once and use the `_with_digests` methods.
+
+use crate::envelope::{Enveloped, LabelDigest};
+use crate::Label;
+use alloc::collections::{BTreeMap, VecDeque};
+
+/// Counts of cache lookups and evictions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+/// A component whose implications can be cached.
+pub trait Implies {
+    /// The digest of the component, equal for equivalent components.
+    fn component_digest(&self) -> LabelDigest;
+
+    fn implies(&self, other: &Self) -> bool;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Query {
+    CanFlowTo,
+    Implies,
+}
+
+type Key = (Query, LabelDigest, LabelDigest);
+
+#[derive(Debug, Clone)]
+pub struct FlowCache {
+    capacity: usize,
+    decisions: BTreeMap<Key, bool>,
+    order: VecDeque<Key>,
+    stats: Stats,
+}
+
+impl FlowCache {
+    pub fn new(capacity: usize) -> Self {
+        FlowCache { capacity, decisions: BTreeMap::new(), order: VecDeque::new(), stats: Stats::default() }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.decisions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.decisions.is_empty()
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    /// Drops all decisions, keeping the statistics.
+    pub fn clear(&mut self) {
+        self.decisions.clear();
+        self.order.clear();
+    }
+
+    pub fn can_flow_to<L: Label + Enveloped>(&mut self, lhs: &L, rhs: &L) -> bool {
+        self.can_flow_to_with_digests(lhs.label_digest(), lhs, rhs.label_digest(), rhs)
+    }
+
+    /// Like `can_flow_to`, with the labels' digests computed by the caller.
+    pub fn can_flow_to_with_digests<L: Label>(
+        &mut self,
+        lhs_digest: LabelDigest,
+        lhs: &L,
+        rhs_digest: LabelDigest,
+        rhs: &L,
+    ) -> bool {
+        self.decide((Query::CanFlowTo, lhs_digest, rhs_digest), || lhs.can_flow_to(rhs))
+    }
+
+    pub fn implies<C: Implies>(&mut self, lhs: &C, rhs: &C) -> bool {
+        self.implies_with_digests(lhs.component_digest(), lhs, rhs.component_digest(), rhs)
+    }
+
+    /// Like `implies`, with the components' digests computed by the caller.
+    pub fn implies_with_digests<C: Implies>(
+        &mut self,
+        lhs_digest: LabelDigest,
+        lhs: &C,
+        rhs_digest: LabelDigest,
+        rhs: &C,
+    ) -> bool {
+        self.decide((Query::Implies, lhs_digest, rhs_digest), || lhs.implies(rhs))
+    }
+
+    fn decide<F: FnOnce() -> bool>(&mut self, key: Key, f: F) -> bool {
+        if let Some(decision) = self.decisions.get(&key) {
+            self.stats.hits += 1;
+            return *decision;
+        }
+        self.stats.misses += 1;
+        let decision = f();
+        if self.capacity > 0 {
+            if self.decisions.len() == self.capacity {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.decisions.remove(&oldest);
+                    self.stats.evictions += 1;
+                }
+            }
+            self.decisions.insert(key, decision);
+            self.order.push_back(key);
+        }
+        decision
+    }
+}
+
+// A component is digested as the secrecy of an otherwise public label, so
+// equivalent components have equal canonical forms.
+
+#[cfg(feature = "dclabel")]
+mod dclabel {
+    use super::*;
+    use crate::dclabel::{Component, DCLabel};
+
+    impl Implies for Component {
+        fn component_digest(&self) -> LabelDigest {
+            DCLabel::new(self.clone(), Component::dc_true()).label_digest()
+        }
+
+        fn implies(&self, other: &Self) -> bool {
+            Component::implies(self, other)
+        }
+    }
+}
+
+#[cfg(feature = "buckle")]
+mod buckle {
+    use super::*;
+    use crate::buckle::{Buckle, Component};
+
+    impl Implies for Component {
+        fn component_digest(&self) -> LabelDigest {
+            Buckle::new(self.clone(), Component::dc_true()).label_digest()
+        }
+
+        fn implies(&self, other: &Self) -> bool {
+            Component::implies(self, other)
+        }
+    }
+}
+
+#[cfg(feature = "buckle2")]
+mod buckle2 {
+    use super::*;
+    use crate::buckle2::{Buckle2, Component};
+
+    impl Implies for Component {
+        fn component_digest(&self) -> LabelDigest {
+            Buckle2::new(self.clone(), Component::dc_true()).label_digest()
+        }
+
+        fn implies(&self, other: &Self) -> bool {
+            Component::implies(self, other)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "buckle2"))]
+mod tests {
+    use super::*;
+    use crate::buckle2::Buckle2;
+
+    #[test]
+    fn test_can_flow_to() {
+        let mut cache = FlowCache::new(8);
+        let a = Buckle2::parse("a,T").unwrap();
+        let ab = Buckle2::parse("a&b,T").unwrap();
+        assert!(cache.can_flow_to(&a, &ab));
+        assert!(!cache.can_flow_to(&ab, &a));
+        assert_eq!(Stats { hits: 0, misses: 2, evictions: 0 }, cache.stats());
+
+        // Equivalent labels hit the same entries.
+        assert!(cache.can_flow_to(&Buckle2::parse("a|a/b&a,T").unwrap(), &ab));
+        assert!(!cache.can_flow_to_with_digests(ab.label_digest(), &ab, a.label_digest(), &a));
+        assert_eq!(Stats { hits: 2, misses: 2, evictions: 0 }, cache.stats());
+        assert_eq!(2, cache.len());
+    }
+
+    #[test]
+    fn test_implies() {
+        let mut cache = FlowCache::new(8);
+        let a = Buckle2::parse("a,T").unwrap().secrecy;
+        let ab = Buckle2::parse("a/b,T").unwrap().secrecy;
+        assert!(cache.implies(&a, &ab));
+        assert!(!cache.implies(&ab, &a));
+        assert!(cache.implies(&a, &ab));
+        assert_eq!(Stats { hits: 1, misses: 2, evictions: 0 }, cache.stats());
+
+        // Implications and flows are cached separately.
+        let (la, lab) = (Buckle2::new(a, Buckle2::public().integrity), Buckle2::new(ab, Buckle2::public().integrity));
+        assert!(!cache.can_flow_to(&la, &lab));
+        assert_eq!(3, cache.len());
+    }
+
+    #[test]
+    fn test_empty_clause_is_not_false() {
+        use crate::buckle2::{Clause, Component};
+
+        // `{∅}` and `DCFalse` must not share entries: only `DCFalse` implies
+        // `DCFalse`.
+        let mut cache = FlowCache::new(8);
+        let x = Buckle2::new(Component::dc_false(), true);
+        let y = Buckle2::new(Component::from([Clause::empty()]), true);
+        assert_ne!(x.label_digest(), y.label_digest());
+        assert!(cache.can_flow_to(&y, &x));
+        assert!(!cache.can_flow_to(&x, &y));
+        assert!(cache.implies(&x.secrecy, &y.secrecy));
+        assert!(!cache.implies(&y.secrecy, &x.secrecy));
+        assert_eq!(Stats { hits: 0, misses: 4, evictions: 0 }, cache.stats());
+    }
+
+    #[test]
+    fn test_eviction() {
+        let mut cache = FlowCache::new(2);
+        let labels: alloc::vec::Vec<_> =
+            ["a,T", "b,T", "c,T"].iter().map(|l| Buckle2::parse(l).unwrap()).collect();
+        for l in labels.iter() {
+            cache.can_flow_to(l, l);
+        }
+        assert_eq!(Stats { hits: 0, misses: 3, evictions: 1 }, cache.stats());
+        assert_eq!(2, cache.len());
+
+        // The oldest decision was evicted.
+        cache.can_flow_to(&labels[2], &labels[2]);
+        cache.can_flow_to(&labels[0], &labels[0]);
+        assert_eq!(Stats { hits: 1, misses: 4, evictions: 2 }, cache.stats());
+
+        let mut cache = FlowCache::new(0);
+        assert!(cache.can_flow_to(&labels[0], &labels[0]));
+        assert!(cache.is_empty());
+    }
+}
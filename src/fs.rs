@@ -0,0 +1,86 @@
+//! Persistent file labels in extended attributes.
+//!
+//! A file's label is stored in the `user.labeled.label` extended attribute
+//! as its envelope (see `envelope`), so the stored form is compact and
+//! versioned. Files without the attribute are unlabeled.
+
+extern crate std;
+
+use crate::buckle::Buckle;
+use crate::envelope;
+use std::io;
+use std::path::Path;
+
+/// The extended attribute holding the label.
+pub const ATTRIBUTE: &str = "user.labeled.label";
+
+#[derive(Debug)]
+pub enum Error {
+    /// Reading or writing the attribute failed, e.g. because the filesystem
+    /// doesn't support extended attributes.
+    Io(io::Error),
+    /// The attribute doesn't hold a `Buckle` envelope.
+    Envelope(envelope::Error),
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<envelope::Error> for Error {
+    fn from(e: envelope::Error) -> Self {
+        Error::Envelope(e)
+    }
+}
+
+/// Labels the file at `path`, replacing any existing label.
+pub fn set_label<P: AsRef<Path>>(path: P, label: &Buckle) -> Result<(), Error> {
+    Ok(xattr::set(path, ATTRIBUTE, &envelope::encode(label))?)
+}
+
+/// Reads the label of the file at `path`, if it has one.
+pub fn get_label<P: AsRef<Path>>(path: P) -> Result<Option<Buckle>, Error> {
+    match xattr::get(path, ATTRIBUTE)? {
+        Some(bytes) => Ok(Some(envelope::decode(&bytes)?)),
+        None => Ok(None),
+    }
+}
+
+/// Removes the label of the file at `path`, if it has one.
+pub fn remove_label<P: AsRef<Path>>(path: P) -> Result<(), Error> {
+    let path = path.as_ref();
+    if xattr::get(path, ATTRIBUTE)?.is_some() {
+        xattr::remove(path, ATTRIBUTE)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    #[test]
+    fn test_round_trip() {
+        if !xattr::SUPPORTED_PLATFORM {
+            return;
+        }
+        let path = std::env::temp_dir().join(alloc::format!("labeled-xattr-{}", std::process::id()));
+        File::create(&path).unwrap();
+        match set_label(&path, &Buckle::parse("amit/grades|yue,T").unwrap()) {
+            // The temporary directory may be on a filesystem without user
+            // extended attributes.
+            Err(Error::Io(e)) if e.kind() == io::ErrorKind::Unsupported => {}
+            result => {
+                result.unwrap();
+                assert_eq!(Buckle::parse("amit/grades|yue,T").ok(), get_label(&path).unwrap());
+                remove_label(&path).unwrap();
+                remove_label(&path).unwrap();
+                assert_eq!(None, get_label(&path).unwrap());
+            }
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
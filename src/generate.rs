@@ -0,0 +1,166 @@
+//! Configurable generation of random labels for property tests.
+//!
+//! The `Arbitrary` impls of the label types, clauses and components build
+//! their values from `GenConfig::default()`, which draws principals from
+//! quickcheck's arbitrary strings: mostly long runs of unrelated Unicode, so
+//! two random labels rarely share a principal. Downstream tests whose
+//! production labels are, say, a few clauses over a small set of short
+//! names can generate labels of that shape instead with `Generate`, e.g.
+//! from a newtype's `Arbitrary` impl:
+//!
+//! ```ignore
+//! struct Production(DCLabel);
+//!
+//! impl Arbitrary for Production {
+//!     fn arbitrary(g: &mut Gen) -> Self {
+//!         let config = GenConfig { principal_alphabet: Some("abc".into()), max_clauses: 3, ..GenConfig::default() };
+//!         Production(DCLabel::generate(g, &config))
+//!     }
+//! }
+//! ```
+//!
+//! The `quickcheck` feature exposes the impls outside the crate's tests.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use quickcheck::{Arbitrary, Gen};
+
+/// The shape of generated labels.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GenConfig {
+    /// The characters principals are made of, or `None` for quickcheck's
+    /// arbitrary strings, or bytes for `Buckle2`.
+    pub principal_alphabet: Option<String>,
+    /// The most clauses in a formula.
+    pub max_clauses: usize,
+    /// The most principals, or for `Buckle` paths, in a clause.
+    pub max_principals: usize,
+    /// The most principals in a `Buckle` path.
+    pub max_depth: usize,
+    /// The probability that a component is `DCFalse`.
+    pub false_probability: f64,
+    /// Whether principals, paths and clauses may be empty, which the label
+    /// syntax can't write.
+    pub allow_empty: bool,
+}
+
+/// Generates labels as quickcheck's defaults would: collections of up to 9
+/// elements, and `DCFalse` half of the time.
+impl Default for GenConfig {
+    fn default() -> Self {
+        GenConfig {
+            principal_alphabet: None,
+            max_clauses: 9,
+            max_principals: 9,
+            max_depth: 9,
+            false_probability: 0.5,
+            allow_empty: true,
+        }
+    }
+}
+
+impl GenConfig {
+    /// Returns a value shaped by this configuration.
+    pub fn generate<T: Generate>(&self, g: &mut Gen) -> T {
+        T::generate(g, self)
+    }
+
+    /// Returns the length of a collection of at most `max` elements.
+    pub(crate) fn len(&self, g: &mut Gen, max: usize) -> usize {
+        let min = usize::from(!self.allow_empty).min(max);
+        min + usize::arbitrary(g) % (max - min + 1)
+    }
+
+    /// Returns whether to generate `DCFalse`.
+    pub(crate) fn is_false(&self, g: &mut Gen) -> bool {
+        (u32::arbitrary(g) as f64) < self.false_probability * (u32::MAX as f64 + 1.0)
+    }
+
+    /// Returns a principal of up to `g.size()` characters.
+    pub(crate) fn principal(&self, g: &mut Gen) -> String {
+        let mut principal = match &self.principal_alphabet {
+            None => String::arbitrary(g),
+            Some(alphabet) => {
+                let alphabet: Vec<char> = alphabet.chars().collect();
+                let size = g.size();
+                let len = self.len(g, size);
+                (0..len).filter_map(|_| g.choose(&alphabet).copied()).collect()
+            }
+        };
+        if principal.is_empty() && !self.allow_empty {
+            principal.push(char::arbitrary(g));
+        }
+        principal
+    }
+
+    /// Returns a principal as bytes, arbitrary if there is no alphabet.
+    #[cfg(feature = "buckle2")]
+    pub(crate) fn principal_bytes(&self, g: &mut Gen) -> Vec<u8> {
+        match self.principal_alphabet {
+            None => {
+                let mut principal = Vec::<u8>::arbitrary(g);
+                if principal.is_empty() && !self.allow_empty {
+                    principal.push(u8::arbitrary(g));
+                }
+                principal
+            }
+            Some(_) => self.principal(g).into_bytes(),
+        }
+    }
+}
+
+/// Values generated in a shape given by a `GenConfig`.
+pub trait Generate: Sized {
+    fn generate(g: &mut Gen, config: &GenConfig) -> Self;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    quickcheck! {
+        fn respects_config(max: u8, allow_empty: bool) -> bool {
+            let config = GenConfig {
+                principal_alphabet: Some("ab".into()),
+                max_clauses: max as usize % 4,
+                max_principals: max as usize % 3,
+                allow_empty,
+                ..GenConfig::default()
+            };
+            let g = &mut Gen::new(10);
+            let principal = config.principal(g);
+            let len = config.len(g, config.max_clauses);
+            principal.chars().all(|c| c == 'a' || c == 'b')
+                && (allow_empty || !principal.is_empty())
+                && len <= config.max_clauses
+                && (allow_empty || config.max_clauses == 0 || len > 0)
+        }
+    }
+
+    #[cfg(feature = "dclabel")]
+    quickcheck! {
+        fn dclabel_respects_config(seed: u8) -> bool {
+            use crate::dclabel::{Component, DCLabel};
+
+            let config = GenConfig {
+                principal_alphabet: Some("ab".into()),
+                max_clauses: 2,
+                max_principals: 2,
+                false_probability: 0.0,
+                allow_empty: false,
+                ..GenConfig::default()
+            };
+            let g = &mut Gen::new(1 + seed as usize % 10);
+            let label: DCLabel = config.generate(g);
+            [label.secrecy, label.integrity].iter().all(|component| match component {
+                Component::DCFalse => false,
+                Component::DCFormula(clauses) => {
+                    clauses.len() <= 2
+                        && clauses.iter().all(|c| {
+                            (1..=2).contains(&c.0.len()) && c.check().is_ok() && c.0.iter().all(|p| p.chars().all(|x| "ab".contains(x)))
+                        })
+                }
+            })
+        }
+    }
+}
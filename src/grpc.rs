@@ -0,0 +1,99 @@
+//! Label propagation through gRPC metadata.
+//!
+//! A label travels either under the ASCII key `sec-label`, in the same
+//! encoding as the `Sec-Label` HTTP header (see `header`), or under the
+//! binary key `sec-label-bin` as its raw envelope, which tonic base64-encodes
+//! on the wire. Either way the encoded value is limited to `header::MAX_LEN`
+//! bytes, and failures convert into `Status::invalid_argument`, so handlers
+//! can use `?`.
+
+use crate::envelope::{self, Enveloped};
+use crate::header::{self, Error};
+use tonic::metadata::{AsciiMetadataValue, BinaryMetadataValue, MetadataMap};
+use tonic::Status;
+
+/// The ASCII metadata key.
+pub const KEY: &str = "sec-label";
+
+/// The binary metadata key.
+pub const BIN_KEY: &str = "sec-label-bin";
+
+impl From<Error> for Status {
+    fn from(e: Error) -> Status {
+        let message = match e {
+            Error::Empty => "empty label",
+            Error::TooLong => "label too long",
+            Error::InvalidCharacter(_) => "invalid character in label",
+            Error::Envelope(_) => "malformed label",
+        };
+        Status::invalid_argument(message)
+    }
+}
+
+/// Stores `label` under `KEY`, replacing any existing value.
+pub fn insert<L: Enveloped>(metadata: &mut MetadataMap, label: &L) -> Result<(), Error> {
+    let value = header::encode(label)?;
+    // base64url characters are all valid in ASCII metadata.
+    let value: AsciiMetadataValue = value.parse().map_err(|_| Error::InvalidCharacter(0))?;
+    metadata.insert(KEY, value);
+    Ok(())
+}
+
+/// Stores `label` under `BIN_KEY`, replacing any existing value.
+pub fn insert_bin<L: Enveloped>(metadata: &mut MetadataMap, label: &L) -> Result<(), Error> {
+    let value = BinaryMetadataValue::from_bytes(&envelope::encode(label));
+    if value.as_encoded_bytes().len() > header::MAX_LEN {
+        return Err(Error::TooLong);
+    }
+    metadata.insert_bin(BIN_KEY, value);
+    Ok(())
+}
+
+/// Reads the label from `metadata`, preferring `BIN_KEY` over `KEY`. Returns
+/// `None` if neither is present.
+pub fn extract<L: Enveloped>(metadata: &MetadataMap) -> Result<Option<L>, Error> {
+    if let Some(value) = metadata.get_bin(BIN_KEY) {
+        if value.as_encoded_bytes().len() > header::MAX_LEN {
+            return Err(Error::TooLong);
+        }
+        let bytes = value.to_bytes().map_err(|_| Error::InvalidCharacter(0))?;
+        return Ok(Some(envelope::decode(&bytes)?));
+    }
+    match metadata.get(KEY) {
+        Some(value) => header::decode(value.as_bytes()).map(Some),
+        None => Ok(None),
+    }
+}
+
+#[cfg(all(test, feature = "buckle2"))]
+mod tests {
+    use super::*;
+    use crate::buckle2::Buckle2;
+
+    #[test]
+    fn test_round_trip() {
+        let lbl = Buckle2::parse("amit/grades|yue,T").unwrap();
+
+        let mut metadata = MetadataMap::new();
+        assert_eq!(Ok(None), extract::<Buckle2>(&metadata));
+        insert(&mut metadata, &lbl).unwrap();
+        assert_eq!(Ok(Some(lbl.clone())), extract(&metadata));
+
+        let mut metadata = MetadataMap::new();
+        insert_bin(&mut metadata, &lbl).unwrap();
+        assert_eq!(Ok(Some(lbl)), extract(&metadata));
+    }
+
+    #[test]
+    fn test_rejects_malformed() {
+        let mut metadata = MetadataMap::new();
+        metadata.insert(KEY, AsciiMetadataValue::from_static("not+base64url"));
+        let err = extract::<Buckle2>(&metadata).unwrap_err();
+        assert_eq!(Error::InvalidCharacter(b'+'), err);
+        assert_eq!(tonic::Code::InvalidArgument, Status::from(err).code());
+
+        let mut metadata = MetadataMap::new();
+        metadata.insert_bin(BIN_KEY, BinaryMetadataValue::from_bytes(&[1, 0xff]));
+        assert!(matches!(extract::<Buckle2>(&metadata), Err(Error::Envelope(_))));
+    }
+}
@@ -0,0 +1,149 @@
+//! Hash-consed clauses.
+//!
+//! Under the `hashcons` feature a clause shares its principals between its
+//! clones. A `ClauseCache` hands out one shared instance per distinct clause,
+//! so components built through it from repeated clauses, like an
+//! organization clause that appears in every label, hold a single copy of
+//! each clause, and comparing two instances of a clause compares pointers
+//! instead of principals.
+//!
+//! A cache can be shared between threads. It keeps every clause it has
+//! handed out; `ClauseCache::purge` drops the clauses it alone still holds.
+
+extern crate std;
+
+use alloc::collections::BTreeSet;
+use std::sync::{Mutex, MutexGuard};
+
+/// A cache of shared clause instances.
+pub struct ClauseCache<C> {
+    clauses: Mutex<BTreeSet<C>>,
+}
+
+impl<C> Default for ClauseCache<C> {
+    fn default() -> Self {
+        ClauseCache { clauses: Mutex::new(BTreeSet::new()) }
+    }
+}
+
+impl<C> core::fmt::Debug for ClauseCache<C> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ClauseCache").field("len", &self.len()).finish()
+    }
+}
+
+impl<C> ClauseCache<C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeSet<C>> {
+        // The set is consistent between statements, so a panic while
+        // holding the lock can't leave it broken.
+        self.clauses.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns the number of cached clauses.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<C: Ord + Clone> ClauseCache<C> {
+    /// Returns the shared instance of `clause`, caching it if needed.
+    pub fn clause(&self, clause: C) -> C {
+        share(&mut self.lock(), clause)
+    }
+}
+
+fn share<C: Ord + Clone>(clauses: &mut BTreeSet<C>, clause: C) -> C {
+    if let Some(shared) = clauses.get(&clause) {
+        return shared.clone();
+    }
+    clauses.insert(clause.clone());
+    clause
+}
+
+macro_rules! impl_cache {
+    ($module:ident, $label:ident) => {
+        impl ClauseCache<crate::$module::Clause> {
+            /// Returns `component` with each clause replaced by its shared
+            /// instance.
+            pub fn component(&self, component: crate::$module::Component) -> crate::$module::Component {
+                use crate::$module::Component;
+
+                match component {
+                    Component::DCFalse => Component::DCFalse,
+                    Component::DCFormula(clauses) => {
+                        let mut cached = self.lock();
+                        Component::from(
+                            clauses.iter().map(|c| share(&mut cached, c.clone())).collect::<BTreeSet<_>>(),
+                        )
+                    }
+                }
+            }
+
+            /// Returns `label` with each clause replaced by its shared
+            /// instance.
+            pub fn label(&self, label: crate::$module::$label) -> crate::$module::$label {
+                // Each clause is replaced by an equal one, so the label stays
+                // reduced.
+                crate::$module::$label {
+                    secrecy: self.component(label.secrecy),
+                    integrity: self.component(label.integrity),
+                }
+            }
+
+            /// Forgets the clauses no longer used outside the cache.
+            pub fn purge(&self) {
+                self.lock().retain(|c| alloc::sync::Arc::strong_count(&c.0) > 1)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "dclabel")]
+impl_cache!(dclabel, DCLabel);
+#[cfg(feature = "buckle")]
+impl_cache!(buckle, Buckle);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "dclabel")]
+    #[test]
+    fn test_clause_cache() {
+        use crate::dclabel::{Clause, DCLabel};
+
+        let cache = ClauseCache::new();
+        let a = cache.clause(Clause::new(["org", "amit"]));
+        let b = cache.clause(Clause::new(["amit", "org"]));
+        assert!(alloc::sync::Arc::ptr_eq(&a.0, &b.0));
+        assert_eq!(1, cache.len());
+
+        let l1 = cache.label(DCLabel::new([["org", "amit"]], true));
+        let l2 = cache.label(DCLabel::new([Clause::new(["org", "amit"]), Clause::new(["yue"])], true));
+        assert_eq!(2, cache.len());
+        for label in [&l1, &l2] {
+            let crate::dclabel::Component::DCFormula(clauses) = &label.secrecy else { panic!() };
+            assert!(clauses.iter().any(|c| alloc::sync::Arc::ptr_eq(&a.0, &c.0)));
+        }
+
+        drop((a, b, l2));
+        cache.purge();
+        assert_eq!(1, cache.len());
+    }
+
+    #[cfg(feature = "buckle")]
+    quickcheck! {
+        fn buckle_preserves_labels(lbl: crate::buckle::Buckle) -> bool {
+            let cache = ClauseCache::<crate::buckle::Clause>::new();
+            cache.label(lbl.clone()) == lbl
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! Canonical HTTP header representation of labels.
+//!
+//! A label is carried in a single `Sec-Label` header whose value is the
+//! label's envelope (see `envelope`) in unpadded URL-safe base64. Values are
+//! parsed strictly: apart from optional surrounding whitespace, the value
+//! must be non-empty, at most `MAX_LEN` bytes long and contain only base64url
+//! characters.
+
+use crate::envelope::{self, Enveloped};
+use alloc::string::String;
+
+/// The header name, in canonical case.
+pub const NAME: &str = "Sec-Label";
+
+/// The maximum length of an encoded header value, in bytes.
+pub const MAX_LEN: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The value is empty.
+    Empty,
+    /// The value is longer than `MAX_LEN`.
+    TooLong,
+    /// The value contains a byte that isn't base64url.
+    InvalidCharacter(u8),
+    /// The value doesn't decode to a label of the requested kind.
+    Envelope(envelope::Error),
+}
+
+impl From<envelope::Error> for Error {
+    fn from(e: envelope::Error) -> Self {
+        Error::Envelope(e)
+    }
+}
+
+/// Encodes `label` as a header value, failing if it exceeds `MAX_LEN`.
+pub fn encode<L: Enveloped>(label: &L) -> Result<String, Error> {
+    let value = label.to_b64url();
+    if value.len() > MAX_LEN {
+        return Err(Error::TooLong);
+    }
+    Ok(value)
+}
+
+/// Decodes a header value, as raw bytes from the request.
+pub fn decode<L: Enveloped>(value: &[u8]) -> Result<L, Error> {
+    let value = value.trim_ascii();
+    if value.is_empty() {
+        return Err(Error::Empty);
+    }
+    if value.len() > MAX_LEN {
+        return Err(Error::TooLong);
+    }
+    if let Some(b) = value.iter().find(|b| !(b.is_ascii_alphanumeric() || **b == b'-' || **b == b'_')) {
+        return Err(Error::InvalidCharacter(*b));
+    }
+    // Every byte is ASCII, so this can't fail.
+    let value = core::str::from_utf8(value).map_err(|_| Error::InvalidCharacter(0))?;
+    Ok(L::from_b64url(value)?)
+}
+
+/// Compares two encoded values in time that depends only on their lengths,
+/// e.g. to check a presented label against an expected one without leaking
+/// the position of the first difference.
+pub fn eq_encoded(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+    core::hint::black_box(diff) == 0
+}
+
+#[cfg(all(test, feature = "buckle2"))]
+mod tests {
+    use super::*;
+    use crate::buckle2::Buckle2;
+
+    #[test]
+    fn test_round_trip() {
+        let lbl = Buckle2::parse("amit/grades|yue,T").unwrap();
+        let value = encode(&lbl).unwrap();
+        assert_eq!(Ok(lbl.clone()), decode(value.as_bytes()));
+        let padded = alloc::format!(" \t{} ", value);
+        assert_eq!(Ok(lbl), decode(padded.as_bytes()));
+    }
+
+    #[test]
+    fn test_strict_limits() {
+        assert_eq!(Err(Error::Empty), decode::<Buckle2>(b"  "));
+        assert_eq!(Err(Error::InvalidCharacter(b'=')), decode::<Buckle2>(b"AQMAAQA="));
+        assert_eq!(Err(Error::InvalidCharacter(b' ')), decode::<Buckle2>(b"AQMA AQA"));
+        assert_eq!(Err(Error::TooLong), decode::<Buckle2>(&[b'A'; MAX_LEN + 1]));
+        assert_eq!(
+            Err(Error::Envelope(envelope::Error::UnsupportedVersion(9))),
+            decode::<Buckle2>(b"CQMAAQA")
+        );
+    }
+
+    #[test]
+    fn test_eq_encoded() {
+        assert!(eq_encoded(b"AQMAAQA", b"AQMAAQA"));
+        assert!(!eq_encoded(b"AQMAAQA", b"AQMAAQB"));
+        assert!(!eq_encoded(b"AQMAAQA", b"AQMAAQ"));
+    }
+}
@@ -0,0 +1,140 @@
+//! Heap footprints of labels.
+//!
+//! `approx_heap_size` counts the bytes a label owns on the heap: the
+//! capacity of its vectors and strings and the entries of its sets, but not
+//! the bookkeeping of set nodes, reference counts or allocators. Storage
+//! shared between labels, e.g. under the `arc` or `smolstr` features, is
+//! counted in full by every label sharing it.
+//!
+//! `shrink_to_fit` releases the excess capacity left by operations like
+//! `lub` that grow a label and then reduce it. Shared storage is left alone,
+//! as compacting it would copy it.
+
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::sync::Arc;
+use core::mem::size_of;
+
+pub(crate) trait Heap {
+    /// The number of bytes owned on the heap.
+    fn heap_size(&self) -> usize;
+
+    /// Releases excess capacity.
+    fn compact(&mut self);
+}
+
+impl Heap for u8 {
+    fn heap_size(&self) -> usize {
+        0
+    }
+
+    fn compact(&mut self) {}
+}
+
+impl Heap for String {
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+
+    fn compact(&mut self) {
+        self.shrink_to_fit()
+    }
+}
+
+#[cfg(feature = "smolstr")]
+impl Heap for smol_str::SmolStr {
+    fn heap_size(&self) -> usize {
+        if self.is_heap_allocated() {
+            self.len()
+        } else {
+            0
+        }
+    }
+
+    fn compact(&mut self) {}
+}
+
+// With `nightly`, `allocator_api2` re-exports the standard `Vec`, so the
+// impl below covers it.
+#[cfg(not(all(feature = "buckle2", feature = "nightly")))]
+impl<T: Heap> Heap for alloc::vec::Vec<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<T>() + self.iter().map(Heap::heap_size).sum::<usize>()
+    }
+
+    fn compact(&mut self) {
+        self.shrink_to_fit();
+        self.iter_mut().for_each(Heap::compact)
+    }
+}
+
+#[cfg(feature = "buckle2")]
+impl<T: Heap, A: allocator_api2::alloc::Allocator> Heap for allocator_api2::vec::Vec<T, A> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<T>() + self.iter().map(Heap::heap_size).sum::<usize>()
+    }
+
+    fn compact(&mut self) {
+        self.shrink_to_fit();
+        self.iter_mut().for_each(Heap::compact)
+    }
+}
+
+impl<T: Heap + Ord> Heap for BTreeSet<T> {
+    fn heap_size(&self) -> usize {
+        self.len() * size_of::<T>() + self.iter().map(Heap::heap_size).sum::<usize>()
+    }
+
+    /// Entries can't be changed in place, so the set is rebuilt.
+    fn compact(&mut self) {
+        *self = core::mem::take(self)
+            .into_iter()
+            .map(|mut entry| {
+                entry.compact();
+                entry
+            })
+            .collect();
+    }
+}
+
+impl<T: Heap> Heap for Arc<T> {
+    fn heap_size(&self) -> usize {
+        size_of::<T>() + T::heap_size(self)
+    }
+
+    fn compact(&mut self) {
+        if let Some(inner) = Arc::get_mut(self) {
+            inner.compact()
+        }
+    }
+}
+
+#[cfg(all(feature = "arc", any(feature = "dclabel", feature = "buckle")))]
+impl<T: Heap> Heap for crate::shared::Shared<T> {
+    fn heap_size(&self) -> usize {
+        let allocated = if crate::shared::Shared::is_allocated(self) { size_of::<T>() } else { 0 };
+        allocated + T::heap_size(self)
+    }
+
+    fn compact(&mut self) {
+        if let Some(inner) = crate::shared::Shared::get_mut(self) {
+            inner.compact()
+        }
+    }
+}
+
+#[cfg(feature = "small")]
+impl<A: smallvec::Array> Heap for smallvec::SmallVec<A>
+where
+    A::Item: Heap,
+{
+    fn heap_size(&self) -> usize {
+        let spilled = if self.spilled() { self.capacity() * size_of::<A::Item>() } else { 0 };
+        spilled + self.iter().map(Heap::heap_size).sum::<usize>()
+    }
+
+    fn compact(&mut self) {
+        self.shrink_to_fit();
+        self.iter_mut().for_each(Heap::compact)
+    }
+}
@@ -0,0 +1,237 @@
+//! Interned labels with constant-time clones and comparisons.
+//!
+//! Systems labeling many objects with few distinct labels can intern them
+//! in a `LabelPool` and hand out `LabelRc`s. Labels are interned in their
+//! canonical form (see the `envelope` module), so equal labels interned in
+//! the same pool share one allocation: cloning a handle is a reference count
+//! increment, and `can_flow_to` between two handles to the same label
+//! returns without comparing the labels. The results of `lub`, `glb` and
+//! `downgrade` are interned in the pool of their left-hand side.
+//!
+//! A pool can be shared between threads and is kept alive by its handles.
+//! It holds its labels weakly, so a label is freed with its last handle;
+//! `LabelPool::purge` drops the bookkeeping left for freed labels.
+
+extern crate std;
+
+use crate::envelope::{self, Enveloped};
+use crate::{HasPrivilege, Label};
+use alloc::collections::BTreeMap;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use std::sync::{Mutex, MutexGuard};
+
+struct Pool<L> {
+    labels: Mutex<BTreeMap<Vec<u8>, Weak<Interned<L>>>>,
+}
+
+impl<L> Pool<L> {
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<Vec<u8>, Weak<Interned<L>>>> {
+        // The map is consistent between statements, so a panic while
+        // holding the lock can't leave it broken.
+        self.labels.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+struct Interned<L> {
+    label: L,
+    pool: Arc<Pool<L>>,
+}
+
+/// A pool of interned labels.
+pub struct LabelPool<L>(Arc<Pool<L>>);
+
+impl<L> Clone for LabelPool<L> {
+    fn clone(&self) -> Self {
+        LabelPool(self.0.clone())
+    }
+}
+
+impl<L> Default for LabelPool<L> {
+    fn default() -> Self {
+        LabelPool(Arc::new(Pool { labels: Mutex::new(BTreeMap::new()) }))
+    }
+}
+
+impl<L> core::fmt::Debug for LabelPool<L> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("LabelPool").field("len", &self.len()).finish()
+    }
+}
+
+impl<L> LabelPool<L> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of labels with live handles.
+    pub fn len(&self) -> usize {
+        self.0.lock().values().filter(|l| l.strong_count() > 0).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forgets the labels whose handles were all dropped.
+    pub fn purge(&self) {
+        self.0.lock().retain(|_, l| l.strong_count() > 0)
+    }
+}
+
+impl<L: Enveloped> LabelPool<L> {
+    /// Returns a handle to the canonical form of `label`, interning it if
+    /// needed.
+    pub fn intern(&self, label: &L) -> LabelRc<L> {
+        let canonical = label.canonical();
+        let bytes = envelope::encode(&canonical);
+        let mut labels = self.0.lock();
+        if let Some(interned) = labels.get(&bytes).and_then(Weak::upgrade) {
+            return LabelRc(interned);
+        }
+        let interned = Arc::new(Interned { label: canonical, pool: self.0.clone() });
+        labels.insert(bytes, Arc::downgrade(&interned));
+        LabelRc(interned)
+    }
+}
+
+/// A handle to a label interned in a `LabelPool`.
+pub struct LabelRc<L>(Arc<Interned<L>>);
+
+impl<L> LabelRc<L> {
+    /// Returns whether both handles point to the same interned label.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// Returns the pool the label is interned in.
+    pub fn pool(&self) -> LabelPool<L> {
+        LabelPool(self.0.pool.clone())
+    }
+}
+
+impl<L> Clone for LabelRc<L> {
+    fn clone(&self) -> Self {
+        LabelRc(self.0.clone())
+    }
+}
+
+impl<L> core::ops::Deref for LabelRc<L> {
+    type Target = L;
+
+    fn deref(&self) -> &L {
+        &self.0.label
+    }
+}
+
+impl<L: core::fmt::Debug> core::fmt::Debug for LabelRc<L> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.0.label.fmt(f)
+    }
+}
+
+/// Handles are equal if they point to equal labels, which for handles from
+/// the same pool means the same label.
+impl<L: PartialEq> PartialEq for LabelRc<L> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr_eq(other) || (!Arc::ptr_eq(&self.0.pool, &other.0.pool) && self.0.label == other.0.label)
+    }
+}
+
+impl<L: Eq> Eq for LabelRc<L> {}
+
+impl<L: Label + Enveloped + Clone> Label for LabelRc<L> {
+    fn lub(self, rhs: Self) -> Self {
+        if self.ptr_eq(&rhs) {
+            return self;
+        }
+        self.pool().intern(&self.0.label.clone().lub(rhs.0.label.clone()))
+    }
+
+    fn glb(self, rhs: Self) -> Self {
+        if self.ptr_eq(&rhs) {
+            return self;
+        }
+        self.pool().intern(&self.0.label.clone().glb(rhs.0.label.clone()))
+    }
+
+    fn can_flow_to(&self, rhs: &Self) -> bool {
+        self.ptr_eq(rhs) || self.0.label.can_flow_to(&rhs.0.label)
+    }
+}
+
+impl<L: HasPrivilege + Enveloped + Clone> HasPrivilege for LabelRc<L> {
+    type Privilege = L::Privilege;
+
+    fn downgrade(self, privilege: &L::Privilege) -> Self {
+        self.pool().intern(&self.0.label.clone().downgrade(privilege))
+    }
+
+    fn downgrade_to(self, target: Self, privilege: &L::Privilege) -> Self {
+        if self.can_flow_to_with_privilege(&target, privilege) {
+            target
+        } else {
+            self
+        }
+    }
+
+    fn can_flow_to_with_privilege(&self, rhs: &Self, privilege: &L::Privilege) -> bool {
+        self.ptr_eq(rhs) || self.0.label.can_flow_to_with_privilege(&rhs.0.label, privilege)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "buckle2")]
+    #[test]
+    fn test_intern() {
+        use crate::buckle2::Buckle2;
+
+        let pool = LabelPool::new();
+        let a = pool.intern(&Buckle2::parse("amit/grades&amit,T").unwrap());
+        let b = pool.intern(&Buckle2::parse("amit,T").unwrap());
+        assert!(a.ptr_eq(&b));
+        assert_eq!(Buckle2::parse("amit,T").unwrap(), *a);
+        assert_eq!(1, pool.len());
+
+        let grades = pool.intern(&Buckle2::parse("amit/grades,T").unwrap());
+        assert!(grades.can_flow_to(&a));
+        assert!(!a.can_flow_to(&grades));
+        assert!(a.clone().lub(grades.clone()).ptr_eq(&a));
+        assert_eq!(2, pool.len());
+
+        // Another pool's handles are equal, but not the same.
+        let other = LabelPool::new().intern(&Buckle2::parse("amit,T").unwrap());
+        assert_eq!(a, other);
+        assert!(!a.ptr_eq(&other));
+    }
+
+    #[cfg(feature = "buckle2")]
+    #[test]
+    fn test_purge() {
+        use crate::buckle2::Buckle2;
+
+        let pool = LabelPool::new();
+        let a = pool.intern(&Buckle2::parse("amit,T").unwrap());
+        drop(pool.intern(&Buckle2::parse("yue,T").unwrap()));
+        assert_eq!(1, pool.len());
+        pool.purge();
+        assert_eq!(1, pool.0.lock().len());
+        assert!(pool.intern(&Buckle2::parse("amit,T").unwrap()).ptr_eq(&a));
+    }
+
+    #[cfg(feature = "buckle")]
+    quickcheck! {
+        fn agrees_with_buckle(lbl1: crate::buckle::Buckle, lbl2: crate::buckle::Buckle, privilege: crate::buckle::Component) -> bool {
+            let pool = LabelPool::new();
+            let (i1, i2) = (pool.intern(&lbl1), pool.intern(&lbl2));
+            let (c1, c2) = (lbl1.canonical(), lbl2.canonical());
+            *i1.clone().lub(i2.clone()) == c1.clone().lub(c2.clone()).canonical()
+                && *i1.clone().glb(i2.clone()) == c1.clone().glb(c2.clone()).canonical()
+                && i1.can_flow_to(&i2) == c1.can_flow_to(&c2)
+                && i1.can_flow_to_with_privilege(&i2, &privilege) == c1.can_flow_to_with_privilege(&c2, &privilege)
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! Embedding labels and privileges in JWT claim sets.
+//!
+//! A label is carried in the `lbl` claim and a delegated privilege in the
+//! `prv` claim, both as strings in the label's textual syntax. Tokens are
+//! signed and verified by the caller's JWT library; these helpers only map
+//! between labels and claim sets. Claims are required to be in canonical
+//! form, i.e. exactly as this crate formats them, so equal labels always
+//! produce identical tokens.
+
+use alloc::string::{String, ToString};
+use serde_json::{Map, Value};
+
+/// The claim holding the label.
+pub const LABEL_CLAIM: &str = "lbl";
+
+/// The claim holding the delegated privilege.
+pub const PRIVILEGE_CLAIM: &str = "prv";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The claim is absent.
+    Missing(&'static str),
+    /// The claim is not a string in the label syntax.
+    Invalid(&'static str),
+    /// The claim parses, but isn't in canonical form.
+    NonCanonical(&'static str),
+}
+
+/// A label type with a textual syntax.
+pub trait ClaimLabel: Sized + ToString {
+    type Privilege: ToString;
+
+    fn parse_label(input: &str) -> Option<Self>;
+
+    fn parse_privilege(input: &str) -> Option<Self::Privilege>;
+}
+
+macro_rules! impl_claim_label {
+    ($label:path, $privilege:path, $parser:path, $component:path) => {
+        impl ClaimLabel for $label {
+            type Privilege = $privilege;
+
+            fn parse_label(input: &str) -> Option<Self> {
+                nom::combinator::all_consuming($parser)(input).ok().map(|(_, label)| label)
+            }
+
+            fn parse_privilege(input: &str) -> Option<$privilege> {
+                let (_, mut privilege) = nom::combinator::all_consuming($component)(input).ok()?;
+                privilege.reduce();
+                Some(privilege)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "dclabel")]
+impl_claim_label!(crate::dclabel::DCLabel, crate::dclabel::Component, crate::dclabel::DCLabel::parse, crate::dclabel::component);
+#[cfg(feature = "buckle")]
+impl_claim_label!(crate::buckle::Buckle, crate::buckle::Component, crate::buckle::Buckle::parser, crate::buckle::component);
+
+/// Adds `label`, and `privilege` if given, to `claims`.
+pub fn insert_claims<L: ClaimLabel>(claims: &mut Map<String, Value>, label: &L, privilege: Option<&L::Privilege>) {
+    claims.insert(LABEL_CLAIM.into(), label.to_string().into());
+    if let Some(privilege) = privilege {
+        claims.insert(PRIVILEGE_CLAIM.into(), privilege.to_string().into());
+    }
+}
+
+fn canonical_claim<'a, T: ToString>(
+    claims: &'a Map<String, Value>,
+    claim: &'static str,
+    parse: impl FnOnce(&'a str) -> Option<T>,
+) -> Result<Option<T>, Error> {
+    let text = match claims.get(claim) {
+        None => return Ok(None),
+        Some(value) => value.as_str().ok_or(Error::Invalid(claim))?,
+    };
+    let value = parse(text).ok_or(Error::Invalid(claim))?;
+    if value.to_string() != text {
+        return Err(Error::NonCanonical(claim));
+    }
+    Ok(Some(value))
+}
+
+/// Reads the label from `claims`.
+pub fn label_from_claims<L: ClaimLabel>(claims: &Map<String, Value>) -> Result<L, Error> {
+    canonical_claim(claims, LABEL_CLAIM, L::parse_label)?.ok_or(Error::Missing(LABEL_CLAIM))
+}
+
+/// Reads the delegated privilege from `claims`, if there is one.
+pub fn privilege_from_claims<L: ClaimLabel>(claims: &Map<String, Value>) -> Result<Option<L::Privilege>, Error> {
+    canonical_claim(claims, PRIVILEGE_CLAIM, L::parse_privilege)
+}
+
+#[cfg(all(test, feature = "buckle"))]
+mod tests {
+    use super::*;
+    use crate::buckle::Buckle;
+
+    #[test]
+    fn test_round_trip() {
+        let lbl = Buckle::parse("amit/grades|yue,T").unwrap();
+        let privilege = Buckle::parse("amit,T").unwrap().secrecy;
+        let mut claims = Map::new();
+        claims.insert("sub".into(), "amit".into());
+        insert_claims(&mut claims, &lbl, Some(&privilege));
+
+        assert_eq!(Some(&Value::from("amit/grades|yue,T")), claims.get(LABEL_CLAIM));
+        assert_eq!(Ok(lbl), label_from_claims::<Buckle>(&claims));
+        assert_eq!(Ok(Some(privilege)), privilege_from_claims::<Buckle>(&claims));
+    }
+
+    #[test]
+    fn test_validation() {
+        let claims = |lbl: Value| {
+            let mut claims = Map::new();
+            claims.insert(LABEL_CLAIM.into(), lbl);
+            claims
+        };
+        assert_eq!(Err(Error::Missing(LABEL_CLAIM)), label_from_claims::<Buckle>(&Map::new()));
+        assert_eq!(Ok(None), privilege_from_claims::<Buckle>(&Map::new()));
+        assert_eq!(Err(Error::Invalid(LABEL_CLAIM)), label_from_claims::<Buckle>(&claims(1.into())));
+        assert_eq!(Err(Error::Invalid(LABEL_CLAIM)), label_from_claims::<Buckle>(&claims("amit".into())));
+        // Unordered and unreduced forms are rejected.
+        assert_eq!(Err(Error::NonCanonical(LABEL_CLAIM)), label_from_claims::<Buckle>(&claims("yue|amit,T".into())));
+        assert_eq!(Err(Error::NonCanonical(LABEL_CLAIM)), label_from_claims::<Buckle>(&claims("amit&amit/a,T".into())));
+    }
+}
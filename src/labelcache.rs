@@ -0,0 +1,201 @@
+//! A concurrent map from resources to labels, optimized for reads.
+//!
+//! Enforcement points look up the label of a resource on every access, while
+//! labels change rarely. A `LabelCache` keeps its entries in an immutable map
+//! behind an atomically swapped pointer: reads never take a lock or wait for
+//! writers, and an update copies the map and publishes the copy. Updates are
+//! serialized and cost time linear in the number of entries, so the cache
+//! suits workloads where reads vastly outnumber writes.
+//!
+//! Labels are handed out as `Arc`s, so a reader holding one keeps the label
+//! it saw even if the entry is updated or invalidated concurrently. A
+//! `Snapshot` is a consistent view of the whole cache at one point in time.
+
+extern crate std;
+
+use crate::Label;
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use arc_swap::ArcSwap;
+use std::sync::{Mutex, MutexGuard};
+
+type Map<K, L> = BTreeMap<K, Arc<L>>;
+
+pub struct LabelCache<K, L> {
+    map: ArcSwap<Map<K, L>>,
+    writer: Mutex<()>,
+}
+
+impl<K, L> Default for LabelCache<K, L> {
+    fn default() -> Self {
+        LabelCache { map: ArcSwap::from_pointee(BTreeMap::new()), writer: Mutex::new(()) }
+    }
+}
+
+impl<K: core::fmt::Debug, L: core::fmt::Debug> core::fmt::Debug for LabelCache<K, L> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_map().entries(self.map.load().iter()).finish()
+    }
+}
+
+impl<K, L> LabelCache<K, L> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of cached labels.
+    pub fn len(&self) -> usize {
+        self.map.load().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a consistent view of the cache as it is now. Later updates
+    /// don't affect the snapshot.
+    pub fn snapshot(&self) -> Snapshot<K, L> {
+        Snapshot(self.map.load_full())
+    }
+
+    /// Invalidates every label.
+    pub fn clear(&self) {
+        let _writer = self.lock();
+        self.map.store(Arc::new(BTreeMap::new()));
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ()> {
+        // The lock guards no data, so it can't be left broken by a panic.
+        self.writer.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<K: Ord + Clone, L> LabelCache<K, L> {
+    /// Returns the label of `key`, if cached.
+    pub fn get(&self, key: &K) -> Option<Arc<L>> {
+        self.map.load().get(key).cloned()
+    }
+
+    /// Returns whether `key` has a cached label.
+    pub fn contains(&self, key: &K) -> bool {
+        self.map.load().contains_key(key)
+    }
+
+    /// Caches the label of `key`, returning the label it replaces.
+    pub fn insert(&self, key: K, label: L) -> Option<Arc<L>> {
+        self.update(|map| map.insert(key, Arc::new(label)))
+    }
+
+    /// Invalidates the label of `key`, returning it.
+    pub fn invalidate(&self, key: &K) -> Option<Arc<L>> {
+        if !self.contains(key) {
+            return None;
+        }
+        self.update(|map| map.remove(key))
+    }
+
+    /// Invalidates the labels of the keys `f` returns true for.
+    pub fn invalidate_where<F: FnMut(&K, &L) -> bool>(&self, mut f: F) {
+        self.update(|map| map.retain(|k, l| !f(k, l)))
+    }
+
+    /// Publishes a copy of the map changed by `f`.
+    fn update<T, F: FnOnce(&mut Map<K, L>) -> T>(&self, f: F) -> T {
+        let _writer = self.lock();
+        let mut map = Map::clone(&self.map.load());
+        let result = f(&mut map);
+        self.map.store(Arc::new(map));
+        result
+    }
+}
+
+impl<K: Ord + Clone, L: Label> LabelCache<K, L> {
+    /// Returns whether the label of `key` can flow to `target`, or `None` if
+    /// `key` has no cached label.
+    pub fn can_flow_to(&self, key: &K, target: &L) -> Option<bool> {
+        self.map.load().get(key).map(|label| label.can_flow_to(target))
+    }
+}
+
+/// A view of a `LabelCache` at one point in time.
+#[derive(Debug)]
+pub struct Snapshot<K, L>(Arc<Map<K, L>>);
+
+impl<K, L> Clone for Snapshot<K, L> {
+    fn clone(&self) -> Self {
+        Snapshot(self.0.clone())
+    }
+}
+
+impl<K: Ord, L> Snapshot<K, L> {
+    /// Returns the label of `key` in the snapshot.
+    pub fn get(&self, key: &K) -> Option<&Arc<L>> {
+        self.0.get(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &Arc<L>)> {
+        self.0.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "buckle2")]
+    #[test]
+    fn test_label_cache() {
+        use crate::buckle2::Buckle2;
+
+        let cache = LabelCache::new();
+        assert_eq!(None, cache.insert(1, Buckle2::parse("amit,T").unwrap()));
+        cache.insert(2, Buckle2::parse("yue,T").unwrap());
+        assert_eq!(Some(true), cache.can_flow_to(&1, &Buckle2::parse("amit&yue,T").unwrap()));
+        assert_eq!(None, cache.can_flow_to(&3, &Buckle2::parse("amit,T").unwrap()));
+
+        let snapshot = cache.snapshot();
+        let old = cache.insert(1, Buckle2::parse("T,T").unwrap()).unwrap();
+        assert_eq!(Buckle2::parse("amit,T").unwrap(), *old);
+        assert_eq!(Some(&old), snapshot.get(&1));
+        assert_eq!(Buckle2::parse("T,T").unwrap(), *cache.get(&1).unwrap());
+
+        assert!(cache.invalidate(&2).is_some());
+        assert!(cache.invalidate(&2).is_none());
+        assert_eq!(2, snapshot.len());
+        assert_eq!(1, cache.len());
+
+        cache.invalidate_where(|k, _| *k == 1);
+        assert!(cache.is_empty());
+    }
+
+    #[cfg(feature = "buckle2")]
+    #[test]
+    fn test_concurrent_reads() {
+        use crate::buckle2::Buckle2;
+        use std::thread;
+
+        let cache = Arc::new(LabelCache::new());
+        let public = Buckle2::parse("T,T").unwrap();
+        cache.insert(0, public.clone());
+        let readers: alloc::vec::Vec<_> = (0..4)
+            .map(|_| {
+                let (cache, public) = (cache.clone(), public.clone());
+                thread::spawn(move || (0..1000).all(|_| cache.can_flow_to(&0, &public) == Some(true)))
+            })
+            .collect();
+        for i in 1..100 {
+            cache.insert(i, public.clone());
+            cache.invalidate(&(i - 1).max(1));
+        }
+        assert!(readers.into_iter().all(|r| r.join().unwrap()));
+        assert!(cache.contains(&0));
+    }
+}
@@ -0,0 +1,233 @@
+//! The laws every label model must obey.
+//!
+//! Each law is a function of arbitrary labels and privileges that returns
+//! whether the law holds for them, so it can be checked with any property
+//! testing library, and `check` runs the whole suite. The crate checks the
+//! suite against `dclabel`, `buckle` and `buckle2`, so that the modules can't
+//! drift apart in behavior; implementations of `Label` outside the crate can
+//! check it the same way.
+//!
+//! Labels are compared by equivalence, i.e. flowing to each other, rather
+//! than by structure, as equivalent labels may be written differently.
+
+use crate::{HasPrivilege, Label};
+
+/// A label law, reported by `check` when it doesn't hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Law {
+    CanFlowToReflexive,
+    CanFlowToTransitive,
+    LubCommutative,
+    LubAssociative,
+    LubIdempotent,
+    LubUpperBound,
+    LubLeast,
+    GlbCommutative,
+    GlbAssociative,
+    GlbIdempotent,
+    GlbLowerBound,
+    GlbGreatest,
+    ReduceIdempotent,
+    ReducePreserves,
+    PrivilegeWeakens,
+    DowngradeMonotone,
+    DowngradeSound,
+    DowngradeTo,
+}
+
+/// A label with a normal form.
+pub trait Reduce {
+    fn reduce(&mut self);
+}
+
+/// Returns whether the labels flow to each other.
+pub fn equivalent<L: Label>(a: &L, b: &L) -> bool {
+    a.can_flow_to(b) && b.can_flow_to(a)
+}
+
+pub fn can_flow_to_reflexive<L: Label>(a: &L) -> bool {
+    a.can_flow_to(a)
+}
+
+pub fn can_flow_to_transitive<L: Label>(a: &L, b: &L, c: &L) -> bool {
+    !(a.can_flow_to(b) && b.can_flow_to(c)) || a.can_flow_to(c)
+}
+
+pub fn lub_commutative<L: Label + Clone>(a: &L, b: &L) -> bool {
+    equivalent(&a.clone().lub(b.clone()), &b.clone().lub(a.clone()))
+}
+
+pub fn lub_associative<L: Label + Clone>(a: &L, b: &L, c: &L) -> bool {
+    equivalent(&a.clone().lub(b.clone()).lub(c.clone()), &a.clone().lub(b.clone().lub(c.clone())))
+}
+
+pub fn lub_idempotent<L: Label + Clone>(a: &L) -> bool {
+    equivalent(&a.clone().lub(a.clone()), a)
+}
+
+pub fn lub_upper_bound<L: Label + Clone>(a: &L, b: &L) -> bool {
+    let lub = a.clone().lub(b.clone());
+    a.can_flow_to(&lub) && b.can_flow_to(&lub)
+}
+
+pub fn lub_least<L: Label + Clone>(a: &L, b: &L, c: &L) -> bool {
+    !(a.can_flow_to(c) && b.can_flow_to(c)) || a.clone().lub(b.clone()).can_flow_to(c)
+}
+
+pub fn glb_commutative<L: Label + Clone>(a: &L, b: &L) -> bool {
+    equivalent(&a.clone().glb(b.clone()), &b.clone().glb(a.clone()))
+}
+
+pub fn glb_associative<L: Label + Clone>(a: &L, b: &L, c: &L) -> bool {
+    equivalent(&a.clone().glb(b.clone()).glb(c.clone()), &a.clone().glb(b.clone().glb(c.clone())))
+}
+
+pub fn glb_idempotent<L: Label + Clone>(a: &L) -> bool {
+    equivalent(&a.clone().glb(a.clone()), a)
+}
+
+pub fn glb_lower_bound<L: Label + Clone>(a: &L, b: &L) -> bool {
+    let glb = a.clone().glb(b.clone());
+    glb.can_flow_to(a) && glb.can_flow_to(b)
+}
+
+pub fn glb_greatest<L: Label + Clone>(a: &L, b: &L, c: &L) -> bool {
+    !(c.can_flow_to(a) && c.can_flow_to(b)) || c.can_flow_to(&a.clone().glb(b.clone()))
+}
+
+/// Reducing a reduced label changes nothing.
+pub fn reduce_idempotent<L: Reduce + Clone + PartialEq>(a: &L) -> bool {
+    let mut once = a.clone();
+    once.reduce();
+    let mut twice = once.clone();
+    twice.reduce();
+    once == twice
+}
+
+/// Reducing a label yields an equivalent label.
+pub fn reduce_preserves<L: Label + Reduce + Clone>(a: &L) -> bool {
+    let mut reduced = a.clone();
+    reduced.reduce();
+    equivalent(&reduced, a)
+}
+
+/// A privilege permits every flow permitted without it.
+pub fn privilege_weakens<L: HasPrivilege + Label>(a: &L, b: &L, privilege: &L::Privilege) -> bool {
+    !a.can_flow_to(b) || a.can_flow_to_with_privilege(b, privilege)
+}
+
+pub fn downgrade_monotone<L: HasPrivilege + Label + Clone>(a: &L, b: &L, privilege: &L::Privilege) -> bool {
+    !a.can_flow_to(b) || a.clone().downgrade(privilege).can_flow_to(&b.clone().downgrade(privilege))
+}
+
+/// A label flows to another with a privilege exactly when its downgrade
+/// flows to it without.
+pub fn downgrade_sound<L: HasPrivilege + Label + Clone>(a: &L, b: &L, privilege: &L::Privilege) -> bool {
+    a.can_flow_to_with_privilege(b, privilege) == a.clone().downgrade(privilege).can_flow_to(b)
+}
+
+pub fn downgrade_to<L: HasPrivilege + Label + Clone + PartialEq>(a: &L, b: &L, privilege: &L::Privilege) -> bool {
+    let expected = if a.can_flow_to_with_privilege(b, privilege) { b } else { a };
+    a.clone().downgrade_to(b.clone(), privilege) == *expected
+}
+
+/// Checks every law on the given labels and privilege, returning the first
+/// that doesn't hold.
+pub fn check<L>(a: &L, b: &L, c: &L, privilege: &L::Privilege) -> Result<(), Law>
+where
+    L: HasPrivilege + Label + Reduce + Clone + PartialEq,
+{
+    check_lattice(a, b, c).and_then(|()| check_privilege(a, b, privilege))
+}
+
+/// Checks the laws not involving privileges.
+pub fn check_lattice<L>(a: &L, b: &L, c: &L) -> Result<(), Law>
+where
+    L: Label + Reduce + Clone + PartialEq,
+{
+    let laws = [
+        (Law::CanFlowToReflexive, can_flow_to_reflexive(a)),
+        (Law::CanFlowToTransitive, can_flow_to_transitive(a, b, c)),
+        (Law::LubCommutative, lub_commutative(a, b)),
+        (Law::LubAssociative, lub_associative(a, b, c)),
+        (Law::LubIdempotent, lub_idempotent(a)),
+        (Law::LubUpperBound, lub_upper_bound(a, b)),
+        (Law::LubLeast, lub_least(a, b, c)),
+        (Law::GlbCommutative, glb_commutative(a, b)),
+        (Law::GlbAssociative, glb_associative(a, b, c)),
+        (Law::GlbIdempotent, glb_idempotent(a)),
+        (Law::GlbLowerBound, glb_lower_bound(a, b)),
+        (Law::GlbGreatest, glb_greatest(a, b, c)),
+        (Law::ReduceIdempotent, reduce_idempotent(a)),
+        (Law::ReducePreserves, reduce_preserves(a)),
+    ];
+    first_failure(&laws)
+}
+
+/// Checks the laws involving privileges.
+pub fn check_privilege<L>(a: &L, b: &L, privilege: &L::Privilege) -> Result<(), Law>
+where
+    L: HasPrivilege + Label + Clone + PartialEq,
+{
+    let laws = [
+        (Law::PrivilegeWeakens, privilege_weakens(a, b, privilege)),
+        (Law::DowngradeMonotone, downgrade_monotone(a, b, privilege)),
+        (Law::DowngradeSound, downgrade_sound(a, b, privilege)),
+        (Law::DowngradeTo, downgrade_to(a, b, privilege)),
+    ];
+    first_failure(&laws)
+}
+
+fn first_failure(laws: &[(Law, bool)]) -> Result<(), Law> {
+    match laws.iter().find(|(_, holds)| !holds) {
+        Some((law, _)) => Err(*law),
+        None => Ok(()),
+    }
+}
+
+#[cfg(feature = "dclabel")]
+impl Reduce for crate::dclabel::DCLabel {
+    fn reduce(&mut self) {
+        crate::dclabel::DCLabel::reduce(self)
+    }
+}
+
+#[cfg(feature = "buckle")]
+impl Reduce for crate::buckle::Buckle {
+    fn reduce(&mut self) {
+        crate::buckle::Buckle::reduce(self)
+    }
+}
+
+#[cfg(feature = "buckle2")]
+impl<A: allocator_api2::alloc::Allocator + Clone> Reduce for crate::buckle2::Buckle2<A> {
+    fn reduce(&mut self) {
+        crate::buckle2::Buckle2::reduce(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "dclabel")]
+    quickcheck! {
+        fn dclabel_obeys_laws(a: crate::dclabel::DCLabel, b: crate::dclabel::DCLabel, c: crate::dclabel::DCLabel, privilege: crate::dclabel::Component) -> bool {
+            check(&a, &b, &c, &privilege) == Ok(())
+        }
+    }
+
+    #[cfg(feature = "buckle")]
+    quickcheck! {
+        fn buckle_obeys_laws(a: crate::buckle::Buckle, b: crate::buckle::Buckle, c: crate::buckle::Buckle, privilege: crate::buckle::Component) -> bool {
+            check(&a, &b, &c, &privilege) == Ok(())
+        }
+    }
+
+    #[cfg(feature = "buckle2")]
+    quickcheck! {
+        fn buckle2_obeys_laws(a: crate::buckle2::Buckle2, b: crate::buckle2::Buckle2, c: crate::buckle2::Buckle2, privilege: crate::buckle2::Component) -> bool {
+            check(&a, &b, &c, &privilege) == Ok(())
+        }
+    }
+}
@@ -0,0 +1,64 @@
+//! Compile-time checks of the label types' auto traits and sizes.
+//!
+//! Concurrent runtimes move labels between threads and share them behind
+//! references, and pin futures holding them, so every label type, and the
+//! components, clauses and compiled privileges they're built from, must stay
+//! `Send + Sync + Unpin`. `Buckle2<A>` is `Send` or `Sync` whenever its
+//! allocator is. Labels are also held inline in messages and cache entries,
+//! so their sizes are bounded too; the bounds hold with or without the
+//! `hashcons` and `smolstr` features.
+//!
+//! The checks are constant items, so a change breaking one fails the build
+//! rather than a test.
+
+use core::mem::size_of;
+
+const fn assert_send_sync_unpin<T: Send + Sync + Unpin>() {}
+
+#[cfg(feature = "dclabel")]
+const _: () = {
+    use crate::dclabel::{Clause, CompiledPrivilege, Component, DCLabel};
+
+    assert_send_sync_unpin::<DCLabel>();
+    assert_send_sync_unpin::<Component>();
+    assert_send_sync_unpin::<Clause>();
+    assert_send_sync_unpin::<CompiledPrivilege>();
+    assert!(size_of::<DCLabel>() <= 64);
+    assert!(size_of::<Component>() <= 32);
+};
+
+#[cfg(feature = "buckle")]
+const _: () = {
+    use crate::buckle::{Buckle, Clause, CompiledPrivilege, Component};
+
+    assert_send_sync_unpin::<Buckle>();
+    assert_send_sync_unpin::<Component>();
+    assert_send_sync_unpin::<Clause>();
+    assert_send_sync_unpin::<CompiledPrivilege>();
+    assert!(size_of::<Buckle>() <= 64);
+    assert!(size_of::<Component>() <= 32);
+};
+
+#[cfg(feature = "buckle2")]
+const _: () = {
+    use crate::buckle2::{Buckle2, Clause, Component};
+    use allocator_api2::alloc::Allocator;
+
+    assert_send_sync_unpin::<Buckle2>();
+    assert_send_sync_unpin::<Component>();
+    assert_send_sync_unpin::<Clause>();
+    assert!(size_of::<Buckle2>() <= 48);
+    assert!(size_of::<Component>() <= 24);
+
+    #[allow(dead_code)]
+    fn send_with<A: Allocator + Clone + Send>() {
+        const fn assert_send<T: Send>() {}
+        assert_send::<Buckle2<A>>();
+    }
+
+    #[allow(dead_code)]
+    fn sync_with<A: Allocator + Clone + Sync>() {
+        const fn assert_sync<T: Sync>() {}
+        assert_sync::<Buckle2<A>>();
+    }
+};
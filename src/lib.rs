@@ -1,24 +1,167 @@
 #![no_std]
-#![cfg_attr(feature = "buckle2", feature(btreemap_alloc, allocator_api))]
+#![cfg_attr(feature = "nightly", feature(allocator_api))]
+#![cfg_attr(all(test, feature = "nightly"), feature(alloc_error_hook))]
 
 extern crate alloc;
 #[cfg(test)]
+extern crate std;
+#[cfg(test)]
 #[macro_use]
 extern crate quickcheck;
+// Lets the crate's tests use the macros, which name it `::labeled`.
+#[cfg(all(test, feature = "macros"))]
+extern crate self as labeled;
 
+#[cfg(any(feature = "dclabel", feature = "buckle"))]
+mod serde_label;
+#[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2"))]
+mod fingerprint;
+#[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2"))]
+mod subsume;
+#[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2", feature = "small"))]
+mod heap;
 #[cfg(feature = "buckle")]
 pub mod buckle;
 #[cfg(feature = "dclabel")]
 pub mod dclabel;
+#[cfg(all(feature = "arc", any(feature = "dclabel", feature = "buckle")))]
+pub mod shared;
 #[cfg(feature = "buckle2")]
 pub mod buckle2;
+#[cfg(feature = "differential")]
+pub mod differential;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+#[cfg(all(feature = "capnp", any(feature = "dclabel", feature = "buckle")))]
+pub mod capnproto;
+#[cfg(all(feature = "flatbuffers", any(feature = "dclabel", feature = "buckle")))]
+pub mod fbs;
+#[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2"))]
+pub mod envelope;
+#[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2"))]
+pub mod text;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(all(feature = "header", any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+pub mod header;
+#[cfg(all(feature = "sqlx", any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+pub mod sqlx_pg;
+#[cfg(all(feature = "diesel", any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+pub mod diesel_pg;
+#[cfg(all(feature = "redis", any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+pub mod redis_value;
+#[cfg(feature = "web")]
+pub mod web;
+#[cfg(feature = "tower")]
+pub mod middleware;
+#[cfg(feature = "tracing")]
+pub mod telemetry;
+#[cfg(all(feature = "jwt", any(feature = "dclabel", feature = "buckle")))]
+pub mod jwt;
+#[cfg(all(feature = "pki", any(feature = "dclabel", feature = "buckle")))]
+pub mod pki;
+#[cfg(feature = "macaroon")]
+pub mod macaroon;
+#[cfg(all(feature = "grpc", any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+pub mod grpc;
+#[cfg(all(feature = "arrow", any(feature = "dclabel", feature = "buckle")))]
+pub mod arrow;
+#[cfg(feature = "xattr")]
+pub mod fs;
+#[cfg(feature = "selinux")]
+pub mod selinux;
+#[cfg(feature = "tags")]
+pub mod tags;
+#[cfg(all(feature = "opa", any(feature = "dclabel", feature = "buckle")))]
+pub mod opa;
+#[cfg(all(feature = "bounded", any(feature = "dclabel", feature = "buckle")))]
+pub mod bounded;
+#[cfg(feature = "migrate")]
+pub mod migrate;
+#[cfg(all(feature = "vectors", any(feature = "dclabel", feature = "buckle")))]
+pub mod vectors;
+#[cfg(all(feature = "msgpack", any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+pub mod msgpack;
+#[cfg(all(feature = "registry", any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+pub mod registry;
+#[cfg(feature = "symbol")]
+pub mod symbol;
+#[cfg(feature = "bitlabel")]
+pub mod bitlabel;
+#[cfg(all(feature = "flowcache", any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+pub mod flowcache;
+#[cfg(all(feature = "rayon", any(feature = "dclabel", feature = "buckle")))]
+pub mod parallel;
+#[cfg(feature = "persistent")]
+pub mod persistent;
+#[cfg(feature = "small")]
+pub mod small;
+#[cfg(all(feature = "intern", any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+pub mod intern;
+#[cfg(all(feature = "hashcons", any(feature = "dclabel", feature = "buckle")))]
+pub mod hashcons;
+#[cfg(feature = "labelcache")]
+pub mod labelcache;
+#[cfg(feature = "arena")]
+pub mod arena;
+#[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2"))]
+pub mod laws;
+#[cfg(all(kani, feature = "buckle2"))]
+mod verification;
+#[cfg(all(test, any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+mod model_check;
+#[cfg(all(feature = "smt", any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+pub mod smt;
+pub mod error;
+#[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2"))]
+pub mod normalized;
+#[cfg(all(test, any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+mod conformance;
+#[cfg(all(test, any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+mod panic_free;
+#[cfg(all(any(test, feature = "quickcheck"), any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+pub mod generate;
+#[cfg(all(feature = "proptest", any(feature = "buckle", feature = "buckle2")))]
+pub mod strategy;
+#[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2"))]
+mod layout;
+#[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2"))]
+pub mod proof;
+#[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2"))]
+pub mod lint;
+#[cfg(all(feature = "buckle2", any(test, feature = "test-support")))]
+pub mod test_support;
+#[cfg(all(any(test, feature = "testvectors"), any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+pub mod testvectors;
+
+pub use error::Error;
+#[cfg(all(feature = "macros", feature = "buckle"))]
+pub use labeled_macros::buckle;
+#[cfg(all(feature = "macros", feature = "dclabel"))]
+pub use labeled_macros::dclabel;
 
+/// A security lattice.
+///
+/// The crate's label types implement the operations, and their components'
+/// `implies` and `reduce`, without panicking on any input, reduced or not,
+/// so that kernels and FFI callers can run them where unwinding isn't an
+/// option. They still allocate, and allocation failure goes to the global
+/// allocation error handler.
+///
+/// The guarantee covers only these lattice operations. Encoders, the
+/// `symbol::Interner` and the HTTP integrations may panic as their own
+/// `# Panics` sections describe, or on a broken invariant such as polling a
+/// future after completion.
 pub trait Label {
     fn lub(self, rhs: Self) -> Self;
     fn glb(self, rhs: Self) -> Self;
     fn can_flow_to(&self, rhs: &Self) -> bool;
 }
 
+/// A label that privileges can downgrade. Like the `Label` operations,
+/// downgrading never panics in the crate's label types.
 pub trait HasPrivilege {
     type Privilege;
 
@@ -26,3 +169,130 @@ pub trait HasPrivilege {
     fn downgrade_to(self, target: Self, privilege: &Self::Privilege) -> Self;
     fn can_flow_to_with_privilege(&self, rhs: &Self, privilege: &Self::Privilege) -> bool;
 }
+
+/// Size and shape statistics for a label, used to monitor label growth and
+/// enforce budgets on pathological labels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LabelStats {
+    /// Number of clauses in the secrecy component (zero for `DCFalse`).
+    pub clauses_secrecy: usize,
+    /// Number of clauses in the integrity component (zero for `DCFalse`).
+    pub clauses_integrity: usize,
+    /// Total number of principals across all clauses, counting repeats. For
+    /// hierarchical labels each principal path counts once.
+    pub principals: usize,
+    /// Length of the longest principal path (one for flat DCLabels, zero for
+    /// labels with no principals).
+    pub max_path_depth: usize,
+    /// Approximate heap and inline size of the label in bytes, ignoring
+    /// collection bookkeeping.
+    pub approx_bytes: usize,
+}
+
+#[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2"))]
+impl LabelStats {
+    /// Computes the statistics of a label of type `L` from the clauses of
+    /// its components, or `None` for a `DCFalse` component.
+    pub(crate) fn of<'a, L, C: ClauseStats + 'a>(
+        secrecy: Option<impl Iterator<Item = &'a C>>,
+        integrity: Option<impl Iterator<Item = &'a C>>,
+    ) -> LabelStats {
+        let mut stats = LabelStats { approx_bytes: core::mem::size_of::<L>(), ..LabelStats::default() };
+        stats.clauses_secrecy = stats.add_clauses(secrecy);
+        stats.clauses_integrity = stats.add_clauses(integrity);
+        stats
+    }
+
+    /// Adds the clauses of a component, returning how many there are.
+    fn add_clauses<'a, C: ClauseStats + 'a>(&mut self, clauses: Option<impl Iterator<Item = &'a C>>) -> usize {
+        let mut count = 0;
+        for clause in clauses.into_iter().flatten() {
+            count += 1;
+            self.approx_bytes += core::mem::size_of::<C>();
+            for path in clause.paths() {
+                let mut depth = 0;
+                self.principals += 1;
+                self.approx_bytes += C::PATH_SIZE;
+                for len in path {
+                    depth += 1;
+                    self.approx_bytes += C::PRINCIPAL_SIZE + len;
+                }
+                self.max_path_depth = self.max_path_depth.max(depth);
+            }
+        }
+        count
+    }
+}
+
+/// A clause whose principal paths `LabelStats` measures.
+#[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2"))]
+pub(crate) trait ClauseStats {
+    /// The bytes a path takes in the clause besides its principals.
+    const PATH_SIZE: usize;
+    /// The bytes a principal takes in its path besides its text.
+    const PRINCIPAL_SIZE: usize;
+
+    /// The byte lengths of the principals of each path. A `DCLabel`
+    /// principal is a path of one.
+    fn paths(&self) -> impl Iterator<Item = impl Iterator<Item = usize> + '_>;
+}
+
+/// Why a component's `new_checked` rejected a clause.
+#[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MalformedClause {
+    /// A principal is empty, which no text label can spell.
+    EmptyPrincipal,
+    /// A principal path has no principals, which would imply nothing but
+    /// be implied by every path.
+    EmptyPath,
+}
+
+#[cfg(any(feature = "dclabel", feature = "buckle", feature = "buckle2"))]
+impl core::fmt::Display for MalformedClause {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MalformedClause::EmptyPrincipal => write!(f, "clause has an empty principal"),
+            MalformedClause::EmptyPath => write!(f, "clause has an empty principal path"),
+        }
+    }
+}
+
+#[cfg(all(test, any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+mod tests {
+    use super::LabelStats;
+    use alloc::vec;
+
+    #[test]
+    fn test_stats() {
+        // `top` has no principals, and `label` two secrecy clauses and one
+        // integrity clause of four principals, on paths up to `depth` long.
+        let check = |top: LabelStats, public: LabelStats, label: LabelStats, depth: usize| {
+            assert_eq!((0, 0, 0, 0), (top.clauses_secrecy, top.clauses_integrity, top.principals, top.max_path_depth));
+            assert_eq!(2, label.clauses_secrecy);
+            assert_eq!(1, label.clauses_integrity);
+            assert_eq!(4, label.principals);
+            assert_eq!(depth, label.max_path_depth);
+            assert!(label.approx_bytes > public.approx_bytes);
+        };
+
+        #[cfg(feature = "dclabel")]
+        {
+            use crate::dclabel::DCLabel;
+            let label = DCLabel::new([vec!["Amit", "Yue"], vec!["Deian"]], [["Amit"]]);
+            check(DCLabel::top().stats(), DCLabel::public().stats(), label.stats(), 1);
+        }
+        #[cfg(feature = "buckle")]
+        {
+            use crate::buckle::{Buckle, Clause};
+            let label = Buckle::new([Clause::new_from_vec(vec![vec!["Amit", "staff"], vec!["Yue"]]), Clause::from(["Deian"])], [["Amit"]]);
+            check(Buckle::top().stats(), Buckle::public().stats(), label.stats(), 2);
+        }
+        #[cfg(feature = "buckle2")]
+        {
+            use crate::buckle2::{Buckle2, Clause};
+            let label = Buckle2::new([Clause::new_from_vec(vec![vec!["Amit", "staff"], vec!["Yue"]]), Clause::from(["Deian"])], [["Amit"]]);
+            check(Buckle2::top().stats(), Buckle2::public().stats(), label.stats(), 2);
+        }
+    }
+}
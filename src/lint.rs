@@ -0,0 +1,234 @@
+//! A linter for labels and privileges.
+//!
+//! Labels and privileges written by hand, e.g. in a policy repository, can
+//! be well formed yet suspicious: a clause that another makes redundant, a
+//! privilege of `DCFalse`, which downgrades every label, a secrecy component
+//! naming both a principal and a path under it, or an empty principal or
+//! path, which the text syntax can't even write. `label` and `privilege`
+//! return such patterns as `Finding`s, in a stable order, so CI can gate on
+//! them or print them with `Display`.
+//!
+//! Clauses are identified by their position in the component's sorted
+//! order, as iterated. `DCLabel` principals are paths of one principal.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Which component a finding is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Part {
+    Secrecy,
+    Integrity,
+    Privilege,
+}
+
+/// A suspicious pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Finding {
+    /// Clause `clause` is implied by clause `by` of the same component, so
+    /// it adds nothing, and `reduce` would drop it.
+    SubsumedClause { part: Part, clause: usize, by: usize },
+    /// The privilege is `DCFalse`, which downgrades every label to public.
+    FalsePrivilege,
+    /// The secrecy component names path `path` and, possibly in another
+    /// clause, `under`, which extends it, so `path` speaks for `under`.
+    PrincipalUnderPrefix { path: String, under: String },
+    /// Clause `clause` has an empty principal or path.
+    EmptyPrincipal { part: Part, clause: usize },
+}
+
+impl core::fmt::Display for Finding {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Finding::SubsumedClause { part, clause, by } => {
+                write!(f, "{:?} clause {} is implied by clause {}", part, clause, by)
+            }
+            Finding::FalsePrivilege => f.write_str("privilege is F, which downgrades every label"),
+            Finding::PrincipalUnderPrefix { path, under } => {
+                write!(f, "secrecy names both {} and {}, which it speaks for", path, under)
+            }
+            Finding::EmptyPrincipal { part, clause } => {
+                write!(f, "{:?} clause {} has an empty principal", part, clause)
+            }
+        }
+    }
+}
+
+/// The clauses of a component, each a list of paths of principals' bytes.
+pub type Paths<'a> = Vec<Vec<Vec<&'a [u8]>>>;
+
+/// Components the linter inspects.
+pub trait Lint {
+    /// Returns the component's clauses, or `None` if it is `DCFalse`.
+    fn paths(&self) -> Option<Paths<'_>>;
+}
+
+/// Returns the findings for a label's components, secrecy first.
+pub fn label<C: Lint>(secrecy: &C, integrity: &C) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    if let Some(clauses) = secrecy.paths() {
+        component(Part::Secrecy, &clauses, &mut findings);
+        prefixes(&clauses, &mut findings);
+    }
+    if let Some(clauses) = integrity.paths() {
+        component(Part::Integrity, &clauses, &mut findings);
+    }
+    findings
+}
+
+/// Returns the findings for a privilege.
+pub fn privilege<C: Lint>(privilege: &C) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    match privilege.paths() {
+        None => findings.push(Finding::FalsePrivilege),
+        Some(clauses) => component(Part::Privilege, &clauses, &mut findings),
+    }
+    findings
+}
+
+/// Returns whether clause `a` implies clause `b`, as `Clause::implies` does.
+fn implies(a: &[Vec<&[u8]>], b: &[Vec<&[u8]>]) -> bool {
+    a.iter().all(|p| b.iter().any(|q| q.starts_with(p)))
+}
+
+fn component(part: Part, clauses: &[Vec<Vec<&[u8]>>], findings: &mut Vec<Finding>) {
+    for (clause, c) in clauses.iter().enumerate() {
+        if c.iter().any(|path| path.is_empty() || path.iter().any(|p| p.is_empty())) {
+            findings.push(Finding::EmptyPrincipal { part, clause });
+        }
+    }
+    for (clause, c) in clauses.iter().enumerate() {
+        if let Some(by) = clauses.iter().enumerate().position(|(i, d)| i != clause && implies(d, c)) {
+            findings.push(Finding::SubsumedClause { part, clause, by });
+        }
+    }
+}
+
+fn prefixes(clauses: &[Vec<Vec<&[u8]>>], findings: &mut Vec<Finding>) {
+    let mut paths: Vec<&Vec<&[u8]>> = clauses.iter().flatten().filter(|p| !p.is_empty()).collect();
+    paths.sort();
+    paths.dedup();
+    for path in &paths {
+        for under in paths.iter().filter(|q| q.len() > path.len() && q.starts_with(path)) {
+            findings.push(Finding::PrincipalUnderPrefix { path: text(path), under: text(under) });
+        }
+    }
+}
+
+/// Formats a path as the text syntax would, without escapes.
+fn text(path: &[&[u8]]) -> String {
+    let mut text = String::new();
+    for (i, principal) in path.iter().enumerate() {
+        if i > 0 {
+            text.push('/');
+        }
+        text.push_str(&String::from_utf8_lossy(principal));
+    }
+    text
+}
+
+#[cfg(feature = "dclabel")]
+impl Lint for crate::dclabel::Component {
+    fn paths(&self) -> Option<Paths<'_>> {
+        match self {
+            crate::dclabel::Component::DCFalse => None,
+            crate::dclabel::Component::DCFormula(clauses) => Some(
+                clauses.iter().map(|c| c.0.iter().map(|p| alloc::vec![p.as_bytes()]).collect()).collect(),
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "buckle")]
+impl Lint for crate::buckle::Component {
+    fn paths(&self) -> Option<Paths<'_>> {
+        match self {
+            crate::buckle::Component::DCFalse => None,
+            crate::buckle::Component::DCFormula(clauses) => Some(
+                clauses
+                    .iter()
+                    .map(|c| c.0.iter().map(|path| path.iter().map(|p| p.as_bytes()).collect()).collect())
+                    .collect(),
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "buckle2")]
+impl<A: allocator_api2::alloc::Allocator + Clone> Lint for crate::buckle2::Component<A> {
+    fn paths(&self) -> Option<Paths<'_>> {
+        match self {
+            crate::buckle2::Component::DCFalse => None,
+            crate::buckle2::Component::DCFormula(clauses, _) => Some(
+                clauses
+                    .iter()
+                    .map(|c| c.0.iter().map(|path| path.iter().map(|p| &p[..]).collect()).collect())
+                    .collect(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "buckle")]
+    #[test]
+    fn test_buckle() {
+        use crate::buckle::{Buckle, Clause, Component};
+        use alloc::string::ToString;
+
+        let clean = Buckle::parse("amit|yue&bob,staff").unwrap();
+        assert_eq!(Vec::<Finding>::new(), label(clean.secrecy(), clean.integrity()));
+
+        // Unreduced, as a policy file might write it.
+        let secrecy = Component::from(
+            alloc::vec![
+                Clause::new_from_vec(alloc::vec![alloc::vec!["amit"]]),
+                Clause::new_from_vec(alloc::vec![alloc::vec!["amit", "grades"], alloc::vec!["yue"]]),
+            ]
+            .into_iter()
+            .collect::<alloc::collections::BTreeSet<_>>(),
+        );
+        let findings = label(&secrecy, &Component::from([Clause::new_from_vec(alloc::vec![Vec::<&str>::new()])]));
+        assert_eq!(
+            alloc::vec![
+                Finding::SubsumedClause { part: Part::Secrecy, clause: 1, by: 0 },
+                Finding::PrincipalUnderPrefix { path: "amit".into(), under: "amit/grades".into() },
+                Finding::EmptyPrincipal { part: Part::Integrity, clause: 0 },
+            ],
+            findings
+        );
+        assert_eq!("secrecy names both amit and amit/grades, which it speaks for", findings[1].to_string());
+        assert_eq!(alloc::vec![Finding::FalsePrivilege], privilege(&Component::dc_false()));
+        assert_eq!(Vec::<Finding>::new(), privilege(clean.secrecy()));
+    }
+
+    #[cfg(feature = "buckle2")]
+    #[test]
+    fn test_buckle2() {
+        use crate::buckle2::Buckle2;
+
+        let label = Buckle2::parse("a/b&a|c,T").unwrap();
+        assert_eq!(
+            alloc::vec![Finding::PrincipalUnderPrefix { path: "a".into(), under: "a/b".into() }],
+            super::label(label.secrecy(), label.integrity())
+        );
+        assert_eq!(alloc::vec![Finding::FalsePrivilege], privilege(Buckle2::top().secrecy()));
+    }
+
+    #[cfg(feature = "dclabel")]
+    #[test]
+    fn test_dclabel() {
+        use crate::dclabel::{Clause, Component};
+
+        let privilege = Component::from(
+            alloc::vec![Clause::from(["amit"]), Clause::from(["amit", "yue"])].into_iter().collect::<alloc::collections::BTreeSet<_>>(),
+        );
+        assert_eq!(
+            alloc::vec![Finding::SubsumedClause { part: Part::Privilege, clause: 1, by: 0 }],
+            super::privilege(&privilege)
+        );
+    }
+}
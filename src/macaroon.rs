@@ -0,0 +1,283 @@
+//! Attenuable bearer tokens carrying `Buckle` privileges.
+//!
+//! A `Token` is a macaroon whose caveats are privileges. It is minted by the
+//! holder of a root key with an initial privilege, and anyone holding the
+//! token can attenuate it by appending a caveat, as long as the current
+//! privilege implies the caveat, e.g. narrowing `amit` to `amit/grades`.
+//! Since each caveat is chained into the token's HMAC-SHA256 signature,
+//! caveats can't be removed or reordered without the root key.
+//!
+//! Verifying a token with the root key checks the signature and that each
+//! caveat attenuates the one before it, and yields the effective privilege,
+//! i.e. the last caveat.
+//!
+//! A caveat is signed and serialized as the canonical bytes (see
+//! `envelope`) of the label with the caveat as secrecy and `T` as integrity,
+//! rather than as text, which can't tell every component apart, e.g. `{∅}`
+//! from `DCFalse`. Caveats are kept in that canonical form, so a token
+//! round-trips exactly.
+//!
+//! Tokens are serialized as the identifier, the caveats' bytes and the
+//! signature, each prefixed by its LEB128-encoded length. Parsing is strict:
+//! a caveat must be the canonical bytes of such a label.
+
+use crate::buckle::{Buckle, Component};
+use crate::envelope::{self, Enveloped};
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The length of a token's signature, in bytes.
+pub const SIGNATURE_LEN: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The signature doesn't match the identifier and caveats.
+    InvalidSignature,
+    /// A caveat isn't implied by the privilege before it.
+    NotAttenuation,
+    /// The token has no caveats, so grants no privilege.
+    NoPrivilege,
+    /// The serialized token is malformed.
+    Malformed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    identifier: Vec<u8>,
+    caveats: Vec<Component>,
+    signature: [u8; SIGNATURE_LEN],
+}
+
+fn sign(key: &[u8], message: &[u8]) -> [u8; SIGNATURE_LEN] {
+    // HMAC accepts keys of any length.
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC key");
+    mac.update(message);
+    mac.finalize().into_bytes().into()
+}
+
+fn caveat_label(caveat: Component) -> Buckle {
+    Buckle { secrecy: caveat, integrity: Component::dc_true() }
+}
+
+fn caveat_bytes(caveat: &Component) -> Vec<u8> {
+    envelope::encode(&caveat_label(caveat.clone()))
+}
+
+fn read_caveat(bytes: &[u8]) -> Result<Component, Error> {
+    let label: Buckle = envelope::decode(bytes).map_err(|_| Error::Malformed)?;
+    if label.integrity != Component::dc_true() || label.canonical_bytes() != bytes {
+        return Err(Error::Malformed);
+    }
+    Ok(label.secrecy)
+}
+
+impl Token {
+    /// Mints a token granting `privilege`, identified by `identifier` so the
+    /// verifier can find `root_key`.
+    pub fn mint(root_key: &[u8], identifier: &[u8], privilege: Component) -> Token {
+        let mut token = Token {
+            identifier: identifier.to_vec(),
+            caveats: Vec::new(),
+            signature: sign(root_key, identifier),
+        };
+        token.push(privilege);
+        token
+    }
+
+    fn push(&mut self, caveat: Component) {
+        let caveat = caveat_label(caveat).canonical().secrecy;
+        self.signature = sign(&self.signature, &caveat_bytes(&caveat));
+        self.caveats.push(caveat);
+    }
+
+    /// Appends `caveat`, narrowing the token's privilege to it. Fails unless
+    /// the current privilege implies `caveat`.
+    pub fn attenuate(&mut self, caveat: Component) -> Result<(), Error> {
+        match self.caveats.last() {
+            Some(current) if current.implies(&caveat) => {
+                self.push(caveat);
+                Ok(())
+            }
+            Some(_) => Err(Error::NotAttenuation),
+            None => Err(Error::NoPrivilege),
+        }
+    }
+
+    pub fn identifier(&self) -> &[u8] {
+        &self.identifier
+    }
+
+    pub fn caveats(&self) -> &[Component] {
+        &self.caveats
+    }
+
+    /// The token's privilege, assuming it verifies.
+    pub fn privilege(&self) -> Option<&Component> {
+        self.caveats.last()
+    }
+
+    /// Verifies the token against `root_key`, returning its privilege.
+    pub fn verify(&self, root_key: &[u8]) -> Result<&Component, Error> {
+        let mut signature = sign(root_key, &self.identifier);
+        let (last, init) = self.caveats.split_last().ok_or(Error::NoPrivilege)?;
+        for caveat in init {
+            signature = sign(&signature, &caveat_bytes(caveat));
+        }
+        // Compare the final MAC in constant time.
+        let mut mac = HmacSha256::new_from_slice(&signature).expect("HMAC key");
+        mac.update(&caveat_bytes(last));
+        mac.verify_slice(&self.signature).map_err(|_| Error::InvalidSignature)?;
+        if !self.caveats.windows(2).all(|w| w[0].implies(&w[1])) {
+            return Err(Error::NotAttenuation);
+        }
+        Ok(last)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_bytes(&mut out, &self.identifier);
+        write_uvarint(&mut out, self.caveats.len());
+        for caveat in self.caveats.iter() {
+            write_bytes(&mut out, &caveat_bytes(caveat));
+        }
+        out.extend_from_slice(&self.signature);
+        out
+    }
+
+    pub fn from_bytes(mut input: &[u8]) -> Result<Token, Error> {
+        let identifier = read_bytes(&mut input)?.to_vec();
+        let count = read_uvarint(&mut input)?;
+        let mut caveats = Vec::new();
+        for _ in 0..count {
+            caveats.push(read_caveat(read_bytes(&mut input)?)?);
+        }
+        let signature = input.try_into().map_err(|_| Error::Malformed)?;
+        Ok(Token { identifier, caveats, signature })
+    }
+}
+
+fn write_uvarint(out: &mut Vec<u8>, mut n: usize) {
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_uvarint(out, bytes.len());
+    out.extend_from_slice(bytes);
+}
+
+fn read_uvarint(input: &mut &[u8]) -> Result<usize, Error> {
+    let mut n = 0usize;
+    for shift in (0..usize::BITS).step_by(7) {
+        let (&b, rest) = input.split_first().ok_or(Error::Malformed)?;
+        *input = rest;
+        n |= ((b & 0x7f) as usize).checked_shl(shift).ok_or(Error::Malformed)?;
+        if b & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+    Err(Error::Malformed)
+}
+
+fn read_bytes<'a>(input: &mut &'a [u8]) -> Result<&'a [u8], Error> {
+    let len = read_uvarint(input)?;
+    if input.len() < len {
+        return Err(Error::Malformed);
+    }
+    let (bytes, rest) = input.split_at(len);
+    *input = rest;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &[u8] = b"root key";
+
+    fn privilege(s: &str) -> Component {
+        crate::buckle::component(s).unwrap().1
+    }
+
+    #[test]
+    fn test_attenuate() {
+        let mut token = Token::mint(KEY, b"id", privilege("amit"));
+        assert_eq!(Ok(&privilege("amit")), token.verify(KEY));
+
+        token.attenuate(privilege("amit/grades")).unwrap();
+        assert_eq!(Ok(&privilege("amit/grades")), token.verify(KEY));
+
+        // Widening back to `amit`, or to another principal, isn't allowed.
+        assert_eq!(Err(Error::NotAttenuation), token.attenuate(privilege("amit")));
+        assert_eq!(Err(Error::NotAttenuation), token.attenuate(privilege("yue")));
+        assert_eq!(2, token.caveats().len());
+    }
+
+    #[test]
+    fn test_rejects_tampering() {
+        let mut token = Token::mint(KEY, b"id", privilege("amit"));
+        token.attenuate(privilege("amit/grades")).unwrap();
+        assert_eq!(Err(Error::InvalidSignature), token.verify(b"other key"));
+
+        // Dropping the last caveat restores a wider privilege.
+        let mut dropped = token.clone();
+        dropped.caveats.pop();
+        assert_eq!(Err(Error::InvalidSignature), dropped.verify(KEY));
+
+        let mut replaced = token.clone();
+        replaced.caveats[1] = privilege("amit");
+        assert_eq!(Err(Error::InvalidSignature), replaced.verify(KEY));
+    }
+
+    #[test]
+    fn test_bytes() {
+        let mut token = Token::mint(KEY, b"id", privilege("amit&yue"));
+        token.attenuate(privilege("amit/grades")).unwrap();
+        let bytes = token.to_bytes();
+        assert_eq!(Ok(token), Token::from_bytes(&bytes));
+        assert_eq!(Err(Error::Malformed), Token::from_bytes(&bytes[..bytes.len() - 1]));
+    }
+
+    #[test]
+    fn test_bytes_keep_every_privilege() {
+        use crate::buckle::Clause;
+
+        // Text would print `{∅}` as `F` and drop the empty principal.
+        let empty = Component::from([Clause::empty()]);
+        let path = Component::from([Clause::new_from_vec(alloc::vec![alloc::vec!["a", ""]])]);
+        for privilege in [empty, path, Component::dc_false()] {
+            let token = Token::from_bytes(&Token::mint(KEY, b"id", privilege.clone()).to_bytes()).unwrap();
+            assert_eq!(Ok(&privilege), token.verify(KEY));
+        }
+    }
+
+    #[test]
+    fn test_bytes_rejects_noncanonical_caveats() {
+        use crate::buckle::Clause;
+
+        let token = Token::mint(KEY, b"id", privilege("amit"));
+        let with_caveat = |caveat: &[u8]| {
+            let mut out = Vec::new();
+            write_bytes(&mut out, token.identifier());
+            write_uvarint(&mut out, 1);
+            write_bytes(&mut out, caveat);
+            out.extend_from_slice(&token.signature);
+            out
+        };
+        assert_eq!(Ok(&token), Token::from_bytes(&with_caveat(&caveat_bytes(&privilege("amit")))).as_ref());
+        assert_eq!(Err(Error::Malformed), Token::from_bytes(&with_caveat(b"amit")));
+        // `a | a/b` canonicalizes to `a/b`.
+        let paths = alloc::vec![alloc::vec!["a"], alloc::vec!["a", "b"]];
+        let subsumed = Buckle::new(Component::from([Clause::new_from_vec(paths)]), true);
+        assert_eq!(Err(Error::Malformed), Token::from_bytes(&with_caveat(&envelope::encode(&subsumed))));
+        let integrity = Buckle::new(privilege("amit"), privilege("amit"));
+        assert_eq!(Err(Error::Malformed), Token::from_bytes(&with_caveat(&envelope::encode(&integrity))));
+    }
+}
@@ -0,0 +1,230 @@
+//! Tower middleware propagating labels across a service.
+//!
+//! `LabelLayer` wraps a service whose handlers run at a fixed, static label.
+//! For each request it reads the incoming label from the `Sec-Label` header
+//! (see `header`), treating a missing header as public and rejecting a
+//! malformed one with `400 Bad Request`, and stores it in the request's
+//! extensions as a `Buckle`. The response is stamped with the lub of the
+//! incoming label, the static label and any label the handler already set,
+//! downgraded by the layer's privilege, if it has one.
+
+use crate::buckle::{Buckle, Component};
+use crate::{header, HasPrivilege, Label};
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use http::{HeaderValue, Request, Response, StatusCode};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// A layer applying `LabelService` to a service.
+#[derive(Debug, Clone)]
+pub struct LabelLayer {
+    label: Buckle,
+    privilege: Option<Component>,
+}
+
+impl LabelLayer {
+    /// Creates a layer for handlers running at `label`.
+    pub fn new(label: Buckle) -> Self {
+        LabelLayer { label, privilege: None }
+    }
+
+    /// Declassifies response labels using `privilege`.
+    pub fn with_privilege(mut self, privilege: Component) -> Self {
+        self.privilege = Some(privilege);
+        self
+    }
+}
+
+impl<S> Layer<S> for LabelLayer {
+    type Service = LabelService<S>;
+
+    fn layer(&self, inner: S) -> LabelService<S> {
+        LabelService { inner, layer: self.clone() }
+    }
+}
+
+/// Propagates labels from requests to responses of the inner service.
+#[derive(Debug, Clone)]
+pub struct LabelService<S> {
+    inner: S,
+    layer: LabelLayer,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for LabelService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    ResBody: Default,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<ReqBody>) -> Self::Future {
+        let incoming = match request.headers().get(header::NAME) {
+            None => Buckle::public(),
+            Some(value) => match header::decode::<Buckle>(value.as_bytes()) {
+                Ok(label) => label,
+                Err(_) => return ResponseFuture { state: State::Rejected },
+            },
+        };
+        let label = incoming.clone().lub(self.layer.label.clone());
+        request.extensions_mut().insert(incoming);
+        ResponseFuture {
+            state: State::Called {
+                future: self.inner.call(request),
+                label: Some(label),
+                privilege: self.layer.privilege.clone(),
+            },
+        }
+    }
+}
+
+pin_project_lite::pin_project! {
+    /// The response future of `LabelService`.
+    pub struct ResponseFuture<F> {
+        #[pin]
+        state: State<F>,
+    }
+}
+
+pin_project_lite::pin_project! {
+    #[project = StateProj]
+    enum State<F> {
+        Rejected,
+        Called {
+            #[pin]
+            future: F,
+            label: Option<Buckle>,
+            privilege: Option<Component>,
+        },
+    }
+}
+
+impl<F, B, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<Response<B>, E>>,
+    B: Default,
+{
+    type Output = Result<Response<B>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project().state.project() {
+            StateProj::Rejected => {
+                let mut response = Response::new(B::default());
+                *response.status_mut() = StatusCode::BAD_REQUEST;
+                Poll::Ready(Ok(response))
+            }
+            StateProj::Called { future, label, privilege } => {
+                let mut response = core::task::ready!(future.poll(cx))?;
+                let mut label = label.take().expect("polled after completion");
+                if let Some(Ok(set)) = response.headers().get(header::NAME).map(|v| header::decode(v.as_bytes())) {
+                    label = label.lub(set);
+                }
+                if let Some(privilege) = privilege {
+                    label = label.downgrade(privilege);
+                }
+                match header::encode(&label) {
+                    Ok(value) => {
+                        let value = HeaderValue::from_str(&value).expect("base64url is a valid header value");
+                        response.headers_mut().insert(header::NAME, value);
+                    }
+                    Err(_) => {
+                        // Never release a response without its label.
+                        response = Response::new(B::default());
+                        *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+                    }
+                }
+                Poll::Ready(Ok(response))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::convert::Infallible;
+    use core::future::Ready;
+
+    /// Responds with the request's label extension in the body, and the given
+    /// response label, if any.
+    struct Echo(Option<Buckle>);
+
+    impl Service<Request<()>> for Echo {
+        type Response = Response<Option<Buckle>>;
+        type Error = Infallible;
+        type Future = Ready<Result<Self::Response, Infallible>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: Request<()>) -> Self::Future {
+            let mut response = Response::new(request.extensions().get::<Buckle>().cloned());
+            if let Some(label) = &self.0 {
+                let value = HeaderValue::from_str(&header::encode(label).unwrap()).unwrap();
+                response.headers_mut().insert(header::NAME, value);
+            }
+            core::future::ready(Ok(response))
+        }
+    }
+
+    fn call<S: Service<Request<()>, Response = Response<Option<Buckle>>, Error = Infallible>>(
+        service: &mut S,
+        label: Option<&str>,
+    ) -> Response<Option<Buckle>> {
+        let mut request = Request::builder();
+        if let Some(label) = label {
+            request = request.header(header::NAME, header::encode(&Buckle::parse(label).unwrap()).unwrap());
+        }
+        let future = core::pin::pin!(service.call(request.body(()).unwrap()));
+        match future.poll(&mut Context::from_waker(core::task::Waker::noop())) {
+            Poll::Ready(Ok(response)) => response,
+            _ => panic!("echo service is always ready"),
+        }
+    }
+
+    fn label<B>(response: &Response<B>) -> Buckle {
+        header::decode(response.headers()[header::NAME].as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_propagates_lub() {
+        let mut service = LabelLayer::new(Buckle::parse("yue,T").unwrap()).layer(Echo(None));
+        let response = call(&mut service, Some("amit,T"));
+        assert_eq!(Some(Buckle::parse("amit,T").unwrap()), *response.body());
+        assert_eq!(Buckle::parse("amit&yue,T").unwrap(), label(&response));
+
+        let response = call(&mut service, None);
+        assert_eq!(Some(Buckle::public()), *response.body());
+        assert_eq!(Buckle::parse("yue,T").unwrap(), label(&response));
+    }
+
+    #[test]
+    fn test_handler_label_and_privilege() {
+        let handler_label = Buckle::parse("grades,T").unwrap();
+        let mut service = LabelLayer::new(Buckle::public())
+            .with_privilege(Buckle::parse("amit,T").unwrap().secrecy)
+            .layer(Echo(Some(handler_label)));
+        let response = call(&mut service, Some("amit,T"));
+        assert_eq!(Buckle::parse("grades,amit").unwrap(), label(&response));
+    }
+
+    #[test]
+    fn test_rejects_malformed() {
+        let mut service = LabelLayer::new(Buckle::public()).layer(Echo(None));
+        let mut request = Request::new(());
+        request.headers_mut().insert(header::NAME, HeaderValue::from_static("not=base64"));
+        let future = core::pin::pin!(service.call(request));
+        match future.poll(&mut Context::from_waker(core::task::Waker::noop())) {
+            Poll::Ready(Ok(response)) => assert_eq!(StatusCode::BAD_REQUEST, response.status()),
+            _ => panic!("rejection is immediate"),
+        }
+    }
+}
@@ -0,0 +1,281 @@
+//! Migration of persisted labels to the current format.
+//!
+//! Long-lived stores hold labels written by older releases: `DCLabel`s, from
+//! before principals were hierarchical, as text or envelopes, and `Buckle`
+//! labels in any envelope version `decode` still reads. `read` accepts all of
+//! these and returns the equivalent `Buckle` label, a `DCLabel` principal
+//! becoming a path of length one. `migrate` rewrites a record as a `Buckle`
+//! label in the current `Target` format, and `migrate_batch` rewrites many,
+//! reporting failures per record rather than stopping at the first.
+//!
+//! A record is an envelope if it starts with a supported envelope version,
+//! and text otherwise; neither format's text can start with such a byte.
+//! `DCLabel` text is read in the syntax of releases before canonical text
+//! v1, which had no `T` and `F` constants: a component of just `T` or `F`
+//! named a principal there, but is a constant in `Buckle` text. `read`
+//! can't tell which a record is in, and rejects such records as ambiguous;
+//! `read_dclabel_text` reads them as `DCLabel` text. Labels are returned
+//! reduced.
+
+use crate::buckle::{self, Buckle};
+use crate::dclabel::{self, DCLabel};
+use crate::envelope::{self, LabelEnvelope, LabelKind};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The record is an envelope that can't be decoded.
+    Envelope(envelope::Error),
+    /// The record is text, but not a label in either textual syntax.
+    InvalidText,
+    /// The record is text in both syntaxes, with a component of just `T`
+    /// or `F`, which is a principal in `DCLabel` text and a constant in
+    /// `Buckle` text.
+    AmbiguousText,
+    /// A principal isn't valid UTF-8.
+    InvalidUtf8,
+    /// The envelope holds a kind of label this build can't convert.
+    UnsupportedKind(LabelKind),
+}
+
+impl From<envelope::Error> for Error {
+    fn from(e: envelope::Error) -> Self {
+        Error::Envelope(e)
+    }
+}
+
+/// The format records are rewritten to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    /// The label's textual syntax.
+    Text,
+    /// An envelope of the current `envelope::VERSION`.
+    Envelope,
+}
+
+fn component_from_dclabel(component: &dclabel::Component) -> buckle::Component {
+    match component {
+        dclabel::Component::DCFalse => buckle::Component::DCFalse,
+        dclabel::Component::DCFormula(clauses) => buckle::Component::from(
+            clauses
+                .iter()
+                .map(|c| buckle::Clause::new_from_vec(c.0.iter().map(|p| alloc::vec![p.clone()]).collect()))
+                .collect::<alloc::collections::BTreeSet<_>>(),
+        ),
+    }
+}
+
+/// Converts a `DCLabel` into the equivalent `Buckle` label.
+pub fn from_dclabel(label: &DCLabel) -> Buckle {
+    // Paths of one principal imply each other only when equal, so the
+    // clauses of a reduced `DCLabel` stay reduced.
+    Buckle { secrecy: component_from_dclabel(&label.secrecy), integrity: component_from_dclabel(&label.integrity) }
+}
+
+#[cfg(feature = "buckle2")]
+fn from_buckle2(label: &crate::buckle2::Buckle2) -> Result<Buckle, Error> {
+    use crate::buckle2::Component;
+    use alloc::collections::BTreeSet;
+
+    let component = |component: &Component| -> Result<buckle::Component, Error> {
+        let clauses = match component {
+            Component::DCFalse => return Ok(buckle::Component::DCFalse),
+            Component::DCFormula(clauses, _) => clauses,
+        };
+        let mut result = BTreeSet::new();
+        for clause in clauses.iter() {
+            let mut paths = BTreeSet::new();
+            for path in clause.0.iter() {
+                let path = path
+                    .iter()
+                    .map(|p| core::str::from_utf8(p).map(buckle::Principal::from).map_err(|_| Error::InvalidUtf8))
+                    .collect::<Result<Vec<_>, _>>()?;
+                paths.insert(path);
+            }
+            result.insert(buckle::Clause::from(paths));
+        }
+        Ok(buckle::Component::from(result))
+    };
+    Ok(Buckle::new(component(&label.secrecy)?, component(&label.integrity)?))
+}
+
+/// Parses a `DCLabel` in the textual syntax of releases before canonical
+/// text v1: `&`-separated clauses of `|`-separated alphanumeric principals,
+/// with `\` escaping `,`, `|`, `&` and itself, and no constants. Returns the
+/// principals of each component's clauses, or `None` unless all of `text`
+/// parses.
+fn parse_legacy_dclabel(text: &str) -> Option<[Vec<Vec<String>>; 2]> {
+    use nom::{
+        bytes::complete::{escaped_transform, tag},
+        character::complete::{alphanumeric1, one_of},
+        combinator::all_consuming,
+        multi::separated_list1,
+        sequence::separated_pair,
+        Parser,
+    };
+
+    fn component(input: &str) -> nom::IResult<&str, Vec<Vec<String>>> {
+        separated_list1(tag("&"), separated_list1(tag("|"), escaped_transform(alphanumeric1, '\\', one_of(r#",|&\"#))))
+            .parse(input)
+    }
+
+    let (_, (secrecy, integrity)) = all_consuming(separated_pair(component, tag(","), component)).parse(text).ok()?;
+    Some([secrecy, integrity])
+}
+
+/// Returns whether a legacy `DCLabel` component is just `T` or `F`.
+fn is_constant(component: &[Vec<String>]) -> bool {
+    matches!(component, [clause] if matches!(clause.as_slice(), [p] if p == "T" || p == "F"))
+}
+
+fn dclabel_from_legacy([secrecy, integrity]: [Vec<Vec<String>>; 2]) -> DCLabel {
+    let component = |clauses: Vec<Vec<String>>| {
+        dclabel::Component::from(
+            clauses
+                .into_iter()
+                .map(dclabel::Clause::new_from_vec)
+                .collect::<alloc::collections::BTreeSet<_>>(),
+        )
+    };
+    DCLabel::new(component(secrecy), component(integrity))
+}
+
+/// Reads a `DCLabel` in the textual syntax of releases before canonical
+/// text v1, where `T` and `F` are principals, and returns the equivalent
+/// `Buckle` label.
+pub fn read_dclabel_text(text: &str) -> Result<Buckle, Error> {
+    let components = parse_legacy_dclabel(text).ok_or(Error::InvalidText)?;
+    Ok(from_dclabel(&dclabel_from_legacy(components)))
+}
+
+/// Reads a record in any supported format.
+pub fn read(record: &[u8]) -> Result<Buckle, Error> {
+    match record.first() {
+        Some(version) if (1..=envelope::VERSION).contains(version) => {
+            match LabelEnvelope::parse(record)?.kind {
+                LabelKind::Buckle => Ok(envelope::decode(record)?),
+                LabelKind::DCLabel => Ok(from_dclabel(&envelope::decode(record)?)),
+                #[cfg(feature = "buckle2")]
+                LabelKind::Buckle2 => from_buckle2(&envelope::decode(record)?),
+                #[cfg(not(feature = "buckle2"))]
+                kind => Err(Error::UnsupportedKind(kind)),
+            }
+        }
+        _ => {
+            let text = core::str::from_utf8(record).map_err(|_| Error::InvalidUtf8)?;
+            // Legacy `DCLabel` text is also `Buckle` text with the same
+            // meaning, whose paths have length one, unless a component is
+            // just `T` or `F`.
+            match parse_legacy_dclabel(text) {
+                Some(components) if components.iter().any(|c| is_constant(c)) => Err(Error::AmbiguousText),
+                Some(components) => Ok(from_dclabel(&dclabel_from_legacy(components))),
+                None => Buckle::parse(text).map_err(|_| Error::InvalidText),
+            }
+        }
+    }
+}
+
+/// Rewrites a record as a `Buckle` label in `target` format.
+pub fn migrate(record: &[u8], target: Target) -> Result<Vec<u8>, Error> {
+    let label = read(record)?;
+    Ok(match target {
+        Target::Text => label.to_string().into_bytes(),
+        Target::Envelope => envelope::encode(&label),
+    })
+}
+
+/// The outcome of `migrate_batch`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report<K> {
+    /// The records that changed, with their new contents.
+    pub migrated: Vec<(K, Vec<u8>)>,
+    /// The number of records already in the target format.
+    pub unchanged: usize,
+    /// The records that couldn't be migrated.
+    pub failed: Vec<(K, Error)>,
+}
+
+/// Rewrites each `(key, record)` pair in `target` format.
+pub fn migrate_batch<K, R, I>(records: I, target: Target) -> Report<K>
+where
+    R: AsRef<[u8]>,
+    I: IntoIterator<Item = (K, R)>,
+{
+    let mut report = Report { migrated: Vec::new(), unchanged: 0, failed: Vec::new() };
+    for (key, record) in records {
+        match migrate(record.as_ref(), target) {
+            Ok(migrated) if migrated == record.as_ref() => report.unchanged += 1,
+            Ok(migrated) => report.migrated.push((key, migrated)),
+            Err(e) => report.failed.push((key, e)),
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read() {
+        let expected = Buckle::parse("amit|yue,T").unwrap();
+        assert_eq!(Ok(Buckle::parse("amit|yue,bob").unwrap()), read(b"amit|yue,bob"));
+        assert_eq!(Ok(Buckle::parse("a/b,T").unwrap()), read(b"a/b,T"));
+        let dclabel = DCLabel::parse("amit|yue,T").unwrap().1;
+        assert_eq!(Ok(expected.clone()), read(&envelope::encode(&dclabel)));
+        assert_eq!(Ok(expected.clone()), read(&envelope::encode(&expected)));
+        #[cfg(feature = "buckle2")]
+        assert_eq!(
+            Ok(Buckle::parse("a/b,T").unwrap()),
+            read(&envelope::encode(&crate::buckle2::Buckle2::parse("a/b,T").unwrap()))
+        );
+
+        assert_eq!(Err(Error::InvalidText), read(b"amit"));
+        assert_eq!(Err(Error::InvalidUtf8), read(&[0xff]));
+        assert_eq!(Err(Error::Envelope(envelope::Error::TrailingBytes)), read(&[1, 2, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_legacy_constants() {
+        // `F,T` was secrecy `F` and integrity `T` in legacy `DCLabel` text,
+        // but is the top label in `Buckle` text.
+        assert_eq!(Err(Error::AmbiguousText), read(b"F,T"));
+        assert_eq!(Err(Error::AmbiguousText), read(b"amit,T"));
+        let legacy = read_dclabel_text("F,T").unwrap();
+        assert!(!legacy.is_top());
+        assert_eq!(Buckle::new([["F"]], [["T"]]), legacy);
+        assert_eq!(b"\\F,\\T", migrate(&envelope::encode(&legacy), Target::Text).unwrap().as_slice());
+        assert_eq!(Ok(Buckle::new([["T", "a"]], [["b"], ["F"]])), read(b"T|a,b&F"));
+        assert_eq!(Err(Error::InvalidText), read_dclabel_text("a/b,T"));
+    }
+
+    #[test]
+    fn test_dclabel_principals() {
+        // Characters special to `Buckle` but not `DCLabel` stay in a single
+        // principal.
+        let dclabel = DCLabel::new([["a/b"]], true);
+        let migrated = migrate(&envelope::encode(&dclabel), Target::Text).unwrap();
+        assert_eq!(b"a\\/b,T", migrated.as_slice());
+        assert_eq!(Ok(Buckle::new([["a/b"]], true)), read(&migrated));
+    }
+
+    #[test]
+    fn test_migrate_batch() {
+        let current = envelope::encode(&Buckle::parse("amit,T").unwrap());
+        let records: [(u32, &[u8]); 5] =
+            [(1, b"amit,bob"), (2, &current), (3, b"amit"), (4, b"yue&amit/x,F"), (5, b"amit,T")];
+        let report = migrate_batch(records, Target::Envelope);
+        assert_eq!(
+            Report {
+                migrated: alloc::vec![
+                    (1, envelope::encode(&Buckle::parse("amit,bob").unwrap())),
+                    (4, envelope::encode(&Buckle::parse("amit/x&yue,F").unwrap()))
+                ],
+                unchanged: 1,
+                failed: alloc::vec![(3, Error::InvalidText), (5, Error::AmbiguousText)],
+            },
+            report
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! Exhaustive checks of the laws in `laws` over tiny universes.
+//!
+//! Quickcheck draws principals from arbitrary strings, so two random labels
+//! rarely share principals, and corner cases like `DCFalse` or the empty
+//! clause meeting other formulas are seldom hit. `model_check` instead runs
+//! the laws on every triple of labels, and every pair with every privilege,
+//! from a universe small enough to enumerate: the principals `a` and `b`,
+//! and for `Buckle` the paths `a` and `a/b`, of which the first implies the
+//! second.
+//!
+//! Universes include the formula of just the empty clause, which is false
+//! but not `DCFalse`. Otherwise formulas are reduced, as quickcheck covers
+//! reduction, and each further formula multiplies the triples to check.
+
+use crate::laws::{self, Law, Reduce};
+use crate::{HasPrivilege, Label};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// The most clauses in an enumerated formula.
+const MAX_CLAUSES: usize = 2;
+
+/// Returns every subset of `items` with at most `max` elements.
+fn subsets<T: Clone>(items: &[T], max: usize) -> Vec<Vec<T>> {
+    let mut subsets = alloc::vec![Vec::new()];
+    for item in items {
+        for i in 0..subsets.len() {
+            if subsets[i].len() < max {
+                let mut subset = subsets[i].clone();
+                subset.push(item.clone());
+                subsets.push(subset);
+            }
+        }
+    }
+    subsets
+}
+
+/// Returns the reduced formulas of up to `MAX_CLAUSES` nonempty clauses
+/// over `principals`, and the formula of just the empty clause.
+///
+/// A formula is reduced if none of its clauses is a subset of another, but
+/// `implies` may add implications between principals, e.g. between paths.
+fn formulas<P: Clone + PartialEq>(principals: &[P], implies: impl Fn(&[P], &[P]) -> bool) -> Vec<Vec<Vec<P>>> {
+    let clauses: Vec<Vec<P>> = subsets(principals, principals.len()).into_iter().filter(|c| !c.is_empty()).collect();
+    let mut formulas: Vec<Vec<Vec<P>>> = subsets(&clauses, MAX_CLAUSES)
+        .into_iter()
+        .filter(|formula| {
+            formula.iter().enumerate().all(|(i, c)| formula.iter().enumerate().all(|(j, d)| i == j || !implies(c, d)))
+        })
+        .collect();
+    formulas.push(alloc::vec![Vec::new()]);
+    formulas
+}
+
+/// Returns every label with both components in `components`.
+fn labels<C: Clone, L>(components: &[C], label: impl Fn(C, C) -> L) -> Vec<L> {
+    let mut labels = Vec::new();
+    for secrecy in components {
+        labels.extend(components.iter().map(|integrity| label(secrecy.clone(), integrity.clone())));
+    }
+    labels
+}
+
+/// Checks the laws on every triple of `labels` and every pair of them with
+/// every one of `privileges`, describing the first failure.
+pub(crate) fn model_check<L>(labels: &[L], privileges: &[L::Privilege]) -> Result<(), (Law, String)>
+where
+    L: HasPrivilege + Label + Reduce + Clone + PartialEq + core::fmt::Debug,
+    L::Privilege: core::fmt::Debug,
+{
+    // `check_lattice` on every triple would repeat the laws of fewer labels,
+    // so each law is checked on as many labels as it takes.
+    fn holds(law: Law, holds: bool, labels: impl FnOnce() -> String) -> Result<(), (Law, String)> {
+        if holds {
+            Ok(())
+        } else {
+            Err((law, labels()))
+        }
+    }
+
+    for a in labels {
+        let describe = || format!("{:?}", a);
+        holds(Law::CanFlowToReflexive, laws::can_flow_to_reflexive(a), describe)?;
+        holds(Law::LubIdempotent, laws::lub_idempotent(a), describe)?;
+        holds(Law::GlbIdempotent, laws::glb_idempotent(a), describe)?;
+        holds(Law::ReduceIdempotent, laws::reduce_idempotent(a), describe)?;
+        holds(Law::ReducePreserves, laws::reduce_preserves(a), describe)?;
+        for b in labels {
+            let describe = || format!("{:?}, {:?}", a, b);
+            holds(Law::LubCommutative, laws::lub_commutative(a, b), describe)?;
+            holds(Law::LubUpperBound, laws::lub_upper_bound(a, b), describe)?;
+            holds(Law::GlbCommutative, laws::glb_commutative(a, b), describe)?;
+            holds(Law::GlbLowerBound, laws::glb_lower_bound(a, b), describe)?;
+            for privilege in privileges {
+                laws::check_privilege(a, b, privilege).map_err(|law| (law, format!("{:?}, {:?}, {:?}", a, b, privilege)))?;
+            }
+            for c in labels {
+                let describe = || format!("{:?}, {:?}, {:?}", a, b, c);
+                holds(Law::CanFlowToTransitive, laws::can_flow_to_transitive(a, b, c), describe)?;
+                holds(Law::LubAssociative, laws::lub_associative(a, b, c), describe)?;
+                holds(Law::LubLeast, laws::lub_least(a, b, c), describe)?;
+                holds(Law::GlbAssociative, laws::glb_associative(a, b, c), describe)?;
+                holds(Law::GlbGreatest, laws::glb_greatest(a, b, c), describe)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "dclabel")]
+#[test]
+fn dclabel_model_check() {
+    use crate::dclabel::{Clause, Component, DCLabel};
+
+    let mut components = alloc::vec![Component::DCFalse];
+    components.extend(formulas(&["a", "b"], |c, d| c.iter().all(|p| d.contains(p))).into_iter().map(|formula| {
+        Component::from(formula.into_iter().map(Clause::new_from_vec).collect::<alloc::collections::BTreeSet<_>>())
+    }));
+    let labels = labels(&components, |secrecy, integrity| DCLabel { secrecy, integrity });
+    assert_eq!(Ok(()), model_check(&labels, &components));
+}
+
+#[cfg(feature = "buckle")]
+#[test]
+fn buckle_model_check() {
+    use crate::buckle::{Buckle, Clause, Component};
+
+    let paths: [Vec<&str>; 2] = [alloc::vec!["a"], alloc::vec!["a", "b"]];
+    let mut components = alloc::vec![Component::DCFalse];
+    components.extend(formulas(&paths, |c, d| c.iter().all(|p| d.iter().any(|q| q.starts_with(p)))).into_iter().map(|formula| {
+        Component::from(formula.into_iter().map(Clause::new_from_vec).collect::<alloc::collections::BTreeSet<_>>())
+    }));
+    let labels = labels(&components, |secrecy, integrity| Buckle { secrecy, integrity });
+    assert_eq!(Ok(()), model_check(&labels, &components));
+}
+
+#[cfg(feature = "buckle2")]
+#[test]
+fn buckle2_model_check() {
+    use crate::buckle2::{Buckle2, Clause, Component};
+
+    let paths: [Vec<&[u8]>; 2] = [alloc::vec![b"a"], alloc::vec![b"a", b"b"]];
+    let mut components = alloc::vec![Component::DCFalse];
+    components.extend(formulas(&paths, |c, d| c.iter().all(|p| d.iter().any(|q| q.starts_with(p)))).into_iter().map(|formula| {
+        Component::from(formula.into_iter().map(Clause::new_from_vec).collect::<alloc::collections::BTreeSet<_>>())
+    }));
+    let labels = labels(&components, |secrecy, integrity| {
+        let mut label = Buckle2::public();
+        label.secrecy = secrecy;
+        label.integrity = integrity;
+        label
+    });
+    assert_eq!(Ok(()), model_check(&labels, &components));
+}
@@ -0,0 +1,509 @@
+//! MessagePack encoding of labels.
+//!
+//! Labels use the same array-based layout as the `cbor` module, which is
+//! considered stable:
+//!
+//! ```text
+//! label     = [secrecy, integrity]
+//! component = false            ; DCFalse
+//!           / [* clause]       ; DCFormula, the empty array being DCTrue
+//! ```
+//!
+//! Clauses differ by label type:
+//!
+//! - `dclabel`: `clause = [* str]`, one string per principal.
+//! - `buckle`: `clause = [* [* str]]`, one array of strings per principal
+//!   path.
+//! - `buckle2`: `clause = [* [* bin]]`, one array of binary strings per
+//!   principal path.
+//!
+//! Encoding always uses the shortest header for each array, string and
+//! binary string; decoding accepts any header size, as other encoders may not
+//! minimize them. Decoded `DCLabel` and `Buckle` labels are reduced, so a
+//! label that wasn't round-trips to its reduced form; `Buckle2` components
+//! are decoded as written.
+//!
+//! # Version header
+//!
+//! `to_vec_versioned` prefixes the label with a MessagePack extension
+//! recording the layout's `VERSION`:
+//!
+//! ```text
+//! header = fixext1 %x4c version          ; d4 4c 01
+//! ```
+//!
+//! `from_slice` accepts labels with or without the header. A header of any
+//! other version fails with `Error::UnsupportedVersion`, so a reader can tell
+//! labels written by a newer release from corrupt input.
+
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The input ends in the middle of a label.
+    Truncated,
+    /// An item has the wrong type, given by its marker byte.
+    UnexpectedMarker(u8),
+    /// A label array doesn't have exactly two elements.
+    InvalidLength,
+    /// A principal isn't valid UTF-8.
+    InvalidUtf8,
+    /// The input continues after the label.
+    TrailingBytes,
+    /// The version header gives a layout version other than `VERSION`.
+    UnsupportedVersion(u8),
+}
+
+/// The layout version written by `to_vec_versioned`.
+pub const VERSION: u8 = 1;
+
+/// The marker and extension type of the version header.
+const HEADER: [u8; 2] = [0xd4, 0x4c];
+
+/// A label with a MessagePack encoding.
+pub trait MsgPack: Sized {
+    /// Writes the label's items.
+    fn write(&self, w: &mut Writer);
+
+    /// Reads the label's items.
+    fn read(r: &mut Reader) -> Result<Self, Error>;
+}
+
+/// Encodes `label` as MessagePack.
+///
+/// # Panics
+///
+/// Panics if an array or string has more than `u32::MAX` elements or bytes,
+/// which MessagePack can't represent.
+pub fn to_vec<L: MsgPack>(label: &L) -> Vec<u8> {
+    let mut w = Writer(Vec::new());
+    label.write(&mut w);
+    w.0
+}
+
+/// Encodes `label` as MessagePack, preceded by the version header.
+///
+/// # Panics
+///
+/// Panics as `to_vec` does.
+pub fn to_vec_versioned<L: MsgPack>(label: &L) -> Vec<u8> {
+    let mut w = Writer(alloc::vec![HEADER[0], HEADER[1], VERSION]);
+    label.write(&mut w);
+    w.0
+}
+
+/// Decodes a label encoded as MessagePack, with or without the version
+/// header.
+pub fn from_slice<L: MsgPack>(bytes: &[u8]) -> Result<L, Error> {
+    let mut r = Reader(bytes);
+    if let Some(rest) = bytes.strip_prefix(&HEADER[..]) {
+        r.0 = rest;
+        match r.byte()? {
+            VERSION => {}
+            version => return Err(Error::UnsupportedVersion(version)),
+        }
+    }
+    let label = L::read(&mut r)?;
+    if !r.0.is_empty() {
+        return Err(Error::TrailingBytes);
+    }
+    Ok(label)
+}
+
+const FALSE: u8 = 0xc2;
+
+pub struct Writer(Vec<u8>);
+
+impl Writer {
+    /// Writes a header with the length inline in the `fix` marker if it fits
+    /// in the marker's given number of low bits, or else one of the 8-, 16- or
+    /// 32-bit `markers` followed by the length.
+    fn header(&mut self, len: usize, fix: Option<(u8, u32)>, markers: [Option<u8>; 3]) {
+        match (fix, markers) {
+            (Some((fix, bits)), _) if len < 1 << bits => self.0.push(fix | len as u8),
+            (_, [Some(marker), _, _]) if len <= u8::MAX as usize => self.0.extend_from_slice(&[marker, len as u8]),
+            (_, [_, Some(marker), _]) if len <= u16::MAX as usize => {
+                self.0.push(marker);
+                self.0.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+            (_, [_, _, Some(marker)]) if len <= u32::MAX as usize => {
+                self.0.push(marker);
+                self.0.extend_from_slice(&(len as u32).to_be_bytes());
+            }
+            _ => panic!("length {} is too large for MessagePack", len),
+        }
+    }
+
+    fn array(&mut self, len: usize) {
+        self.header(len, Some((0x90, 4)), [None, Some(0xdc), Some(0xdd)]);
+    }
+
+    #[cfg(any(feature = "dclabel", feature = "buckle"))]
+    fn str(&mut self, s: &str) {
+        self.header(s.len(), Some((0xa0, 5)), [Some(0xd9), Some(0xda), Some(0xdb)]);
+        self.0.extend_from_slice(s.as_bytes());
+    }
+
+    #[cfg(feature = "buckle2")]
+    fn bin(&mut self, bytes: &[u8]) {
+        self.header(bytes.len(), None, [Some(0xc4), Some(0xc5), Some(0xc6)]);
+        self.0.extend_from_slice(bytes);
+    }
+
+    /// Writes a component given its clauses, or `None` for `DCFalse`.
+    fn component<'c, C: 'c, I, F>(&mut self, clauses: Option<I>, mut clause: F)
+    where
+        I: ExactSizeIterator<Item = &'c C>,
+        F: FnMut(&mut Self, &'c C),
+    {
+        match clauses {
+            None => self.0.push(FALSE),
+            Some(clauses) => {
+                self.array(clauses.len());
+                clauses.for_each(|c| clause(self, c));
+            }
+        }
+    }
+}
+
+pub struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if n > self.0.len() {
+            return Err(Error::Truncated);
+        }
+        let (bytes, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn peek(&self) -> Result<u8, Error> {
+        self.0.first().copied().ok_or(Error::Truncated)
+    }
+
+    fn be(&mut self, n: usize) -> Result<usize, Error> {
+        Ok(self.take(n)?.iter().fold(0, |len, b| len << 8 | *b as usize))
+    }
+
+    /// Reads an array header, rejecting lengths that can't possibly fit in
+    /// the remaining input so that a corrupt header can't cause a large
+    /// allocation.
+    fn array(&mut self) -> Result<usize, Error> {
+        let len = match self.byte()? {
+            b @ 0x90..=0x9f => (b & 0x0f) as usize,
+            0xdc => self.be(2)?,
+            0xdd => self.be(4)?,
+            b => return Err(Error::UnexpectedMarker(b)),
+        };
+        // Every element takes at least one byte.
+        if len > self.0.len() {
+            return Err(Error::Truncated);
+        }
+        Ok(len)
+    }
+
+    #[cfg(any(feature = "dclabel", feature = "buckle"))]
+    fn string<S: From<&'a str>>(&mut self) -> Result<S, Error> {
+        let len = match self.byte()? {
+            b @ 0xa0..=0xbf => (b & 0x1f) as usize,
+            0xd9 => self.be(1)?,
+            0xda => self.be(2)?,
+            0xdb => self.be(4)?,
+            b => return Err(Error::UnexpectedMarker(b)),
+        };
+        core::str::from_utf8(self.take(len)?).map(Into::into).map_err(|_| Error::InvalidUtf8)
+    }
+
+    #[cfg(feature = "buckle2")]
+    fn bin(&mut self) -> Result<&'a [u8], Error> {
+        let len = match self.byte()? {
+            0xc4 => self.be(1)?,
+            0xc5 => self.be(2)?,
+            0xc6 => self.be(4)?,
+            b => return Err(Error::UnexpectedMarker(b)),
+        };
+        self.take(len)
+    }
+
+    /// Reads a component, calling `clause` once per clause. Returns `false`
+    /// for `DCFalse`.
+    fn component<F>(&mut self, mut clause: F) -> Result<bool, Error>
+    where
+        F: FnMut(&mut Self) -> Result<(), Error>,
+    {
+        if self.peek()? == FALSE {
+            self.byte()?;
+            return Ok(false);
+        }
+        for _ in 0..self.array()? {
+            clause(self)?;
+        }
+        Ok(true)
+    }
+
+    fn label_array(&mut self) -> Result<(), Error> {
+        match self.array()? {
+            2 => Ok(()),
+            _ => Err(Error::InvalidLength),
+        }
+    }
+}
+
+#[cfg(feature = "dclabel")]
+mod dclabel {
+    use super::*;
+    use crate::dclabel::{Clause, Component, DCLabel};
+    use alloc::collections::BTreeSet;
+
+    fn write_component(w: &mut Writer, c: &Component) {
+        let clauses = match c {
+            Component::DCFalse => None,
+            Component::DCFormula(clauses) => Some(clauses.iter()),
+        };
+        w.component(clauses, |w, clause: &Clause| {
+            w.array(clause.0.len());
+            clause.0.iter().for_each(|p| w.str(p));
+        });
+    }
+
+    fn read_component(r: &mut Reader) -> Result<Component, Error> {
+        let mut clauses = BTreeSet::new();
+        let formula = r.component(|r| {
+            let mut clause = BTreeSet::new();
+            for _ in 0..r.array()? {
+                clause.insert(r.string()?);
+            }
+            clauses.insert(Clause::from(clause));
+            Ok(())
+        })?;
+        Ok(if formula { Component::from(clauses) } else { Component::DCFalse })
+    }
+
+    impl MsgPack for DCLabel {
+        fn write(&self, w: &mut Writer) {
+            w.array(2);
+            write_component(w, &self.secrecy);
+            write_component(w, &self.integrity);
+        }
+
+        fn read(r: &mut Reader) -> Result<Self, Error> {
+            r.label_array()?;
+            Ok(DCLabel::new(read_component(r)?, read_component(r)?))
+        }
+    }
+}
+
+#[cfg(feature = "buckle")]
+mod buckle {
+    use super::*;
+    use crate::buckle::{Buckle, Clause, Component};
+    use alloc::collections::BTreeSet;
+
+    fn write_component(w: &mut Writer, c: &Component) {
+        let clauses = match c {
+            Component::DCFalse => None,
+            Component::DCFormula(clauses) => Some(clauses.iter()),
+        };
+        w.component(clauses, |w, clause: &Clause| {
+            w.array(clause.0.len());
+            for path in clause.0.iter() {
+                w.array(path.len());
+                path.iter().for_each(|p| w.str(p));
+            }
+        });
+    }
+
+    fn read_component(r: &mut Reader) -> Result<Component, Error> {
+        let mut clauses = BTreeSet::new();
+        let formula = r.component(|r| {
+            let mut clause = BTreeSet::new();
+            for _ in 0..r.array()? {
+                let mut path = Vec::new();
+                for _ in 0..r.array()? {
+                    path.push(r.string()?);
+                }
+                clause.insert(path);
+            }
+            clauses.insert(Clause::from(clause));
+            Ok(())
+        })?;
+        Ok(if formula { Component::from(clauses) } else { Component::DCFalse })
+    }
+
+    impl MsgPack for Buckle {
+        fn write(&self, w: &mut Writer) {
+            w.array(2);
+            write_component(w, &self.secrecy);
+            write_component(w, &self.integrity);
+        }
+
+        fn read(r: &mut Reader) -> Result<Self, Error> {
+            r.label_array()?;
+            Ok(Buckle::new(read_component(r)?, read_component(r)?))
+        }
+    }
+}
+
+#[cfg(feature = "buckle2")]
+mod buckle2 {
+    use super::*;
+    use crate::buckle2::clause::principal_in;
+    use allocator_api2::vec::Vec;
+    use crate::buckle2::{Buckle2, Clause, Component};
+    use crate::buckle2::FlatSet;
+    use allocator_api2::alloc::Allocator;
+
+    fn write_component<A: Allocator + Clone>(w: &mut Writer, c: &Component<A>) {
+        let clauses = match c {
+            Component::DCFalse => None,
+            Component::DCFormula(clauses, _) => Some(clauses.iter()),
+        };
+        w.component(clauses, |w, clause: &Clause<A>| {
+            w.array(clause.0.len());
+            for path in clause.0.iter() {
+                w.array(path.len());
+                path.iter().for_each(|p| w.bin(p));
+            }
+        });
+    }
+
+    fn read_component<A: Allocator + Clone>(r: &mut Reader, alloc: &A) -> Result<Component<A>, Error> {
+        let mut clauses = FlatSet::new_in(alloc.clone());
+        let formula = r.component(|r| {
+            let mut clause = FlatSet::new_in(alloc.clone());
+            for _ in 0..r.array()? {
+                let mut path = Vec::new_in(alloc.clone());
+                for _ in 0..r.array()? {
+                    path.push(principal_in(r.bin()?, alloc.clone()));
+                }
+                clause.insert(path);
+            }
+            clauses.insert(Clause(clause));
+            Ok(())
+        })?;
+        Ok(if formula { Component::DCFormula(clauses, alloc.clone()) } else { Component::DCFalse })
+    }
+
+    /// Labels are decoded into `A::default()`.
+    impl<A: Allocator + Clone + Default> MsgPack for Buckle2<A> {
+        fn write(&self, w: &mut Writer) {
+            w.array(2);
+            write_component(w, &self.secrecy);
+            write_component(w, &self.integrity);
+        }
+
+        fn read(r: &mut Reader) -> Result<Self, Error> {
+            r.label_array()?;
+            let alloc = A::default();
+            let mut result = Buckle2::public_in(alloc.clone());
+            result.secrecy = read_component(r, &alloc)?;
+            result.integrity = read_component(r, &alloc)?;
+            Ok(result)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "dclabel")]
+    quickcheck! {
+        fn dclabel_round_trip(lbl: crate::dclabel::DCLabel) -> bool {
+            from_slice::<crate::dclabel::DCLabel>(&to_vec(&lbl)) == Ok(lbl)
+        }
+    }
+
+    #[cfg(feature = "buckle")]
+    quickcheck! {
+        fn buckle_round_trip(lbl: crate::buckle::Buckle) -> bool {
+            from_slice::<crate::buckle::Buckle>(&to_vec(&lbl)) == Ok(lbl)
+        }
+    }
+
+    #[cfg(feature = "buckle")]
+    #[test]
+    fn test_decode_reduces() {
+        use crate::buckle::{Buckle, Clause, Component};
+
+        let unreduced = Buckle {
+            secrecy: Component::from([Clause::from(["amit"]), Clause::from(["amit", "yue"])]),
+            integrity: Component::dc_true(),
+        };
+        assert!(!unreduced.is_reduced());
+        assert_eq!(Ok(Buckle::parse("amit,T").unwrap()), from_slice::<Buckle>(&to_vec(&unreduced)));
+    }
+
+    #[cfg(feature = "buckle2")]
+    quickcheck! {
+        fn buckle2_round_trip(lbl: crate::buckle2::Buckle2) -> bool {
+            from_slice::<crate::buckle2::Buckle2>(&to_vec(&lbl)) == Ok(lbl)
+        }
+    }
+
+    #[cfg(feature = "buckle2")]
+    quickcheck! {
+        fn buckle2_versioned_round_trip(lbl: crate::buckle2::Buckle2) -> bool {
+            from_slice::<crate::buckle2::Buckle2>(&to_vec_versioned(&lbl)) == Ok(lbl)
+        }
+    }
+
+    #[cfg(feature = "buckle2")]
+    #[test]
+    fn test_version_header() {
+        use crate::buckle2::Buckle2;
+
+        let mut bytes = to_vec_versioned(&Buckle2::top());
+        assert_eq!(alloc::vec![0xd4, 0x4c, 0x01, 0x92, 0xc2, 0x90], bytes);
+        bytes[2] = 2;
+        assert_eq!(Err(Error::UnsupportedVersion(2)), from_slice::<Buckle2>(&bytes));
+        assert_eq!(Err(Error::Truncated), from_slice::<Buckle2>(&bytes[..2]));
+    }
+
+    #[cfg(feature = "buckle2")]
+    #[test]
+    fn test_buckle2_layout() {
+        use crate::buckle2::Buckle2;
+
+        // [false, []]
+        assert_eq!(alloc::vec![0x92, 0xc2, 0x90], to_vec(&Buckle2::top()));
+        // [[[[bin "A"]]], [[[bin "B", bin "C"]]]]
+        let bytes =
+            alloc::vec![0x92, 0x91, 0x91, 0x91, 0xc4, 1, b'A', 0x91, 0x91, 0x92, 0xc4, 1, b'B', 0xc4, 1, b'C'];
+        assert_eq!(bytes, to_vec(&Buckle2::parse("A,B/C").unwrap()));
+
+        // Non-minimal headers are accepted.
+        let long = [0x92, 0xc2, 0xdc, 0, 1, 0xdd, 0, 0, 0, 1, 0xdd, 0, 0, 0, 1, 0xc6, 0, 0, 0, 1, b'A'];
+        assert_eq!(Ok(Buckle2::parse("F,A").unwrap()), from_slice(&long));
+
+        assert_eq!(Err(Error::UnexpectedMarker(0xc3)), from_slice::<Buckle2>(&[0x92, 0xc3, 0x90]));
+        assert_eq!(Err(Error::InvalidLength), from_slice::<Buckle2>(&[0x91, 0x90]));
+        assert_eq!(Err(Error::Truncated), from_slice::<Buckle2>(&[0x92, 0xdd, 0xff, 0xff, 0xff, 0xff]));
+        assert_eq!(Err(Error::TrailingBytes), from_slice::<Buckle2>(&[0x92, 0x90, 0x90, 0]));
+    }
+
+    #[cfg(feature = "buckle")]
+    #[test]
+    fn test_buckle_layout() {
+        use crate::buckle::Buckle;
+
+        // [[["amit"], ["yue"]], [["a", "b"]]] with str8 for a long principal.
+        let lbl = Buckle::parse("amit|yue,a/b").unwrap();
+        let bytes = to_vec(&lbl);
+        assert_eq!(
+            alloc::vec![
+                0x92, 0x91, 0x92, 0x91, 0xa4, b'a', b'm', b'i', b't', 0x91, 0xa3, b'y', b'u', b'e', 0x91, 0x91, 0x92,
+                0xa1, b'a', 0xa1, b'b'
+            ],
+            bytes
+        );
+        let long = Buckle::new([[alloc::string::String::from_utf8(alloc::vec![b'x'; 32]).unwrap()]], true);
+        assert_eq!(&[0x92, 0x91, 0x91, 0x91, 0xd9, 32], &to_vec(&long)[..6]);
+        assert_eq!(Err(Error::InvalidUtf8), from_slice::<Buckle>(&[0x92, 0x91, 0x91, 0x91, 0xa1, 0xff, 0x90]));
+    }
+}
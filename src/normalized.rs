@@ -0,0 +1,167 @@
+//! Labels kept in canonical form.
+//!
+//! Label operations leave their results reduced, but labels built from
+//! public fields, decoded envelopes or deserialized documents need not be,
+//! and two unreduced labels can be equivalent yet compare unequal. Code that
+//! keys maps on labels would have to remember to call `reduce()` on every
+//! one. A `Normalized<L>` instead holds its label in canonical form (see the
+//! `envelope` module) from construction on, and only hands it out by
+//! reference, so equal labels compare, hash, order and serialize equally.
+
+use crate::envelope::{self, Enveloped};
+use crate::{HasPrivilege, Label};
+
+/// A label in canonical form.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Normalized<L>(L);
+
+impl<L: Enveloped> Normalized<L> {
+    /// Returns the canonical form of `label`.
+    pub fn new(label: &L) -> Self {
+        Normalized(label.canonical())
+    }
+
+    pub fn into_inner(self) -> L {
+        self.0
+    }
+
+    /// Returns the label's canonical bytes, which are its envelope.
+    pub fn canonical_bytes(&self) -> alloc::vec::Vec<u8> {
+        envelope::encode(&self.0)
+    }
+}
+
+impl<L: Enveloped> From<L> for Normalized<L> {
+    fn from(label: L) -> Self {
+        Normalized::new(&label)
+    }
+}
+
+impl<L> core::ops::Deref for Normalized<L> {
+    type Target = L;
+
+    fn deref(&self) -> &L {
+        &self.0
+    }
+}
+
+impl<L> AsRef<L> for Normalized<L> {
+    fn as_ref(&self) -> &L {
+        &self.0
+    }
+}
+
+impl<L: core::fmt::Display> core::fmt::Display for Normalized<L> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Hashes the canonical bytes, which equal labels share.
+impl<L: Enveloped> core::hash::Hash for Normalized<L> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.canonical_bytes().hash(state)
+    }
+}
+
+/// Orders labels by their canonical bytes, e.g. to key a `BTreeMap`. The
+/// order is arbitrary, and unrelated to `can_flow_to`.
+impl<L: Enveloped + Eq> PartialOrd for Normalized<L> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<L: Enveloped + Eq> Ord for Normalized<L> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.canonical_bytes().cmp(&other.canonical_bytes())
+    }
+}
+
+#[cfg(any(feature = "dclabel", feature = "buckle"))]
+impl<L: serde::Serialize> serde::Serialize for Normalized<L> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+/// Canonicalizes the deserialized label.
+#[cfg(any(feature = "dclabel", feature = "buckle"))]
+impl<'de, L: serde::Deserialize<'de> + Enveloped> serde::Deserialize<'de> for Normalized<L> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        L::deserialize(deserializer).map(Normalized::from)
+    }
+}
+
+impl<L: Label + Enveloped> Label for Normalized<L> {
+    fn lub(self, rhs: Self) -> Self {
+        Normalized::from(self.0.lub(rhs.0))
+    }
+
+    fn glb(self, rhs: Self) -> Self {
+        Normalized::from(self.0.glb(rhs.0))
+    }
+
+    fn can_flow_to(&self, rhs: &Self) -> bool {
+        self.0.can_flow_to(&rhs.0)
+    }
+}
+
+impl<L: HasPrivilege + Enveloped> HasPrivilege for Normalized<L> {
+    type Privilege = L::Privilege;
+
+    fn downgrade(self, privilege: &L::Privilege) -> Self {
+        Normalized::from(self.0.downgrade(privilege))
+    }
+
+    fn downgrade_to(self, target: Self, privilege: &L::Privilege) -> Self {
+        if self.can_flow_to_with_privilege(&target, privilege) {
+            target
+        } else {
+            self
+        }
+    }
+
+    fn can_flow_to_with_privilege(&self, rhs: &Self, privilege: &L::Privilege) -> bool {
+        self.0.can_flow_to_with_privilege(&rhs.0, privilege)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(any(feature = "buckle", feature = "buckle2"))]
+    use super::*;
+
+    #[cfg(feature = "buckle2")]
+    #[test]
+    fn test_normalized() {
+        use crate::buckle2::Buckle2;
+        use std::collections::HashSet;
+
+        let a = Normalized::from(Buckle2::parse("amit/grades&amit,T").unwrap());
+        let b = Normalized::from(Buckle2::parse("amit,T").unwrap());
+        assert_eq!(a, b);
+        assert_eq!(Buckle2::parse("amit,T").unwrap(), *a);
+        assert_eq!(1, alloc::vec![a.clone(), b].into_iter().collect::<HashSet<_>>().len());
+
+        let grades = Normalized::from(Buckle2::parse("amit/grades,T").unwrap());
+        assert!(grades.can_flow_to(&a));
+        assert_eq!(a, a.clone().lub(grades));
+    }
+
+    #[cfg(feature = "buckle")]
+    quickcheck! {
+        fn agrees_with_canonical(lbl1: crate::buckle::Buckle, lbl2: crate::buckle::Buckle) -> bool {
+            let (n1, n2) = (Normalized::new(&lbl1), Normalized::new(&lbl2));
+            let (c1, c2) = (lbl1.canonical(), lbl2.canonical());
+            *n1.clone().lub(n2.clone()) == c1.clone().lub(c2.clone()).canonical()
+                && n1.can_flow_to(&n2) == c1.can_flow_to(&c2)
+                && (n1 == n2) == (n1.canonical_bytes() == n2.canonical_bytes())
+        }
+
+        fn deserializes_canonical(lbl: crate::buckle::Buckle) -> bool {
+            let bytes = postcard::to_allocvec(&lbl).unwrap();
+            postcard::from_bytes::<Normalized<crate::buckle::Buckle>>(&bytes) == Ok(Normalized::new(&lbl))
+        }
+    }
+}
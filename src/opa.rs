@@ -0,0 +1,208 @@
+//! Export of labels and flow checks to Open Policy Agent.
+//!
+//! `flow_input` builds the input document for a flow check, which the Rego
+//! policy in `POLICY` (package `labeled`) decides at `ALLOW_QUERY`. Each label
+//! is exported as an object holding its text form and its components, which
+//! are `false`, or arrays of clauses, each an array of principal paths. A
+//! `DCLabel` principal is a path of length one, so the same policy handles
+//! both label types.
+//!
+//! `explain_flow` exports a trace of the decision, giving for each clause that
+//! must be implied the clause implying it, if any, so policies can report why
+//! a flow was denied.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use serde_json::{json, Value};
+
+/// The Rego policy deciding flow checks.
+pub const POLICY: &str = include_str!("../policy/labeled.rego");
+
+/// The query deciding a flow check, for use with `opa eval` or an SDK.
+pub const ALLOW_QUERY: &str = "data.labeled.allow";
+
+/// The path deciding a flow check through OPA's REST data API.
+pub const ALLOW_PATH: &str = "/v1/data/labeled/allow";
+
+/// A label that can be exported to OPA.
+pub trait OpaLabel: ToString {
+    type Privilege;
+
+    /// Exports the label as an object with `label`, `secrecy` and
+    /// `integrity` keys.
+    fn to_opa(&self) -> Value;
+
+    fn privilege_to_opa(privilege: &Self::Privilege) -> Value;
+
+    /// Explains whether the label can flow to `target`, exercising
+    /// `privilege` if given.
+    fn explain_flow(&self, target: &Self, privilege: Option<&Self::Privilege>) -> Value;
+}
+
+/// Builds the input document deciding whether `source` can flow to `target`,
+/// exercising `privilege` if given. The result is the value of `input`, as
+/// passed to `opa eval --input`.
+pub fn flow_input<L: OpaLabel>(source: &L, target: &L, privilege: Option<&L::Privilege>) -> Value {
+    let mut input = json!({ "source": source.to_opa(), "target": target.to_opa() });
+    if let Some(privilege) = privilege {
+        input["privilege"] = L::privilege_to_opa(privilege);
+    }
+    input
+}
+
+/// Wraps `input` in the request body expected by the REST data API.
+pub fn request_body(input: Value) -> Value {
+    json!({ "input": input })
+}
+
+/// Returns the query deciding `rule` of the policy, e.g. for rules added to
+/// the `labeled` package by an organization's own policies.
+pub fn query(rule: &str) -> String {
+    alloc::format!("data.labeled.{}", rule)
+}
+
+fn path(principals: impl Iterator<Item = impl ToString>) -> Value {
+    Value::Array(principals.map(|p| Value::String(p.to_string())).collect())
+}
+
+/// Implements `OpaLabel` for `$label`, given a function `clause` exporting a
+/// clause as an array of paths.
+macro_rules! impl_opa {
+    ($label:ident, $component:ident) => {
+        fn component(component: &$component) -> Value {
+            match component {
+                $component::DCFalse => Value::Bool(false),
+                $component::DCFormula(clauses) => Value::Array(clauses.iter().map(clause).collect()),
+            }
+        }
+
+        /// Explains whether `premise` implies `conclusion`.
+        fn explain_implies(premise: &$component, conclusion: &$component) -> Value {
+            let (holds, clauses) = match (premise, conclusion) {
+                ($component::DCFalse, _) => (true, Vec::new()),
+                (_, $component::DCFalse) => (false, Vec::new()),
+                ($component::DCFormula(premise), $component::DCFormula(conclusion)) => {
+                    let clauses: Vec<Value> = conclusion
+                        .iter()
+                        .map(|c| {
+                            let witness = premise.iter().find(|p| p.implies(c));
+                            json!({ "clause": clause(c), "witness": witness.map(clause) })
+                        })
+                        .collect();
+                    (clauses.iter().all(|c| !c["witness"].is_null()), clauses)
+                }
+            };
+            json!({ "holds": holds, "clauses": clauses })
+        }
+
+        impl OpaLabel for $label {
+            type Privilege = $component;
+
+            fn to_opa(&self) -> Value {
+                json!({
+                    "label": self.to_string(),
+                    "secrecy": component(&self.secrecy),
+                    "integrity": component(&self.integrity),
+                })
+            }
+
+            fn privilege_to_opa(privilege: &$component) -> Value {
+                component(privilege)
+            }
+
+            fn explain_flow(&self, target: &Self, privilege: Option<&$component>) -> Value {
+                let privilege = privilege.cloned().unwrap_or_else($component::dc_true);
+                let secrecy = explain_implies(&(target.secrecy.clone() & privilege.clone()), &self.secrecy);
+                let integrity = explain_implies(&(self.integrity.clone() & privilege), &target.integrity);
+                json!({
+                    "allow": secrecy["holds"] == true && integrity["holds"] == true,
+                    "secrecy": secrecy,
+                    "integrity": integrity,
+                })
+            }
+        }
+    };
+}
+
+#[cfg(feature = "dclabel")]
+mod dclabel {
+    use super::*;
+    use crate::dclabel::{Clause, Component, DCLabel};
+
+    fn clause(clause: &Clause) -> Value {
+        Value::Array(clause.0.iter().map(|p| path(core::iter::once(p))).collect())
+    }
+
+    impl_opa!(DCLabel, Component);
+}
+
+#[cfg(feature = "buckle")]
+mod buckle {
+    use super::*;
+    use crate::buckle::{Buckle, Clause, Component};
+
+    fn clause(clause: &Clause) -> Value {
+        Value::Array(clause.0.iter().map(|p| path(p.iter())).collect())
+    }
+
+    impl_opa!(Buckle, Component);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "dclabel")]
+    #[test]
+    fn test_dclabel_input() {
+        use crate::dclabel::DCLabel;
+
+        let source = DCLabel::parse("amit|yue,T").unwrap().1;
+        let target = DCLabel::parse("amit,F").unwrap().1;
+        assert_eq!(
+            json!({
+                "source": { "label": "amit|yue,T", "secrecy": [[["amit"], ["yue"]]], "integrity": [] },
+                "target": { "label": "amit,F", "secrecy": [[["amit"]]], "integrity": false },
+            }),
+            flow_input(&source, &target, None)
+        );
+    }
+
+    #[cfg(feature = "buckle")]
+    #[test]
+    fn test_buckle_explain() {
+        use crate::buckle::Buckle;
+        use crate::{HasPrivilege, Label};
+
+        let source = Buckle::parse("amit&yue,T").unwrap();
+        let target = Buckle::parse("amit/grades,T").unwrap();
+        let privilege = Buckle::parse("yue,T").unwrap().secrecy;
+
+        let explanation = source.explain_flow(&target, None);
+        assert_eq!(Value::Bool(source.can_flow_to(&target)), explanation["allow"]);
+        assert_eq!(
+            json!({
+                "holds": false,
+                "clauses": [
+                    { "clause": [["amit"]], "witness": null },
+                    { "clause": [["yue"]], "witness": null },
+                ],
+            }),
+            explanation["secrecy"]
+        );
+
+        let reverse = target.explain_flow(&source, Some(&privilege));
+        assert_eq!(Value::Bool(target.can_flow_to_with_privilege(&source, &privilege)), reverse["allow"]);
+        assert_eq!(json!([["amit"]]), reverse["secrecy"]["clauses"][0]["witness"]);
+
+        let input = flow_input(&source, &target, Some(&privilege));
+        assert_eq!(json!([[["yue"]]]), input["privilege"]);
+        assert_eq!(json!({ "input": input.clone() }), request_body(input));
+    }
+
+    #[test]
+    fn test_query() {
+        assert_eq!(ALLOW_QUERY, query("allow"));
+        assert!(POLICY.contains("package labeled"));
+    }
+}
@@ -0,0 +1,76 @@
+//! Checks that the core operations never panic.
+//!
+//! `no_panic` runs an operation under `catch_unwind`, in the spirit of the
+//! `#[no_panic]` attribute, which proves the same at link time but only for
+//! optimized, monomorphic code. The properties feed it arbitrary labels,
+//! which need not be reduced and may contain empty principals, paths and
+//! clauses, and arbitrary text, including text made of the syntax's own
+//! characters.
+
+use crate::{HasPrivilege, Label};
+use alloc::string::String;
+use alloc::vec::Vec;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// Returns whether `f` returns without panicking.
+fn no_panic<T>(f: impl FnOnce() -> T) -> bool {
+    catch_unwind(AssertUnwindSafe(f)).is_ok()
+}
+
+/// Returns whether the lattice operations and downgrades of `a` and `b`,
+/// with `privilege`, return without panicking.
+fn algebra<L>(a: &L, b: &L, privilege: &L::Privilege) -> bool
+where
+    L: Label + HasPrivilege + Clone,
+{
+    no_panic(|| a.clone().lub(b.clone()))
+        && no_panic(|| a.clone().glb(b.clone()))
+        && no_panic(|| a.can_flow_to(b))
+        && no_panic(|| a.clone().downgrade(privilege))
+        && no_panic(|| a.clone().downgrade_to(b.clone(), privilege))
+        && no_panic(|| a.can_flow_to_with_privilege(b, privilege))
+}
+
+/// Returns text of the syntax's characters.
+fn syntax(input: Vec<u8>) -> String {
+    input.into_iter().map(|b| char::from(b",|&/\\TFa1 "[b as usize % 10])).collect()
+}
+
+#[cfg(feature = "dclabel")]
+quickcheck! {
+    fn dclabel_never_panics(a: crate::dclabel::DCLabel, b: crate::dclabel::DCLabel, privilege: crate::dclabel::Component) -> bool {
+        algebra(&a, &b, &privilege)
+            && no_panic(|| a.secrecy.implies(&b.integrity))
+            && no_panic(|| a.clone().reduce())
+    }
+
+    fn dclabel_parse_never_panics(input: String, text: Vec<u8>) -> bool {
+        no_panic(|| crate::dclabel::DCLabel::parse(&input).is_ok()) && no_panic(|| crate::dclabel::DCLabel::parse(&syntax(text)).is_ok())
+    }
+}
+
+#[cfg(feature = "buckle")]
+quickcheck! {
+    fn buckle_never_panics(a: crate::buckle::Buckle, b: crate::buckle::Buckle, privilege: crate::buckle::Component) -> bool {
+        algebra(&a, &b, &privilege)
+            && no_panic(|| a.secrecy.implies(&b.integrity))
+            && no_panic(|| a.clone().reduce())
+    }
+
+    fn buckle_parse_never_panics(input: String, text: Vec<u8>) -> bool {
+        no_panic(|| crate::buckle::Buckle::parse(&input).is_ok()) && no_panic(|| crate::buckle::Buckle::parse(&syntax(text)).is_ok())
+    }
+}
+
+#[cfg(feature = "buckle2")]
+quickcheck! {
+    fn buckle2_never_panics(a: crate::buckle2::Buckle2, b: crate::buckle2::Buckle2, privilege: crate::buckle2::Component) -> bool {
+        algebra(&a, &b, &privilege)
+            && no_panic(|| a.secrecy.implies(&b.integrity))
+            && no_panic(|| a.clone().reduce())
+    }
+
+    fn buckle2_parse_never_panics(input: String, text: Vec<u8>) -> bool {
+        no_panic(|| crate::buckle2::Buckle2::parse(&input).is_ok()) && no_panic(|| crate::buckle2::Buckle2::parse(&syntax(text)).is_ok())
+    }
+}
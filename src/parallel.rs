@@ -0,0 +1,164 @@
+//! Parallel `implies` and `reduce` for large components.
+//!
+//! Folding many inputs into a label can leave components with hundreds of
+//! clauses, where the pairwise clause comparisons of `implies` and `reduce`
+//! dominate. With the `rayon` feature, `dclabel` and `buckle` components
+//! split that work across rayon's thread pool once they exceed a threshold
+//! number of clause pairs: `implies` checks the target clauses in parallel,
+//! and `reduce` looks every clause up in parallel in the same subsumption
+//! index the sequential path uses. Smaller components keep the sequential
+//! path, where the cost of spawning work would outweigh the gain. Results
+//! are the same either way.
+//!
+//! `buckle2` components are generic over an allocator that may not be
+//! shareable between threads, and stay sequential.
+
+use crate::subsume::{self, Entry, Subsumes};
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use rayon::prelude::*;
+
+/// The default number of clause pairs above which work is parallelized,
+/// e.g. two components of 100 clauses each.
+pub const DEFAULT_THRESHOLD: usize = 10_000;
+
+static THRESHOLD: AtomicUsize = AtomicUsize::new(DEFAULT_THRESHOLD);
+
+/// Returns the number of clause pairs above which work is parallelized.
+pub fn threshold() -> usize {
+    THRESHOLD.load(Ordering::Relaxed)
+}
+
+/// Sets the number of clause pairs above which work is parallelized for the
+/// whole process. `usize::MAX` disables parallelism.
+pub fn set_threshold(pairs: usize) {
+    THRESHOLD.store(pairs, Ordering::Relaxed)
+}
+
+/// Returns whether comparing `lhs` by `rhs` clauses should be parallelized.
+pub(crate) fn is_large(lhs: usize, rhs: usize) -> bool {
+    lhs.saturating_mul(rhs) > threshold()
+}
+
+/// Returns whether `f` holds for every clause of `targets`, checking them on
+/// rayon's thread pool.
+pub(crate) fn all<C, F>(targets: &[&C], f: F) -> bool
+where
+    C: Sync,
+    F: Fn(&C) -> bool + Sync,
+{
+    targets.par_iter().all(|c| f(c))
+}
+
+/// Returns, for each of `clauses` in ascending order, whether `reduce`
+/// removes it: whether it is implied by another clause, keeping the least
+/// of equivalent clauses. The clauses are looked up in the same subsumption
+/// index as the sequential `reduce`, split across rayon's thread pool.
+pub(crate) fn redundant<C>(clauses: &BTreeSet<C>) -> Vec<bool>
+where
+    C: Subsumes + Sync,
+    C::Key: Sync,
+{
+    let clauses: Vec<&C> = clauses.iter().collect();
+    let mut index: Vec<_> = clauses.iter().enumerate().map(|(i, c)| Entry::new(i, *c)).collect();
+    subsume::sort(&mut index);
+    clauses.par_iter().enumerate().map(|(i, c)| subsume::is_redundant(&index, i, *c)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "buckle")]
+    quickcheck! {
+        fn implies_agrees(c1: crate::buckle::Component, c2: crate::buckle::Component) -> bool {
+            use crate::buckle::Component;
+            match (&c1, &c2) {
+                (Component::DCFormula(s), Component::DCFormula(o)) => {
+                    let targets: Vec<_> = o.iter().collect();
+                    all(&targets, |r| s.iter().any(|l| l.implies(r))) == c1.implies(&c2)
+                }
+                _ => true,
+            }
+        }
+
+        fn reduce_agrees(component: crate::buckle::Component) -> bool {
+            use crate::buckle::Component;
+            if let Component::DCFormula(clauses) = &component {
+                let mut expected = component.clone();
+                expected.reduce_with_budget(usize::MAX);
+                let mut removed = redundant(clauses).into_iter();
+                let reduced = clauses.iter().filter(|_| !removed.next().unwrap_or(false)).cloned().collect::<BTreeSet<_>>();
+                expected == Component::from(reduced)
+            } else {
+                true
+            }
+        }
+    }
+
+    #[cfg(feature = "dclabel")]
+    #[test]
+    fn test_large() {
+        use crate::dclabel::{Clause, Component};
+
+        let clauses = |n: usize, extra: &str| {
+            Component::from(
+                (0..n)
+                    .map(|i| Clause::new_from_vec(alloc::vec![alloc::format!("p{}", i), extra.into()]))
+                    .collect::<BTreeSet<_>>(),
+            )
+        };
+        assert!(is_large(200, 200));
+        // Each clause of the first implies the corresponding clause of the
+        // second, and the conjunction reduces to the first.
+        let (small, large) = (clauses(200, "a"), clauses(200, "a") | Component::from([["b"]]));
+        assert!(small.implies(&large));
+        assert!(!large.implies(&small));
+        let mut both = small.clone() & large;
+        both.reduce();
+        assert_eq!(small, both);
+    }
+
+    // `reduce` takes the parallel path above the threshold, and
+    // `reduce_with_budget` the sequential one.
+    #[cfg(feature = "dclabel")]
+    #[test]
+    fn test_reduce_large_dclabel() {
+        use crate::dclabel::{Clause, Component};
+
+        let clauses = (0..150)
+            .flat_map(|i| {
+                let p = alloc::format!("p{}", i);
+                [Clause::from([p.as_str()]), Clause::from([p.as_str(), "a"]), Clause::from(["a", "b", p.as_str()])]
+            })
+            .collect::<BTreeSet<_>>();
+        assert!(is_large(clauses.len(), clauses.len()));
+        let component = Component::from(clauses);
+        let (mut parallel, mut sequential) = (component.clone(), component);
+        parallel.reduce();
+        assert!(sequential.reduce_with_budget(usize::MAX));
+        assert_eq!(sequential, parallel);
+        assert_eq!(Component::from((0..150).map(|i| Clause::from([alloc::format!("p{}", i).as_str()])).collect::<BTreeSet<_>>()), parallel);
+    }
+
+    #[cfg(feature = "buckle")]
+    #[test]
+    fn test_reduce_large_buckle() {
+        use crate::buckle::{Clause, Component};
+
+        let clauses = (0..150)
+            .flat_map(|i| {
+                let p = alloc::format!("p{}", i);
+                [Clause::from([p.as_str()]), Clause::from([p.as_str(), "a"]), Clause::from(["a", "b", p.as_str()])]
+            })
+            .collect::<BTreeSet<_>>();
+        assert!(is_large(clauses.len(), clauses.len()));
+        let component = Component::from(clauses);
+        let (mut parallel, mut sequential) = (component.clone(), component);
+        parallel.reduce();
+        assert!(sequential.reduce_with_budget(usize::MAX));
+        assert_eq!(sequential, parallel);
+        assert_eq!(Component::from((0..150).map(|i| Clause::from([alloc::format!("p{}", i).as_str()])).collect::<BTreeSet<_>>()), parallel);
+    }
+}
@@ -0,0 +1,230 @@
+//! Buckle labels on persistent trees that share structure.
+//!
+//! A `PersistentBuckle` keeps its clauses in persistent red-black trees
+//! (`rpds::RedBlackTreeSetSync`), so cloning a label is constant time and
+//! `lub`, `glb` and `downgrade` return labels sharing every subtree they
+//! don't change with their inputs, rather than copying whole `BTreeSet`s.
+//! This suits systems keeping the full history of a value's labels, where
+//! consecutive labels mostly overlap. Results are equal to `Buckle`'s, and
+//! labels convert to and from `Buckle`.
+
+use crate::buckle::{self, Buckle, Clause};
+use crate::{HasPrivilege, Label};
+use alloc::vec::Vec;
+use rpds::RedBlackTreeSetSync;
+
+/// The clauses of a formula, shared between labels.
+pub type Clauses = RedBlackTreeSetSync<Clause>;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Component {
+    DCFalse,
+    DCFormula(Clauses),
+}
+
+impl Component {
+    pub fn dc_false() -> Self {
+        Component::DCFalse
+    }
+
+    pub fn dc_true() -> Self {
+        Component::DCFormula(Clauses::new_sync())
+    }
+
+    pub fn is_false(&self) -> bool {
+        matches!(self, Component::DCFalse)
+    }
+
+    pub fn is_true(&self) -> bool {
+        match self {
+            Component::DCFalse => false,
+            Component::DCFormula(clauses) => clauses.is_empty(),
+        }
+    }
+
+    pub fn implies(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Component::DCFalse, _) => true,
+            (_, Component::DCFalse) => false,
+            (Component::DCFormula(s), Component::DCFormula(o)) => {
+                o.iter().all(|oclause| s.iter().any(|sclause| sclause.implies(oclause)))
+            }
+        }
+    }
+
+    /// Removes the clauses implied by other clauses, as `buckle::Component`
+    /// does, leaving the rest of the tree shared.
+    pub fn reduce(&mut self) {
+        if let Component::DCFormula(clauses) = self {
+            let mut rmlist = Vec::new();
+            for (i, clausef) in clauses.iter().enumerate() {
+                for clauser in clauses.iter().skip(i + 1) {
+                    if clausef.implies(clauser) {
+                        rmlist.push(clauser.clone());
+                    } else if clauser.implies(clausef) {
+                        rmlist.push(clausef.clone());
+                    }
+                }
+            }
+            for rmclause in rmlist.iter() {
+                clauses.remove_mut(rmclause);
+            }
+        }
+    }
+}
+
+/// Conjoins the components as `buckle::Component` does, inserting the
+/// clauses of the smaller side into the larger, which keeps its structure.
+impl core::ops::BitAnd for Component {
+    type Output = Component;
+    fn bitand(self, rhs: Self) -> Component {
+        match (self, rhs) {
+            (Component::DCFalse, _) | (_, Component::DCFalse) => Component::DCFalse,
+            (s, o) if o.is_true() => s,
+            (s, o) if s.is_true() => o,
+            (Component::DCFormula(s), Component::DCFormula(o)) => {
+                let (mut large, small) = if s.size() >= o.size() { (s, o) } else { (o, s) };
+                for clause in small.iter() {
+                    large.insert_mut(clause.clone());
+                }
+                let mut result = Component::DCFormula(large);
+                result.reduce();
+                result
+            }
+        }
+    }
+}
+
+/// Disjoins the components as `buckle::Component` does.
+impl core::ops::BitOr for Component {
+    type Output = Component;
+    fn bitor(self, rhs: Self) -> Component {
+        match (self, rhs) {
+            (s, Component::DCFalse) => s,
+            (Component::DCFalse, o) => o,
+            (s, o) if s.is_true() || o.is_true() => Component::dc_true(),
+            (s, o) => Component::from(buckle::Component::from(s) | buckle::Component::from(o)),
+        }
+    }
+}
+
+impl From<buckle::Component> for Component {
+    fn from(component: buckle::Component) -> Self {
+        match component.into_clauses() {
+            None => Component::DCFalse,
+            Some(clauses) => Component::DCFormula(clauses.into_iter().collect()),
+        }
+    }
+}
+
+impl From<Component> for buckle::Component {
+    fn from(component: Component) -> Self {
+        match component {
+            Component::DCFalse => buckle::Component::DCFalse,
+            Component::DCFormula(clauses) => buckle::Component::from(clauses.iter().cloned().collect::<alloc::collections::BTreeSet<_>>()),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PersistentBuckle {
+    pub secrecy: Component,
+    pub integrity: Component,
+}
+
+impl PersistentBuckle {
+    pub fn reduce(&mut self) {
+        self.secrecy.reduce();
+        self.integrity.reduce();
+    }
+}
+
+impl From<Buckle> for PersistentBuckle {
+    fn from(label: Buckle) -> Self {
+        PersistentBuckle { secrecy: label.secrecy.into(), integrity: label.integrity.into() }
+    }
+}
+
+impl From<PersistentBuckle> for Buckle {
+    fn from(label: PersistentBuckle) -> Self {
+        Buckle { secrecy: label.secrecy.into(), integrity: label.integrity.into() }
+    }
+}
+
+impl Label for PersistentBuckle {
+    fn lub(self, rhs: Self) -> Self {
+        let mut res = PersistentBuckle { secrecy: self.secrecy & rhs.secrecy, integrity: self.integrity | rhs.integrity };
+        res.reduce();
+        res
+    }
+
+    fn glb(self, rhs: Self) -> Self {
+        let mut res = PersistentBuckle { secrecy: self.secrecy | rhs.secrecy, integrity: self.integrity & rhs.integrity };
+        res.reduce();
+        res
+    }
+
+    fn can_flow_to(&self, rhs: &Self) -> bool {
+        rhs.secrecy.implies(&self.secrecy) && self.integrity.implies(&rhs.integrity)
+    }
+}
+
+impl HasPrivilege for PersistentBuckle {
+    type Privilege = Component;
+
+    fn downgrade(mut self, privilege: &Component) -> PersistentBuckle {
+        self.secrecy = match (self.secrecy, privilege) {
+            (_, Component::DCFalse) => Component::dc_true(),
+            (Component::DCFalse, _) => Component::DCFalse,
+            (Component::DCFormula(mut sec), Component::DCFormula(p)) => {
+                let removed: Vec<Clause> =
+                    sec.iter().filter(|c| p.iter().any(|pclause| pclause.implies(c))).cloned().collect();
+                for clause in removed.iter() {
+                    sec.remove_mut(clause);
+                }
+                Component::DCFormula(sec)
+            }
+        };
+        self.integrity = privilege.clone() & self.integrity;
+        self
+    }
+
+    fn downgrade_to(self, target: Self, privilege: &Component) -> Self {
+        if self.can_flow_to_with_privilege(&target, privilege) {
+            target
+        } else {
+            self
+        }
+    }
+
+    fn can_flow_to_with_privilege(&self, rhs: &Self, privilege: &Component) -> bool {
+        (rhs.secrecy.clone() & privilege.clone()).implies(&self.secrecy)
+            && (self.integrity.clone() & privilege.clone()).implies(&rhs.integrity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lub() {
+        let a = PersistentBuckle::from(Buckle::parse("amit&yue,T").unwrap());
+        let b = PersistentBuckle::from(Buckle::parse("amit/grades,T").unwrap());
+        assert_eq!(Buckle::parse("amit&yue,T").unwrap(), Buckle::from(a.clone().lub(b.clone())));
+        assert!(b.can_flow_to(&a));
+        assert!(!a.can_flow_to(&b));
+    }
+
+    quickcheck! {
+        fn agrees_with_buckle(lbl1: Buckle, lbl2: Buckle, privilege: buckle::Component) -> bool {
+            let (p1, p2) = (PersistentBuckle::from(lbl1.clone()), PersistentBuckle::from(lbl2.clone()));
+            let pprivilege = Component::from(privilege.clone());
+            Buckle::from(p1.clone().lub(p2.clone())) == lbl1.clone().lub(lbl2.clone())
+                && Buckle::from(p1.clone().glb(p2.clone())) == lbl1.clone().glb(lbl2.clone())
+                && p1.can_flow_to(&p2) == lbl1.can_flow_to(&lbl2)
+                && p1.can_flow_to_with_privilege(&p2, &pprivilege) == lbl1.can_flow_to_with_privilege(&lbl2, &privilege)
+                && Buckle::from(p1.downgrade(&pprivilege)) == lbl1.downgrade(&privilege)
+        }
+    }
+}
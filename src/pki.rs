@@ -0,0 +1,377 @@
+//! X.509 extension carrying a label and, optionally, a privilege.
+//!
+//! Workload certificates can embed the holder's maximum label and privilege
+//! in an extension identified by `LABEL_OID`, so that TLS-terminating
+//! proxies can enforce flows from the handshake. The extension value is the
+//! DER encoding of:
+//!
+//! ```text
+//! LabelExtension ::= SEQUENCE {
+//!     secrecy    Component,
+//!     integrity  Component,
+//!     privilege  [0] EXPLICIT Component OPTIONAL }
+//!
+//! Component ::= CHOICE {
+//!     false    NULL,
+//!     formula  SEQUENCE OF Clause }
+//!
+//! Clause ::= SEQUENCE OF Principal              -- dclabel
+//! Clause ::= SEQUENCE OF SEQUENCE OF Principal  -- buckle, one per path
+//!
+//! Principal ::= UTF8String
+//! ```
+//!
+//! `encode_value`/`decode_value` produce and consume the extension value,
+//! for use with X.509 libraries that build the `Extension` themselves, while
+//! `encode_extension`/`decode_extension` handle the complete `Extension`.
+//! Decoding is strict: only DER (definite, minimal lengths) is accepted, and
+//! the decoded label is reduced.
+
+use alloc::vec::Vec;
+
+/// The extension's object identifier, under the UUID arc `2.25`.
+pub const LABEL_OID: &str = "2.25.74746845071682141312383776907048282925";
+
+/// The DER contents of `LABEL_OID`.
+pub const LABEL_OID_DER: &[u8] = &[
+    0x69, 0xf0, 0xbb, 0xdb, 0xff, 0xbb, 0x9c, 0x82, 0xaa, 0xb9, 0xbe, 0xa6, 0xba, 0xbd, 0xc6, 0xdc, 0xf1, 0xd6, 0x2d,
+];
+
+const BOOLEAN: u8 = 0x01;
+const OCTET_STRING: u8 = 0x04;
+const NULL: u8 = 0x05;
+const OID: u8 = 0x06;
+const UTF8_STRING: u8 = 0x0c;
+const SEQUENCE: u8 = 0x30;
+const PRIVILEGE: u8 = 0xa0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The input isn't valid DER, or doesn't match the expected structure.
+    Malformed,
+    /// The extension has a different OID.
+    WrongOid,
+}
+
+fn write_tlv(out: &mut Vec<u8>, tag: u8, content: &[u8]) {
+    out.push(tag);
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|b| **b == 0).count();
+        out.push(0x80 | (bytes.len() - skip) as u8);
+        out.extend_from_slice(&bytes[skip..]);
+    }
+    out.extend_from_slice(content);
+}
+
+/// Writes a constructed value whose contents are produced by `f`.
+fn write_nested(out: &mut Vec<u8>, tag: u8, f: impl FnOnce(&mut Vec<u8>)) {
+    let mut content = Vec::new();
+    f(&mut content);
+    write_tlv(out, tag, &content);
+}
+
+/// Reads a TLV with tag `tag` from the front of `input`, returning its
+/// contents.
+fn read_tlv<'a>(input: &mut &'a [u8], tag: u8) -> Result<&'a [u8], Error> {
+    let (&t, rest) = input.split_first().ok_or(Error::Malformed)?;
+    let (&first, mut rest) = rest.split_first().ok_or(Error::Malformed)?;
+    if t != tag {
+        return Err(Error::Malformed);
+    }
+    let len = if first < 0x80 {
+        first as usize
+    } else {
+        let n = (first & 0x7f) as usize;
+        if n == 0 || n > core::mem::size_of::<usize>() || rest.len() < n || rest[0] == 0 {
+            return Err(Error::Malformed);
+        }
+        let len = rest[..n].iter().fold(0usize, |acc, b| acc << 8 | *b as usize);
+        rest = &rest[n..];
+        if len < 0x80 {
+            return Err(Error::Malformed);
+        }
+        len
+    };
+    if rest.len() < len {
+        return Err(Error::Malformed);
+    }
+    let (content, rest) = rest.split_at(len);
+    *input = rest;
+    Ok(content)
+}
+
+fn peek_tag(input: &[u8]) -> Option<u8> {
+    input.first().copied()
+}
+
+/// Writes a component given its clauses, or `None` for `DCFalse`.
+fn write_component<'c, C: 'c>(
+    out: &mut Vec<u8>,
+    clauses: Option<impl Iterator<Item = &'c C>>,
+    clause: impl Fn(&mut Vec<u8>, &'c C),
+) {
+    match clauses {
+        None => write_tlv(out, NULL, &[]),
+        Some(clauses) => write_nested(out, SEQUENCE, |out| clauses.for_each(|c| clause(out, c))),
+    }
+}
+
+/// Reads a component, calling `clause` with each clause's contents. Returns
+/// `false` for `DCFalse`.
+fn read_component<'a>(
+    input: &mut &'a [u8],
+    mut clause: impl FnMut(&'a [u8]) -> Result<(), Error>,
+) -> Result<bool, Error> {
+    if peek_tag(input) == Some(NULL) {
+        if !read_tlv(input, NULL)?.is_empty() {
+            return Err(Error::Malformed);
+        }
+        return Ok(false);
+    }
+    let mut clauses = read_tlv(input, SEQUENCE)?;
+    while !clauses.is_empty() {
+        clause(read_tlv(&mut clauses, SEQUENCE)?)?;
+    }
+    Ok(true)
+}
+
+fn read_principal<'a, S: From<&'a str>>(input: &mut &'a [u8]) -> Result<S, Error> {
+    let bytes = read_tlv(input, UTF8_STRING)?;
+    core::str::from_utf8(bytes).map(Into::into).map_err(|_| Error::Malformed)
+}
+
+/// Wraps an extension value in an `Extension` with `LABEL_OID`.
+fn wrap_extension(value: &[u8], critical: bool) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_nested(&mut out, SEQUENCE, |out| {
+        write_tlv(out, OID, LABEL_OID_DER);
+        // DER omits `critical` when it has its default, FALSE.
+        if critical {
+            write_tlv(out, BOOLEAN, &[0xff]);
+        }
+        write_tlv(out, OCTET_STRING, value);
+    });
+    out
+}
+
+/// Unwraps the value of an `Extension` with `LABEL_OID`, returning it along
+/// with the extension's criticality.
+fn unwrap_extension(mut input: &[u8]) -> Result<(&[u8], bool), Error> {
+    let mut extension = read_tlv(&mut input, SEQUENCE)?;
+    if !input.is_empty() {
+        return Err(Error::Malformed);
+    }
+    if read_tlv(&mut extension, OID)? != LABEL_OID_DER {
+        return Err(Error::WrongOid);
+    }
+    let critical = if peek_tag(extension) == Some(BOOLEAN) {
+        // DER forbids encoding the default, so only TRUE may appear.
+        if read_tlv(&mut extension, BOOLEAN)? != [0xff] {
+            return Err(Error::Malformed);
+        }
+        true
+    } else {
+        false
+    };
+    let value = read_tlv(&mut extension, OCTET_STRING)?;
+    if !extension.is_empty() {
+        return Err(Error::Malformed);
+    }
+    Ok((value, critical))
+}
+
+/// Implements the public API for a label type, given functions writing and
+/// reading its components.
+macro_rules! extension_api {
+    ($label:ident, $component:ident) => {
+        /// Encodes the extension value for `label` and `privilege`.
+        pub fn encode_value(label: &$label, privilege: Option<&$component>) -> Vec<u8> {
+            let mut out = Vec::new();
+            write_nested(&mut out, SEQUENCE, |out| {
+                write(out, &label.secrecy);
+                write(out, &label.integrity);
+                if let Some(privilege) = privilege {
+                    write_nested(out, PRIVILEGE, |out| write(out, privilege));
+                }
+            });
+            out
+        }
+
+        /// Decodes an extension value into a label and optional privilege.
+        pub fn decode_value(mut input: &[u8]) -> Result<($label, Option<$component>), Error> {
+            let mut value = read_tlv(&mut input, SEQUENCE)?;
+            if !input.is_empty() {
+                return Err(Error::Malformed);
+            }
+            let secrecy = read(&mut value)?;
+            let integrity = read(&mut value)?;
+            let privilege = if peek_tag(value) == Some(PRIVILEGE) {
+                let mut privilege = read_tlv(&mut value, PRIVILEGE)?;
+                let component = read(&mut privilege)?;
+                if !privilege.is_empty() {
+                    return Err(Error::Malformed);
+                }
+                Some(component)
+            } else {
+                None
+            };
+            if !value.is_empty() {
+                return Err(Error::Malformed);
+            }
+            Ok(($label::new(secrecy, integrity), privilege))
+        }
+
+        /// Encodes a complete `Extension` for `label` and `privilege`.
+        pub fn encode_extension(label: &$label, privilege: Option<&$component>, critical: bool) -> Vec<u8> {
+            wrap_extension(&encode_value(label, privilege), critical)
+        }
+
+        /// Decodes a complete `Extension`, checking its OID.
+        pub fn decode_extension(input: &[u8]) -> Result<($label, Option<$component>), Error> {
+            decode_value(unwrap_extension(input)?.0)
+        }
+    };
+}
+
+#[cfg(feature = "dclabel")]
+pub mod dclabel {
+    use super::*;
+    use crate::dclabel::{Clause, Component, DCLabel};
+    use alloc::collections::BTreeSet;
+
+    fn write(out: &mut Vec<u8>, component: &Component) {
+        let clauses = match component {
+            Component::DCFalse => None,
+            Component::DCFormula(clauses) => Some(clauses.iter()),
+        };
+        write_component(out, clauses, |out, clause: &Clause| {
+            write_nested(out, SEQUENCE, |out| {
+                clause.0.iter().for_each(|p| write_tlv(out, UTF8_STRING, p.as_bytes()))
+            })
+        });
+    }
+
+    fn read(input: &mut &[u8]) -> Result<Component, Error> {
+        let mut clauses = BTreeSet::new();
+        let formula = read_component(input, |mut clause| {
+            let mut principals = BTreeSet::new();
+            while !clause.is_empty() {
+                principals.insert(read_principal(&mut clause)?);
+            }
+            clauses.insert(Clause::from(principals));
+            Ok(())
+        })?;
+        Ok(if formula { Component::from(clauses) } else { Component::DCFalse })
+    }
+
+    extension_api!(DCLabel, Component);
+}
+
+#[cfg(feature = "buckle")]
+pub mod buckle {
+    use super::*;
+    use crate::buckle::{Buckle, Clause, Component};
+    use alloc::collections::BTreeSet;
+
+    fn write(out: &mut Vec<u8>, component: &Component) {
+        let clauses = match component {
+            Component::DCFalse => None,
+            Component::DCFormula(clauses) => Some(clauses.iter()),
+        };
+        write_component(out, clauses, |out, clause: &Clause| {
+            write_nested(out, SEQUENCE, |out| {
+                for path in clause.0.iter() {
+                    write_nested(out, SEQUENCE, |out| {
+                        path.iter().for_each(|p| write_tlv(out, UTF8_STRING, p.as_bytes()))
+                    });
+                }
+            })
+        });
+    }
+
+    fn read(input: &mut &[u8]) -> Result<Component, Error> {
+        let mut clauses = BTreeSet::new();
+        let formula = read_component(input, |mut clause| {
+            let mut paths = BTreeSet::new();
+            while !clause.is_empty() {
+                let mut path = read_tlv(&mut clause, SEQUENCE)?;
+                let mut principals = Vec::new();
+                while !path.is_empty() {
+                    principals.push(read_principal(&mut path)?);
+                }
+                paths.insert(principals);
+            }
+            clauses.insert(Clause::from(paths));
+            Ok(())
+        })?;
+        Ok(if formula { Component::from(clauses) } else { Component::DCFalse })
+    }
+
+    extension_api!(Buckle, Component);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "dclabel")]
+    quickcheck! {
+        fn dclabel_round_trip(lbl: crate::dclabel::DCLabel, privilege: Option<crate::dclabel::Component>) -> bool {
+            let der = dclabel::encode_extension(&lbl, privilege.as_ref(), false);
+            dclabel::decode_extension(&der) == Ok((lbl, privilege))
+        }
+    }
+
+    #[cfg(feature = "buckle")]
+    quickcheck! {
+        fn buckle_round_trip(lbl: crate::buckle::Buckle, privilege: Option<crate::buckle::Component>) -> bool {
+            let der = buckle::encode_extension(&lbl, privilege.as_ref(), true);
+            buckle::decode_extension(&der) == Ok((lbl, privilege))
+        }
+    }
+
+    #[cfg(feature = "buckle")]
+    #[test]
+    fn test_buckle_layout() {
+        use crate::buckle::Buckle;
+
+        // SEQUENCE { NULL, SEQUENCE { SEQUENCE { SEQUENCE { UTF8String "a" } } } }
+        assert_eq!(
+            alloc::vec![0x30, 0x0c, 0x05, 0x00, 0x30, 0x08, 0x30, 0x06, 0x30, 0x04, 0x0c, 0x02, b'a', b'b'],
+            buckle::encode_value(&Buckle::new(false, [["ab"]]), None)
+        );
+        assert_eq!(
+            alloc::vec![0x30, 0x04, 0x30, 0x00, 0x05, 0x00],
+            buckle::encode_value(&Buckle::bottom(), None)
+        );
+    }
+
+    #[cfg(feature = "buckle")]
+    #[test]
+    fn test_rejects_non_der() {
+        use crate::buckle::Buckle;
+
+        let der = buckle::encode_extension(&Buckle::public(), None, false);
+        assert_eq!(Ok((Buckle::public(), None)), buckle::decode_extension(&der));
+        // Trailing bytes.
+        let mut trailing = der.clone();
+        trailing.push(0);
+        assert_eq!(Err(Error::Malformed), buckle::decode_extension(&trailing));
+        // Non-minimal length.
+        assert_eq!(Err(Error::Malformed), buckle::decode_value(&[0x30, 0x81, 0x04, 0x30, 0x00, 0x30, 0x00]));
+        // An explicit `critical FALSE`.
+        let mut explicit = alloc::vec![0x30, (der[1] + 3)];
+        explicit.extend_from_slice(&der[2..2 + 2 + LABEL_OID_DER.len()]);
+        explicit.extend_from_slice(&[0x01, 0x01, 0x00]);
+        explicit.extend_from_slice(&der[2 + 2 + LABEL_OID_DER.len()..]);
+        assert_eq!(Err(Error::Malformed), buckle::decode_extension(&explicit));
+        // A different OID.
+        let mut other = der.clone();
+        other[4] ^= 1;
+        assert_eq!(Err(Error::WrongOid), buckle::decode_extension(&other));
+    }
+}
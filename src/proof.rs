@@ -0,0 +1,125 @@
+//! Evidence for implications between components.
+//!
+//! A component implies another if it is `DCFalse`, or if each of the
+//! other's clauses is implied by one of its own. A clause implies another
+//! if each of its principals (for `Buckle` and `Buckle2`, paths) is a
+//! prefix of one of the other's, a `DCLabel` principal being a prefix only
+//! of itself. `implies_with_proof` returns these choices as an
+//! `ImplicationProof`, which `check_proof` verifies without searching, so an
+//! auditor can record why a declassification was allowed and later confirm
+//! it, or a verifier can accept proofs computed elsewhere.
+//!
+//! Clauses and their elements are identified by their position in the
+//! component's, or clause's, sorted order, as iterated.
+//!
+//! When a flow is denied, `flow_counterexample` instead returns a
+//! `FlowCounterexample`: a clause that isn't implied, which names the
+//! principals responsible, for error messages that say what's missing.
+
+use alloc::vec::Vec;
+
+/// Why a component implies another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImplicationProof {
+    /// The component is `DCFalse`, which implies every component.
+    False,
+    /// One step per clause of the implied component, in order.
+    Clauses(Vec<ClauseProof>),
+}
+
+/// Why a clause of the implied component is implied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClauseProof {
+    /// The position of the implied clause.
+    pub target: usize,
+    /// The position of the clause implying it.
+    pub source: usize,
+    /// For each principal or path of the source clause, the position of the
+    /// principal or path of the target clause it's a prefix of.
+    pub prefixes: Vec<usize>,
+}
+
+/// Why a label can't flow to another, as one clause, given as a component
+/// of that clause alone, or `DCFalse` if the clause is the constant false.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlowCounterexample<C> {
+    /// A clause of the source's secrecy that the target's secrecy doesn't
+    /// imply, so the target would disclose the data beyond the principals
+    /// the clause allows.
+    Secrecy(C),
+    /// A clause of the target's integrity that the source's integrity
+    /// doesn't imply: an endorsement the target requires and the data
+    /// lacks.
+    Integrity(C),
+}
+
+impl<C: core::fmt::Display> core::fmt::Display for FlowCounterexample<C> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            FlowCounterexample::Secrecy(clause) => write!(f, "the target doesn't protect secrecy clause {}", clause),
+            FlowCounterexample::Integrity(clause) => write!(f, "the source lacks integrity clause {}", clause),
+        }
+    }
+}
+
+/// Returns a step per target clause, each from the first source clause
+/// implying it, or `None` if some target clause has none. `covers(s, t)`
+/// is whether clause element `s` is a prefix of `t`.
+pub(crate) fn prove<E: ?Sized>(
+    sources: &[Vec<&E>],
+    targets: &[Vec<&E>],
+    covers: impl Fn(&E, &E) -> bool,
+) -> Option<ImplicationProof> {
+    let steps = targets.iter().enumerate().map(|(target, t)| {
+        sources.iter().enumerate().find_map(|(source, s)| {
+            let prefixes = s.iter().map(|e| t.iter().position(|o| covers(e, o))).collect::<Option<Vec<_>>>()?;
+            Some(ClauseProof { target, source, prefixes })
+        })
+    });
+    steps.collect::<Option<Vec<_>>>().map(ImplicationProof::Clauses)
+}
+
+/// Returns whether `steps` proves that the source clauses imply the target
+/// clauses, checking each step's mapping rather than searching for one.
+pub(crate) fn check<E: ?Sized>(
+    sources: &[Vec<&E>],
+    targets: &[Vec<&E>],
+    steps: &[ClauseProof],
+    covers: impl Fn(&E, &E) -> bool,
+) -> bool {
+    steps.len() == targets.len()
+        && steps.iter().enumerate().all(|(j, step)| {
+            match (sources.get(step.source), targets.get(step.target)) {
+                (Some(s), Some(t)) if step.target == j && step.prefixes.len() == s.len() => {
+                    s.iter().zip(&step.prefixes).all(|(e, i)| t.get(*i).is_some_and(|o| covers(e, o)))
+                }
+                _ => false,
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prove_and_check() {
+        let prefix = |s: &str, t: &str| t.starts_with(s);
+        let sources = [alloc::vec!["a", "b"], alloc::vec!["c"]];
+        let targets = [alloc::vec!["c", "d"], alloc::vec!["ax", "bx"]];
+        let proof = prove(&sources, &targets, prefix).unwrap();
+        let expected = ImplicationProof::Clauses(alloc::vec![
+            ClauseProof { target: 0, source: 1, prefixes: alloc::vec![0] },
+            ClauseProof { target: 1, source: 0, prefixes: alloc::vec![0, 1] },
+        ]);
+        assert_eq!(expected, proof);
+        let ImplicationProof::Clauses(mut steps) = proof else { unreachable!() };
+        assert!(check(&sources, &targets, &steps, prefix));
+        steps[1].prefixes = alloc::vec![1, 1];
+        assert!(!check(&sources, &targets, &steps, prefix));
+        steps[1].prefixes = alloc::vec![0, 7];
+        assert!(!check(&sources, &targets, &steps, prefix));
+        assert!(!check(&sources, &targets, &steps[..1], prefix));
+        assert_eq!(None, prove(&sources, &[alloc::vec!["b"]], prefix));
+    }
+}
@@ -0,0 +1,60 @@
+//! `redis` conversions for labels.
+//!
+//! Labels are stored as bulk strings holding their envelope (see
+//! `envelope`), so they can be used directly as values in label-keyed caches
+//! or labeled session stores.
+
+use crate::envelope;
+use alloc::format;
+use redis::{ErrorKind, FromRedisValue, RedisResult, RedisWrite, ToRedisArgs, Value};
+
+macro_rules! impl_redis {
+    ($label:path) => {
+        impl ToRedisArgs for $label {
+            fn write_redis_args<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+                out.write_arg(&envelope::encode(self));
+            }
+        }
+
+        impl FromRedisValue for $label {
+            fn from_redis_value(v: &Value) -> RedisResult<Self> {
+                match v {
+                    Value::BulkString(bytes) => envelope::decode(bytes).map_err(|e| {
+                        (ErrorKind::TypeError, "Invalid label envelope", format!("{:?}", e)).into()
+                    }),
+                    _ => Err((ErrorKind::TypeError, "Response was of incompatible type", format!("{:?}", v)).into()),
+                }
+            }
+        }
+    };
+}
+
+#[cfg(feature = "dclabel")]
+impl_redis!(crate::dclabel::DCLabel);
+#[cfg(feature = "buckle")]
+impl_redis!(crate::buckle::Buckle);
+#[cfg(feature = "buckle2")]
+impl_redis!(crate::buckle2::Buckle2);
+
+#[cfg(all(test, feature = "buckle2"))]
+mod tests {
+    use super::*;
+    use crate::buckle2::Buckle2;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_round_trip() {
+        let lbl = Buckle2::parse("amit/grades|yue,T").unwrap();
+        let mut args = lbl.to_redis_args();
+        assert_eq!(1, args.len());
+        let value = Value::BulkString(args.pop().unwrap());
+        assert_eq!(lbl, Buckle2::from_redis_value(&value).unwrap());
+    }
+
+    #[test]
+    fn test_rejects_invalid() {
+        assert!(Buckle2::from_redis_value(&Value::BulkString(Vec::from([1, 3, 0]))).is_err());
+        assert!(Buckle2::from_redis_value(&Value::Int(1)).is_err());
+        assert!(Buckle2::from_redis_value(&Value::Nil).is_err());
+    }
+}
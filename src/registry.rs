@@ -0,0 +1,193 @@
+//! A registry assigning stable identifiers to labels.
+//!
+//! Storage systems holding many records with few distinct labels can store a
+//! small `LabelId` per record and keep the labels themselves in a
+//! `LabelRegistry`. Labels are registered in their canonical form (see the
+//! `envelope` module), so equal labels always get the same identifier.
+//! Identifiers are assigned sequentially from zero and never reused, and a
+//! registry keeps them when serialized with `to_bytes`.
+//!
+//! A serialized registry is a version byte followed by the canonical bytes of
+//! each label in identifier order, each prefixed by its LEB128-encoded
+//! length.
+
+use crate::envelope::{self, Enveloped};
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+/// The version of the serialized registry format.
+pub const VERSION: u8 = 1;
+
+/// The identifier of a label in a `LabelRegistry`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LabelId(pub u64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The input isn't a serialized registry.
+    Malformed,
+    /// The registry has an unsupported version.
+    UnsupportedVersion(u8),
+    /// A label can't be decoded.
+    Envelope(envelope::Error),
+    /// Two identifiers are assigned equal labels.
+    DuplicateLabel(LabelId),
+}
+
+impl From<envelope::Error> for Error {
+    fn from(e: envelope::Error) -> Self {
+        Error::Envelope(e)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabelRegistry<L> {
+    labels: Vec<L>,
+    ids: BTreeMap<Vec<u8>, LabelId>,
+}
+
+impl<L> Default for LabelRegistry<L> {
+    fn default() -> Self {
+        LabelRegistry { labels: Vec::new(), ids: BTreeMap::new() }
+    }
+}
+
+impl<L: Enveloped> LabelRegistry<L> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the identifier of `label`, registering it if needed.
+    pub fn register(&mut self, label: &L) -> LabelId {
+        let canonical = label.canonical();
+        let bytes = envelope::encode(&canonical);
+        if let Some(id) = self.ids.get(&bytes) {
+            return *id;
+        }
+        let id = LabelId(self.labels.len() as u64);
+        self.labels.push(canonical);
+        self.ids.insert(bytes, id);
+        id
+    }
+
+    /// Returns the identifier of `label`, if registered.
+    pub fn id(&self, label: &L) -> Option<LabelId> {
+        self.ids.get(&label.canonical_bytes()).copied()
+    }
+
+    /// Returns the canonical form of the label identified by `id`.
+    pub fn get(&self, id: LabelId) -> Option<&L> {
+        usize::try_from(id.0).ok().and_then(|i| self.labels.get(i))
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// Iterates over the registered labels in identifier order.
+    pub fn iter(&self) -> impl Iterator<Item = (LabelId, &L)> {
+        self.labels.iter().enumerate().map(|(i, label)| (LabelId(i as u64), label))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = alloc::vec![VERSION];
+        for label in self.labels.iter() {
+            let bytes = envelope::encode(label);
+            write_uvarint(&mut out, bytes.len());
+            out.extend_from_slice(&bytes);
+        }
+        out
+    }
+
+    pub fn from_bytes(input: &[u8]) -> Result<Self, Error> {
+        let mut input = match input.split_first() {
+            Some((&VERSION, rest)) => rest,
+            Some((&version, _)) => return Err(Error::UnsupportedVersion(version)),
+            None => return Err(Error::Malformed),
+        };
+        let mut registry = Self::new();
+        while !input.is_empty() {
+            let len = read_uvarint(&mut input)?;
+            if input.len() < len {
+                return Err(Error::Malformed);
+            }
+            let (bytes, rest) = input.split_at(len);
+            input = rest;
+            let label: L = envelope::decode(bytes)?;
+            let id = LabelId(registry.labels.len() as u64);
+            if registry.register(&label) != id {
+                return Err(Error::DuplicateLabel(id));
+            }
+        }
+        Ok(registry)
+    }
+}
+
+fn write_uvarint(out: &mut Vec<u8>, mut n: usize) {
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn read_uvarint(input: &mut &[u8]) -> Result<usize, Error> {
+    let mut n = 0usize;
+    for shift in (0..usize::BITS).step_by(7) {
+        let (&b, rest) = input.split_first().ok_or(Error::Malformed)?;
+        *input = rest;
+        n |= ((b & 0x7f) as usize).checked_shl(shift).ok_or(Error::Malformed)?;
+        if b & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+    Err(Error::Malformed)
+}
+
+#[cfg(all(test, feature = "buckle"))]
+mod tests {
+    use super::*;
+    use crate::buckle::Buckle;
+
+    #[test]
+    fn test_register() {
+        let mut registry = LabelRegistry::new();
+        let a = registry.register(&Buckle::parse("amit,T").unwrap());
+        let b = registry.register(&Buckle::parse("amit&yue,T").unwrap());
+        assert_eq!((LabelId(0), LabelId(1)), (a, b));
+
+        // Equivalent labels share an identifier.
+        assert_eq!(b, registry.register(&Buckle::parse("yue&amit&amit/grades|amit,T").unwrap()));
+        assert_eq!(Some(a), registry.id(&Buckle::parse("amit&amit|yue,T").unwrap()));
+        assert_eq!(None, registry.id(&Buckle::parse("yue,T").unwrap()));
+        assert_eq!(Some(&Buckle::parse("amit&yue,T").unwrap()), registry.get(b));
+        assert_eq!(None, registry.get(LabelId(2)));
+        assert_eq!(2, registry.len());
+    }
+
+    #[test]
+    fn test_bytes() {
+        let mut registry = LabelRegistry::new();
+        for label in ["amit,T", "yue,amit", "T,T"].iter() {
+            registry.register(&Buckle::parse(label).unwrap());
+        }
+        let bytes = registry.to_bytes();
+        let decoded = LabelRegistry::<Buckle>::from_bytes(&bytes).unwrap();
+        assert!(registry.iter().eq(decoded.iter()));
+        assert_eq!(Some(LabelId(1)), decoded.id(&Buckle::parse("yue,amit").unwrap()));
+
+        assert_eq!(Err(Error::Malformed), LabelRegistry::<Buckle>::from_bytes(&bytes[..bytes.len() - 1]));
+        assert_eq!(Err(Error::UnsupportedVersion(2)), LabelRegistry::<Buckle>::from_bytes(&[2]));
+
+        // The first label repeated.
+        let mut duplicated = bytes.clone();
+        let len = bytes[1] as usize;
+        duplicated.extend_from_slice(&bytes[1..2 + len]);
+        assert_eq!(Err(Error::DuplicateLabel(LabelId(3))), LabelRegistry::<Buckle>::from_bytes(&duplicated));
+    }
+}
@@ -0,0 +1,218 @@
+//! Mapping between SELinux MLS contexts and `DCLabel`s.
+//!
+//! A context `user:role:type:s2:c1,c5` maps to a `DCLabel` whose secrecy
+//! conjoins one principal per category (`c1 & c5`) with one per sensitivity
+//! up to its own (`s1 & s2`; `s0` adds none), so that the MLS dominance order
+//! coincides with `can_flow_to`. The integrity is always `T`, since MLS has no
+//! integrity. The user, role and type don't take part in the mapping.
+//!
+//! A `DCLabel` maps back to a level only if it has this shape: `T` integrity
+//! and a secrecy made of singleton clauses naming categories and a prefix of
+//! the sensitivities. Context ranges (`s0-s1`) aren't supported.
+
+use crate::dclabel::{Clause, Component, DCLabel};
+use alloc::collections::BTreeSet;
+use alloc::string::{String, ToString};
+use core::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The context isn't of the form `user:role:type:sN[:cN,...]`.
+    Malformed,
+    /// The label has no equivalent MLS level.
+    Unrepresentable,
+}
+
+/// An MLS level: a sensitivity and a set of categories.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Level {
+    pub sensitivity: u16,
+    pub categories: BTreeSet<u16>,
+}
+
+/// A simple SELinux security context.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Context {
+    pub user: String,
+    pub role: String,
+    pub type_: String,
+    pub level: Level,
+}
+
+fn number(input: &str, prefix: char) -> Result<u16, Error> {
+    let digits = input.strip_prefix(prefix).ok_or(Error::Malformed)?;
+    // Reject signs and leading zeros, so each level has one spelling.
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) || (digits.len() > 1 && digits.starts_with('0'))
+    {
+        return Err(Error::Malformed);
+    }
+    digits.parse().map_err(|_| Error::Malformed)
+}
+
+impl Level {
+    /// Parses a level such as `s0` or `s2:c1,c3.c5`.
+    pub fn parse(input: &str) -> Result<Level, Error> {
+        let (sensitivity, categories) = match input.split_once(':') {
+            Some((sensitivity, categories)) => (sensitivity, Some(categories)),
+            None => (input, None),
+        };
+        let sensitivity = number(sensitivity, 's')?;
+        let mut level = Level { sensitivity, categories: BTreeSet::new() };
+        for item in categories.into_iter().flat_map(|c| c.split(',')) {
+            match item.split_once('.') {
+                Some((low, high)) => {
+                    let (low, high) = (number(low, 'c')?, number(high, 'c')?);
+                    if low >= high {
+                        return Err(Error::Malformed);
+                    }
+                    level.categories.extend(low..=high);
+                }
+                None => {
+                    level.categories.insert(number(item, 'c')?);
+                }
+            }
+        }
+        Ok(level)
+    }
+
+    /// Returns the equivalent `DCLabel`.
+    pub fn to_dclabel(&self) -> DCLabel {
+        let sensitivities = (1..=self.sensitivity).map(|s| alloc::format!("s{}", s));
+        let categories = self.categories.iter().map(|c| alloc::format!("c{}", c));
+        let clauses: BTreeSet<Clause> = sensitivities.chain(categories).map(|p| Clause::from([p])).collect();
+        DCLabel::new(clauses, true)
+    }
+
+    /// Returns the level equivalent to `label`, if there is one.
+    pub fn from_dclabel(label: &DCLabel) -> Result<Level, Error> {
+        let mut label = label.clone();
+        label.reduce();
+        let clauses = match (&label.secrecy, &label.integrity) {
+            (Component::DCFormula(clauses), integrity) if integrity.is_true() => clauses,
+            _ => return Err(Error::Unrepresentable),
+        };
+        let mut level = Level::default();
+        let mut sensitivities = BTreeSet::new();
+        for clause in clauses.iter() {
+            let mut principals = clause.0.iter();
+            let principal = match (principals.next(), principals.next()) {
+                (Some(principal), None) => principal,
+                _ => return Err(Error::Unrepresentable),
+            };
+            if let Ok(c) = number(principal, 'c') {
+                level.categories.insert(c);
+            } else if let Ok(s) = number(principal, 's') {
+                sensitivities.insert(s);
+            } else {
+                return Err(Error::Unrepresentable);
+            }
+        }
+        level.sensitivity = sensitivities.len() as u16;
+        if !sensitivities.into_iter().eq(1..=level.sensitivity) {
+            return Err(Error::Unrepresentable);
+        }
+        Ok(level)
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "s{}", self.sensitivity)?;
+        for (i, c) in self.categories.iter().enumerate() {
+            write!(f, "{}c{}", if i == 0 { ':' } else { ',' }, c)?;
+        }
+        Ok(())
+    }
+}
+
+impl Context {
+    /// Parses a context such as `user_u:user_r:user_t:s0:c1,c2`.
+    pub fn parse(input: &str) -> Result<Context, Error> {
+        let mut parts = input.splitn(4, ':');
+        let mut field = || match parts.next() {
+            Some(field) if !field.is_empty() => Ok(field.to_string()),
+            _ => Err(Error::Malformed),
+        };
+        let (user, role, type_) = (field()?, field()?, field()?);
+        let level = Level::parse(&field()?)?;
+        Ok(Context { user, role, type_, level })
+    }
+
+    /// Returns the `DCLabel` equivalent to the context's level.
+    pub fn to_dclabel(&self) -> DCLabel {
+        self.level.to_dclabel()
+    }
+
+    /// Replaces the context's level with the one equivalent to `label`.
+    pub fn with_dclabel(self, label: &DCLabel) -> Result<Context, Error> {
+        Ok(Context { level: Level::from_dclabel(label)?, ..self })
+    }
+}
+
+impl fmt::Display for Context {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}:{}", self.user, self.role, self.type_, self.level)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Label;
+    use alloc::string::ToString;
+
+    fn label(s: &str) -> DCLabel {
+        DCLabel::parse(s).unwrap().1
+    }
+
+    #[test]
+    fn test_parse() {
+        let context = Context::parse("user_u:user_r:user_t:s2:c1,c3.c5").unwrap();
+        assert_eq!("user_t", context.type_);
+        assert_eq!(2, context.level.sensitivity);
+        assert_eq!("user_u:user_r:user_t:s2:c1,c3,c4,c5", context.to_string());
+        assert_eq!(Ok(Level::default()), Level::parse("s0"));
+
+        for malformed in ["user_u:user_r:user_t", "u:r:t:s0-s1", "u:r:t:s01", "u:r:t:s0:c2.c1", "u::t:s0", "u:r:t:s0:"] {
+            assert_eq!(Err(Error::Malformed), Context::parse(malformed), "{}", malformed);
+        }
+    }
+
+    #[test]
+    fn test_to_dclabel() {
+        let level = |s| Level::parse(s).unwrap().to_dclabel();
+        assert_eq!(DCLabel::public(), level("s0"));
+        assert_eq!(label("c1&c2&s1&s2,T"), level("s2:c1,c2"));
+        assert!(level("s0:c1").can_flow_to(&level("s1:c1,c2")));
+        assert!(!level("s1:c1").can_flow_to(&level("s0:c1,c2")));
+        assert!(!level("s1:c1").can_flow_to(&level("s1:c2")));
+    }
+
+    #[test]
+    fn test_from_dclabel() {
+        let context = Context::parse("u:r:t:s0").unwrap();
+        assert_eq!(
+            "u:r:t:s2:c1,c7",
+            context.clone().with_dclabel(&label("s2&c7&s1&c1,T")).unwrap().to_string()
+        );
+        for unrepresentable in ["F,T", "c1,amit", "c1|c2,T", "amit,T", "s2,T", "c01,T"] {
+            assert_eq!(
+                Err(Error::Unrepresentable),
+                context.clone().with_dclabel(&label(unrepresentable)),
+                "{}",
+                unrepresentable
+            );
+        }
+    }
+
+    quickcheck! {
+        fn level_round_trip(sensitivity: u8, categories: BTreeSet<u8>) -> bool {
+            let level = Level {
+                sensitivity: sensitivity.into(),
+                categories: categories.into_iter().map(Into::into).collect(),
+            };
+            Level::from_dclabel(&level.to_dclabel()) == Ok(level.clone())
+                && Level::parse(&level.to_string()) == Ok(level)
+        }
+    }
+}
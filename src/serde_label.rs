@@ -0,0 +1,82 @@
+//! Serde support shared by `DCLabel` and `Buckle`.
+//!
+//! Human-readable formats (JSON, YAML, ...) represent a label as a string in
+//! the label's textual syntax, e.g. `"amit|yue,T"`. Binary formats represent
+//! it as a `(secrecy, integrity)` tuple of components. Either way the
+//! deserialized label is reduced.
+//!
+//! These impls place no bound on the size of a label; input from untrusted
+//! sources should be deserialized with `bounded::BoundedSeed` instead.
+
+/// Implements `Serialize` and `Deserialize` for `$label`, parsing the
+/// textual syntax with the nom parser `$parser`.
+macro_rules! impl_serde {
+    ($label:ident, $parser:path, $expecting:literal) => {
+        impl serde::Serialize for $label {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeTuple;
+
+                if serializer.is_human_readable() {
+                    serializer.collect_str(self)
+                } else {
+                    let mut tuple = serializer.serialize_tuple(2)?;
+                    tuple.serialize_element(&self.secrecy)?;
+                    tuple.serialize_element(&self.integrity)?;
+                    tuple.end()
+                }
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $label {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct Visitor;
+
+                impl<'de> serde::de::Visitor<'de> for Visitor {
+                    type Value = $label;
+
+                    fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                        f.write_str($expecting)
+                    }
+
+                    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<$label, E> {
+                        nom::combinator::all_consuming($parser)(v)
+                            .map(|(_, label)| label)
+                            .map_err(|_| E::invalid_value(serde::de::Unexpected::Str(v), &self))
+                    }
+
+                    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<$label, A::Error> {
+                        let secrecy: Component = seq
+                            .next_element()?
+                            .ok_or_else(|| serde::de::Error::invalid_length(0, &self))?;
+                        let integrity: Component = seq
+                            .next_element()?
+                            .ok_or_else(|| serde::de::Error::invalid_length(1, &self))?;
+                        Ok($label::new(secrecy, integrity))
+                    }
+                }
+
+                if deserializer.is_human_readable() {
+                    deserializer.deserialize_str(Visitor)
+                } else {
+                    deserializer.deserialize_tuple(2, Visitor)
+                }
+            }
+        }
+
+        #[cfg(feature = "schemars")]
+        impl schemars::JsonSchema for $label {
+            fn schema_name() -> alloc::borrow::Cow<'static, str> {
+                stringify!($label).into()
+            }
+
+            fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+                schemars::json_schema!({
+                    "type": "string",
+                    "description": $expecting,
+                })
+            }
+        }
+    };
+}
+
+pub(crate) use impl_serde;
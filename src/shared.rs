@@ -0,0 +1,140 @@
+//! Copy-on-write values for the `arc` feature.
+//!
+//! Under `arc`, formulas share their clauses between clones as a
+//! `Shared<BTreeSet<Clause>>`, which behaves like an `Arc` except that
+//! `Shared::new_const` holds its value inline. That keeps `Component::dc_true`
+//! and the constant labels `const fn`s whether or not `arc` is on: the first
+//! write moves an inline value behind an `Arc`, and until then clones copy it,
+//! which for the empty set costs nothing.
+
+use alloc::sync::Arc;
+use core::ops::Deref;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A value shared between clones and copied on write.
+#[derive(Clone)]
+pub struct Shared<T>(Repr<T>);
+
+#[derive(Clone)]
+enum Repr<T> {
+    Inline(T),
+    Arc(Arc<T>),
+}
+
+impl<T> Shared<T> {
+    pub fn new(value: T) -> Self {
+        Shared(Repr::Arc(Arc::new(value)))
+    }
+
+    /// Holds `value` inline, so that it can be built in constants. Meant for
+    /// values that are free to copy, e.g. empty sets.
+    pub const fn new_const(value: T) -> Self {
+        Shared(Repr::Inline(value))
+    }
+
+    /// Returns the value for mutation if it isn't shared, as `Arc::get_mut`.
+    pub fn get_mut(this: &mut Self) -> Option<&mut T> {
+        match &mut this.0 {
+            Repr::Inline(value) => Some(value),
+            Repr::Arc(value) => Arc::get_mut(value),
+        }
+    }
+
+    /// Returns whether the value is held behind an `Arc` rather than inline.
+    pub(crate) fn is_allocated(this: &Self) -> bool {
+        matches!(this.0, Repr::Arc(_))
+    }
+
+    /// Returns whether `this` and `other` share their value, as `Arc::ptr_eq`.
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        match (&this.0, &other.0) {
+            (Repr::Arc(this), Repr::Arc(other)) => Arc::ptr_eq(this, other),
+            _ => false,
+        }
+    }
+}
+
+impl<T: Clone> Shared<T> {
+    /// Returns the value for mutation, first copying it if it is shared, as
+    /// `Arc::make_mut`.
+    pub fn make_mut(this: &mut Self) -> &mut T {
+        if let Repr::Inline(value) = &this.0 {
+            this.0 = Repr::Arc(Arc::new(value.clone()));
+        }
+        match &mut this.0 {
+            Repr::Arc(value) => Arc::make_mut(value),
+            Repr::Inline(value) => value,
+        }
+    }
+
+    /// Returns the value, copying it if it is shared, as
+    /// `Arc::unwrap_or_clone`.
+    pub fn unwrap_or_clone(this: Self) -> T {
+        match this.0 {
+            Repr::Inline(value) => value,
+            Repr::Arc(value) => Arc::unwrap_or_clone(value),
+        }
+    }
+}
+
+impl<T> Deref for Shared<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match &self.0 {
+            Repr::Inline(value) => value,
+            Repr::Arc(value) => value,
+        }
+    }
+}
+
+impl<T> From<T> for Shared<T> {
+    fn from(value: T) -> Self {
+        Shared::new(value)
+    }
+}
+
+impl<T: PartialEq> PartialEq for Shared<T> {
+    fn eq(&self, other: &Self) -> bool {
+        Shared::ptr_eq(self, other) || **self == **other
+    }
+}
+
+impl<T: Eq> Eq for Shared<T> {}
+
+impl<T: core::fmt::Debug> core::fmt::Debug for Shared<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: Serialize> Serialize for Shared<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (**self).serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Shared<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Shared::new)
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl<T: schemars::JsonSchema> schemars::JsonSchema for Shared<T> {
+    fn inline_schema() -> bool {
+        T::inline_schema()
+    }
+
+    fn schema_name() -> alloc::borrow::Cow<'static, str> {
+        T::schema_name()
+    }
+
+    fn schema_id() -> alloc::borrow::Cow<'static, str> {
+        T::schema_id()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        T::json_schema(generator)
+    }
+}
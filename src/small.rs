@@ -0,0 +1,375 @@
+//! DC labels whose small formulas are stored inline.
+//!
+//! Most labels in practice are one or two clauses of a single principal,
+//! e.g. `amit,T` or `amit&yue,amit`, yet a `DCLabel` allocates a tree node
+//! per clause and another per principal set. A `SmallLabel<N, M>` keeps up
+//! to `N` clauses of up to `M` principals each inline in the label, and only
+//! spills a component's clauses, or a clause's principals, to the heap once
+//! they outgrow that. Principals themselves are still `String`s.
+//!
+//! `SmallLabel` has the semantics of `DCLabel`, and converts to and from it
+//! with the `dclabel` feature.
+
+use crate::heap::Heap;
+use crate::{HasPrivilege, Label};
+use smallvec::SmallVec;
+
+#[cfg(not(feature = "smolstr"))]
+pub type Principal = alloc::string::String;
+#[cfg(feature = "smolstr")]
+pub type Principal = smol_str::SmolStr;
+
+/// A disjunction of principals, sorted and without duplicates, inline up to
+/// `M` principals.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Clause<const M: usize>(SmallVec<[Principal; M]>);
+
+impl<const M: usize> Clause<M> {
+    pub fn new<P: Into<Principal>, I: IntoIterator<Item = P>>(principals: I) -> Self {
+        let mut principals: SmallVec<[Principal; M]> = principals.into_iter().map(Into::into).collect();
+        principals.sort_unstable();
+        principals.dedup();
+        Clause(principals)
+    }
+
+    /// The principals in ascending order.
+    pub fn principals(&self) -> &[Principal] {
+        &self.0
+    }
+
+    /// Returns whether the principals are stored inline.
+    pub fn is_inline(&self) -> bool {
+        !self.0.spilled()
+    }
+
+    /// A clause implies another if its principals are a subset of the
+    /// other's.
+    pub fn implies(&self, other: &Self) -> bool {
+        self.0.len() <= other.0.len() && self.0.iter().all(|p| other.0.binary_search(p).is_ok())
+    }
+}
+
+impl<const M: usize> Heap for Clause<M> {
+    fn heap_size(&self) -> usize {
+        self.0.heap_size()
+    }
+
+    fn compact(&mut self) {
+        self.0.compact()
+    }
+}
+
+impl<P: Into<Principal>, const K: usize, const M: usize> From<[P; K]> for Clause<M> {
+    fn from(principals: [P; K]) -> Self {
+        Clause::new(principals)
+    }
+}
+
+/// A conjunction of clauses, sorted and without duplicates, inline up to `N`
+/// clauses, or false.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Component<const N: usize, const M: usize> {
+    DCFalse,
+    DCFormula(SmallVec<[Clause<M>; N]>),
+}
+
+impl<const N: usize, const M: usize> Component<N, M> {
+    pub fn formula<C: Into<Clause<M>>, I: IntoIterator<Item = C>>(clauses: I) -> Self {
+        let mut clauses: SmallVec<[Clause<M>; N]> = clauses.into_iter().map(Into::into).collect();
+        clauses.sort_unstable();
+        clauses.dedup();
+        Component::DCFormula(clauses)
+    }
+
+    pub fn dc_false() -> Self {
+        Component::DCFalse
+    }
+
+    pub fn dc_true() -> Self {
+        Component::DCFormula(SmallVec::new())
+    }
+
+    pub fn is_false(&self) -> bool {
+        matches!(self, Component::DCFalse)
+    }
+
+    pub fn is_true(&self) -> bool {
+        match self {
+            Component::DCFalse => false,
+            Component::DCFormula(clauses) => clauses.is_empty(),
+        }
+    }
+
+    /// Returns whether the clauses and their principals are stored inline.
+    pub fn is_inline(&self) -> bool {
+        match self {
+            Component::DCFalse => true,
+            Component::DCFormula(clauses) => !clauses.spilled() && clauses.iter().all(Clause::is_inline),
+        }
+    }
+
+    pub fn implies(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Component::DCFalse, _) => true,
+            (_, Component::DCFalse) => false,
+            (Component::DCFormula(s), Component::DCFormula(o)) => {
+                o.iter().all(|oclause| s.iter().any(|sclause| sclause.implies(oclause)))
+            }
+        }
+    }
+
+    /// Returns the approximate number of bytes the component owns on the
+    /// heap, zero if it and its principals' names are inline.
+    pub fn approx_heap_size(&self) -> usize {
+        match self {
+            Component::DCFalse => 0,
+            Component::DCFormula(clauses) => clauses.heap_size(),
+        }
+    }
+
+    /// Releases excess capacity, moving clauses and principals back inline
+    /// if they fit again.
+    pub fn shrink_to_fit(&mut self) {
+        if let Component::DCFormula(clauses) = self {
+            clauses.compact()
+        }
+    }
+
+    /// Removes the clauses implied by other clauses.
+    pub fn reduce(&mut self) {
+        if let Component::DCFormula(clauses) = self {
+            let redundant: SmallVec<[bool; N]> =
+                clauses.iter().map(|c| clauses.iter().any(|o| o != c && o.implies(c))).collect();
+            let mut redundant = redundant.into_iter();
+            clauses.retain(|_| !redundant.next().unwrap_or(false));
+        }
+    }
+}
+
+impl<const N: usize, const M: usize> core::ops::BitAnd for Component<N, M> {
+    type Output = Self;
+    fn bitand(self, rhs: Self) -> Self {
+        match (self, rhs) {
+            (Component::DCFormula(s), Component::DCFormula(o)) => Component::formula(s.into_iter().chain(o)),
+            _ => Component::DCFalse,
+        }
+    }
+}
+
+/// Disjoins the components as `DCLabel` does, extending each clause of
+/// `self` with every principal of `rhs`.
+impl<const N: usize, const M: usize> core::ops::BitOr for Component<N, M> {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self {
+        match (self, rhs) {
+            (s, Component::DCFalse) => s,
+            (Component::DCFalse, o) => o,
+            (s, o) if s.is_true() || o.is_true() => Component::dc_true(),
+            (Component::DCFormula(s), Component::DCFormula(o)) => {
+                Component::formula(s.iter().flat_map(|sclause| {
+                    o.iter().map(move |oclause| Clause::new(sclause.0.iter().chain(oclause.0.iter()).cloned()))
+                }))
+            }
+        }
+    }
+}
+
+/// A DC label storing up to `N` clauses of up to `M` principals per
+/// component inline, by default the two singleton clauses most labels have.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SmallLabel<const N: usize = 2, const M: usize = 1> {
+    pub secrecy: Component<N, M>,
+    pub integrity: Component<N, M>,
+}
+
+impl<const N: usize, const M: usize> SmallLabel<N, M> {
+    pub fn new(secrecy: Component<N, M>, integrity: Component<N, M>) -> Self {
+        let mut label = SmallLabel { secrecy, integrity };
+        label.reduce();
+        label
+    }
+
+    pub fn public() -> Self {
+        Self::new(Component::dc_true(), Component::dc_true())
+    }
+
+    pub fn top() -> Self {
+        Self::new(Component::dc_false(), Component::dc_true())
+    }
+
+    pub fn bottom() -> Self {
+        Self::new(Component::dc_true(), Component::dc_false())
+    }
+
+    pub fn reduce(&mut self) {
+        self.secrecy.reduce();
+        self.integrity.reduce();
+    }
+
+    /// Returns the approximate number of bytes the label owns on the heap.
+    pub fn approx_heap_size(&self) -> usize {
+        self.secrecy.approx_heap_size() + self.integrity.approx_heap_size()
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        self.secrecy.shrink_to_fit();
+        self.integrity.shrink_to_fit();
+    }
+
+    /// Returns whether the label is stored without heap allocations besides
+    /// its principals' names.
+    pub fn is_inline(&self) -> bool {
+        self.secrecy.is_inline() && self.integrity.is_inline()
+    }
+}
+
+impl<const N: usize, const M: usize> Label for SmallLabel<N, M> {
+    fn lub(self, rhs: Self) -> Self {
+        SmallLabel::new(self.secrecy & rhs.secrecy, self.integrity | rhs.integrity)
+    }
+
+    fn glb(self, rhs: Self) -> Self {
+        SmallLabel::new(self.secrecy | rhs.secrecy, self.integrity & rhs.integrity)
+    }
+
+    fn can_flow_to(&self, rhs: &Self) -> bool {
+        rhs.secrecy.implies(&self.secrecy) && self.integrity.implies(&rhs.integrity)
+    }
+}
+
+impl<const N: usize, const M: usize> HasPrivilege for SmallLabel<N, M> {
+    type Privilege = Component<N, M>;
+
+    fn downgrade(mut self, privilege: &Component<N, M>) -> Self {
+        self.secrecy = match (self.secrecy, privilege) {
+            (_, Component::DCFalse) => Component::dc_true(),
+            (Component::DCFalse, _) => Component::DCFalse,
+            (Component::DCFormula(mut sec), Component::DCFormula(p)) => {
+                sec.retain(|c| !p.iter().any(|pclause| pclause.implies(c)));
+                Component::DCFormula(sec)
+            }
+        };
+        self.integrity = privilege.clone() & self.integrity;
+        self
+    }
+
+    fn downgrade_to(self, target: Self, privilege: &Component<N, M>) -> Self {
+        if self.can_flow_to_with_privilege(&target, privilege) {
+            target
+        } else {
+            self
+        }
+    }
+
+    fn can_flow_to_with_privilege(&self, rhs: &Self, privilege: &Component<N, M>) -> bool {
+        (rhs.secrecy.clone() & privilege.clone()).implies(&self.secrecy)
+            && (self.integrity.clone() & privilege.clone()).implies(&rhs.integrity)
+    }
+}
+
+#[cfg(feature = "dclabel")]
+impl<const N: usize, const M: usize> From<&crate::dclabel::Component> for Component<N, M> {
+    fn from(component: &crate::dclabel::Component) -> Self {
+        match component {
+            crate::dclabel::Component::DCFalse => Component::DCFalse,
+            crate::dclabel::Component::DCFormula(clauses) => {
+                Component::formula(clauses.iter().map(|c| Clause::new(c.0.iter().cloned())))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "dclabel")]
+impl<const N: usize, const M: usize> From<Component<N, M>> for crate::dclabel::Component {
+    fn from(component: Component<N, M>) -> Self {
+        match component {
+            Component::DCFalse => crate::dclabel::Component::DCFalse,
+            Component::DCFormula(clauses) => crate::dclabel::Component::from(
+                clauses
+                    .into_iter()
+                    .map(|c| crate::dclabel::Clause::new_from_vec(c.0.into_vec()))
+                    .collect::<alloc::collections::BTreeSet<_>>(),
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "dclabel")]
+impl<const N: usize, const M: usize> From<&crate::dclabel::DCLabel> for SmallLabel<N, M> {
+    fn from(label: &crate::dclabel::DCLabel) -> Self {
+        SmallLabel { secrecy: (&label.secrecy).into(), integrity: (&label.integrity).into() }
+    }
+}
+
+#[cfg(feature = "dclabel")]
+impl<const N: usize, const M: usize> From<SmallLabel<N, M>> for crate::dclabel::DCLabel {
+    fn from(label: SmallLabel<N, M>) -> Self {
+        crate::dclabel::DCLabel::new(label.secrecy, label.integrity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inline() {
+        let amit: Component<2, 1> = Component::formula([["amit"]]);
+        let yue: Component<2, 1> = Component::formula([["yue"]]);
+        let lbl = SmallLabel::new(amit.clone(), Component::dc_true()).lub(SmallLabel::new(yue.clone(), amit.clone()));
+        assert_eq!(SmallLabel::new(Component::formula([["amit"], ["yue"]]), Component::dc_true()), lbl);
+        assert!(lbl.is_inline());
+
+        // A third clause spills the secrecy, a disjunction the integrity.
+        let deian: Component<2, 1> = Component::formula([["deian"]]);
+        assert!(!SmallLabel::new(lbl.secrecy.clone() & deian, Component::dc_true()).is_inline());
+        assert!(!SmallLabel::new(Component::dc_true(), amit | yue).is_inline());
+    }
+
+    #[test]
+    fn test_shrink_to_fit() {
+        let mut lbl: SmallLabel = SmallLabel::new(Component::formula([["amit"], ["yue"], ["deian"]]), Component::dc_true());
+        let size = lbl.approx_heap_size();
+        if let Component::DCFormula(clauses) = &mut lbl.secrecy {
+            clauses.pop();
+        }
+        assert!(!lbl.is_inline());
+        lbl.shrink_to_fit();
+        assert!(lbl.is_inline());
+        assert!(lbl.approx_heap_size() < size);
+    }
+
+    #[test]
+    fn test_flow() {
+        let amit: Component<2, 1> = Component::formula([["amit"]]);
+        let both = SmallLabel::new(Component::formula([["amit"], ["yue"]]), Component::dc_true());
+        let lbl = SmallLabel::new(amit.clone(), Component::dc_true());
+        assert!(lbl.can_flow_to(&both));
+        assert!(!both.can_flow_to(&lbl));
+        let yue = Component::formula([["yue"]]);
+        assert!(both.can_flow_to_with_privilege(&lbl, &yue));
+        assert_eq!(SmallLabel::new(amit, yue.clone()), both.downgrade(&yue));
+    }
+
+    #[cfg(feature = "dclabel")]
+    quickcheck! {
+        fn agrees_with_dclabel(lbl1: crate::dclabel::DCLabel, lbl2: crate::dclabel::DCLabel, privilege: crate::dclabel::Component) -> bool {
+            use crate::dclabel::DCLabel;
+
+            let (s1, s2) = (SmallLabel::<2, 1>::from(&lbl1), SmallLabel::<2, 1>::from(&lbl2));
+            let sprivilege = Component::from(&privilege);
+            let reduced = |l: SmallLabel<2, 1>| {
+                let mut l = DCLabel::from(l);
+                l.reduce();
+                l
+            };
+            let mut downgraded = lbl1.clone().downgrade(&privilege);
+            downgraded.reduce();
+            reduced(s1.clone().lub(s2.clone())) == lbl1.clone().lub(lbl2.clone())
+                && reduced(s1.clone().glb(s2.clone())) == lbl1.clone().glb(lbl2.clone())
+                && s1.can_flow_to(&s2) == lbl1.can_flow_to(&lbl2)
+                && s1.can_flow_to_with_privilege(&s2, &sprivilege) == lbl1.can_flow_to_with_privilege(&lbl2, &privilege)
+                && reduced(s1.downgrade(&sprivilege)) == downgraded
+        }
+    }
+}
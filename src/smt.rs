@@ -0,0 +1,220 @@
+//! A logical oracle for component implication.
+//!
+//! `Component::implies` decides implication syntactically, clause by clause,
+//! which is only sound and complete because components are monotone
+//! formulas. This module decides it semantically instead, to check the
+//! syntactic version against: components are translated to propositional
+//! formulas with one variable per principal, and `entails` asks a small
+//! built-in DPLL solver whether the first formula and the negation of the
+//! second are unsatisfiable together.
+//!
+//! For `Buckle` and `Buckle2`, each path is a variable, and every path is
+//! constrained to imply the longer paths it prefixes, so e.g. `a` entails
+//! `a/b`. The solver is exponential in the worst case and meant for tests.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+/// A literal: a variable numbered from 1, negated if negative.
+pub type Literal = i32;
+
+/// A component that can be read as a propositional formula in CNF over
+/// atoms.
+pub trait Propositional {
+    type Atom: Ord + Clone;
+
+    /// Returns the component's clauses of atoms, or `None` if it is false.
+    fn cnf(&self) -> Option<Vec<Vec<Self::Atom>>>;
+
+    /// Returns whether `atom` implies `other`, which must be distinct.
+    fn atom_implies(_atom: &Self::Atom, _other: &Self::Atom) -> bool {
+        false
+    }
+}
+
+/// Returns whether the clauses, of literals, are satisfiable together.
+pub fn satisfiable(mut clauses: Vec<Vec<Literal>>) -> bool {
+    // Propagates unit clauses before branching.
+    while let Some(unit) = clauses.iter().find(|c| c.len() == 1).map(|c| c[0]) {
+        clauses = assign(clauses, unit);
+    }
+    if clauses.iter().any(Vec::is_empty) {
+        return false;
+    }
+    match clauses.first().map(|c| c[0]) {
+        None => true,
+        Some(literal) => satisfiable(assign(clauses.clone(), literal)) || satisfiable(assign(clauses, -literal)),
+    }
+}
+
+/// Makes `literal` true: drops the clauses it satisfies, and its negation
+/// from the rest.
+fn assign(clauses: Vec<Vec<Literal>>, literal: Literal) -> Vec<Vec<Literal>> {
+    clauses
+        .into_iter()
+        .filter(|c| !c.contains(&literal))
+        .map(|mut c| {
+            c.retain(|&l| l != -literal);
+            c
+        })
+        .collect()
+}
+
+/// Returns whether `component` logically entails `other`.
+///
+/// As in `implies`, only `DCFalse` entails `DCFalse`.
+pub fn entails<C: Propositional>(component: &C, other: &C) -> bool {
+    let (premise, conclusion) = match (component.cnf(), other.cnf()) {
+        (None, _) => return true,
+        // `DCFalse` is the top of the order, which only it implies, even
+        // though the formula of the empty clause is also false.
+        (_, None) => return false,
+        (Some(premise), Some(conclusion)) => (premise, conclusion),
+    };
+    let mut variables: BTreeMap<C::Atom, Literal> = BTreeMap::new();
+    let mut variable = |atom: &C::Atom| {
+        let next = variables.len() as Literal + 1;
+        *variables.entry(atom.clone()).or_insert(next)
+    };
+    let premise: Vec<Vec<Literal>> = premise.iter().map(|c| c.iter().map(&mut variable).collect()).collect();
+    let conclusion: Vec<Vec<Literal>> = conclusion.iter().map(|c| c.iter().map(&mut variable).collect()).collect();
+
+    let mut axioms = premise;
+    for (atom, &x) in variables.iter() {
+        for (other, &y) in variables.iter() {
+            if x != y && C::atom_implies(atom, other) {
+                axioms.push(alloc::vec![-x, y]);
+            }
+        }
+    }
+    // The premise entails each clause if it contradicts its negation, i.e.
+    // every literal in it being false.
+    conclusion.iter().all(|clause| {
+        let mut clauses = axioms.clone();
+        clauses.extend(clause.iter().map(|&l| alloc::vec![-l]));
+        !satisfiable(clauses)
+    })
+}
+
+#[cfg(feature = "dclabel")]
+impl Propositional for crate::dclabel::Component {
+    type Atom = crate::dclabel::Principal;
+
+    fn cnf(&self) -> Option<Vec<Vec<Self::Atom>>> {
+        match self {
+            crate::dclabel::Component::DCFalse => None,
+            crate::dclabel::Component::DCFormula(clauses) => {
+                Some(clauses.iter().map(|c| c.0.iter().cloned().collect()).collect())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "buckle")]
+impl Propositional for crate::buckle::Component {
+    type Atom = Vec<crate::buckle::Principal>;
+
+    fn cnf(&self) -> Option<Vec<Vec<Self::Atom>>> {
+        match self {
+            crate::buckle::Component::DCFalse => None,
+            crate::buckle::Component::DCFormula(clauses) => {
+                Some(clauses.iter().map(|c| c.0.iter().cloned().collect()).collect())
+            }
+        }
+    }
+
+    fn atom_implies(atom: &Self::Atom, other: &Self::Atom) -> bool {
+        other.starts_with(atom)
+    }
+}
+
+#[cfg(feature = "buckle2")]
+impl<A: allocator_api2::alloc::Allocator + Clone> Propositional for crate::buckle2::Component<A> {
+    type Atom = Vec<Vec<u8>>;
+
+    fn cnf(&self) -> Option<Vec<Vec<Self::Atom>>> {
+        match self {
+            crate::buckle2::Component::DCFalse => None,
+            crate::buckle2::Component::DCFormula(clauses, _) => Some(
+                clauses
+                    .iter()
+                    .map(|c| c.0.iter().map(|path| path.iter().map(|p| p.to_vec()).collect()).collect())
+                    .collect(),
+            ),
+        }
+    }
+
+    fn atom_implies(atom: &Self::Atom, other: &Self::Atom) -> bool {
+        other.starts_with(atom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_satisfiable() {
+        assert!(satisfiable(alloc::vec![]));
+        assert!(!satisfiable(alloc::vec![alloc::vec![]]));
+        assert!(satisfiable(alloc::vec![alloc::vec![1, 2], alloc::vec![-1]]));
+        assert!(!satisfiable(alloc::vec![alloc::vec![1, 2], alloc::vec![-1], alloc::vec![-2]]));
+        assert!(!satisfiable(alloc::vec![
+            alloc::vec![1, 2],
+            alloc::vec![1, -2],
+            alloc::vec![-1, 2],
+            alloc::vec![-1, -2]
+        ]));
+    }
+
+    /// Returns a component over the paths `a`, `b`, `a/b` and `a/b/c`, one
+    /// clause per byte with the paths of its low bits, so that components
+    /// share principals and paths prefix each other.
+    #[cfg(feature = "buckle")]
+    fn buckle_component(clauses: &[u8]) -> crate::buckle::Component {
+        const PATHS: [&[&str]; 4] = [&["a"], &["b"], &["a", "b"], &["a", "b", "c"]];
+        let clauses = clauses.iter().take(4).map(|bits| {
+            crate::buckle::Clause::new_from_vec(
+                PATHS.iter().enumerate().filter(|(i, _)| bits & (1 << i) != 0).map(|(_, p)| p.to_vec()).collect(),
+            )
+        });
+        crate::buckle::Component::from(clauses.collect::<alloc::collections::BTreeSet<_>>())
+    }
+
+    #[cfg(feature = "buckle")]
+    #[test]
+    fn test_prefix_entailment() {
+        let a = crate::buckle::Component::from([["a"]]);
+        let ab = crate::buckle::Component::from([crate::buckle::Clause::new_from_vec(alloc::vec![alloc::vec!["a", "b"]])]);
+        assert!(entails(&a, &ab));
+        assert!(!entails(&ab, &a));
+        assert!(entails(&crate::buckle::Component::DCFalse, &a));
+        assert!(!entails(&a, &crate::buckle::Component::DCFalse));
+    }
+
+    #[cfg(feature = "dclabel")]
+    quickcheck! {
+        fn dclabel_implies_is_entailment(component: crate::dclabel::Component, other: crate::dclabel::Component) -> bool {
+            component.implies(&other) == entails(&component, &other)
+        }
+    }
+
+    #[cfg(feature = "buckle")]
+    quickcheck! {
+        fn buckle_implies_is_entailment(component: crate::buckle::Component, other: crate::buckle::Component) -> bool {
+            component.implies(&other) == entails(&component, &other)
+        }
+
+        fn buckle_implies_is_entailment_on_paths(component: alloc::vec::Vec<u8>, other: alloc::vec::Vec<u8>) -> bool {
+            let (component, other) = (buckle_component(&component), buckle_component(&other));
+            component.implies(&other) == entails(&component, &other)
+        }
+    }
+
+    #[cfg(feature = "buckle2")]
+    quickcheck! {
+        fn buckle2_implies_is_entailment(component: crate::buckle2::Component, other: crate::buckle2::Component) -> bool {
+            component.implies(&other) == entails(&component, &other)
+        }
+    }
+}
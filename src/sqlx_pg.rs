@@ -0,0 +1,105 @@
+//! `sqlx` support for storing labels in Postgres.
+//!
+//! `DCLabel` and `Buckle` map to `TEXT` (or `VARCHAR`) columns holding the
+//! label's textual syntax, e.g. `amit/grades|yue,T`. Wrapping a label in
+//! `Binary` instead maps it to a `BYTEA` column holding its envelope (see
+//! `envelope`), which is more compact and also supports `Buckle2`.
+
+use crate::envelope::{self, Enveloped};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef, Postgres};
+use sqlx::{Decode, Encode, Type};
+
+/// Implements the text mapping for `$label`, parsing with the nom parser
+/// `$parser`.
+#[cfg(any(feature = "dclabel", feature = "buckle"))]
+macro_rules! impl_text {
+    ($label:path, $parser:path) => {
+        impl Type<Postgres> for $label {
+            fn type_info() -> PgTypeInfo {
+                <&str as Type<Postgres>>::type_info()
+            }
+
+            fn compatible(ty: &PgTypeInfo) -> bool {
+                <&str as Type<Postgres>>::compatible(ty)
+            }
+        }
+
+        impl Encode<'_, Postgres> for $label {
+            fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+                use alloc::string::ToString;
+                <&str as Encode<Postgres>>::encode(&self.to_string(), buf)
+            }
+        }
+
+        impl Decode<'_, Postgres> for $label {
+            fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+                let text = <&str as Decode<Postgres>>::decode(value)?;
+                nom::combinator::all_consuming($parser)(text)
+                    .map(|(_, label)| label)
+                    .map_err(|_| alloc::format!("invalid label `{}`", text).into())
+            }
+        }
+    };
+}
+
+#[cfg(feature = "dclabel")]
+impl_text!(crate::dclabel::DCLabel, crate::dclabel::DCLabel::parse);
+#[cfg(feature = "buckle")]
+impl_text!(crate::buckle::Buckle, crate::buckle::Buckle::parser);
+
+/// A label stored in a `BYTEA` column in its envelope encoding.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Binary<L>(pub L);
+
+impl<L> Type<Postgres> for Binary<L> {
+    fn type_info() -> PgTypeInfo {
+        <&[u8] as Type<Postgres>>::type_info()
+    }
+}
+
+impl<L: Enveloped> Encode<'_, Postgres> for Binary<L> {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        buf.extend_from_slice(&envelope::encode(&self.0));
+        Ok(IsNull::No)
+    }
+}
+
+impl<L: Enveloped> Decode<'_, Postgres> for Binary<L> {
+    fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+        let bytes = <alloc::vec::Vec<u8> as Decode<Postgres>>::decode(value)?;
+        envelope::decode(&bytes)
+            .map(Binary)
+            .map_err(|e| alloc::format!("invalid label envelope: {:?}", e).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "buckle")]
+    #[test]
+    fn test_buckle_text() {
+        use crate::buckle::Buckle;
+
+        assert!(<Buckle as Type<Postgres>>::compatible(&PgTypeInfo::with_name("VARCHAR")));
+        assert!(!<Buckle as Type<Postgres>>::compatible(&PgTypeInfo::with_name("BYTEA")));
+        let mut buf = PgArgumentBuffer::default();
+        let lbl = Buckle::parse("amit/grades|yue,T").unwrap();
+        assert!(matches!(Encode::<Postgres>::encode_by_ref(&lbl, &mut buf), Ok(IsNull::No)));
+        assert_eq!(b"amit/grades|yue,T", &buf[..]);
+    }
+
+    #[cfg(feature = "buckle2")]
+    #[test]
+    fn test_buckle2_binary() {
+        use crate::buckle2::Buckle2;
+
+        let mut buf = PgArgumentBuffer::default();
+        let lbl = Binary(Buckle2::top());
+        assert!(matches!(Encode::<Postgres>::encode_by_ref(&lbl, &mut buf), Ok(IsNull::No)));
+        assert_eq!(envelope::encode(&lbl.0), &buf[..]);
+    }
+}
@@ -0,0 +1,199 @@
+//! Proptest strategies for `Buckle` and `Buckle2` labels.
+//!
+//! The strategies mirror the quickcheck support in `generate`, but draw
+//! principals from a handful of short names and paths at most three deep,
+//! so that generated labels share principals and their paths prefix each
+//! other, the cases where hierarchical implication matters. Every value is
+//! well formed: principals, paths and clauses are nonempty and components
+//! reduced. Shrinking works on the underlying collections and rebuilds the
+//! value from them, so shrunk values are well formed too.
+//!
+//! The label types, clauses and components implement proptest's
+//! `Arbitrary`, so `any::<Buckle>()` uses these strategies.
+
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+use proptest::arbitrary::Arbitrary;
+use proptest::collection::{btree_set, vec};
+use proptest::prelude::*;
+
+/// The names principals are drawn from.
+pub const PRINCIPALS: [&str; 4] = ["a", "b", "c", "d"];
+
+/// The most paths in a clause, and clauses in a component.
+const MAX_LEN: usize = 3;
+
+/// Returns a strategy for principal paths of one to three names.
+pub fn path() -> impl Strategy<Value = Vec<&'static str>> {
+    vec(proptest::sample::select(&PRINCIPALS[..]), 1..=MAX_LEN)
+}
+
+/// Returns a strategy for the paths of a nonempty clause.
+fn paths() -> impl Strategy<Value = BTreeSet<Vec<&'static str>>> {
+    btree_set(path(), 1..=MAX_LEN)
+}
+
+/// Returns a strategy for components, given one for their clauses, which
+/// are `DCFalse` a fifth of the time.
+fn component<C: Ord + core::fmt::Debug, T: Clone + core::fmt::Debug>(
+    clause: impl Strategy<Value = C>,
+    formula: impl Fn(BTreeSet<C>) -> T,
+    dc_false: T,
+) -> impl Strategy<Value = T> {
+    prop_oneof![1 => Just(dc_false), 4 => btree_set(clause, 0..=MAX_LEN).prop_map(formula)]
+}
+
+#[cfg(feature = "buckle")]
+pub mod buckle {
+    use super::*;
+    use crate::buckle::{Buckle, Clause, Component};
+
+    pub fn clause() -> impl Strategy<Value = Clause> {
+        paths().prop_map(|paths| Clause::new_from_vec(paths.into_iter().collect()))
+    }
+
+    /// Returns a strategy for reduced components.
+    pub fn component() -> impl Strategy<Value = Component> {
+        super::component(
+            clause(),
+            |clauses| {
+                let mut component = Component::from(clauses);
+                component.reduce();
+                component
+            },
+            Component::DCFalse,
+        )
+    }
+
+    pub fn label() -> impl Strategy<Value = Buckle> {
+        (component(), component()).prop_map(|(secrecy, integrity)| Buckle::new(secrecy, integrity))
+    }
+
+    /// Returns a strategy for privileges, which are components.
+    pub fn privilege() -> impl Strategy<Value = Component> {
+        component()
+    }
+
+    impl Arbitrary for Clause {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: ()) -> Self::Strategy {
+            clause().boxed()
+        }
+    }
+
+    impl Arbitrary for Component {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: ()) -> Self::Strategy {
+            component().boxed()
+        }
+    }
+
+    impl Arbitrary for Buckle {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: ()) -> Self::Strategy {
+            label().boxed()
+        }
+    }
+}
+
+#[cfg(feature = "buckle2")]
+pub mod buckle2 {
+    use super::*;
+    use crate::buckle2::{Buckle2, Clause, Component};
+
+    pub fn clause() -> impl Strategy<Value = Clause> {
+        paths().prop_map(|paths| Clause::new_from_vec(paths.into_iter().collect()))
+    }
+
+    /// Returns a strategy for reduced components.
+    pub fn component() -> impl Strategy<Value = Component> {
+        super::component(
+            clause(),
+            |clauses| {
+                let mut component = Component::from(clauses);
+                component.reduce();
+                component
+            },
+            Component::DCFalse,
+        )
+    }
+
+    pub fn label() -> impl Strategy<Value = Buckle2> {
+        (component(), component()).prop_map(|(secrecy, integrity)| Buckle2::new(secrecy, integrity))
+    }
+
+    /// Returns a strategy for privileges, which are components.
+    pub fn privilege() -> impl Strategy<Value = Component> {
+        component()
+    }
+
+    impl Arbitrary for Clause {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: ()) -> Self::Strategy {
+            clause().boxed()
+        }
+    }
+
+    impl Arbitrary for Component {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: ()) -> Self::Strategy {
+            component().boxed()
+        }
+    }
+
+    impl Arbitrary for Buckle2 {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: ()) -> Self::Strategy {
+            label().boxed()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "buckle")]
+    use crate::HasPrivilege;
+    #[cfg(feature = "buckle2")]
+    use crate::Label;
+
+    #[cfg(feature = "buckle")]
+    proptest! {
+        #[test]
+        fn buckle_well_formed(label in any::<crate::buckle::Buckle>(), privilege in buckle::privilege()) {
+            prop_assert!(label.is_reduced() && privilege.is_reduced());
+            for component in [&label.secrecy, &label.integrity, &privilege] {
+                if let crate::buckle::Component::DCFormula(clauses) = component {
+                    prop_assert!(clauses.iter().all(|c| !c.0.is_empty() && c.check().is_ok()));
+                }
+            }
+            prop_assert!(label.clone().downgrade(&privilege).can_flow_to_with_privilege(&label, &privilege));
+        }
+    }
+
+    #[cfg(feature = "buckle2")]
+    proptest! {
+        #[test]
+        fn buckle2_well_formed(label in any::<crate::buckle2::Buckle2>(), other in buckle2::label()) {
+            prop_assert!(label.is_reduced());
+            for component in [&label.secrecy, &label.integrity] {
+                if let crate::buckle2::Component::DCFormula(clauses, _) = component {
+                    prop_assert!(clauses.iter().all(|c| !c.0.is_empty() && c.check().is_ok()));
+                }
+            }
+            prop_assert!(label.can_flow_to(&label.clone().lub(other)));
+        }
+    }
+}
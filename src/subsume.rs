@@ -0,0 +1,213 @@
+//! Clause reduction through a subsumption index.
+//!
+//! Reducing a component removes every clause implied by another clause.
+//! Comparing all pairs of clauses is quadratic, but a clause can only imply
+//! clauses sharing its key: for principal sets, the least principal of the
+//! implying clause is in the implied one, and for paths, the first principal
+//! of the implying clause's least path starts a path of the implied one.
+//! Sorting the clauses by key, and then by length, makes the candidates
+//! implying a clause the few ranges found by binary search for its keys, so
+//! reduction takes O(n log n) comparisons for clauses of distinct
+//! principals. Clauses without a key, e.g. the empty clause, may imply any
+//! clause and are candidates for all.
+//!
+//! The index borrows the clauses and allocates nothing itself, so each
+//! component keeps it in storage of its choosing. An `Index` instead owns
+//! its clauses, for privileges whose clauses are matched against many
+//! labels.
+
+/// A clause that can be indexed by `redundant`.
+pub(crate) trait Subsumes {
+    type Key: Ord + ?Sized;
+
+    /// The key every clause this one implies has among its `keys`, or `None`
+    /// if it may imply clauses without it.
+    fn key(&self) -> Option<&Self::Key>;
+
+    /// The keys of the clauses that may imply this one, in ascending order.
+    fn keys(&self) -> impl Iterator<Item = &Self::Key>;
+
+    fn len(&self) -> usize;
+
+    fn implies(&self, other: &Self) -> bool;
+}
+
+/// A clause of the index, with its position among the component's clauses.
+pub(crate) struct Entry<'a, C: Subsumes> {
+    key: Option<&'a C::Key>,
+    len: usize,
+    position: usize,
+    clause: &'a C,
+}
+
+impl<'a, C: Subsumes> Entry<'a, C> {
+    pub(crate) fn new(position: usize, clause: &'a C) -> Self {
+        Entry { key: clause.key(), len: clause.len(), position, clause }
+    }
+}
+
+/// Calls `redundant` with the position of every clause `reduce` removes, in
+/// ascending order: those implied by another clause, keeping the least of
+/// equivalent clauses. `clauses` are the component's clauses in ascending
+/// order, and `index` holds an `Entry` for each.
+///
+/// Checks at most `budget` pairs of clauses for implication, deducting the
+/// checks made from it. Returns whether it found every redundant clause; if
+/// not, the positions it reported are still of redundant clauses, and
+/// removing them leaves an equivalent component.
+pub(crate) fn redundant<'a, C, I, F>(index: &mut [Entry<'a, C>], clauses: I, budget: &mut usize, mut redundant: F) -> bool
+where
+    C: Subsumes + 'a,
+    I: IntoIterator<Item = &'a C>,
+    F: FnMut(usize),
+{
+    sort(index);
+    let exhausted = core::cell::Cell::new(false);
+    let mut implies = |entry: &Entry<'_, C>, position: usize, clause: &C| {
+        if entry.position == position {
+            return false;
+        } else if *budget == 0 {
+            exhausted.set(true);
+            return false;
+        }
+        *budget -= 1;
+        implies(entry, position, clause)
+    };
+    for (position, clause) in clauses.into_iter().enumerate() {
+        if any_candidate(index, clause, |e| implies(e, position, clause)) {
+            redundant(position);
+        } else if exhausted.get() {
+            return false;
+        }
+    }
+    true
+}
+
+/// Sorts `index` as `redundant` and `is_redundant` expect: keyless entries
+/// first, and shorter clauses first within a key.
+pub(crate) fn sort<C: Subsumes>(index: &mut [Entry<'_, C>]) {
+    index.sort_unstable_by(|e, f| (e.key, e.len, e.position).cmp(&(f.key, f.len, f.position)));
+}
+
+/// Returns whether `reduce` removes the clause at `position`, given the
+/// sorted `index` of the component's clauses. Each clause is looked up
+/// independently, so the lookups of a component can run on many threads.
+#[cfg(feature = "rayon")]
+pub(crate) fn is_redundant<C: Subsumes>(index: &[Entry<'_, C>], position: usize, clause: &C) -> bool {
+    any_candidate(index, clause, |e| implies(e, position, clause))
+}
+
+/// Returns whether `f` holds for an entry of the sorted `index` that may
+/// imply `clause`.
+fn any_candidate<'a, C, F>(index: &[Entry<'a, C>], clause: &C, mut f: F) -> bool
+where
+    C: Subsumes,
+    F: FnMut(&Entry<'a, C>) -> bool,
+{
+    let mut previous = None;
+    index.iter().take_while(|e| e.key.is_none()).any(&mut f)
+        || clause.keys().any(|key| {
+            if previous == Some(key) {
+                return false;
+            }
+            previous = Some(key);
+            let start = index.partition_point(|e| e.key < Some(key));
+            index.iter().skip(start).take_while(|e| e.key == Some(key)).any(&mut f)
+        })
+}
+
+/// Returns whether `entry` makes the clause at `position` redundant.
+fn implies<C: Subsumes>(entry: &Entry<'_, C>, position: usize, clause: &C) -> bool {
+    entry.position != position
+        && entry.clause.implies(clause)
+        && (entry.position < position || !clause.implies(entry.clause))
+}
+
+/// Clauses sorted like the entries of `redundant`, with their fingerprints,
+/// answering whether any of them implies a clause.
+#[cfg(any(feature = "dclabel", feature = "buckle"))]
+#[derive(Debug, Clone)]
+pub(crate) struct Index<C> {
+    clauses: alloc::vec::Vec<(u64, C)>,
+    keyless: usize,
+}
+
+#[cfg(any(feature = "dclabel", feature = "buckle"))]
+impl<C: Subsumes> Index<C> {
+    /// Indexes clauses, each paired with its fingerprint.
+    pub(crate) fn new(mut clauses: alloc::vec::Vec<(u64, C)>) -> Self {
+        clauses.sort_by(|(_, c), (_, d)| (c.key(), c.len()).cmp(&(d.key(), d.len())));
+        let keyless = clauses.partition_point(|(_, c)| c.key().is_none());
+        Index { clauses, keyless }
+    }
+
+    /// Returns whether an indexed clause implies `clause`, whose
+    /// fingerprint is `fingerprint`.
+    pub(crate) fn implies(&self, fingerprint: u64, clause: &C) -> bool {
+        let implies = |(f, c): &(u64, C)| crate::fingerprint::may_imply(*f, fingerprint) && c.implies(clause);
+        let mut previous = None;
+        self.clauses.iter().take(self.keyless).any(implies)
+            || clause.keys().any(|key| {
+                if previous == Some(key) {
+                    return false;
+                }
+                previous = Some(key);
+                let start = self.clauses.partition_point(|(_, c)| c.key() < Some(key));
+                self.clauses.iter().skip(start).take_while(|(_, c)| c.key() == Some(key)).any(implies)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    /// Whether `redundant` agrees with comparing every pair of `clauses`,
+    /// given in ascending order.
+    fn agrees_with_pairwise<C: Subsumes>(clauses: &[&C]) -> bool {
+        let mut index: Vec<_> = clauses.iter().enumerate().map(|(i, c)| Entry::new(i, *c)).collect();
+        let mut positions = Vec::new();
+        // A budget of every pair of clauses suffices.
+        let finished = redundant(&mut index, clauses.iter().copied(), &mut (clauses.len() * clauses.len()), |i| positions.push(i));
+        let expected: Vec<usize> = clauses
+            .iter()
+            .enumerate()
+            .filter(|(i, c)| {
+                clauses.iter().enumerate().any(|(j, o)| i != &j && o.implies(c) && (j < *i || !c.implies(o)))
+            })
+            .map(|(i, _)| i)
+            .collect();
+        finished && positions == expected
+    }
+
+    #[cfg(feature = "dclabel")]
+    quickcheck! {
+        fn dclabel_agrees_with_pairwise(component: crate::dclabel::Component) -> bool {
+            match component {
+                crate::dclabel::Component::DCFalse => true,
+                crate::dclabel::Component::DCFormula(clauses) => agrees_with_pairwise(&clauses.iter().collect::<Vec<_>>()),
+            }
+        }
+    }
+
+    #[cfg(feature = "buckle")]
+    quickcheck! {
+        fn buckle_agrees_with_pairwise(component: crate::buckle::Component) -> bool {
+            match component {
+                crate::buckle::Component::DCFalse => true,
+                crate::buckle::Component::DCFormula(clauses) => agrees_with_pairwise(&clauses.iter().collect::<Vec<_>>()),
+            }
+        }
+    }
+
+    #[cfg(feature = "buckle2")]
+    quickcheck! {
+        fn buckle2_agrees_with_pairwise(component: crate::buckle2::Component) -> bool {
+            match component {
+                crate::buckle2::Component::DCFalse => true,
+                crate::buckle2::Component::DCFormula(clauses, _) => agrees_with_pairwise(&clauses.iter().collect::<Vec<_>>()),
+            }
+        }
+    }
+}
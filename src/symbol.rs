@@ -0,0 +1,351 @@
+//! Labels over interned principals.
+//!
+//! An `Interner` maps principal names to `Symbol`s, small integers that are
+//! compared and ordered as integers. `SymbolLabel` is a label whose paths are
+//! sequences of symbols, with the same semantics as `Buckle`: a path implies
+//! the paths it's a prefix of. Both `DCLabel`s, whose principals become paths
+//! of length one, and `Buckle` labels can be interned into `SymbolLabel`s,
+//! which then share the memory of repeated principal names, and resolved
+//! back.
+//!
+//! Symbols are only meaningful with the interner that created them, and
+//! labels interned with different interners must not be compared. Applications
+//! wanting a process-wide interner can keep one behind a lock of their
+//! choosing.
+
+use crate::{HasPrivilege, Label};
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::num::NonZeroU32;
+
+/// An interned principal name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(NonZeroU32);
+
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    names: Vec<Arc<str>>,
+    symbols: BTreeMap<Arc<str>, Symbol>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the symbol for `name`, interning it if needed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if more than `u32::MAX - 1` names are interned.
+    pub fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(name) {
+            return *symbol;
+        }
+        let symbol = u32::try_from(self.names.len() + 1)
+            .ok()
+            .and_then(NonZeroU32::new)
+            .map(Symbol)
+            .expect("too many interned names");
+        let name: Arc<str> = name.into();
+        self.names.push(name.clone());
+        self.symbols.insert(name, symbol);
+        symbol
+    }
+
+    /// Returns the symbol for `name`, if interned.
+    pub fn get(&self, name: &str) -> Option<Symbol> {
+        self.symbols.get(name).copied()
+    }
+
+    /// Returns the name of `symbol`, if created by this interner.
+    pub fn resolve(&self, symbol: Symbol) -> Option<&str> {
+        self.names.get(symbol.0.get() as usize - 1).map(|name| &**name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+/// A disjunction of paths of symbols.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Clause(pub BTreeSet<Vec<Symbol>>);
+
+impl Clause {
+    pub fn implies(&self, other: &Self) -> bool {
+        self.0.iter().all(|s| other.0.iter().any(|o| o.starts_with(s)))
+    }
+}
+
+/// A conjunction of clauses, or false.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Component {
+    DCFalse,
+    DCFormula(BTreeSet<Clause>),
+}
+
+impl Component {
+    pub fn dc_true() -> Self {
+        Component::DCFormula(BTreeSet::new())
+    }
+
+    pub fn implies(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Component::DCFalse, _) => true,
+            (_, Component::DCFalse) => false,
+            (Component::DCFormula(s), Component::DCFormula(o)) => {
+                o.iter().all(|oclause| s.iter().any(|sclause| sclause.implies(oclause)))
+            }
+        }
+    }
+
+    /// Removes the clauses implied by other clauses, keeping the least of
+    /// equivalent clauses.
+    pub fn reduce(&mut self) {
+        if let Component::DCFormula(clauses) = self {
+            let redundant: Vec<_> = clauses
+                .iter()
+                .filter(|c| clauses.iter().any(|o| o != *c && o.implies(c) && (o < *c || !c.implies(o))))
+                .cloned()
+                .collect();
+            for clause in redundant.iter() {
+                clauses.remove(clause);
+            }
+        }
+    }
+}
+
+impl core::ops::BitAnd for Component {
+    type Output = Component;
+    fn bitand(self, rhs: Self) -> Component {
+        match (self, rhs) {
+            (Component::DCFormula(mut s), Component::DCFormula(mut o)) => {
+                s.append(&mut o);
+                Component::DCFormula(s)
+            }
+            _ => Component::DCFalse,
+        }
+    }
+}
+
+/// Distributes the disjunction over both conjunctions.
+impl core::ops::BitOr for Component {
+    type Output = Component;
+    fn bitor(self, rhs: Self) -> Component {
+        match (self, rhs) {
+            (s, Component::DCFalse) => s,
+            (Component::DCFalse, o) => o,
+            (Component::DCFormula(s), Component::DCFormula(o)) => Component::DCFormula(
+                s.iter()
+                    .flat_map(|sclause| {
+                        o.iter().map(move |oclause| Clause(sclause.0.union(&oclause.0).cloned().collect()))
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SymbolLabel {
+    pub secrecy: Component,
+    pub integrity: Component,
+}
+
+impl SymbolLabel {
+    pub fn reduce(&mut self) {
+        self.secrecy.reduce();
+        self.integrity.reduce();
+    }
+}
+
+impl Label for SymbolLabel {
+    fn lub(self, rhs: Self) -> Self {
+        let mut res = SymbolLabel { secrecy: self.secrecy & rhs.secrecy, integrity: self.integrity | rhs.integrity };
+        res.reduce();
+        res
+    }
+
+    fn glb(self, rhs: Self) -> Self {
+        let mut res = SymbolLabel { secrecy: self.secrecy | rhs.secrecy, integrity: self.integrity & rhs.integrity };
+        res.reduce();
+        res
+    }
+
+    fn can_flow_to(&self, rhs: &Self) -> bool {
+        rhs.secrecy.implies(&self.secrecy) && self.integrity.implies(&rhs.integrity)
+    }
+}
+
+impl HasPrivilege for SymbolLabel {
+    type Privilege = Component;
+
+    fn downgrade(mut self, privilege: &Component) -> SymbolLabel {
+        self.secrecy = match (self.secrecy, privilege) {
+            (_, Component::DCFalse) => Component::dc_true(),
+            (Component::DCFalse, _) => Component::DCFalse,
+            (Component::DCFormula(mut sec), Component::DCFormula(p)) => {
+                sec.retain(|c| !p.iter().any(|pclause| pclause.implies(c)));
+                Component::DCFormula(sec)
+            }
+        };
+        self.integrity = privilege.clone() & self.integrity;
+        self
+    }
+
+    fn downgrade_to(self, target: Self, privilege: &Component) -> Self {
+        if self.can_flow_to_with_privilege(&target, privilege) {
+            target
+        } else {
+            self
+        }
+    }
+
+    fn can_flow_to_with_privilege(&self, rhs: &Self, privilege: &Component) -> bool {
+        (rhs.secrecy.clone() & privilege.clone()).implies(&self.secrecy)
+            && (self.integrity.clone() & privilege.clone()).implies(&rhs.integrity)
+    }
+}
+
+impl Interner {
+    /// Interns the principals of a `DCLabel`.
+    #[cfg(feature = "dclabel")]
+    pub fn dclabel(&mut self, label: &crate::dclabel::DCLabel) -> SymbolLabel {
+        use crate::dclabel::Component as C;
+
+        let mut component = |c: &C| match c {
+            C::DCFalse => Component::DCFalse,
+            C::DCFormula(clauses) => Component::DCFormula(
+                clauses.iter().map(|c| Clause(c.0.iter().map(|p| alloc::vec![self.intern(p)]).collect())).collect(),
+            ),
+        };
+        SymbolLabel { secrecy: component(&label.secrecy), integrity: component(&label.integrity) }
+    }
+
+    /// Interns the principals of a `Buckle` label.
+    #[cfg(feature = "buckle")]
+    pub fn buckle(&mut self, label: &crate::buckle::Buckle) -> SymbolLabel {
+        use crate::buckle::Component as C;
+
+        let mut component = |c: &C| match c {
+            C::DCFalse => Component::DCFalse,
+            C::DCFormula(clauses) => Component::DCFormula(
+                clauses
+                    .iter()
+                    .map(|c| Clause(c.0.iter().map(|path| path.iter().map(|p| self.intern(p)).collect()).collect()))
+                    .collect(),
+            ),
+        };
+        SymbolLabel { secrecy: component(&label.secrecy), integrity: component(&label.integrity) }
+    }
+
+    /// Resolves a label to a `DCLabel`, or `None` if it has a symbol not
+    /// created by this interner or a path of several principals.
+    #[cfg(feature = "dclabel")]
+    pub fn to_dclabel(&self, label: &SymbolLabel) -> Option<crate::dclabel::DCLabel> {
+        use crate::dclabel::{Clause as C, Component as K};
+
+        let principal = |path: &Vec<Symbol>| match path.as_slice() {
+            [symbol] => self.resolve(*symbol).map(Into::into),
+            _ => None,
+        };
+        let component = |c: &Component| match c {
+            Component::DCFalse => Some(K::DCFalse),
+            Component::DCFormula(clauses) => clauses
+                .iter()
+                .map(|c| c.0.iter().map(principal).collect::<Option<BTreeSet<_>>>().map(C::from))
+                .collect::<Option<BTreeSet<_>>>()
+                .map(K::from),
+        };
+        Some(crate::dclabel::DCLabel::new(component(&label.secrecy)?, component(&label.integrity)?))
+    }
+
+    /// Resolves a label to a `Buckle` label, or `None` if it has a symbol not
+    /// created by this interner.
+    #[cfg(feature = "buckle")]
+    pub fn to_buckle(&self, label: &SymbolLabel) -> Option<crate::buckle::Buckle> {
+        use crate::buckle::{Clause as C, Component as K};
+
+        let path = |path: &Vec<Symbol>| path.iter().map(|s| self.resolve(*s).map(Into::into)).collect();
+        let component = |c: &Component| match c {
+            Component::DCFalse => Some(K::DCFalse),
+            Component::DCFormula(clauses) => clauses
+                .iter()
+                .map(|c| c.0.iter().map(path).collect::<Option<BTreeSet<_>>>().map(C::from))
+                .collect::<Option<BTreeSet<_>>>()
+                .map(K::from),
+        };
+        Some(crate::buckle::Buckle::new(component(&label.secrecy)?, component(&label.integrity)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interner() {
+        let mut interner = Interner::new();
+        let amit = interner.intern("amit");
+        let yue = interner.intern("yue");
+        assert_ne!(amit, yue);
+        assert_eq!(amit, interner.intern("amit"));
+        assert_eq!(Some(yue), interner.get("yue"));
+        assert_eq!(None, interner.get("deian"));
+        assert_eq!(Some("amit"), interner.resolve(amit));
+        assert_eq!(None, Interner::new().resolve(amit));
+        assert_eq!(2, interner.len());
+    }
+
+    #[cfg(feature = "buckle")]
+    #[test]
+    fn test_buckle() {
+        use crate::buckle::Buckle;
+
+        let mut interner = Interner::new();
+        let lbl = Buckle::parse("amit/grades|yue,amit").unwrap();
+        let interned = interner.buckle(&lbl);
+        assert_eq!(Some(lbl), interner.to_buckle(&interned));
+        // Repeated names are interned once.
+        assert_eq!(3, interner.len());
+
+        let grades = interner.buckle(&Buckle::parse("amit/grades,T").unwrap());
+        let amit = interner.buckle(&Buckle::parse("amit,T").unwrap());
+        assert!(grades.can_flow_to(&amit));
+        assert!(!amit.can_flow_to(&grades));
+        assert!(amit.can_flow_to_with_privilege(&grades, &interner.buckle(&Buckle::parse("amit,T").unwrap()).secrecy));
+        assert_eq!(None, Interner::new().to_buckle(&amit));
+    }
+
+    #[cfg(all(feature = "dclabel", feature = "buckle"))]
+    #[test]
+    fn test_shared() {
+        use crate::buckle::Buckle;
+        use crate::dclabel::DCLabel;
+
+        let mut interner = Interner::new();
+        let dclabel = DCLabel::parse("amit&yue,T").unwrap().1;
+        let interned = interner.dclabel(&dclabel);
+        assert_eq!(interned, interner.buckle(&Buckle::parse("yue&amit,T").unwrap()));
+        assert_eq!(Some(dclabel), interner.to_dclabel(&interned));
+        let path = interner.buckle(&Buckle::parse("amit/grades,T").unwrap());
+        assert_eq!(None, interner.to_dclabel(&path));
+    }
+
+    #[cfg(feature = "buckle")]
+    quickcheck! {
+        fn agrees_with_buckle(lbl1: crate::buckle::Buckle, lbl2: crate::buckle::Buckle) -> bool {
+            let mut interner = Interner::new();
+            let (s1, s2) = (interner.buckle(&lbl1), interner.buckle(&lbl2));
+            s1.can_flow_to(&s2) == lbl1.can_flow_to(&lbl2)
+                && s1.clone().lub(s2.clone()).can_flow_to(&interner.buckle(&lbl1.clone().lub(lbl2.clone())))
+        }
+    }
+}
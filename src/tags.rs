@@ -0,0 +1,165 @@
+//! Translation between cloud resource tags and labels.
+//!
+//! A `TagMapping` names the tag keys that carry labels, e.g. the `team` and
+//! `owner` keys of AWS or GCP resources, and which component each one feeds.
+//! A tag `team=payments` on a secrecy key becomes the secrecy clause
+//! `team/payments`, a principal under the key's namespace, and the clauses of
+//! all mapped tags are conjoined. Tags with unmapped keys are ignored.
+//!
+//! The translation back succeeds for labels of that shape, i.e. made only of
+//! singleton `key/value` clauses, with at most one clause per key.
+
+use crate::buckle::{Buckle, Clause, Component};
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
+use alloc::vec;
+
+/// The maximum tag key length, in characters, accepted by both AWS and GCP.
+pub const MAX_KEY_LEN: usize = 63;
+
+/// The maximum tag value length, in characters, accepted by both AWS and GCP.
+pub const MAX_VALUE_LEN: usize = 63;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The mapping lists a key more than once.
+    DuplicateKey(String),
+    /// A key is empty or longer than `MAX_KEY_LEN`.
+    InvalidKey(String),
+    /// A mapped tag's value is empty or longer than `MAX_VALUE_LEN`.
+    InvalidValue(String),
+    /// The label has no equivalent set of tags.
+    Unrepresentable,
+}
+
+/// The label component a tag key maps to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Secrecy,
+    Integrity,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagMapping {
+    keys: BTreeMap<String, Target>,
+}
+
+fn check_len(s: &str, max: usize) -> bool {
+    !s.is_empty() && s.chars().count() <= max
+}
+
+impl TagMapping {
+    /// Creates a mapping from each key to the component it feeds.
+    pub fn new<K: Into<String>, I: IntoIterator<Item = (K, Target)>>(keys: I) -> Result<TagMapping, Error> {
+        let mut mapping = BTreeMap::new();
+        for (key, target) in keys {
+            let key = key.into();
+            if !check_len(&key, MAX_KEY_LEN) {
+                return Err(Error::InvalidKey(key));
+            }
+            if mapping.contains_key(&key) {
+                return Err(Error::DuplicateKey(key));
+            }
+            mapping.insert(key, target);
+        }
+        Ok(TagMapping { keys: mapping })
+    }
+
+    /// Translates a resource's tags into a label.
+    pub fn to_label<'a, I: IntoIterator<Item = (&'a str, &'a str)>>(&self, tags: I) -> Result<Buckle, Error> {
+        let mut secrecy = BTreeSet::new();
+        let mut integrity = BTreeSet::new();
+        for (key, value) in tags {
+            let clauses = match self.keys.get(key) {
+                Some(Target::Secrecy) => &mut secrecy,
+                Some(Target::Integrity) => &mut integrity,
+                None => continue,
+            };
+            if !check_len(value, MAX_VALUE_LEN) {
+                return Err(Error::InvalidValue(value.into()));
+            }
+            clauses.insert(Clause::new_from_vec(vec![vec![key, value]]));
+        }
+        Ok(Buckle::new(secrecy, integrity))
+    }
+
+    /// Translates a label back into tags.
+    pub fn to_tags(&self, label: &Buckle) -> Result<BTreeMap<String, String>, Error> {
+        let mut tags = BTreeMap::new();
+        for (component, target) in [(&label.secrecy, Target::Secrecy), (&label.integrity, Target::Integrity)] {
+            let clauses = match component {
+                Component::DCFormula(clauses) => clauses,
+                Component::DCFalse => return Err(Error::Unrepresentable),
+            };
+            for clause in clauses.iter() {
+                let mut paths = clause.0.iter();
+                let (key, value) = match (paths.next().map(|p| p.as_slice()), paths.next()) {
+                    (Some([key, value]), None) if self.keys.get(key.as_str()) == Some(&target) => (key, value),
+                    _ => return Err(Error::Unrepresentable),
+                };
+                if !check_len(value, MAX_VALUE_LEN) {
+                    return Err(Error::InvalidValue(value.as_str().into()));
+                }
+                if tags.insert(key.as_str().into(), value.as_str().into()).is_some() {
+                    return Err(Error::Unrepresentable);
+                }
+            }
+        }
+        Ok(tags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping() -> TagMapping {
+        TagMapping::new([("team", Target::Secrecy), ("owner", Target::Secrecy), ("pipeline", Target::Integrity)])
+            .unwrap()
+    }
+
+    #[test]
+    fn test_new() {
+        assert_eq!(
+            Err(Error::DuplicateKey("team".into())),
+            TagMapping::new([("team", Target::Secrecy), ("team", Target::Integrity)])
+        );
+        assert_eq!(Err(Error::InvalidKey("".into())), TagMapping::new([("", Target::Secrecy)]));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let tags = [("team", "payments"), ("owner", "amit"), ("pipeline", "ci"), ("cost-center", "42")];
+        let label = mapping().to_label(tags.iter().copied()).unwrap();
+        assert_eq!(Buckle::parse("owner/amit&team/payments,pipeline/ci").unwrap(), label);
+
+        let expected: BTreeMap<String, String> =
+            tags[..3].iter().map(|(k, v)| (String::from(*k), String::from(*v))).collect();
+        assert_eq!(Ok(expected), mapping().to_tags(&label));
+        assert_eq!(Ok(BTreeMap::new()), mapping().to_tags(&Buckle::public()));
+    }
+
+    #[test]
+    fn test_validation() {
+        assert_eq!(Err(Error::InvalidValue("".into())), mapping().to_label([("team", "")]));
+        // Unmapped tags aren't validated.
+        assert!(mapping().to_label([("other", "")]).is_ok());
+
+        for unrepresentable in [
+            "F,T",
+            "team/a|team/b,T",
+            "team/a&team/b,T",
+            "team,T",
+            "team/a/b,T",
+            "other/a,T",
+            "pipeline/ci,T",
+        ] {
+            assert_eq!(
+                Err(Error::Unrepresentable),
+                mapping().to_tags(&Buckle::parse(unrepresentable).unwrap()),
+                "{}",
+                unrepresentable
+            );
+        }
+    }
+}
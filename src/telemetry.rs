@@ -0,0 +1,253 @@
+//! `tracing` integration for label-aware telemetry.
+//!
+//! Labels implement `Display` in their textual syntax, so they can be
+//! recorded as span or event fields with `%label` or `label_value`. The
+//! `traced_*` functions perform flow checks and downgrades while emitting an
+//! event with the labels involved, and `LabelSpanExt` tracks the label of
+//! the work done in a span.
+//!
+//! Spans recording labels must declare the `label` field up front, e.g.
+//! `tracing::info_span!("handler", label = tracing::field::Empty)`.
+//!
+//! # Decision events
+//!
+//! With the `trace` feature, every `can_flow_to`, `downgrade` and `endorse`
+//! of the crate's label types emits a trace-level event with target
+//! `DECISION_TARGET`, so operators can find out why a flow was denied
+//! without instrumenting call sites. Events identify labels by their
+//! `LabelDigest`, which doesn't reveal principals, and record the outcome:
+//! whether the flow is allowed, or the label after downgrading or
+//! endorsing. Digests are only computed when a subscriber is interested, so
+//! the feature costs a callsite check per operation otherwise.
+
+#[cfg(all(feature = "trace", any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+use crate::envelope::{Enveloped, LabelDigest};
+use crate::{HasPrivilege, Label};
+use core::fmt::Display;
+use tracing::field::DisplayValue;
+use tracing::Span;
+
+/// The field `LabelSpanExt` records labels in.
+pub const LABEL_FIELD: &str = "label";
+
+/// Formats `label` as a `tracing` field value.
+pub fn label_value<L: Display>(label: &L) -> DisplayValue<&L> {
+    tracing::field::display(label)
+}
+
+/// `lhs.can_flow_to(rhs)`, emitting a debug event with both labels and the
+/// decision.
+pub fn traced_can_flow_to<L: Label + Display>(lhs: &L, rhs: &L) -> bool {
+    let allowed = lhs.can_flow_to(rhs);
+    tracing::debug!(from = %lhs, to = %rhs, allowed, "label flow check");
+    allowed
+}
+
+/// `label.downgrade(privilege)`, emitting an info event with the labels
+/// before and after, and the privilege exercised.
+pub fn traced_downgrade<L>(label: L, privilege: &L::Privilege) -> L
+where
+    L: HasPrivilege + Clone + Display,
+    L::Privilege: Display,
+{
+    let before = label.clone();
+    let after = label.downgrade(privilege);
+    tracing::info!(before = %before, after = %after, privilege = %privilege, "label downgrade");
+    after
+}
+
+/// The target of decision events, e.g. for enabling them alone with the
+/// filter `labeled::decisions=trace`.
+#[cfg(feature = "trace")]
+pub const DECISION_TARGET: &str = "labeled::decisions";
+
+/// Labels that decision events can identify.
+#[cfg(all(feature = "trace", any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+pub(crate) trait Traced {
+    fn trace_digest(&self) -> LabelDigest;
+}
+
+#[cfg(all(feature = "trace", feature = "dclabel"))]
+impl Traced for crate::dclabel::DCLabel {
+    fn trace_digest(&self) -> LabelDigest {
+        self.label_digest()
+    }
+}
+
+#[cfg(all(feature = "trace", feature = "buckle"))]
+impl Traced for crate::buckle::Buckle {
+    fn trace_digest(&self) -> LabelDigest {
+        self.label_digest()
+    }
+}
+
+/// Labels in other allocators are digested as copies in the global one.
+#[cfg(all(feature = "trace", feature = "buckle2"))]
+impl<A: allocator_api2::alloc::Allocator + Clone> Traced for crate::buckle2::Buckle2<A> {
+    fn trace_digest(&self) -> LabelDigest {
+        self.clone_in(allocator_api2::alloc::Global).label_digest()
+    }
+}
+
+/// Emits the decision event of a flow check.
+#[cfg(all(feature = "trace", any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+pub(crate) fn trace_flow<L: Traced>(from: &L, to: &L, allowed: bool) {
+    tracing::trace!(
+        target: DECISION_TARGET,
+        from = %from.trace_digest(),
+        to = %to.trace_digest(),
+        allowed,
+        "can_flow_to"
+    );
+}
+
+/// Returns the digest of a label about to be downgraded or endorsed, if
+/// decision events are enabled, to pass to `trace_privileged`.
+#[cfg(all(feature = "trace", any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+pub(crate) fn digest_before<L: Traced>(label: &L) -> Option<LabelDigest> {
+    tracing::enabled!(target: DECISION_TARGET, tracing::Level::TRACE).then(|| label.trace_digest())
+}
+
+/// Emits the decision event of a downgrade or endorsement, `operation`,
+/// that turned the label digested by `digest_before` into `after`.
+#[cfg(all(feature = "trace", any(feature = "dclabel", feature = "buckle", feature = "buckle2")))]
+pub(crate) fn trace_privileged<L: Traced>(operation: &'static str, before: Option<LabelDigest>, after: &L) {
+    if let Some(before) = before {
+        let after = after.trace_digest();
+        tracing::trace!(
+            target: DECISION_TARGET,
+            before = %before,
+            after = %after,
+            changed = before != after,
+            "{}",
+            operation
+        );
+    }
+}
+
+/// Extensions for recording labels on spans.
+pub trait LabelSpanExt {
+    /// Records `label` in the span's `label` field.
+    fn record_label<L: Display>(&self, label: &L) -> &Self;
+
+    /// Raises `current`, the label of the span's work so far, to its lub
+    /// with `taint`, and records the result in the span's `label` field.
+    fn taint_span<L: Label + Clone + Display>(&self, current: &mut L, taint: L) -> &Self;
+}
+
+impl LabelSpanExt for Span {
+    fn record_label<L: Display>(&self, label: &L) -> &Self {
+        self.record(LABEL_FIELD, label_value(label))
+    }
+
+    fn taint_span<L: Label + Clone + Display>(&self, current: &mut L, taint: L) -> &Self {
+        *current = current.clone().lub(taint);
+        self.record_label(current)
+    }
+}
+
+#[cfg(all(test, feature = "buckle"))]
+mod tests {
+    use super::*;
+    use crate::buckle::Buckle;
+    use alloc::string::{String, ToString};
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use std::sync::Mutex;
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    /// Collects every recorded field as `name=value`.
+    #[derive(Clone, Default)]
+    struct Collector(Arc<Mutex<Vec<String>>>);
+
+    impl Visit for Collector {
+        fn record_debug(&mut self, field: &Field, value: &dyn core::fmt::Debug) {
+            self.0.lock().unwrap().push(alloc::format!("{}={:?}", field.name(), value));
+        }
+    }
+
+    impl Subscriber for Collector {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _: &Attributes<'_>) -> Id {
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _: &Id, values: &Record<'_>) {
+            values.record(&mut self.clone());
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            event.record(&mut self.clone());
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    fn collect(f: impl FnOnce()) -> Vec<String> {
+        let collector = Collector::default();
+        tracing::subscriber::with_default(collector.clone(), f);
+        let fields = collector.0.lock().unwrap().clone();
+        fields
+    }
+
+    #[test]
+    fn test_traced_operations() {
+        let fields = collect(|| {
+            let secret = Buckle::parse("amit,T").unwrap();
+            assert!(!traced_can_flow_to(&secret, &Buckle::public()));
+            let privilege = Buckle::parse("amit,T").unwrap().secrecy;
+            assert_eq!(Buckle::parse("T,amit").unwrap(), traced_downgrade(secret, &privilege));
+        });
+        for field in ["from=amit,T", "to=T,T", "allowed=false", "before=amit,T", "after=T,amit", "privilege=amit"] {
+            assert!(fields.contains(&field.to_string()), "{} not in {:?}", field, fields);
+        }
+    }
+
+    #[cfg(feature = "trace")]
+    #[test]
+    fn test_decision_events() {
+        let secret = Buckle::parse("amit,T").unwrap();
+        let privilege = secret.secrecy.clone();
+        let fields = collect(|| {
+            assert!(!secret.can_flow_to(&Buckle::public()));
+            secret.clone().downgrade(&privilege);
+            Buckle::public().endorse(&privilege);
+        });
+        let digest = |label: &str| Buckle::parse(label).unwrap().label_digest().to_string();
+        for field in [
+            alloc::format!("from={}", digest("amit,T")),
+            alloc::format!("to={}", digest("T,T")),
+            "allowed=false".to_string(),
+            "message=can_flow_to".to_string(),
+            alloc::format!("before={}", digest("amit,T")),
+            alloc::format!("after={}", digest("T,amit")),
+            "message=downgrade".to_string(),
+            "message=endorse".to_string(),
+            "changed=true".to_string(),
+        ] {
+            assert!(fields.contains(&field), "{} not in {:?}", field, fields);
+        }
+        assert!(fields.iter().all(|f| !f.contains("amit")));
+    }
+
+    #[test]
+    fn test_taint_span() {
+        let fields = collect(|| {
+            let span = tracing::info_span!("handler", label = tracing::field::Empty);
+            let mut current = Buckle::public();
+            span.taint_span(&mut current, Buckle::parse("amit,T").unwrap());
+            span.taint_span(&mut current, Buckle::parse("yue,T").unwrap());
+            assert_eq!(Buckle::parse("amit&yue,T").unwrap(), current);
+        });
+        assert_eq!(["label=amit,T", "label=amit&yue,T"].map(String::from).to_vec(), fields);
+    }
+}
@@ -0,0 +1,167 @@
+//! Test support for code using `Buckle2` labels in custom allocators.
+//!
+//! `FailingAlloc` forwards to the global allocator until it has made a
+//! given number of allocations, then fails every one after, so a test can
+//! make each allocation of an operation fail in turn. It also counts the
+//! bytes it has live, so the test can check that nothing leaks on the
+//! failure paths.
+//!
+//! The infallible `Buckle2` operations call `handle_alloc_error` when an
+//! allocation fails, which aborts by default. Tests that recover instead
+//! install a hook that panics, with the nightly `set_alloc_error_hook`, and
+//! catch the panic, as this module's tests do with the `nightly` feature.
+//!
+//! The module is built for the crate's tests, and with the `test-support`
+//! feature for downstream ones.
+
+use allocator_api2::alloc::Global;
+use alloc::sync::Arc;
+use allocator_api2::alloc::{AllocError, Allocator, Layout};
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// An allocator that fails after a number of allocations. Clones share the
+/// count, as the clones a label holds must.
+#[derive(Debug, Clone)]
+pub struct FailingAlloc {
+    remaining: Arc<AtomicUsize>,
+    allocations: Arc<AtomicUsize>,
+    live: Arc<AtomicUsize>,
+}
+
+impl FailingAlloc {
+    /// Returns an allocator whose first `n` allocations succeed. Growing or
+    /// shrinking an allocation counts as an allocation.
+    pub fn new(n: usize) -> Self {
+        FailingAlloc {
+            remaining: Arc::new(AtomicUsize::new(n)),
+            allocations: Arc::new(AtomicUsize::new(0)),
+            live: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Returns an allocator that never fails, e.g. to count the
+    /// allocations an operation makes.
+    pub fn unlimited() -> Self {
+        Self::new(usize::MAX)
+    }
+
+    /// The number of allocations that succeeded.
+    pub fn allocations(&self) -> usize {
+        self.allocations.load(Ordering::Relaxed)
+    }
+
+    /// The number of bytes allocated and not yet deallocated.
+    pub fn live(&self) -> usize {
+        self.live.load(Ordering::Relaxed)
+    }
+}
+
+// Safe since it forwards to `Global`.
+unsafe impl Allocator for FailingAlloc {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.remaining
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+            .map_err(|_| AllocError)?;
+        let ptr = Global.allocate(layout)?;
+        self.allocations.fetch_add(1, Ordering::Relaxed);
+        self.live.fetch_add(layout.size(), Ordering::Relaxed);
+        Ok(ptr)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.live.fetch_sub(layout.size(), Ordering::Relaxed);
+        unsafe { Global.deallocate(ptr, layout) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failing_alloc() {
+        let alloc = FailingAlloc::new(1);
+        let mut v = allocator_api2::vec::Vec::new_in(alloc.clone());
+        v.push(1u8);
+        assert_eq!(Err(AllocError), alloc.allocate(Layout::new::<u8>()).map(|_| ()));
+        // A vector of bytes starts with a capacity of 8.
+        assert_eq!((1, 8), (alloc.allocations(), alloc.live()));
+        drop(v);
+        assert_eq!(0, alloc.live());
+    }
+}
+
+/// Makes each allocation of the `Buckle2` operations fail in turn, which
+/// needs the nightly `set_alloc_error_hook`.
+#[cfg(all(test, feature = "nightly"))]
+mod failures {
+    use super::*;
+    use crate::buckle2::Buckle2;
+    use crate::{HasPrivilege, Label};
+    use alloc::string::ToString;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    /// Makes failed allocations panic rather than abort, for every test.
+    fn panic_on_alloc_error() {
+        static HOOK: std::sync::Once = std::sync::Once::new();
+        HOOK.call_once(|| std::alloc::set_alloc_error_hook(|layout| panic!("allocation of {:?} failed", layout)));
+    }
+
+    /// Runs `op` with the `n`th allocation and every one after failing, for
+    /// every `n` up to the number `op` makes, checking that it either
+    /// returns what it returns with no failures, or panics, and never leaks.
+    fn check<T: PartialEq + core::fmt::Debug>(op: impl Fn(&FailingAlloc) -> T) {
+        panic_on_alloc_error();
+        let unlimited = FailingAlloc::unlimited();
+        let expected = op(&unlimited);
+        let needed = unlimited.allocations();
+        assert_eq!(0, unlimited.live());
+        for n in 0..=needed {
+            let alloc = FailingAlloc::new(n);
+            match catch_unwind(AssertUnwindSafe(|| op(&alloc))) {
+                Ok(result) => assert_eq!(expected, result, "with {} allocations", n),
+                Err(_) => assert!(n < needed, "failed with all {} allocations", n),
+            }
+            assert_eq!(0, alloc.live(), "leaked with {} allocations", n);
+        }
+    }
+
+    fn parse(input: &str, alloc: &FailingAlloc) -> Buckle2<FailingAlloc> {
+        Buckle2::parse_in(input, alloc.clone()).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        check(|alloc| parse("a/b|c&d,e|f/g", alloc).to_string());
+    }
+
+    #[test]
+    fn test_lattice() {
+        check(|alloc| parse("a|b&c,d", alloc).lub(parse("a&e,d|f", alloc)).to_string());
+        check(|alloc| parse("a|b&c,d", alloc).glb(parse("a&e,d|f", alloc)).to_string());
+        check(|alloc| parse("a/b|c&a,T", alloc).can_flow_to(&parse("a&c,T", alloc)));
+    }
+
+    #[test]
+    fn test_downgrade() {
+        check(|alloc| {
+            let privilege = parse("a|c,T", alloc).secrecy;
+            parse("a/b|c&d,e", alloc).downgrade(&privilege).to_string()
+        });
+        check(|alloc| {
+            let privilege = parse("a,T", alloc).secrecy;
+            parse("a/b|c&d,e", alloc).endorse(&privilege).to_string()
+        });
+    }
+
+    #[test]
+    fn test_reduce_and_clone() {
+        check(|alloc| {
+            let mut label = parse("a,T", alloc);
+            label.secrecy = label.secrecy | parse("b&b/c|d,T", alloc).secrecy;
+            label.reduce();
+            label.clone_in(alloc.clone()).to_string()
+        });
+    }
+}
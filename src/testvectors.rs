@@ -0,0 +1,211 @@
+//! A corpus of labels and expected results, compiled into the crate.
+//!
+//! Wrappers and FFI bindings can check themselves against these answers
+//! without shipping the JSON files and `serde_json` that `vectors` reads.
+//! The corpus covers the extremes `T` and `F`, escaped principals and
+//! `Buckle` paths, including a prefix in the same disjunction and an escaped
+//! `/`. Results are in canonical text form, as `Display` prints them, so a
+//! binding can compare strings.
+//!
+//! `Buckle2` labels share `Buckle`'s syntax and semantics, so they share
+//! its vectors.
+
+use crate::envelope::LabelKind;
+
+/// A pair of labels and the expected results of operations on them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Vector {
+    pub name: &'static str,
+    pub lhs: &'static str,
+    pub rhs: &'static str,
+    /// `lhs` as `Display` prints it once parsed.
+    pub display: &'static str,
+    pub can_flow_to: bool,
+    pub lub: &'static str,
+    pub glb: &'static str,
+    pub privileged: Option<Privileged>,
+}
+
+/// Expected results of privileged operations on a vector's labels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Privileged {
+    /// The privilege, as the text of a secrecy component.
+    pub privilege: &'static str,
+    pub can_flow_to_with_privilege: bool,
+    /// `lhs` downgraded by the privilege.
+    pub downgrade: &'static str,
+}
+
+/// Returns the vectors for labels of the given kind.
+pub fn vectors(kind: LabelKind) -> impl Iterator<Item = &'static Vector> {
+    match kind {
+        LabelKind::DCLabel => DCLABEL.iter(),
+        LabelKind::Buckle | LabelKind::Buckle2 => BUCKLE.iter(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+const fn vector(
+    name: &'static str,
+    lhs: &'static str,
+    rhs: &'static str,
+    display: &'static str,
+    can_flow_to: bool,
+    lub: &'static str,
+    glb: &'static str,
+    privileged: Option<Privileged>,
+) -> Vector {
+    Vector { name, lhs, rhs, display, can_flow_to, lub, glb, privileged }
+}
+
+const fn privileged(privilege: &'static str, can_flow_to_with_privilege: bool, downgrade: &'static str) -> Option<Privileged> {
+    Some(Privileged { privilege, can_flow_to_with_privilege, downgrade })
+}
+
+static DCLABEL: [Vector; 13] = [
+    vector("public", "T,T", "T,T", "T,T", true, "T,T", "T,T", None),
+    vector("top to public", "F,T", "T,T", "F,T", false, "F,T", "T,T", None),
+    vector("bottom to public", "T,F", "T,T", "T,F", true, "T,T", "T,F", None),
+    vector("bottom to top", "T,F", "F,T", "T,F", true, "F,T", "T,F", privileged("F", true, "T,F")),
+    vector("false privilege", "amit&yue,staff", "T,T", "amit&yue,staff", false, "amit&yue,T", "T,staff", privileged("F", true, "T,F")),
+    vector("secrecy conjunction", "amit&yue,T", "amit,T", "amit&yue,T", false, "amit&yue,T", "amit,T", privileged("yue", true, "amit,yue")),
+    vector("secrecy disjunction", "amit|yue,T", "amit,T", "amit|yue,T", true, "amit,T", "amit|yue,T", None),
+    vector("integrity disjunction", "T,amit", "T,amit|yue", "T,amit", true, "T,amit|yue", "T,amit", None),
+    vector("redundant clause", "amit&amit|yue,T", "amit,T", "amit,T", true, "amit,T", "amit,T", None),
+    vector("escaped constant", r"\Tom,T", r"\F,T", "Tom,T", false, r"\F&Tom,T", r"\F|Tom,T", None),
+    vector(
+        "escaped separators",
+        r"a\,b|c\&d,T",
+        r"a\|b,T",
+        r"a\,b|c\&d,T",
+        false,
+        r"a\,b|c\&d&a\|b,T",
+        r"a\,b|a\|b|c\&d,T",
+        privileged(r"a\,b", true, r"T,a\,b"),
+    ),
+    vector("non-ascii principal", r"\é\è,T", r"\é\è|x,T", "éè,T", false, "éè,T", "x|éè,T", None),
+    vector("conjunction and disjunction", "a|b&c,d|e", "a&c,d", "a|b&c,d|e", false, "a&c,d|e", "a|b&c,d", privileged("d", true, "a|b&c,d")),
+];
+
+static BUCKLE: [Vector; 16] = [
+    vector("public", "T,T", "T,T", "T,T", true, "T,T", "T,T", None),
+    vector("top to public", "F,T", "T,T", "F,T", false, "F,T", "T,T", None),
+    vector("bottom to public", "T,F", "T,T", "T,F", true, "T,T", "T,F", None),
+    vector("bottom to top", "T,F", "F,T", "T,F", true, "F,T", "T,F", privileged("F", true, "T,F")),
+    vector("false privilege", "amit&yue,staff", "T,T", "amit&yue,staff", false, "amit&yue,T", "T,staff", privileged("F", true, "T,F")),
+    vector("sub-principal to principal", "amit/grades,T", "amit,T", "amit/grades,T", true, "amit,T", "amit|amit/grades,T", None),
+    vector(
+        "principal to sub-principal",
+        "amit,T",
+        "amit/grades,T",
+        "amit,T",
+        false,
+        "amit,T",
+        "amit|amit/grades,T",
+        privileged("amit/grades", false, "amit,amit/grades"),
+    ),
+    vector(
+        "principal privilege covers sub-principals",
+        "amit/grades&yue,T",
+        "yue,T",
+        "amit/grades&yue,T",
+        false,
+        "amit/grades&yue,T",
+        "yue,T",
+        privileged("amit", true, "yue,amit"),
+    ),
+    vector("hierarchical integrity", "T,amit", "T,amit/bot", "T,amit", true, "T,amit|amit/bot", "T,amit", None),
+    vector(
+        "prefix in disjunction",
+        "amit|amit/grades,T",
+        "amit/grades,T",
+        "amit|amit/grades,T",
+        true,
+        "amit|amit/grades,T",
+        "amit|amit/grades,T",
+        None,
+    ),
+    vector("deep path", "a/b/c/d/e,T", "a/b,T", "a/b/c/d/e,T", true, "a/b,T", "a/b|a/b/c/d/e,T", privileged("a/b/c", true, "T,a/b/c")),
+    vector("escaped constant", r"\Tom,T", r"\F,T", "Tom,T", false, r"\F&Tom,T", r"\F|Tom,T", None),
+    vector(
+        "escaped separators",
+        r"a\,b|c\&d,T",
+        r"a\/b,T",
+        r"a\,b|c\&d,T",
+        false,
+        r"a\,b|c\&d&a\/b,T",
+        r"a\,b|a\/b|c\&d,T",
+        privileged(r"a\,b", true, r"T,a\,b"),
+    ),
+    vector("escaped slash is not a path", r"a\/b,T", "a/b,T", r"a\/b,T", false, r"a/b&a\/b,T", r"a/b|a\/b,T", None),
+    vector("non-ascii principal", r"\é\è,T", r"\é\è|x,T", "éè,T", false, "éè,T", "x|éè,T", None),
+    vector("conjunction and disjunction", "a|b&c,d|e", "a&c,d", "a|b&c,d|e", false, "a&c,d|e", "a|b&c,d", privileged("d", true, "a|b&c,d")),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HasPrivilege, Label};
+    use alloc::format;
+    use alloc::string::ToString;
+
+    #[cfg(feature = "dclabel")]
+    #[test]
+    fn test_dclabel() {
+        use crate::dclabel::DCLabel;
+
+        let parse = |s: &str| DCLabel::parse(s).unwrap().1;
+        for v in vectors(LabelKind::DCLabel) {
+            let (lhs, rhs) = (parse(v.lhs), parse(v.rhs));
+            assert_eq!(v.display, lhs.to_string(), "{}", v.name);
+            assert_eq!(v.can_flow_to, lhs.can_flow_to(&rhs), "{}", v.name);
+            assert_eq!(v.lub, lhs.clone().lub(rhs.clone()).to_string(), "{}", v.name);
+            assert_eq!(v.glb, lhs.clone().glb(rhs.clone()).to_string(), "{}", v.name);
+            if let Some(p) = v.privileged {
+                let privilege = parse(&format!("{},T", p.privilege)).secrecy().clone();
+                assert_eq!(p.can_flow_to_with_privilege, lhs.can_flow_to_with_privilege(&rhs, &privilege), "{}", v.name);
+                assert_eq!(p.downgrade, lhs.downgrade(&privilege).to_string(), "{}", v.name);
+            }
+        }
+    }
+
+    #[cfg(feature = "buckle")]
+    #[test]
+    fn test_buckle() {
+        use crate::buckle::Buckle;
+
+        let parse = |s: &str| Buckle::parse(s).unwrap();
+        for v in vectors(LabelKind::Buckle) {
+            let (lhs, rhs) = (parse(v.lhs), parse(v.rhs));
+            assert_eq!(v.display, lhs.to_string(), "{}", v.name);
+            assert_eq!(v.can_flow_to, lhs.can_flow_to(&rhs), "{}", v.name);
+            assert_eq!(v.lub, lhs.clone().lub(rhs.clone()).to_string(), "{}", v.name);
+            assert_eq!(v.glb, lhs.clone().glb(rhs.clone()).to_string(), "{}", v.name);
+            if let Some(p) = v.privileged {
+                let privilege = parse(&format!("{},T", p.privilege)).secrecy().clone();
+                assert_eq!(p.can_flow_to_with_privilege, lhs.can_flow_to_with_privilege(&rhs, &privilege), "{}", v.name);
+                assert_eq!(p.downgrade, lhs.downgrade(&privilege).to_string(), "{}", v.name);
+            }
+        }
+    }
+
+    #[cfg(feature = "buckle2")]
+    #[test]
+    fn test_buckle2() {
+        use crate::buckle2::Buckle2;
+
+        let parse = |s: &str| Buckle2::parse(s).unwrap();
+        for v in vectors(LabelKind::Buckle2) {
+            let (lhs, rhs) = (parse(v.lhs), parse(v.rhs));
+            assert_eq!(v.display, lhs.to_string(), "{}", v.name);
+            assert_eq!(v.can_flow_to, lhs.can_flow_to(&rhs), "{}", v.name);
+            assert_eq!(v.lub, lhs.clone().lub(rhs.clone()).to_string(), "{}", v.name);
+            assert_eq!(v.glb, lhs.clone().glb(rhs.clone()).to_string(), "{}", v.name);
+            if let Some(p) = v.privileged {
+                let privilege = parse(&format!("{},T", p.privilege)).secrecy().clone();
+                assert_eq!(p.can_flow_to_with_privilege, lhs.can_flow_to_with_privilege(&rhs, &privilege), "{}", v.name);
+                assert_eq!(p.downgrade, lhs.downgrade(&privilege).to_string(), "{}", v.name);
+            }
+        }
+    }
+}
@@ -0,0 +1,868 @@
+//! Canonical text, version 1: one textual syntax for every label type.
+//!
+//! This module defines a single grammar and escaping scheme, a parser with
+//! `Strict` and `Lenient` modes, and a printer whose output the strict parser
+//! accepts. `DCLabel::parse`, `Buckle::parse` and `Buckle2::parse` read the
+//! lenient language, and the label types' `Display` impls use the printer,
+//! so text printed for one type parses as another:
+//!
+//! ```text
+//! label     = component "," component
+//! component = "T" / "F" / clause *("&" clause)
+//! clause    = path *("|" path)
+//! path      = principal *("/" principal)
+//! principal = 1*(char / escape)
+//! escape    = "\" ("," / "|" / "&" / "/" / "\" / "T" / "F")
+//!           / "\x" 2HEXDIG        ; a single byte
+//! char      = any character but ",", "|", "&", "/", "\", space or an
+//!             ASCII control character
+//! ```
+//!
+//! A component is `T` or `F` only if it consists of that single unescaped
+//! character; the principal named `T` is written `\T`. `DCLabel` principals
+//! are paths of length one, and `\xHH` escapes in labels with string
+//! principals must form valid UTF-8.
+//!
+//! The lenient parser also accepts ASCII whitespace around separators and at
+//! either end of the input, unescaped spaces inside principals, and `\`
+//! before any character, standing for that character unless it starts a
+//! `\xHH` escape, so text that escapes every character but the ASCII
+//! alphanumerics parses too.
+//!
+//! The printer writes clauses and paths in the label's own (sorted) order,
+//! escaping special characters with `\` and spaces, ASCII control characters
+//! and bytes that aren't UTF-8 with `\xHH`. A component with an empty clause
+//! is false and printed as `F`. Empty paths and empty principals have no
+//! textual form: `write_label` fails on them, and `Display` writes them as
+//! nothing.
+//!
+//! The printer allocates nothing: `write_label` writes to any
+//! `core::fmt::Write`, and `write_label_slice` to a fixed buffer, e.g. for
+//! logging or IPC in `no_std` environments.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+/// The version of the syntax implemented by this module.
+pub const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Accepts exactly the grammar.
+    Strict,
+    /// Also accepts insignificant whitespace and redundant escapes.
+    Lenient,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The input doesn't match the grammar at the given byte offset.
+    Syntax(usize),
+    /// A principal of a label type with string principals isn't UTF-8.
+    InvalidUtf8,
+    /// A path has several principals, which `DCLabel` can't represent.
+    PathNotSupported,
+    /// The label has an empty path or principal, which has no textual form.
+    Unrepresentable,
+    /// The buffer passed to `write_label_slice` is too small for the label.
+    BufferTooSmall,
+}
+
+/// A label with a canonical text v1 form.
+pub trait TextLabel: Sized {
+    /// Parses a label in the syntax.
+    fn from_text(input: &str, mode: Mode) -> Result<Self, Error>;
+
+    /// Writes the label in the syntax, failing if the label is
+    /// unrepresentable or `w` fails.
+    fn write_label<W: Write + ?Sized>(&self, w: &mut W) -> core::fmt::Result;
+
+    /// Writes the label in the syntax to the start of `buf`, returning the
+    /// number of bytes written.
+    fn write_label_slice(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        let mut w = SliceWriter::new(buf);
+        match self.write_label(&mut w) {
+            Ok(()) => Ok(w.len()),
+            Err(_) if w.overflowed => Err(Error::BufferTooSmall),
+            Err(_) => Err(Error::Unrepresentable),
+        }
+    }
+
+    /// Returns the label in the syntax.
+    fn to_text(&self) -> Result<String, Error> {
+        let mut s = String::new();
+        self.write_label(&mut s).map_err(|_| Error::Unrepresentable)?;
+        Ok(s)
+    }
+}
+
+/// A `core::fmt::Write` sink writing to a fixed buffer, failing once the
+/// buffer is full.
+#[derive(Debug)]
+pub struct SliceWriter<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+    overflowed: bool,
+}
+
+impl<'a> SliceWriter<'a> {
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        SliceWriter { buf, len: 0, overflowed: false }
+    }
+
+    /// Returns the number of bytes written.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the text written. Writes are all-or-nothing, so this is
+    /// valid UTF-8 even after a write overflows.
+    pub fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.buf[..self.len]).expect("only `str`s are written")
+    }
+}
+
+impl Write for SliceWriter<'_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        match self.buf.get_mut(self.len..self.len + s.len()) {
+            Some(dst) => {
+                dst.copy_from_slice(s.as_bytes());
+                self.len += s.len();
+                Ok(())
+            }
+            None => {
+                self.overflowed = true;
+                Err(core::fmt::Error)
+            }
+        }
+    }
+}
+
+/// Parses `input` as an `L` in the given `mode`.
+pub fn parse<L: TextLabel>(input: &str, mode: Mode) -> Result<L, Error> {
+    L::from_text(input, mode)
+}
+
+const SPECIAL: &[u8] = b",|&/\\";
+
+/// Builds a component of some label type from the principals the parser
+/// reads, so that they go straight into the type's own collections.
+pub(crate) trait Builder {
+    type Component;
+
+    /// Returns the component `T` if `value`, or `F`.
+    fn constant(self, value: bool) -> Self::Component;
+
+    /// Adds an unescaped principal to the current path.
+    fn principal(&mut self, principal: &[u8]) -> Result<(), Error>;
+
+    /// Ends the current path, which has at least one principal.
+    fn end_path(&mut self);
+
+    /// Ends the current clause, which has at least one path.
+    fn end_clause(&mut self);
+
+    fn finish(self) -> Self::Component;
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+    mode: Mode,
+    /// Holds principals with escapes while they are unescaped.
+    scratch: Vec<u8>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str, mode: Mode) -> Self {
+        Parser { input: input.as_bytes(), pos: 0, mode, scratch: Vec::new() }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        if self.mode == Mode::Lenient {
+            while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+                self.pos += 1;
+            }
+        }
+    }
+
+    /// Consumes `separator`, with surrounding whitespace in lenient mode.
+    fn separator(&mut self, separator: u8) -> bool {
+        let start = self.pos;
+        self.skip_whitespace();
+        if self.peek() == Some(separator) {
+            self.pos += 1;
+            self.skip_whitespace();
+            true
+        } else {
+            self.pos = start;
+            false
+        }
+    }
+
+    /// Returns the byte the escape at `at` stands for and the offset after
+    /// it. The lenient parser also reads `\` before any other character as
+    /// that character, and `\x` without two hex digits as `x`.
+    fn escape(&self, at: usize) -> Result<(u8, usize), Error> {
+        let digit = |i: usize| self.input.get(i).and_then(|&b| (b as char).to_digit(16));
+        match self.input.get(at + 1) {
+            Some(b'x') => match (digit(at + 2), digit(at + 3)) {
+                (Some(hi), Some(lo)) => Ok(((hi << 4 | lo) as u8, at + 4)),
+                _ if self.mode == Mode::Lenient => Ok((b'x', at + 2)),
+                _ => Err(Error::Syntax(at + 2)),
+            },
+            Some(&b) if SPECIAL.contains(&b) || b == b'T' || b == b'F' => Ok((b, at + 2)),
+            Some(&b) if self.mode == Mode::Lenient => Ok((b, at + 2)),
+            _ => Err(Error::Syntax(at + 1)),
+        }
+    }
+
+    /// Returns the end of the principal at the current offset, before any
+    /// trailing unescaped whitespace, and whether it has escapes.
+    fn scan(&self) -> Result<(usize, bool), Error> {
+        let (mut i, mut end, mut escaped) = (self.pos, self.pos, false);
+        while let Some(&b) = self.input.get(i) {
+            match b {
+                b'\\' => {
+                    i = self.escape(i)?.1;
+                    end = i;
+                    escaped = true;
+                }
+                b if SPECIAL.contains(&b) => break,
+                b if b.is_ascii_whitespace() && self.mode == Mode::Lenient => i += 1,
+                b if b == b' ' || b.is_ascii_control() => break,
+                _ => {
+                    i += 1;
+                    end = i;
+                }
+            }
+        }
+        if end == self.pos {
+            return Err(Error::Syntax(self.pos));
+        }
+        Ok((end, escaped))
+    }
+
+    /// Reads a principal into `builder`. If the builder rejects it, the
+    /// offset is left at its start.
+    fn principal<B: Builder>(&mut self, builder: &mut B) -> Result<(), Error> {
+        let (end, escaped) = self.scan()?;
+        let raw = &self.input[self.pos..end];
+        if escaped {
+            self.scratch.clear();
+            let mut i = self.pos;
+            while i < end {
+                if self.input[i] == b'\\' {
+                    let (b, next) = self.escape(i)?;
+                    self.scratch.push(b);
+                    i = next;
+                } else {
+                    self.scratch.push(self.input[i]);
+                    i += 1;
+                }
+            }
+            builder.principal(&self.scratch)?;
+        } else {
+            builder.principal(raw)?;
+        }
+        self.pos = end;
+        Ok(())
+    }
+
+    fn component<B: Builder>(&mut self, mut builder: B) -> Result<B::Component, Error> {
+        // `T` and `F` are constants only as the whole component.
+        let (end, escaped) = self.scan()?;
+        if !escaped && end == self.pos + 1 && matches!(self.input[self.pos], b'T' | b'F') {
+            let start = self.pos;
+            self.pos = end;
+            if !(self.separator(b'/') || self.separator(b'|') || self.separator(b'&')) {
+                return Ok(builder.constant(self.input[start] == b'T'));
+            }
+            self.pos = start;
+        }
+        loop {
+            loop {
+                loop {
+                    self.principal(&mut builder)?;
+                    if !self.separator(b'/') {
+                        break;
+                    }
+                }
+                builder.end_path();
+                if !self.separator(b'|') {
+                    break;
+                }
+            }
+            builder.end_clause();
+            if !self.separator(b'&') {
+                break;
+            }
+        }
+        Ok(builder.finish())
+    }
+
+    fn label<S: Builder, I: Builder>(&mut self, secrecy: S, integrity: I) -> Result<Components<S, I>, Error> {
+        self.skip_whitespace();
+        let secrecy = self.component(secrecy)?;
+        if !self.separator(b',') {
+            return Err(Error::Syntax(self.pos));
+        }
+        let integrity = self.component(integrity)?;
+        self.skip_whitespace();
+        Ok((secrecy, integrity))
+    }
+
+    /// Returns the offset of `error`: its own for syntax errors, and the
+    /// start of the principal the builder rejected otherwise.
+    fn offset(&self, error: Error) -> usize {
+        match error {
+            Error::Syntax(offset) => offset,
+            _ => self.pos,
+        }
+    }
+}
+
+/// A parse error and its byte offset.
+pub(crate) type Failure = (Error, usize);
+
+/// A label's secrecy and integrity components, as built by `S` and `I`.
+pub(crate) type Components<S, I> = (<S as Builder>::Component, <I as Builder>::Component);
+
+/// Parses a label at the start of `input`, returning its components and the
+/// offset where it ends, or an error and its offset.
+pub(crate) fn label_prefix<S: Builder, I: Builder>(
+    input: &str,
+    mode: Mode,
+    secrecy: S,
+    integrity: I,
+) -> Result<(Components<S, I>, usize), Failure> {
+    let mut parser = Parser::new(input, mode);
+    match parser.label(secrecy, integrity) {
+        Ok(label) => Ok((label, parser.pos)),
+        Err(e) => Err((e, parser.offset(e))),
+    }
+}
+
+/// Parses a component at the start of `input`, as `label_prefix` does.
+#[cfg(any(feature = "dclabel", feature = "buckle", test, feature = "ffi"))]
+pub(crate) fn component_prefix<B: Builder>(input: &str, mode: Mode, builder: B) -> Result<(B::Component, usize), Failure> {
+    let mut parser = Parser::new(input, mode);
+    parser.skip_whitespace();
+    match parser.component(builder) {
+        Ok(component) => {
+            parser.skip_whitespace();
+            Ok((component, parser.pos))
+        }
+        Err(e) => Err((e, parser.offset(e))),
+    }
+}
+
+/// Parses all of `input` as a label.
+pub(crate) fn label<S: Builder, I: Builder>(
+    input: &str,
+    mode: Mode,
+    secrecy: S,
+    integrity: I,
+) -> Result<Components<S, I>, Failure> {
+    match label_prefix(input, mode, secrecy, integrity)? {
+        (label, end) if end == input.len() => Ok(label),
+        (_, end) => Err((Error::Syntax(end), end)),
+    }
+}
+
+/// Writes a principal, escaping as described in the module documentation.
+fn write_principal<W: Write + ?Sized>(w: &mut W, principal: &[u8], lossy: bool) -> core::fmt::Result {
+    match principal {
+        [] if lossy => return Ok(()),
+        [] => return Err(core::fmt::Error),
+        b"T" | b"F" => return write!(w, "\\{}", principal[0] as char),
+        _ => {}
+    }
+    for chunk in principal.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            if c.is_ascii() && SPECIAL.contains(&(c as u8)) {
+                write!(w, "\\{}", c)?;
+            } else if c == ' ' || c.is_ascii_control() {
+                write!(w, "\\x{:02x}", c as u8)?;
+            } else {
+                w.write_char(c)?;
+            }
+        }
+        chunk.invalid().iter().try_for_each(|b| write!(w, "\\x{:02x}", b))?;
+    }
+    Ok(())
+}
+
+/// Writes a component given its clauses, or `None` for `F`. A clause with no
+/// paths makes the component false. An empty path or principal fails unless
+/// `lossy`, for `Display`, which writes it as nothing.
+pub(crate) fn write_component<W, Cs, C, P, S>(w: &mut W, clauses: Option<Cs>, lossy: bool) -> core::fmt::Result
+where
+    W: Write + ?Sized,
+    Cs: IntoIterator<Item = C> + Clone,
+    C: IntoIterator<Item = P>,
+    P: IntoIterator<Item = S>,
+    S: AsRef<[u8]>,
+{
+    let clauses = match clauses {
+        None => return w.write_str("F"),
+        Some(clauses) => clauses,
+    };
+    if clauses.clone().into_iter().any(|c| c.into_iter().next().is_none()) {
+        return w.write_str("F");
+    }
+    let mut clauses = clauses.into_iter().peekable();
+    if clauses.peek().is_none() {
+        return w.write_str("T");
+    }
+    for (i, clause) in clauses.enumerate() {
+        if i > 0 {
+            w.write_char('&')?;
+        }
+        for (j, path) in clause.into_iter().enumerate() {
+            if j > 0 {
+                w.write_char('|')?;
+            }
+            let mut empty = true;
+            for (k, principal) in path.into_iter().enumerate() {
+                if k > 0 {
+                    w.write_char('/')?;
+                }
+                write_principal(w, principal.as_ref(), lossy)?;
+                empty = false;
+            }
+            if empty && !lossy {
+                return Err(core::fmt::Error);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(any(feature = "dclabel", feature = "buckle"))]
+fn string<S: for<'a> From<&'a str>>(bytes: &[u8]) -> Result<S, Error> {
+    core::str::from_utf8(bytes).map(S::from).map_err(|_| Error::InvalidUtf8)
+}
+
+/// Converts a parse error at `offset` in `input` to a nom error.
+#[cfg(any(feature = "dclabel", feature = "buckle"))]
+pub(crate) fn nom_error<T>(input: &str, offset: usize) -> nom::IResult<&str, T> {
+    let rest = input.get(offset..).unwrap_or_default();
+    Err(nom::Err::Error(nom::error::Error::new(rest, nom::error::ErrorKind::Verify)))
+}
+
+#[cfg(feature = "dclabel")]
+pub(crate) mod dclabel {
+    use super::*;
+    use crate::dclabel::{Clause, Component, DCLabel, Principal};
+    use alloc::collections::BTreeSet;
+
+    /// Builds a `DCLabel` component, whose paths are single principals.
+    #[derive(Default)]
+    pub(crate) struct ComponentBuilder {
+        clauses: BTreeSet<Clause>,
+        principals: BTreeSet<Principal>,
+        in_path: bool,
+    }
+
+    impl Builder for ComponentBuilder {
+        type Component = Component;
+
+        fn constant(self, value: bool) -> Component {
+            Component::from(value)
+        }
+
+        fn principal(&mut self, principal: &[u8]) -> Result<(), Error> {
+            if core::mem::replace(&mut self.in_path, true) {
+                return Err(Error::PathNotSupported);
+            }
+            self.principals.insert(string(principal)?);
+            Ok(())
+        }
+
+        fn end_path(&mut self) {
+            self.in_path = false;
+        }
+
+        fn end_clause(&mut self) {
+            self.clauses.insert(Clause::from(core::mem::take(&mut self.principals)));
+        }
+
+        fn finish(self) -> Component {
+            Component::from(self.clauses)
+        }
+    }
+
+    pub(crate) fn write<W: Write + ?Sized>(w: &mut W, c: &Component, lossy: bool) -> core::fmt::Result {
+        let clauses = match c {
+            Component::DCFalse => None,
+            Component::DCFormula(clauses) => {
+                Some(clauses.iter().map(|c| c.0.iter().map(|p| core::iter::once(p.as_bytes()))))
+            }
+        };
+        write_component(w, clauses, lossy)
+    }
+
+    impl TextLabel for DCLabel {
+        fn from_text(input: &str, mode: Mode) -> Result<Self, Error> {
+            let (secrecy, integrity) =
+                label(input, mode, ComponentBuilder::default(), ComponentBuilder::default()).map_err(|e| e.0)?;
+            Ok(DCLabel::new(secrecy, integrity))
+        }
+
+        fn write_label<W: Write + ?Sized>(&self, w: &mut W) -> core::fmt::Result {
+            write(w, &self.secrecy, false)?;
+            w.write_char(',')?;
+            write(w, &self.integrity, false)
+        }
+    }
+}
+
+#[cfg(feature = "buckle")]
+pub(crate) mod buckle {
+    use super::*;
+    use crate::buckle::{Buckle, Clause, Component, Principal};
+    use alloc::collections::BTreeSet;
+
+    /// Builds a `Buckle` component.
+    #[derive(Default)]
+    pub(crate) struct ComponentBuilder {
+        clauses: BTreeSet<Clause>,
+        paths: BTreeSet<Vec<Principal>>,
+        path: Vec<Principal>,
+    }
+
+    impl Builder for ComponentBuilder {
+        type Component = Component;
+
+        fn constant(self, value: bool) -> Component {
+            Component::from(value)
+        }
+
+        fn principal(&mut self, principal: &[u8]) -> Result<(), Error> {
+            self.path.push(string(principal)?);
+            Ok(())
+        }
+
+        fn end_path(&mut self) {
+            self.paths.insert(core::mem::take(&mut self.path));
+        }
+
+        fn end_clause(&mut self) {
+            self.clauses.insert(Clause::from(core::mem::take(&mut self.paths)));
+        }
+
+        fn finish(self) -> Component {
+            Component::from(self.clauses)
+        }
+    }
+
+    pub(crate) fn write<W: Write + ?Sized>(w: &mut W, c: &Component, lossy: bool) -> core::fmt::Result {
+        let clauses = match c {
+            Component::DCFalse => None,
+            Component::DCFormula(clauses) => {
+                Some(clauses.iter().map(|c| c.0.iter().map(|path| path.iter().map(|p| p.as_bytes()))))
+            }
+        };
+        write_component(w, clauses, lossy)
+    }
+
+    impl TextLabel for Buckle {
+        fn from_text(input: &str, mode: Mode) -> Result<Self, Error> {
+            let (secrecy, integrity) =
+                label(input, mode, ComponentBuilder::default(), ComponentBuilder::default()).map_err(|e| e.0)?;
+            Ok(Buckle::new(secrecy, integrity))
+        }
+
+        fn write_label<W: Write + ?Sized>(&self, w: &mut W) -> core::fmt::Result {
+            write(w, &self.secrecy, false)?;
+            w.write_char(',')?;
+            write(w, &self.integrity, false)
+        }
+    }
+}
+
+#[cfg(feature = "buckle2")]
+pub(crate) mod buckle2 {
+    use super::*;
+    use crate::buckle2::clause::principal_in;
+    use allocator_api2::vec::Vec;
+    use crate::buckle2::{Buckle2, Clause, Component, Principal};
+    use crate::buckle2::FlatSet;
+    use allocator_api2::alloc::Allocator;
+
+    /// Builds a `Buckle2` component in `alloc`, unreduced.
+    pub(crate) struct ComponentBuilder<A: Allocator + Clone> {
+        clauses: FlatSet<Clause<A>, A>,
+        paths: FlatSet<Vec<Principal<A>, A>, A>,
+        path: Vec<Principal<A>, A>,
+        alloc: A,
+    }
+
+    impl<A: Allocator + Clone> ComponentBuilder<A> {
+        pub(crate) fn new_in(alloc: A) -> Self {
+            ComponentBuilder {
+                clauses: FlatSet::with_capacity_in(0, alloc.clone()),
+                paths: FlatSet::with_capacity_in(0, alloc.clone()),
+                path: Vec::new_in(alloc.clone()),
+                alloc,
+            }
+        }
+    }
+
+    impl<A: Allocator + Clone> Builder for ComponentBuilder<A> {
+        type Component = Component<A>;
+
+        fn constant(self, value: bool) -> Component<A> {
+            if value {
+                Component::dc_true_in(self.alloc)
+            } else {
+                Component::dc_false()
+            }
+        }
+
+        fn principal(&mut self, principal: &[u8]) -> Result<(), Error> {
+            self.path.push(principal_in(principal, self.alloc.clone()));
+            Ok(())
+        }
+
+        fn end_path(&mut self) {
+            let path = core::mem::replace(&mut self.path, Vec::new_in(self.alloc.clone()));
+            self.paths.insert(path);
+        }
+
+        fn end_clause(&mut self) {
+            let paths = core::mem::replace(&mut self.paths, FlatSet::with_capacity_in(0, self.alloc.clone()));
+            self.clauses.insert(Clause(paths));
+        }
+
+        fn finish(self) -> Component<A> {
+            Component::DCFormula(self.clauses, self.alloc)
+        }
+    }
+
+    pub(crate) fn write<W: Write + ?Sized, A: Allocator + Clone>(w: &mut W, c: &Component<A>, lossy: bool) -> core::fmt::Result {
+        let clauses = match c {
+            Component::DCFalse => None,
+            Component::DCFormula(clauses, _) => Some(clauses.iter().map(|c| c.0.iter().map(|path| path.iter()))),
+        };
+        write_component(w, clauses, lossy)
+    }
+
+    /// Labels are parsed into `A::default()`.
+    impl<A: Allocator + Clone + Default> TextLabel for Buckle2<A> {
+        fn from_text(input: &str, mode: Mode) -> Result<Self, Error> {
+            let alloc = A::default();
+            let builder = || ComponentBuilder::new_in(alloc.clone());
+            let (secrecy, integrity) = label(input, mode, builder(), builder()).map_err(|e| e.0)?;
+            let mut result = Buckle2::public_in(alloc.clone());
+            result.secrecy = secrecy;
+            result.integrity = integrity;
+            Ok(result)
+        }
+
+        fn write_label<W: Write + ?Sized>(&self, w: &mut W) -> core::fmt::Result {
+            write(w, &self.secrecy, false)?;
+            w.write_char(',')?;
+            write(w, &self.integrity, false)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A component as nested lists, or `None` for `F`.
+    type Lists = Option<Vec<Vec<Vec<Vec<u8>>>>>;
+
+    #[derive(Default)]
+    struct ListBuilder {
+        clauses: Vec<Vec<Vec<Vec<u8>>>>,
+        clause: Vec<Vec<Vec<u8>>>,
+        path: Vec<Vec<u8>>,
+    }
+
+    impl Builder for ListBuilder {
+        type Component = Lists;
+
+        fn constant(self, value: bool) -> Lists {
+            value.then(Vec::new)
+        }
+
+        fn principal(&mut self, principal: &[u8]) -> Result<(), Error> {
+            self.path.push(principal.to_vec());
+            Ok(())
+        }
+
+        fn end_path(&mut self) {
+            self.clause.push(core::mem::take(&mut self.path));
+        }
+
+        fn end_clause(&mut self) {
+            self.clauses.push(core::mem::take(&mut self.clause));
+        }
+
+        fn finish(self) -> Lists {
+            Some(self.clauses)
+        }
+    }
+
+    fn components(input: &str, mode: Mode) -> Result<(Lists, Lists), Error> {
+        label(input, mode, ListBuilder::default(), ListBuilder::default()).map_err(|e| Error::Syntax(e.1))
+    }
+
+    #[test]
+    fn test_grammar() {
+        let components = |input| components(input, Mode::Strict);
+        let p = |s: &str| alloc::vec![s.as_bytes().to_vec()];
+        assert_eq!(Ok((Some(alloc::vec![]), None)), components("T,F"));
+        assert_eq!(
+            Ok((Some(alloc::vec![alloc::vec![p("a"), p("b")], alloc::vec![p("c")]]), Some(alloc::vec![]))),
+            components("a|b&c,T")
+        );
+        assert_eq!(
+            Ok((Some(alloc::vec![alloc::vec![alloc::vec![b"a,".to_vec(), b"T".to_vec(), b"\x00".to_vec()]]]), None)),
+            components(r"a\,/\T/\x00,F")
+        );
+        // `T` and `F` are principals unless they are the whole component.
+        assert_eq!(Ok((Some(alloc::vec![alloc::vec![p("T"), p("F")]]), Some(alloc::vec![]))), components("T|F,T"));
+        assert_eq!(Ok((Some(alloc::vec![alloc::vec![p("Tom")]]), None)), components("Tom,F"));
+        assert_eq!(Ok((Some(alloc::vec![alloc::vec![p("T")]]), None)), components(r"\T,F"));
+
+        assert_eq!(Err(Error::Syntax(0)), components(",T"));
+        assert_eq!(Err(Error::Syntax(2)), components("a|,T"));
+        assert_eq!(Err(Error::Syntax(3)), components("T,T,T"));
+        assert_eq!(Err(Error::Syntax(2)), components(r"a\b,T"));
+        assert_eq!(Err(Error::Syntax(3)), components(r"a\xg0,T"));
+        assert_eq!(Err(Error::Syntax(1)), components("a b,T"));
+        assert_eq!(Err(Error::Syntax(1)), components("a\t,T"));
+        assert_eq!(Err(Error::Syntax(0)), components(" a,T"));
+    }
+
+    #[test]
+    fn test_lenient() {
+        let lenient = |input| components(input, Mode::Lenient);
+        let p = |s: &str| alloc::vec![s.as_bytes().to_vec()];
+        assert_eq!(lenient("a b|c&d,T"), lenient(" a b | c\n& d , T "));
+        assert_eq!(Ok((Some(alloc::vec![alloc::vec![p("a\\ ")]]), None)), lenient(r" a\\\x20 , F"));
+        assert_eq!(Ok((Some(alloc::vec![]), None)), lenient(" T ,\tF"));
+        assert_eq!(Err(Error::Syntax(4)), lenient(" a ,"));
+        assert_eq!(Err(Error::Syntax(1)), lenient("a\x07,T"));
+        // Any character may be escaped, as `Display` once escaped every one
+        // but the ASCII alphanumerics.
+        assert_eq!(Ok((Some(alloc::vec![alloc::vec![p("a-b é"), p("xy")]]), None)), lenient(r"a\-b\ \é|\xy,F"));
+        assert_eq!(Ok((Some(alloc::vec![alloc::vec![p("Tom")]]), None)), lenient(r"\Tom,F"));
+        assert_eq!(Err(Error::Syntax(3)), lenient(r"a,\"));
+    }
+
+    #[test]
+    fn test_prefix() {
+        let prefix = |input| label_prefix(input, Mode::Lenient, ListBuilder::default(), ListBuilder::default());
+        assert_eq!(Ok(((Some(alloc::vec![]), None), 4)), prefix("T,F ,rest"));
+        assert_eq!(Ok(((None, None), 3)), prefix("F,F\x00"));
+        assert_eq!(Ok((None, 2)), component_prefix("F ,T", Mode::Lenient, ListBuilder::default()));
+    }
+
+    #[cfg(feature = "buckle")]
+    #[test]
+    fn test_buckle() {
+        use crate::buckle::Buckle;
+
+        let lbl = Buckle::new([["a b", "c,d"]], [crate::buckle::Clause::new_from_vec(alloc::vec![alloc::vec!["e", "T"]])]);
+        assert_eq!(Ok(r"a\x20b|c\,d,e/\T".into()), lbl.to_text());
+        assert_eq!(Ok(lbl), parse(r"a\x20b|c\,d,e/\T", Mode::Strict));
+        assert_eq!(Ok("F,T".into()), Buckle::new(crate::buckle::Component::from(false), true).to_text());
+        assert_eq!(Ok("F,T".into()), Buckle::new([crate::buckle::Clause::empty()], true).to_text());
+        assert_eq!(Err(Error::InvalidUtf8), parse::<Buckle>(r"\xff,T", Mode::Strict));
+
+        let mut empty = Buckle::public();
+        empty.secrecy = crate::buckle::Component::from([crate::buckle::Clause::from(alloc::collections::BTreeSet::from([alloc::vec![]]))]);
+        assert_eq!(Err(Error::Unrepresentable), empty.to_text());
+    }
+
+    #[cfg(feature = "dclabel")]
+    #[test]
+    fn test_dclabel() {
+        use crate::dclabel::DCLabel;
+
+        // `/` is special in every label type, so text moves between them.
+        let lbl = DCLabel::new([["a/b"]], true);
+        assert_eq!(Ok(r"a\/b,T".into()), lbl.to_text());
+        assert_eq!(Ok(lbl), parse(r"a\/b,T", Mode::Strict));
+        assert_eq!(Err(Error::PathNotSupported), parse::<DCLabel>("a/b,T", Mode::Strict));
+    }
+
+    #[cfg(feature = "buckle2")]
+    #[test]
+    fn test_buckle2() {
+        use crate::buckle2::Buckle2;
+
+        let lbl = Buckle2::parse("a/b,c").unwrap();
+        assert_eq!(Ok("a/b,c".into()), lbl.to_text());
+        let mut bytes = Buckle2::public();
+        bytes.secrecy = crate::buckle2::Component::from([[[0xffu8, b'a']]]);
+        assert_eq!(Ok(r"\xffa,T".into()), bytes.to_text());
+        assert_eq!(Ok(bytes), parse(r"\xffa,T", Mode::Strict));
+    }
+
+    #[cfg(feature = "buckle2")]
+    #[test]
+    fn test_write_label_slice() {
+        use crate::buckle2::Buckle2;
+
+        let lbl = Buckle2::parse("amit|yue,T").unwrap();
+        let mut buf = [0u8; 12];
+        assert_eq!(Ok(10), lbl.write_label_slice(&mut buf));
+        assert_eq!(b"amit|yue,T", &buf[..10]);
+        assert_eq!(Err(Error::BufferTooSmall), lbl.write_label_slice(&mut buf[..9]));
+
+        let mut w = SliceWriter::new(&mut buf[..11]);
+        write!(w, "{}:", 1).unwrap();
+        lbl.write_label(&mut w).unwrap_err();
+        assert_eq!("1:amit|yue,", w.as_str());
+
+        let mut empty = Buckle2::public();
+        empty.integrity = crate::buckle2::Component::from([[[0u8; 0]]]);
+        assert_eq!(Err(Error::Unrepresentable), empty.write_label_slice(&mut buf));
+    }
+
+    #[cfg(feature = "dclabel")]
+    quickcheck! {
+        fn dclabel_round_trip(lbl: crate::dclabel::DCLabel) -> bool {
+            let has_empty_clause = |c: &crate::dclabel::Component| match c {
+                crate::dclabel::Component::DCFormula(clauses) => clauses.iter().any(|c| c.0.is_empty()),
+                crate::dclabel::Component::DCFalse => false,
+            };
+            match lbl.to_text() {
+                Ok(_) if has_empty_clause(&lbl.secrecy) || has_empty_clause(&lbl.integrity) => true,
+                Ok(text) => parse(&text, Mode::Strict) == Ok(lbl),
+                Err(_) => true,
+            }
+        }
+    }
+
+    #[cfg(feature = "buckle")]
+    quickcheck! {
+        fn buckle_round_trip(lbl: crate::buckle::Buckle) -> bool {
+            let has_empty_clause = |c: &crate::buckle::Component| match c {
+                crate::buckle::Component::DCFormula(clauses) => clauses.iter().any(|c| c.0.is_empty()),
+                crate::buckle::Component::DCFalse => false,
+            };
+            match lbl.to_text() {
+                Ok(_) if has_empty_clause(&lbl.secrecy) || has_empty_clause(&lbl.integrity) => true,
+                Ok(text) => parse(&text, Mode::Strict) == Ok(lbl),
+                Err(_) => true,
+            }
+        }
+    }
+}
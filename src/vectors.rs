@@ -0,0 +1,340 @@
+//! Cross-language test vectors.
+//!
+//! A test-vector file lists pairs of labels with the expected results of
+//! `can_flow_to`, `lub`, `glb` and implication between their secrecy
+//! components, and, given a privilege, of `can_flow_to_with_privilege` and
+//! `downgrade`. Files are JSON documents
+//! described by `testvectors/schema.json`, with labels and privileges in the
+//! label type's textual syntax, so other implementations of DC labels can
+//! share them. Expected labels are compared up to equivalence, since
+//! implementations may reduce labels differently.
+//!
+//! `run_json` checks this crate against a file, and the vectors in
+//! `testvectors/` are checked by this crate's tests. `generate` goes the
+//! other way, recording this crate's results as a case, so files can be
+//! generated here and checked against another implementation, e.g. the
+//! Haskell `dclabel` library, with the `vectors` example.
+//!
+//! The tests also run every file in the directory named by the
+//! `LABELED_VECTORS` environment variable, if set, so vectors generated by
+//! another implementation fail the test run on any discrepancy.
+
+use crate::{HasPrivilege, Label};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+/// The version of the test-vector format.
+pub const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelType {
+    DCLabel,
+    Buckle,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VectorFile {
+    pub version: u32,
+    pub label_type: LabelType,
+    pub cases: Vec<Case>,
+}
+
+/// A pair of labels with the expected results of operations on them. Absent
+/// results aren't checked.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Case {
+    pub name: String,
+    pub lhs: String,
+    pub rhs: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub privilege: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub can_flow_to: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lub: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub glb: Option<String>,
+    /// Whether the secrecy of `lhs` implies the secrecy of `rhs`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub implies: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub can_flow_to_with_privilege: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub downgrade: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    /// Parsing a label or privilege of the case.
+    Parse,
+    CanFlowTo,
+    Lub,
+    Glb,
+    Implies,
+    CanFlowToWithPrivilege,
+    Downgrade,
+}
+
+/// A case on which this crate disagrees with the vectors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    pub case: String,
+    pub operation: Operation,
+    pub expected: String,
+    pub actual: String,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// The document isn't a test-vector file.
+    Json(serde_json::Error),
+    /// The file has an unsupported version.
+    UnsupportedVersion(u32),
+    /// The file's label type isn't enabled in this build.
+    UnsupportedLabelType(LabelType),
+}
+
+/// A label type that test vectors can be run against.
+pub trait VectorLabel: Label + HasPrivilege + Clone + ToString + Sized {
+    fn parse_label(input: &str) -> Option<Self>;
+
+    fn parse_privilege(input: &str) -> Option<Self::Privilege>;
+
+    /// Writes a privilege in the label type's syntax.
+    fn privilege_to_string(privilege: &Self::Privilege) -> String;
+
+    /// Returns whether the label's secrecy implies `rhs`'s.
+    fn secrecy_implies(&self, rhs: &Self) -> bool;
+}
+
+macro_rules! impl_vector_label {
+    ($label:path, $parser:path, $component:path) => {
+        impl VectorLabel for $label {
+            fn parse_label(input: &str) -> Option<Self> {
+                nom::combinator::all_consuming($parser)(input).ok().map(|(_, label)| label)
+            }
+
+            fn parse_privilege(input: &str) -> Option<Self::Privilege> {
+                nom::combinator::all_consuming($component)(input).ok().map(|(_, privilege)| privilege)
+            }
+
+            fn privilege_to_string(privilege: &Self::Privilege) -> String {
+                privilege.to_string()
+            }
+
+            fn secrecy_implies(&self, rhs: &Self) -> bool {
+                self.secrecy.implies(&rhs.secrecy)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "dclabel")]
+impl_vector_label!(crate::dclabel::DCLabel, crate::dclabel::DCLabel::parse, crate::dclabel::component);
+#[cfg(feature = "buckle")]
+impl_vector_label!(crate::buckle::Buckle, crate::buckle::Buckle::parser, crate::buckle::component);
+
+fn equivalent<L: Label>(a: &L, b: &L) -> bool {
+    a.can_flow_to(b) && b.can_flow_to(a)
+}
+
+/// Records the failures of one case.
+struct Checker<'a> {
+    case: &'a Case,
+    failures: &'a mut Vec<Failure>,
+}
+
+impl Checker<'_> {
+    fn fail(&mut self, operation: Operation, expected: impl ToString, actual: impl ToString) {
+        self.failures.push(Failure {
+            case: self.case.name.clone(),
+            operation,
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        });
+    }
+
+    fn check_bool(&mut self, operation: Operation, expected: Option<bool>, actual: impl FnOnce() -> bool) {
+        if let Some(expected) = expected {
+            let actual = actual();
+            if actual != expected {
+                self.fail(operation, expected, actual);
+            }
+        }
+    }
+
+    fn check_label<L: VectorLabel>(&mut self, operation: Operation, expected: &Option<String>, actual: impl FnOnce() -> L) {
+        if let Some(expected) = expected {
+            let actual = actual();
+            match L::parse_label(expected) {
+                Some(e) if equivalent(&e, &actual) => {}
+                Some(_) => self.fail(operation, expected, actual),
+                None => self.fail(Operation::Parse, "a label", expected),
+            }
+        }
+    }
+}
+
+/// Runs one case, appending its failures to `failures`.
+fn run_case<L: VectorLabel>(case: &Case, failures: &mut Vec<Failure>) {
+    let mut checker = Checker { case, failures };
+    let (lhs, rhs) = match (L::parse_label(&case.lhs), L::parse_label(&case.rhs)) {
+        (Some(lhs), Some(rhs)) => (lhs, rhs),
+        _ => return checker.fail(Operation::Parse, "two labels", alloc::format!("{} / {}", case.lhs, case.rhs)),
+    };
+    checker.check_bool(Operation::CanFlowTo, case.can_flow_to, || lhs.can_flow_to(&rhs));
+    checker.check_label(Operation::Lub, &case.lub, || lhs.clone().lub(rhs.clone()));
+    checker.check_label(Operation::Glb, &case.glb, || lhs.clone().glb(rhs.clone()));
+    checker.check_bool(Operation::Implies, case.implies, || lhs.secrecy_implies(&rhs));
+
+    let privilege = match &case.privilege {
+        None => return,
+        Some(privilege) => match L::parse_privilege(privilege) {
+            Some(privilege) => privilege,
+            None => return checker.fail(Operation::Parse, "a privilege", privilege),
+        },
+    };
+    checker.check_bool(Operation::CanFlowToWithPrivilege, case.can_flow_to_with_privilege, || {
+        lhs.can_flow_to_with_privilege(&rhs, &privilege)
+    });
+    checker.check_label(Operation::Downgrade, &case.downgrade, || lhs.clone().downgrade(&privilege));
+}
+
+/// Runs every case of `file` against `L`, returning the failures.
+pub fn run<L: VectorLabel>(file: &VectorFile) -> Vec<Failure> {
+    let mut failures = Vec::new();
+    file.cases.iter().for_each(|case| run_case::<L>(case, &mut failures));
+    failures
+}
+
+/// Returns a case recording this crate's results on the labels and, if
+/// given, the privilege.
+pub fn generate<L: VectorLabel>(name: impl Into<String>, lhs: &L, rhs: &L, privilege: Option<&L::Privilege>) -> Case {
+    let mut case = Case {
+        name: name.into(),
+        lhs: lhs.to_string(),
+        rhs: rhs.to_string(),
+        can_flow_to: Some(lhs.can_flow_to(rhs)),
+        lub: Some(lhs.clone().lub(rhs.clone()).to_string()),
+        glb: Some(lhs.clone().glb(rhs.clone()).to_string()),
+        implies: Some(lhs.secrecy_implies(rhs)),
+        ..Case::default()
+    };
+    if let Some(privilege) = privilege {
+        case.privilege = Some(L::privilege_to_string(privilege));
+        case.can_flow_to_with_privilege = Some(lhs.can_flow_to_with_privilege(rhs, privilege));
+        case.downgrade = Some(lhs.clone().downgrade(privilege).to_string());
+    }
+    case
+}
+
+/// Parses a test-vector file and runs it against its label type.
+pub fn run_json(json: &str) -> Result<Vec<Failure>, Error> {
+    let file: VectorFile = serde_json::from_str(json).map_err(Error::Json)?;
+    if file.version != VERSION {
+        return Err(Error::UnsupportedVersion(file.version));
+    }
+    match file.label_type {
+        #[cfg(feature = "dclabel")]
+        LabelType::DCLabel => Ok(run::<crate::dclabel::DCLabel>(&file)),
+        #[cfg(feature = "buckle")]
+        LabelType::Buckle => Ok(run::<crate::buckle::Buckle>(&file)),
+        #[allow(unreachable_patterns)]
+        label_type => Err(Error::UnsupportedLabelType(label_type)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    #[cfg(feature = "dclabel")]
+    #[test]
+    fn test_dclabel_vectors() {
+        assert_eq!(Vec::<Failure>::new(), run_json(include_str!("../testvectors/dclabel.json")).unwrap());
+    }
+
+    #[cfg(feature = "buckle")]
+    #[test]
+    fn test_buckle_vectors() {
+        assert_eq!(Vec::<Failure>::new(), run_json(include_str!("../testvectors/buckle.json")).unwrap());
+    }
+
+    #[cfg(feature = "buckle")]
+    #[test]
+    fn test_failures() {
+        let file = VectorFile {
+            version: VERSION,
+            label_type: LabelType::Buckle,
+            cases: alloc::vec![
+                Case {
+                    name: "wrong".into(),
+                    lhs: "amit,T".into(),
+                    rhs: "yue,T".into(),
+                    can_flow_to: Some(true),
+                    lub: Some("amit,T".into()),
+                    ..Case::default()
+                },
+                Case { name: "unparsable".into(), lhs: "amit".into(), rhs: "T,T".into(), ..Case::default() },
+            ],
+        };
+        let failures = run::<crate::buckle::Buckle>(&file);
+        let operations: Vec<_> = failures.iter().map(|f| (f.case.as_str(), f.operation)).collect();
+        assert_eq!(
+            alloc::vec![("wrong", Operation::CanFlowTo), ("wrong", Operation::Lub), ("unparsable", Operation::Parse)],
+            operations
+        );
+        assert_eq!("amit&yue,T", failures[1].actual);
+
+        let json = serde_json::to_string(&file).unwrap();
+        assert_eq!(failures, run_json(&json).unwrap());
+        assert!(matches!(run_json(&json.replace("\"version\":1", "\"version\":2")), Err(Error::UnsupportedVersion(2))));
+    }
+
+    #[test]
+    fn test_external_vectors() {
+        let dir = match std::env::var("LABELED_VECTORS") {
+            Ok(dir) => dir,
+            Err(_) => return,
+        };
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|e| e == "json") {
+                let json = std::fs::read_to_string(&path).unwrap();
+                assert_eq!(Ok(Vec::new()), run_json(&json).map_err(|e| alloc::format!("{:?}", e)), "{}", path.display());
+            }
+        }
+    }
+
+    /// Returns a component over the principals `a` to `d`, one clause per
+    /// byte with the principals of its low bits.
+    #[cfg(feature = "dclabel")]
+    fn dclabel_component(clauses: &[u8]) -> crate::dclabel::Component {
+        use crate::dclabel::{Clause, Component};
+
+        let clauses = clauses.iter().take(3).map(|bits| {
+            Clause::new_from_vec(["a", "b", "c", "d"].iter().enumerate().filter(|(i, _)| bits & (1 << i) != 0).map(|(_, p)| *p).collect())
+        });
+        Component::from(clauses.collect::<alloc::collections::BTreeSet<_>>())
+    }
+
+    #[cfg(feature = "dclabel")]
+    quickcheck! {
+        fn generated_cases_pass(secrecy: Vec<u8>, integrity: Vec<u8>, rhs_secrecy: Vec<u8>, privilege: Vec<u8>) -> bool {
+            use crate::dclabel::DCLabel;
+
+            let lhs = DCLabel::new(dclabel_component(&secrecy), dclabel_component(&integrity));
+            let rhs = DCLabel::new(dclabel_component(&rhs_secrecy), dclabel_component(&privilege));
+            let file = VectorFile {
+                version: VERSION,
+                label_type: LabelType::DCLabel,
+                cases: alloc::vec![generate("generated", &lhs, &rhs, Some(&dclabel_component(&privilege)))],
+            };
+            run::<DCLabel>(&file).is_empty()
+        }
+    }
+}
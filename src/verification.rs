@@ -0,0 +1,78 @@
+//! Kani proof harnesses for the lattice laws of `Buckle2`.
+//!
+//! Where the quickcheck properties sample random labels, these harnesses
+//! prove the laws for every label over a small universe of principals: the
+//! paths `a`, `b` and `a/b`, in components of up to `MAX_CLAUSES` clauses.
+//! Run them with `cargo kani --features buckle2`; the module is only
+//! compiled under Kani's `kani` cfg.
+
+use crate::buckle2::{Buckle2, Clause, Component, FlatSet};
+use crate::{laws, HasPrivilege};
+use alloc::vec::Vec;
+
+/// The paths clauses are drawn from. `a` is a prefix of, and so implies,
+/// `a/b`.
+const PATHS: [&[&[u8]]; 3] = [&[b"a"], &[b"b"], &[b"a", b"b"]];
+
+const MAX_CLAUSES: usize = 2;
+
+/// Returns any clause over `PATHS`, including the empty clause.
+fn any_clause() -> Clause {
+    let mut paths = Vec::new();
+    for path in PATHS {
+        if kani::any() {
+            paths.push(path.to_vec());
+        }
+    }
+    Clause::new_from_vec(paths)
+}
+
+/// Returns false or any formula of up to `MAX_CLAUSES` clauses.
+fn any_component() -> Component {
+    if kani::any() {
+        return Component::DCFalse;
+    }
+    let mut clauses = FlatSet::new();
+    for _ in 0..MAX_CLAUSES {
+        if kani::any() {
+            clauses.insert(any_clause());
+        }
+    }
+    Component::from(clauses)
+}
+
+fn any_label() -> Buckle2 {
+    Buckle2::new(any_component(), any_component())
+}
+
+/// `can_flow_to` is reflexive and transitive, and so a partial order on
+/// labels that flow to each other.
+#[kani::proof]
+#[kani::unwind(8)]
+fn can_flow_to_is_partial_order() {
+    let (a, b, c) = (any_label(), any_label(), any_label());
+    assert!(laws::can_flow_to_reflexive(&a));
+    assert!(laws::can_flow_to_transitive(&a, &b, &c));
+}
+
+/// `lub` is an upper bound of both labels, and flows to every other upper
+/// bound.
+#[kani::proof]
+#[kani::unwind(8)]
+fn lub_is_least_upper_bound() {
+    let (a, b, c) = (any_label(), any_label(), any_label());
+    assert!(laws::lub_upper_bound(&a, &b));
+    assert!(laws::lub_least(&a, &b, &c));
+}
+
+/// `downgrade` only drops secrecy clauses the privilege implies, and with
+/// the empty privilege changes nothing.
+#[kani::proof]
+#[kani::unwind(8)]
+fn downgrade_needs_privilege() {
+    let (a, privilege) = (any_label(), any_component());
+    let downgraded = a.clone().downgrade(&privilege);
+    assert!(a.secrecy.implies(&downgraded.secrecy));
+    assert!(downgraded.secrecy.implies_with(&privilege, &a.secrecy));
+    assert!(a.clone().downgrade(&Component::dc_true()) == a);
+}
@@ -0,0 +1,79 @@
+//! WebAssembly bindings for `DCLabel` and `Buckle`.
+//!
+//! Each label type is exported to JavaScript as a class of the same name,
+//! with a static `parse`, `toString`, `canFlowTo`, `lub`, `glb` and
+//! `downgrade`. Privileges are passed as strings in the component syntax of
+//! the corresponding `parse` (e.g. `"amit/grader|staff"`).
+
+use crate::{HasPrivilege, Label};
+use alloc::string::{String, ToString};
+use wasm_bindgen::prelude::*;
+
+macro_rules! bindings {
+    ($wrapper:ident, $js:ident, $label:path, $parser:path, $component:path) => {
+        #[wasm_bindgen(js_name = $js)]
+        pub struct $wrapper($label);
+
+        #[wasm_bindgen(js_class = $js)]
+        impl $wrapper {
+            pub fn parse(input: &str) -> Result<$wrapper, JsError> {
+                nom::combinator::all_consuming($parser)(input)
+                    .map(|(_, label)| $wrapper(label))
+                    .map_err(|_| JsError::new("invalid label"))
+            }
+
+            #[wasm_bindgen(js_name = toString)]
+            pub fn to_js_string(&self) -> String {
+                self.0.to_string()
+            }
+
+            #[wasm_bindgen(js_name = canFlowTo)]
+            pub fn can_flow_to(&self, rhs: &$wrapper) -> bool {
+                self.0.can_flow_to(&rhs.0)
+            }
+
+            pub fn lub(&self, rhs: &$wrapper) -> $wrapper {
+                $wrapper(self.0.clone().lub(rhs.0.clone()))
+            }
+
+            pub fn glb(&self, rhs: &$wrapper) -> $wrapper {
+                $wrapper(self.0.clone().glb(rhs.0.clone()))
+            }
+
+            pub fn downgrade(&self, privilege: &str) -> Result<$wrapper, JsError> {
+                let (_, mut privilege) = nom::combinator::all_consuming($component)(privilege)
+                    .map_err(|_| JsError::new("invalid privilege"))?;
+                privilege.reduce();
+                Ok($wrapper(self.0.clone().downgrade(&privilege)))
+            }
+        }
+    };
+}
+
+bindings!(WasmDCLabel, DCLabel, crate::dclabel::DCLabel, crate::dclabel::DCLabel::parse, crate::dclabel::component);
+bindings!(WasmBuckle, Buckle, crate::buckle::Buckle, crate::buckle::Buckle::parser, crate::buckle::component);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Only success paths can run natively; errors construct JS values.
+    #[test]
+    fn test_buckle() {
+        let secret = WasmBuckle::parse("amit/grades,T").unwrap();
+        let public = WasmBuckle::parse("T,T").unwrap();
+        assert!(public.can_flow_to(&secret));
+        assert!(!secret.can_flow_to(&public));
+        assert_eq!("amit/grades,T", public.lub(&secret).to_js_string());
+        assert_eq!("T,amit", secret.downgrade("amit").unwrap().to_js_string());
+    }
+
+    #[test]
+    fn test_dclabel() {
+        let secret = WasmDCLabel::parse("amit,T").unwrap();
+        let public = WasmDCLabel::parse("T,T").unwrap();
+        assert!(public.can_flow_to(&secret));
+        assert_eq!("T,T", public.glb(&secret).to_js_string());
+        assert_eq!("T,amit", secret.downgrade("amit").unwrap().to_js_string());
+    }
+}
@@ -0,0 +1,136 @@
+//! axum integration for labeled web services.
+//!
+//! `RequestLabel` extracts the request's `Buckle` label from the `Sec-Label`
+//! header (see `header`), rejecting requests whose header is missing,
+//! malformed or oversized with `400 Bad Request`. Use `Option<RequestLabel>`
+//! to treat a missing header as `None` instead. `ResponseLabel` attaches a
+//! label to a response, e.g. `(ResponseLabel(label), body)`.
+
+use crate::buckle::Buckle;
+use crate::header;
+use alloc::format;
+use axum::extract::{FromRequestParts, OptionalFromRequestParts};
+use axum::http::{request::Parts, HeaderName, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, IntoResponseParts, Response, ResponseParts};
+
+/// The label of an incoming request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestLabel(pub Buckle);
+
+/// Why a request's label was rejected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LabelRejection {
+    Missing,
+    Invalid(header::Error),
+}
+
+impl IntoResponse for LabelRejection {
+    fn into_response(self) -> Response {
+        let message = match self {
+            LabelRejection::Missing => format!("missing {} header", header::NAME),
+            LabelRejection::Invalid(e) => format!("invalid {} header: {:?}", header::NAME, e),
+        };
+        (StatusCode::BAD_REQUEST, message).into_response()
+    }
+}
+
+impl<S: Send + Sync> OptionalFromRequestParts<S> for RequestLabel {
+    type Rejection = LabelRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Option<Self>, Self::Rejection> {
+        match parts.headers.get(header::NAME) {
+            None => Ok(None),
+            Some(value) => header::decode(value.as_bytes())
+                .map(|label| Some(RequestLabel(label)))
+                .map_err(LabelRejection::Invalid),
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for RequestLabel {
+    type Rejection = LabelRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        <Self as OptionalFromRequestParts<S>>::from_request_parts(parts, state)
+            .await?
+            .ok_or(LabelRejection::Missing)
+    }
+}
+
+/// Attaches a label to a response. Fails with `500 Internal Server Error` if
+/// the label is too large to encode in a header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseLabel(pub Buckle);
+
+impl IntoResponseParts for ResponseLabel {
+    type Error = (StatusCode, &'static str);
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        let value = header::encode(&self.0)
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "response label too large"))?;
+        res.headers_mut().insert(
+            HeaderName::from_static("sec-label"),
+            HeaderValue::from_str(&value).expect("base64url is a valid header value"),
+        );
+        Ok(res)
+    }
+}
+
+impl IntoResponse for ResponseLabel {
+    fn into_response(self) -> Response {
+        (self, ()).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+    use core::future::Future;
+
+    /// Runs a future that never waits, as the extractors here don't.
+    fn now<F: Future>(f: F) -> F::Output {
+        let f = core::pin::pin!(f);
+        match f.poll(&mut core::task::Context::from_waker(core::task::Waker::noop())) {
+            core::task::Poll::Ready(output) => output,
+            core::task::Poll::Pending => panic!("future was pending"),
+        }
+    }
+
+    fn parts(label: Option<&str>) -> Parts {
+        let mut request = Request::builder();
+        if let Some(label) = label {
+            request = request.header(header::NAME, label);
+        }
+        request.body(()).unwrap().into_parts().0
+    }
+
+    #[test]
+    fn test_extract() {
+        let lbl = Buckle::parse("amit/grades,T").unwrap();
+        let encoded = header::encode(&lbl).unwrap();
+        let extracted = now(<RequestLabel as FromRequestParts<()>>::from_request_parts(&mut parts(Some(&encoded)), &()));
+        assert_eq!(Ok(RequestLabel(lbl)), extracted);
+    }
+
+    #[test]
+    fn test_reject() {
+        let missing = now(<RequestLabel as FromRequestParts<()>>::from_request_parts(&mut parts(None), &()));
+        assert_eq!(Err(LabelRejection::Missing), missing);
+        let optional = now(<RequestLabel as OptionalFromRequestParts<()>>::from_request_parts(&mut parts(None), &()));
+        assert_eq!(Ok(None), optional);
+
+        let invalid = now(<RequestLabel as FromRequestParts<()>>::from_request_parts(&mut parts(Some("a=b")), &()))
+            .unwrap_err();
+        assert_eq!(LabelRejection::Invalid(header::Error::InvalidCharacter(b'=')), invalid);
+        assert_eq!(StatusCode::BAD_REQUEST, invalid.into_response().status());
+    }
+
+    #[test]
+    fn test_respond() {
+        let lbl = Buckle::parse("amit,T").unwrap();
+        let response = (ResponseLabel(lbl.clone()), "body").into_response();
+        let value = response.headers().get(header::NAME).unwrap();
+        assert_eq!(Ok(lbl), header::decode(value.as_bytes()));
+    }
+}